@@ -1,20 +1,80 @@
 import path = require('path')
 import * as vscode from 'vscode'
-import * as lsp from 'vscode-languageclient'
+import * as lsp from 'vscode-languageclient/node'
 import { Extension } from './extension'
 import { log } from './log'
 
 export type Command = (...args: any[]) => unknown
 
+// commands run in the pack of the file in the active editor, or the first pack of the workspace
+function commandTarget(): string {
+  return vscode.window.activeTextEditor?.document.uri.path ?? vscode.workspace.workspaceFolders[0].uri.path
+}
+
 export function generateGraphDot(e: Extension): Command {
   return async () => {
     await e.lspClient.sendRequest(lsp.ExecuteCommandRequest.type.method, {
       command: 'graphDot',
-      arguments: [vscode.workspace.workspaceFolders[0].uri.path],
+      arguments: [commandTarget()],
+    })
+  }
+}
+
+export function graphExport(e: Extension): Command {
+  return async () => {
+    const format = await vscode.window.showQuickPick(['json', 'mermaid'], { placeHolder: 'Format' })
+    if (!format) return
+
+    const scopes = ['Whole pack']
+    const file = vscode.window.activeTextEditor?.document.uri.path
+    if (file) scopes.push('Around current file', 'Current program')
+    const scope = await vscode.window.showQuickPick(scopes, { placeHolder: 'Part of the include graph' })
+    if (!scope) return
+
+    const around = scope == 'Around current file' ? { file } : scope == 'Current program' ? { program: file } : null
+    const result = await e.lspClient.sendRequest<any>(lsp.ExecuteCommandRequest.type.method, {
+      command: 'graphExport',
+      arguments: [commandTarget(), format, around],
+    })
+
+    const doc = await vscode.workspace.openTextDocument({
+      content: format == 'json' ? JSON.stringify(result, null, 2) : '```mermaid\n' + result + '```\n',
+      language: format == 'json' ? 'json' : 'markdown',
+    })
+    await vscode.window.showTextDocument(doc)
+  }
+}
+
+export function graphCheck(e: Extension): Command {
+  return async () => {
+    const result = await e.lspClient.sendRequest<{ nodes: number, edges: number, mismatches: string[] }>(lsp.ExecuteCommandRequest.type.method, {
+      command: 'graphCheck',
+      arguments: [commandTarget()],
+    })
+    if (result.mismatches.length == 0) {
+      vscode.window.showInformationMessage(`Include graph is consistent (${result.nodes} files, ${result.edges} includes)`)
+    } else {
+      vscode.window.showWarningMessage(`Include graph has ${result.mismatches.length} mismatches: ${result.mismatches.join('; ')}`)
+    }
+  }
+}
+
+export function sarifReport(e: Extension): Command {
+  return async () => {
+    await e.lspClient.sendRequest(lsp.ExecuteCommandRequest.type.method, {
+      command: 'sarifReport',
+      arguments: [commandTarget()],
     })
   }
 }
 
+// applies the edit of a quick fix offered by the language server
+export function applyWorkspaceEdit(e: Extension): Command {
+  return async (edit: lsp.WorkspaceEdit) => {
+    await vscode.workspace.applyEdit(e.lspClient.protocol2CodeConverter.asWorkspaceEdit(edit))
+  }
+}
+
 export function restartExtension(e: Extension): Command {
   return async () => {
     vscode.window.showInformationMessage('Reloading Minecraft GLSL language server...')
@@ -104,4 +164,38 @@ export function parseTree(e: Extension): Command {
       preview: true
     })
   }
-}
\ No newline at end of file
+}
+// files in zipped packs are served read-only by the language server under the mcglsl-zip scheme
+export function openArchiveFile(e: Extension): Command {
+  const docProvider = new class implements vscode.TextDocumentContentProvider {
+    provideTextDocumentContent(uri: vscode.Uri, _: vscode.CancellationToken): vscode.ProviderResult<string> {
+      return e.lspClient.sendRequest<string>(lsp.ExecuteCommandRequest.type.method, {
+        command: 'archiveFile',
+        arguments: [uri.path]
+      })
+    }
+  }
+
+  e.context.subscriptions.push(vscode.workspace.registerTextDocumentContentProvider('mcglsl-zip', docProvider))
+
+  return async () => {
+    const archives = await vscode.workspace.findFiles('**/*.zip')
+    if (archives.length == 0) {
+      vscode.window.showInformationMessage('No zipped packs found in the workspace')
+      return
+    }
+
+    const archive = await vscode.window.showQuickPick(archives.map(uri => uri.path), { placeHolder: 'Zipped pack' })
+    if (!archive) return
+
+    const files = await e.lspClient.sendRequest<string[]>(lsp.ExecuteCommandRequest.type.method, {
+      command: 'archiveFiles',
+      arguments: [archive]
+    })
+    const file = await vscode.window.showQuickPick(files.map(file => path.posix.relative(archive, file)), { placeHolder: 'File' })
+    if (!file) return
+
+    const doc = await vscode.workspace.openTextDocument(vscode.Uri.parse(`mcglsl-zip:${path.posix.join(archive, file)}`))
+    await vscode.window.showTextDocument(doc, { preview: true })
+  }
+}
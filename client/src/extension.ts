@@ -1,6 +1,6 @@
 import { mkdirSync, promises as fs } from 'fs'
 import * as vscode from 'vscode'
-import * as lsp from 'vscode-languageclient'
+import * as lsp from 'vscode-languageclient/node'
 import * as commands from './commands'
 import { log } from './log'
 import { LanguageClient } from './lspClient'
@@ -46,9 +46,14 @@ export class Extension {
     }
 
     this.registerCommand('graphDot', commands.generateGraphDot)
+    this.registerCommand('graphExport', commands.graphExport)
+    this.registerCommand('graphCheck', commands.graphCheck)
+    this.registerCommand('sarifReport', commands.sarifReport)
     this.registerCommand('restart', commands.restartExtension)
     this.registerCommand('virtualMerge', commands.virtualMergedDocument)
     this.registerCommand('parseTree', commands.parseTree)
+    this.registerCommand('openArchiveFile', commands.openArchiveFile)
+    this.registerCommand('applyWorkspaceEdit', commands.applyWorkspaceEdit)
 
     log.info('starting language server...')
 
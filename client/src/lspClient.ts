@@ -1,5 +1,5 @@
 import { ConfigurationTarget, workspace } from 'vscode'
-import * as lsp from 'vscode-languageclient'
+import * as lsp from 'vscode-languageclient/node'
 import { Extension } from './extension'
 import { log, lspOutputChannel } from './log'
 import { ConfigUpdateParams, statusMethod, StatusParams, updateConfigMethod } from './lspExt'
@@ -11,11 +11,15 @@ export class LanguageClient extends lsp.LanguageClient {
     super('vscode-mc-shader', 'VSCode MC Shader', {
       command: lspBinary
     }, {
-      documentSelector: [{ scheme: 'file', language: 'glsl' }],
+      documentSelector: [{ scheme: 'file', language: 'glsl' }, { scheme: 'mcglsl-zip', language: 'glsl' }],
       outputChannel: lspOutputChannel,
       synchronize: {
         configurationSection: 'mcglsl',
-        fileEvents: workspace.createFileSystemWatcher(filewatcherGlob)
+        fileEvents: [
+          workspace.createFileSystemWatcher(filewatcherGlob),
+          workspace.createFileSystemWatcher('**/.mcglsl.toml'),
+          workspace.createFileSystemWatcher('**/.mcglsl-baseline.json'),
+        ]
       },
     })
     this.extension = ext
@@ -1,4 +1,4 @@
-import * as lsp from 'vscode-languageclient'
+import * as lsp from 'vscode-languageclient/node'
 
 export type StatusParams = {
   status: 'loading' | 'ready' | 'failed' | 'clear'
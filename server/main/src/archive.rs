@@ -0,0 +1,345 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{self, Cursor, ErrorKind, Read};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use lazy_static::lazy_static;
+use walkdir::WalkDir;
+use zip::ZipArchive;
+
+/// URI scheme of files inside an archive, which the client serves read-only.
+pub static ARCHIVE_SCHEME: &str = "mcglsl-zip";
+
+// no shader source comes close to this, so larger files in an archive are refused rather than inflated.
+const MAX_FILE_SIZE: u64 = 16 * 1024 * 1024;
+
+lazy_static! {
+    // parsed archives by path, along with the modification time they were read at.
+    static ref ARCHIVES: Mutex<HashMap<PathBuf, (SystemTime, Arc<Archive>)>> = Mutex::new(HashMap::new());
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message.to_string())
+}
+
+fn has_archive_extension(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+}
+
+pub fn is_archive(path: &Path) -> bool {
+    has_archive_extension(path) && path.is_file()
+}
+
+/// The archive `path` is in, and the path of the file inside the archive, which is empty for the
+/// archive itself. Files in zipped packs are addressed by joining their path in the archive onto the
+/// path of the archive, like `/packs/pack.zip/shaders/final.fsh`, so the graph and everything built on
+/// it treat archives like any other folder.
+///
+/// Only paths going through a `.zip` are looked up on disk, and archives already opened aren't looked up
+/// again, as this is called for every path turned into a URL.
+pub fn split(path: &Path) -> Option<(PathBuf, PathBuf)> {
+    let archive = path
+        .ancestors()
+        .filter(|ancestor| has_archive_extension(ancestor))
+        .find(|ancestor| ARCHIVES.lock().unwrap().contains_key(*ancestor) || ancestor.is_file())?;
+    Some((archive.to_path_buf(), path.strip_prefix(archive).unwrap().to_path_buf()))
+}
+
+/// A zip archive read into memory.
+pub struct Archive {
+    zip: ZipArchive<Cursor<Arc<[u8]>>>,
+    // names of the files in the archive by their path, without directories.
+    entries: BTreeMap<PathBuf, String>,
+}
+
+/// The path of an entry in the archive, or `None` for directories and names that would point outside
+/// of the archive.
+fn entry_path(name: &str) -> Option<PathBuf> {
+    if name.ends_with('/') {
+        return None;
+    }
+    let path: PathBuf = name.split(['/', '\\']).filter(|part| !part.is_empty()).collect();
+    if path.components().all(|component| matches!(component, Component::Normal(_))) {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+impl Archive {
+    pub fn parse(data: Vec<u8>) -> io::Result<Archive> {
+        let zip = ZipArchive::new(Cursor::new(Arc::from(data)))?;
+        let entries = zip
+            .file_names()
+            .filter_map(|name| Some((entry_path(name)?, name.to_string())))
+            .collect();
+        Ok(Archive { zip, entries })
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.entries.keys().map(PathBuf::as_path)
+    }
+
+    pub fn is_file(&self, path: &Path) -> bool {
+        self.entries.contains_key(path)
+    }
+
+    /// Whether any file is in the folder `path`, as archives don't necessarily have entries for folders.
+    pub fn is_dir(&self, path: &Path) -> bool {
+        self.entries
+            .range(path.to_path_buf()..)
+            .next()
+            .is_some_and(|(file, _)| file.starts_with(path) && file != path)
+    }
+
+    /// Reads the file at `path`, refusing files over `MAX_FILE_SIZE` whether they claim to be or only turn
+    /// out to be once inflated.
+    pub fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let name = self
+            .entries
+            .get(path)
+            .ok_or_else(|| io::Error::new(ErrorKind::NotFound, format!("{:?} not found in archive", path)))?;
+
+        // the archive data is shared, only the read position is cloned
+        let mut zip = self.zip.clone();
+        let file = zip.by_name(name)?;
+        let size = file.size();
+        if size > MAX_FILE_SIZE {
+            return Err(invalid("zip entry too large"));
+        }
+
+        let mut contents = Vec::with_capacity(size as usize);
+        file.take(MAX_FILE_SIZE + 1).read_to_end(&mut contents)?;
+        if contents.len() as u64 != size {
+            return Err(invalid("zip entry isn't the size it claims to be"));
+        }
+        Ok(contents)
+    }
+}
+
+/// The archive at `path`, which is only parsed again once it was modified.
+fn open(path: &Path) -> io::Result<Arc<Archive>> {
+    let modified = fs::metadata(path)?.modified()?;
+    let mut archives = ARCHIVES.lock().unwrap();
+    if let Some((read_at, archive)) = archives.get(path) {
+        if *read_at == modified {
+            return Ok(archive.clone());
+        }
+    }
+
+    let archive = Arc::new(Archive::parse(fs::read(path)?)?);
+    archives.insert(path.to_path_buf(), (modified, archive.clone()));
+    Ok(archive)
+}
+
+pub fn read(path: &Path) -> io::Result<Vec<u8>> {
+    match split(path) {
+        Some((archive, file)) => open(&archive)?.read(&file),
+        None => fs::read(path),
+    }
+}
+
+pub fn read_to_string(path: &Path) -> io::Result<String> {
+    match split(path) {
+        Some(_) => String::from_utf8(read(path)?).map_err(|e| io::Error::new(ErrorKind::InvalidData, e)),
+        None => fs::read_to_string(path),
+    }
+}
+
+pub fn exists(path: &Path) -> bool {
+    match split(path) {
+        Some((archive, file)) => open(&archive).is_ok_and(|archive| archive.is_file(&file) || archive.is_dir(&file)),
+        None => path.exists(),
+    }
+}
+
+fn is_dir(path: &Path) -> bool {
+    match split(path) {
+        Some((archive, file)) => open(&archive).is_ok_and(|archive| file.as_os_str().is_empty() || archive.is_dir(&file)),
+        None => path.is_dir(),
+    }
+}
+
+/// Every file in the folder `root`, which may be in an archive or be an archive itself.
+pub fn files(root: &Path) -> Vec<PathBuf> {
+    match split(root) {
+        Some((archive_path, folder)) => match open(&archive_path) {
+            Ok(archive) => archive
+                .files()
+                .filter(|file| file.starts_with(&folder))
+                .map(|file| archive_path.join(file))
+                .collect(),
+            Err(_) => vec![],
+        },
+        None => WalkDir::new(root)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| !entry.file_type().is_dir())
+            .map(|entry| entry.into_path())
+            .collect(),
+    }
+}
+
+/// The root of the pack in the archive at `path`, which is either the archive itself or one of the
+/// folders at its top level, as packs are often zipped up along with the folder they're in.
+pub fn pack_root(path: &Path) -> Option<PathBuf> {
+    if is_dir(&path.join("shaders")) {
+        return Some(path.to_path_buf());
+    }
+    let archive = open(path).ok()?;
+    let folder = archive
+        .files()
+        .filter_map(|file| file.iter().next())
+        .find(|folder| archive.is_dir(&Path::new(folder).join("shaders")))?;
+    Some(path.join(folder))
+}
+
+/// Where files generated for a pack are written, which is next to the archive for a zipped pack.
+pub fn output_dir(root: &Path) -> PathBuf {
+    match split(root) {
+        Some((archive, _)) => archive.parent().map(Path::to_path_buf).unwrap_or_default(),
+        None => root.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod archive_test {
+    use std::fs;
+    use std::io::{Cursor, Write};
+    use std::path::{Path, PathBuf};
+
+    use tempdir::TempDir;
+    use zip::write::FileOptions;
+    use zip::{CompressionMethod, ZipWriter};
+
+    use crate::archive::{self, Archive, MAX_FILE_SIZE};
+    use crate::url_norm::{FromUrl, ToUrl};
+
+    static FRAGMENT: &str = "#version 120\n\nuniform sampler2D colortex0;\nvarying vec2 texcoord;\n\nvoid main() {\n    gl_FragColor = texture2D(colortex0, texcoord);\n    gl_FragColor.rgb *= 0.5;\n}\n";
+
+    /// A zip archive of `(name, contents)` entries, where names ending in `/` are folders and the rest are
+    /// deflated.
+    fn zip(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(vec![]));
+        for (name, contents) in entries {
+            if name.ends_with('/') {
+                writer.add_directory(*name, FileOptions::default()).unwrap();
+            } else {
+                writer
+                    .start_file(*name, FileOptions::default().compression_method(CompressionMethod::Deflated))
+                    .unwrap();
+                writer.write_all(contents).unwrap();
+            }
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    /// `data` with the uncompressed size in the central directory header of its only entry set to `size`.
+    fn with_size(mut data: Vec<u8>, size: u32) -> Vec<u8> {
+        let header = data.windows(4).position(|window| window == b"PK\x01\x02").unwrap();
+        data[header + 24..header + 28].copy_from_slice(&size.to_le_bytes());
+        data
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_archive() {
+        let fragment = FRAGMENT.repeat(3);
+        let archive = Archive::parse(zip(&[
+            ("pack/", b""),
+            ("pack/shaders/final.vsh", b"#version 120\n"),
+            ("pack/shaders/final.fsh", fragment.as_bytes()),
+            ("../escape.glsl", b""),
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            archive.files().collect::<Vec<&Path>>(),
+            vec![Path::new("pack/shaders/final.fsh"), Path::new("pack/shaders/final.vsh")]
+        );
+        assert!(archive.is_dir(Path::new("pack/shaders")));
+        assert!(!archive.is_dir(Path::new("pack/shaders/final.fsh")));
+        assert!(!archive.is_file(Path::new("pack/shaders")));
+        assert_eq!(archive.read(Path::new("pack/shaders/final.fsh")).unwrap(), fragment.as_bytes());
+        assert!(archive.read(Path::new("pack/shaders/composite.fsh")).is_err());
+
+        assert!(Archive::parse(b"#version 120\n".to_vec()).is_err());
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_untrusted_archive() {
+        let path = Path::new("final.fsh");
+        let data = zip(&[("final.fsh", FRAGMENT.as_bytes())]);
+
+        // a checksum that doesn't match the contents
+        let mut corrupt = data.clone();
+        let end = corrupt.windows(4).position(|window| window == b"PK\x01\x02").unwrap();
+        corrupt[end - 1] ^= 0xff;
+        assert!(Archive::parse(corrupt).unwrap().read(path).is_err());
+
+        // sizes that don't match what the entry inflates to
+        assert!(Archive::parse(with_size(data.clone(), FRAGMENT.len() as u32 - 1))
+            .unwrap()
+            .read(path)
+            .is_err());
+        assert!(Archive::parse(with_size(data.clone(), FRAGMENT.len() as u32 + 1))
+            .unwrap()
+            .read(path)
+            .is_err());
+
+        // entries claiming to be too large aren't read at all
+        assert!(Archive::parse(with_size(data, MAX_FILE_SIZE as u32 + 1))
+            .unwrap()
+            .read(path)
+            .is_err());
+
+        // nor are entries that only turn out to be too large once inflated
+        let large = vec![b' '; MAX_FILE_SIZE as usize + 1];
+        let data = with_size(zip(&[("final.fsh", &large)]), FRAGMENT.len() as u32);
+        assert!(Archive::parse(data).unwrap().read(path).is_err());
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_zipped_pack() {
+        let tmp_dir = TempDir::new("mcshader").unwrap();
+        let zip_path = tmp_dir.path().join("pack.zip");
+        fs::write(
+            &zip_path,
+            zip(&[
+                ("Pack/shaders/final.fsh", FRAGMENT.as_bytes()),
+                ("Pack/shaders/lib/common.glsl", b""),
+            ]),
+        )
+        .unwrap();
+
+        let root = archive::pack_root(&zip_path).unwrap();
+        assert_eq!(root, zip_path.join("Pack"));
+        assert_eq!(archive::output_dir(&root), tmp_dir.path());
+
+        let file = root.join("shaders").join("final.fsh");
+        assert_eq!(
+            archive::split(&file),
+            Some((zip_path.clone(), PathBuf::from("Pack/shaders/final.fsh")))
+        );
+        assert_eq!(
+            archive::files(&root),
+            vec![file.clone(), root.join("shaders").join("lib").join("common.glsl")]
+        );
+        assert_eq!(archive::read_to_string(&file).unwrap(), FRAGMENT);
+        assert!(archive::exists(&root.join("shaders").join("lib")));
+        assert!(!archive::exists(&root.join("shaders").join("composite.fsh")));
+
+        let url = file.to_url().unwrap();
+        assert_eq!(url.scheme(), archive::ARCHIVE_SCHEME);
+        assert_eq!(PathBuf::from_url(url), file);
+
+        // packs on disk aren't affected
+        fs::create_dir_all(tmp_dir.path().join("shaders")).unwrap();
+        assert_eq!(archive::split(&tmp_dir.path().join("shaders")), None);
+        assert_eq!(archive::pack_root(tmp_dir.path()), Some(tmp_dir.path().to_path_buf()));
+    }
+}
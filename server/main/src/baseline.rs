@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use rust_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+use serde::{Deserialize, Serialize};
+
+use crate::archive;
+use crate::consts;
+use crate::fnv::Fnv1a;
+
+/// Default location of the baseline, relative to the pack root.
+pub static BASELINE_FILE: &str = ".mcglsl-baseline.json";
+
+pub static FIXED_ENTRY_CODE: &str = "fixed-baseline-entry";
+
+static BASELINE_VERSION: u32 = 1;
+
+/// A diagnostic accepted into the baseline. Entries are matched by file, message and the fingerprint
+/// of the line's content, so they keep matching when unrelated edits move the line around.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    // path relative to the pack root, with forward slashes.
+    pub file: String,
+    // 1-indexed line the diagnostic was on when the baseline was written, only used for reporting.
+    pub line: u32,
+    pub message: String,
+    pub fingerprint: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub version: u32,
+    pub entries: Vec<BaselineEntry>,
+}
+
+impl Baseline {
+    /// Builds a baseline accepting every diagnostic of `files`, given as the path of each file relative
+    /// to the pack root, its diagnostics and its content.
+    pub fn new<'a>(files: impl IntoIterator<Item = (String, &'a [Diagnostic], &'a str)>) -> Baseline {
+        let mut entries: Vec<BaselineEntry> = files
+            .into_iter()
+            .flat_map(|(file, diagnostics, source)| {
+                diagnostics
+                    .iter()
+                    .filter(|diagnostic| !is_fixed_entry(diagnostic))
+                    .map(move |diagnostic| BaselineEntry {
+                        file: file.clone(),
+                        line: diagnostic.range.start.line + 1,
+                        message: diagnostic.message.clone(),
+                        fingerprint: fingerprint(line(source, diagnostic.range.start.line)),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        entries.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)).then(a.message.cmp(&b.message)));
+
+        Baseline {
+            version: BASELINE_VERSION,
+            entries,
+        }
+    }
+
+    /// Reads the baseline at `path`, or `None` if there isn't one.
+    pub fn load(path: &Path) -> Result<Option<Baseline>> {
+        let contents = match archive::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(anyhow!("error reading baseline {:?}: {}", path, e)),
+        };
+        let baseline: Baseline = serde_json::from_str(&contents).map_err(|e| anyhow!("error parsing baseline {:?}: {}", path, e))?;
+        if baseline.version != BASELINE_VERSION {
+            return Err(anyhow!("unsupported baseline version {} in {:?}", baseline.version, path));
+        }
+        Ok(Some(baseline))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut contents = serde_json::to_string_pretty(self)?;
+        contents.push('\n');
+        fs::write(path, contents).map_err(|e| anyhow!("error writing baseline {:?}: {}", path, e))
+    }
+
+    /// Removes the diagnostics of `file` (relative to the pack root) that are in the baseline. With
+    /// `report_fixed`, also adds one for every entry of the file that didn't match any diagnostic as it
+    /// has been fixed since, which is only right if `diagnostics` are of the whole pack. Every entry
+    /// matches at most one diagnostic, so new occurrences of a baselined diagnostic on identical lines
+    /// are still reported.
+    pub fn apply(&self, file: &str, diagnostics: &mut Vec<Diagnostic>, source: &str, report_fixed: bool) {
+        let mut remaining: HashMap<(String, String), Vec<&BaselineEntry>> = HashMap::new();
+        for entry in self.entries.iter().filter(|entry| entry.file == file) {
+            remaining
+                .entry((entry.message.clone(), entry.fingerprint.clone()))
+                .or_default()
+                .push(entry);
+        }
+        if remaining.is_empty() {
+            return;
+        }
+
+        diagnostics.retain(|diagnostic| {
+            let key = (diagnostic.message.clone(), fingerprint(line(source, diagnostic.range.start.line)));
+            match remaining.get_mut(&key) {
+                Some(entries) if !entries.is_empty() => {
+                    entries.pop();
+                    false
+                }
+                _ => true,
+            }
+        });
+        if !report_fixed {
+            return;
+        }
+
+        let line_count = source.lines().count().max(1) as u32;
+        let mut fixed: Vec<&BaselineEntry> = remaining.into_values().flatten().collect();
+        fixed.sort_by(|a, b| a.line.cmp(&b.line).then(a.message.cmp(&b.message)));
+        diagnostics.extend(fixed.into_iter().map(|entry| fixed_entry_diagnostic(entry, line_count)));
+    }
+}
+
+fn fixed_entry_diagnostic(entry: &BaselineEntry, line_count: u32) -> Diagnostic {
+    let line = entry.line.saturating_sub(1).min(line_count - 1);
+    Diagnostic {
+        range: Range::new(Position::new(line, 0), Position::new(line, 1000)),
+        severity: Some(DiagnosticSeverity::INFORMATION),
+        code: Some(NumberOrString::String(FIXED_ENTRY_CODE.into())),
+        source: Some(consts::SOURCE.into()),
+        message: format!("fixed, remove from the baseline: {}", entry.message),
+        ..Diagnostic::default()
+    }
+}
+
+pub fn is_fixed_entry(diagnostic: &Diagnostic) -> bool {
+    diagnostic.code == Some(NumberOrString::String(FIXED_ENTRY_CODE.into()))
+}
+
+fn line(source: &str, line: u32) -> &str {
+    source.lines().nth(line as usize).unwrap_or_default()
+}
+
+/// Fingerprint of the content of a line, ignoring differences in whitespace.
+pub fn fingerprint(line: &str) -> String {
+    let mut hasher = Fnv1a::default();
+    for (i, word) in line.split_whitespace().enumerate() {
+        if i > 0 {
+            hasher.write(b" ");
+        }
+        hasher.write(word.as_bytes());
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod baseline_test {
+    use rust_lsp::lsp_types::{Diagnostic, Position, Range};
+
+    use crate::baseline::{fingerprint, is_fixed_entry, Baseline};
+
+    fn diagnostic(line: u32, message: &str) -> Diagnostic {
+        Diagnostic {
+            range: Range::new(Position::new(line, 0), Position::new(line, 1000)),
+            message: message.into(),
+            ..Diagnostic::default()
+        }
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_fingerprint() {
+        assert_eq!(fingerprint("  float x = y;"), fingerprint("float x  =\ty;  "));
+        assert_ne!(fingerprint("float x = y;"), fingerprint("float x = z;"));
+        assert_ne!(fingerprint("ab"), fingerprint("a b"));
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_apply() {
+        let source = "void main() {\n    float x = y;\n    float z = w;\n}";
+        let diagnostics = [diagnostic(1, "undeclared identifier"), diagnostic(2, "undeclared identifier")];
+        let baseline = Baseline::new(vec![("shaders/final.fsh".to_string(), &diagnostics[..], source)]);
+        assert_eq!(baseline.entries.len(), 2);
+        assert_eq!(baseline.entries[0].line, 2);
+
+        // lines moved by an unrelated edit
+        let edited = "#include \"/lib/common.glsl\"\nvoid main() {\n    float x = y;\n    float z = w;\n}";
+        let mut current = vec![diagnostic(2, "undeclared identifier"), diagnostic(3, "undeclared identifier")];
+        baseline.apply("shaders/final.fsh", &mut current, edited, true);
+        assert!(current.is_empty());
+
+        // a new occurrence on a different line, and one of the baselined ones fixed
+        let edited = "void main() {\n    float x = y;\n    float v = u;\n}";
+        let mut current = vec![diagnostic(1, "undeclared identifier"), diagnostic(2, "undeclared identifier")];
+        baseline.apply("shaders/final.fsh", &mut current, edited, true);
+        assert_eq!(current.len(), 2);
+        assert_eq!(current[0].range.start.line, 2);
+        assert!(!is_fixed_entry(&current[0]));
+        assert!(is_fixed_entry(&current[1]));
+        assert_eq!(current[1].range.start.line, 2);
+
+        // only the lint of the whole pack knows what's fixed
+        let mut current = vec![diagnostic(1, "undeclared identifier"), diagnostic(2, "undeclared identifier")];
+        baseline.apply("shaders/final.fsh", &mut current, edited, false);
+        assert_eq!(current.len(), 1);
+        assert!(!is_fixed_entry(&current[0]));
+
+        // other files are left alone
+        let mut current = vec![diagnostic(1, "undeclared identifier")];
+        baseline.apply("shaders/composite.fsh", &mut current, source, true);
+        assert_eq!(current.len(), 1);
+    }
+}
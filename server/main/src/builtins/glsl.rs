@@ -0,0 +1,1830 @@
+use crate::TreeType;
+
+use super::{BuiltinFunction, BuiltinVariable};
+
+const ALL: &[TreeType] = &[];
+const VERTEX: &[TreeType] = &[TreeType::Vertex];
+const FRAGMENT: &[TreeType] = &[TreeType::Fragment];
+const GEOMETRY: &[TreeType] = &[TreeType::Geometry];
+const COMPUTE: &[TreeType] = &[TreeType::Compute];
+
+pub static FUNCTIONS: &[BuiltinFunction] = &[
+    // angle and trigonometry functions
+    BuiltinFunction {
+        name: "radians",
+        signatures: &["genType radians(genType degrees)"],
+        description: "Converts `degrees` to radians, i.e. `(π / 180) * degrees`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "degrees",
+        signatures: &["genType degrees(genType radians)"],
+        description: "Converts `radians` to degrees, i.e. `(180 / π) * radians`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "sin",
+        signatures: &["genType sin(genType angle)"],
+        description: "The standard trigonometric sine function.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "cos",
+        signatures: &["genType cos(genType angle)"],
+        description: "The standard trigonometric cosine function.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "tan",
+        signatures: &["genType tan(genType angle)"],
+        description: "The standard trigonometric tangent.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "asin",
+        signatures: &["genType asin(genType x)"],
+        description: "Arc sine. Returns an angle whose sine is `x`, in the range [-π/2, π/2]. Results are undefined if `|x| > 1`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "acos",
+        signatures: &["genType acos(genType x)"],
+        description: "Arc cosine. Returns an angle whose cosine is `x`, in the range [0, π]. Results are undefined if `|x| > 1`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "atan",
+        signatures: &["genType atan(genType y, genType x)", "genType atan(genType y_over_x)"],
+        description: "Arc tangent. With two arguments, the signs of `x` and `y` determine the quadrant and the result is in [-π, π]. With one argument the result is in [-π/2, π/2].",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "sinh",
+        signatures: &["genType sinh(genType x)"],
+        description: "Returns the hyperbolic sine, `(e^x - e^-x) / 2`.",
+        since: 130,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "cosh",
+        signatures: &["genType cosh(genType x)"],
+        description: "Returns the hyperbolic cosine, `(e^x + e^-x) / 2`.",
+        since: 130,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "tanh",
+        signatures: &["genType tanh(genType x)"],
+        description: "Returns the hyperbolic tangent, `sinh(x) / cosh(x)`.",
+        since: 130,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "asinh",
+        signatures: &["genType asinh(genType x)"],
+        description: "Arc hyperbolic sine; returns the inverse of `sinh`.",
+        since: 130,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "acosh",
+        signatures: &["genType acosh(genType x)"],
+        description: "Arc hyperbolic cosine; returns the non-negative inverse of `cosh`. Results are undefined if `x < 1`.",
+        since: 130,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "atanh",
+        signatures: &["genType atanh(genType x)"],
+        description: "Arc hyperbolic tangent; returns the inverse of `tanh`. Results are undefined if `|x| >= 1`.",
+        since: 130,
+        removed: None,
+        stages: ALL,
+    },
+    // exponential functions
+    BuiltinFunction {
+        name: "pow",
+        signatures: &["genType pow(genType x, genType y)"],
+        description: "Returns `x` raised to the `y` power. Results are undefined if `x < 0`, or if `x == 0` and `y <= 0`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "exp",
+        signatures: &["genType exp(genType x)"],
+        description: "Returns the natural exponentiation of `x`, i.e. e^x.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "log",
+        signatures: &["genType log(genType x)"],
+        description: "Returns the natural logarithm of `x`. Results are undefined if `x <= 0`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "exp2",
+        signatures: &["genType exp2(genType x)"],
+        description: "Returns 2 raised to the `x` power.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "log2",
+        signatures: &["genType log2(genType x)"],
+        description: "Returns the base 2 logarithm of `x`. Results are undefined if `x <= 0`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "sqrt",
+        signatures: &["genType sqrt(genType x)", "genDType sqrt(genDType x)"],
+        description: "Returns the square root of `x`. Results are undefined if `x < 0`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "inversesqrt",
+        signatures: &["genType inversesqrt(genType x)", "genDType inversesqrt(genDType x)"],
+        description: "Returns `1 / sqrt(x)`. Results are undefined if `x <= 0`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    // common functions
+    BuiltinFunction {
+        name: "abs",
+        signatures: &["genType abs(genType x)", "genIType abs(genIType x)", "genDType abs(genDType x)"],
+        description: "Returns `x` if `x >= 0`, otherwise `-x`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "sign",
+        signatures: &["genType sign(genType x)", "genIType sign(genIType x)", "genDType sign(genDType x)"],
+        description: "Returns 1.0 if `x > 0`, 0.0 if `x == 0`, or -1.0 if `x < 0`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "floor",
+        signatures: &["genType floor(genType x)", "genDType floor(genDType x)"],
+        description: "Returns a value equal to the nearest integer that is less than or equal to `x`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "trunc",
+        signatures: &["genType trunc(genType x)", "genDType trunc(genDType x)"],
+        description: "Returns a value equal to the nearest integer to `x` whose absolute value is not larger than the absolute value of `x`.",
+        since: 130,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "round",
+        signatures: &["genType round(genType x)", "genDType round(genDType x)"],
+        description: "Returns a value equal to the nearest integer to `x`. Which way 0.5 rounds is implementation defined.",
+        since: 130,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "roundEven",
+        signatures: &["genType roundEven(genType x)", "genDType roundEven(genDType x)"],
+        description: "Returns a value equal to the nearest integer to `x`, rounding 0.5 towards the nearest even integer.",
+        since: 130,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "ceil",
+        signatures: &["genType ceil(genType x)", "genDType ceil(genDType x)"],
+        description: "Returns a value equal to the nearest integer that is greater than or equal to `x`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "fract",
+        signatures: &["genType fract(genType x)", "genDType fract(genDType x)"],
+        description: "Returns `x - floor(x)`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "mod",
+        signatures: &[
+            "genType mod(genType x, float y)",
+            "genType mod(genType x, genType y)",
+            "genDType mod(genDType x, double y)",
+            "genDType mod(genDType x, genDType y)",
+        ],
+        description: "Modulus. Returns `x - y * floor(x / y)`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "modf",
+        signatures: &["genType modf(genType x, out genType i)", "genDType modf(genDType x, out genDType i)"],
+        description: "Returns the fractional part of `x` and sets `i` to the integer part (as a whole number floating-point value).",
+        since: 130,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "min",
+        signatures: &[
+            "genType min(genType x, genType y)",
+            "genType min(genType x, float y)",
+            "genIType min(genIType x, genIType y)",
+            "genIType min(genIType x, int y)",
+            "genUType min(genUType x, genUType y)",
+            "genUType min(genUType x, uint y)",
+        ],
+        description: "Returns `y` if `y < x`, otherwise `x`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "max",
+        signatures: &[
+            "genType max(genType x, genType y)",
+            "genType max(genType x, float y)",
+            "genIType max(genIType x, genIType y)",
+            "genIType max(genIType x, int y)",
+            "genUType max(genUType x, genUType y)",
+            "genUType max(genUType x, uint y)",
+        ],
+        description: "Returns `y` if `x < y`, otherwise `x`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "clamp",
+        signatures: &[
+            "genType clamp(genType x, genType minVal, genType maxVal)",
+            "genType clamp(genType x, float minVal, float maxVal)",
+            "genIType clamp(genIType x, genIType minVal, genIType maxVal)",
+            "genIType clamp(genIType x, int minVal, int maxVal)",
+            "genUType clamp(genUType x, genUType minVal, genUType maxVal)",
+            "genUType clamp(genUType x, uint minVal, uint maxVal)",
+        ],
+        description: "Returns `min(max(x, minVal), maxVal)`. Results are undefined if `minVal > maxVal`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "mix",
+        signatures: &[
+            "genType mix(genType x, genType y, genType a)",
+            "genType mix(genType x, genType y, float a)",
+            "genType mix(genType x, genType y, genBType a)",
+            "genDType mix(genDType x, genDType y, genDType a)",
+        ],
+        description: "Returns the linear blend of `x` and `y`, i.e. `x * (1 - a) + y * a`. With a boolean `a`, selects `y` where `a` is true and `x` otherwise.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "step",
+        signatures: &["genType step(genType edge, genType x)", "genType step(float edge, genType x)"],
+        description: "Returns 0.0 if `x < edge`, otherwise 1.0.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "smoothstep",
+        signatures: &[
+            "genType smoothstep(genType edge0, genType edge1, genType x)",
+            "genType smoothstep(float edge0, float edge1, genType x)",
+        ],
+        description: "Returns 0.0 if `x <= edge0` and 1.0 if `x >= edge1`, and performs smooth Hermite interpolation between 0 and 1 when `edge0 < x < edge1`. Results are undefined if `edge0 >= edge1`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "isnan",
+        signatures: &["genBType isnan(genType x)", "genBType isnan(genDType x)"],
+        description: "Returns true if `x` holds a NaN.",
+        since: 130,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "isinf",
+        signatures: &["genBType isinf(genType x)", "genBType isinf(genDType x)"],
+        description: "Returns true if `x` holds a positive or negative infinity.",
+        since: 130,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "floatBitsToInt",
+        signatures: &["genIType floatBitsToInt(genType value)"],
+        description: "Returns a signed integer value representing the encoding of a floating-point value.",
+        since: 330,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "floatBitsToUint",
+        signatures: &["genUType floatBitsToUint(genType value)"],
+        description: "Returns an unsigned integer value representing the encoding of a floating-point value.",
+        since: 330,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "intBitsToFloat",
+        signatures: &["genType intBitsToFloat(genIType value)"],
+        description: "Returns a floating-point value corresponding to a signed integer encoding of a floating-point value.",
+        since: 330,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "uintBitsToFloat",
+        signatures: &["genType uintBitsToFloat(genUType value)"],
+        description: "Returns a floating-point value corresponding to an unsigned integer encoding of a floating-point value.",
+        since: 330,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "fma",
+        signatures: &["genType fma(genType a, genType b, genType c)", "genDType fma(genDType a, genDType b, genDType c)"],
+        description: "Computes and returns `a * b + c`, treated as a single operation.",
+        since: 400,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "frexp",
+        signatures: &["genType frexp(genType x, out genIType exp)", "genDType frexp(genDType x, out genIType exp)"],
+        description: "Splits `x` into a floating-point significand in the range [0.5, 1.0) and an integral exponent of two.",
+        since: 400,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "ldexp",
+        signatures: &["genType ldexp(genType x, in genIType exp)", "genDType ldexp(genDType x, in genIType exp)"],
+        description: "Builds a floating-point number from `x` and the corresponding integral exponent of two in `exp`, i.e. `x * 2^exp`.",
+        since: 400,
+        removed: None,
+        stages: ALL,
+    },
+    // floating-point pack and unpack functions
+    BuiltinFunction {
+        name: "packUnorm2x16",
+        signatures: &["uint packUnorm2x16(vec2 v)"],
+        description: "Converts each component of the normalized floating-point value `v` into 16-bit integer values and packs them into a 32-bit unsigned integer.",
+        since: 410,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "packSnorm2x16",
+        signatures: &["uint packSnorm2x16(vec2 v)"],
+        description: "Converts each component of the signed normalized floating-point value `v` into 16-bit integer values and packs them into a 32-bit unsigned integer.",
+        since: 420,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "packUnorm4x8",
+        signatures: &["uint packUnorm4x8(vec4 v)"],
+        description: "Converts each component of the normalized floating-point value `v` into 8-bit integer values and packs them into a 32-bit unsigned integer.",
+        since: 400,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "packSnorm4x8",
+        signatures: &["uint packSnorm4x8(vec4 v)"],
+        description: "Converts each component of the signed normalized floating-point value `v` into 8-bit integer values and packs them into a 32-bit unsigned integer.",
+        since: 400,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "unpackUnorm2x16",
+        signatures: &["vec2 unpackUnorm2x16(uint p)"],
+        description: "Unpacks a 32-bit unsigned integer into a pair of 16-bit unsigned integers and converts them to normalized floating-point values.",
+        since: 410,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "unpackSnorm2x16",
+        signatures: &["vec2 unpackSnorm2x16(uint p)"],
+        description: "Unpacks a 32-bit unsigned integer into a pair of 16-bit signed integers and converts them to normalized floating-point values.",
+        since: 420,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "unpackUnorm4x8",
+        signatures: &["vec4 unpackUnorm4x8(uint p)"],
+        description: "Unpacks a 32-bit unsigned integer into four 8-bit unsigned integers and converts them to normalized floating-point values.",
+        since: 400,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "unpackSnorm4x8",
+        signatures: &["vec4 unpackSnorm4x8(uint p)"],
+        description: "Unpacks a 32-bit unsigned integer into four 8-bit signed integers and converts them to normalized floating-point values.",
+        since: 400,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "packHalf2x16",
+        signatures: &["uint packHalf2x16(vec2 v)"],
+        description: "Converts the components of `v` to 16-bit floating-point values and packs them into a 32-bit unsigned integer.",
+        since: 420,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "unpackHalf2x16",
+        signatures: &["vec2 unpackHalf2x16(uint v)"],
+        description: "Unpacks a 32-bit unsigned integer into a pair of 16-bit floating-point values and converts them to 32-bit floating-point values.",
+        since: 420,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "packDouble2x32",
+        signatures: &["double packDouble2x32(uvec2 v)"],
+        description: "Packs the components of `v` into a 64-bit value.",
+        since: 400,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "unpackDouble2x32",
+        signatures: &["uvec2 unpackDouble2x32(double v)"],
+        description: "Unpacks `v` into a pair of 32-bit unsigned integers.",
+        since: 400,
+        removed: None,
+        stages: ALL,
+    },
+    // geometric functions
+    BuiltinFunction {
+        name: "length",
+        signatures: &["float length(genType x)", "double length(genDType x)"],
+        description: "Returns the length of vector `x`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "distance",
+        signatures: &["float distance(genType p0, genType p1)", "double distance(genDType p0, genDType p1)"],
+        description: "Returns the distance between `p0` and `p1`, i.e. `length(p0 - p1)`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "dot",
+        signatures: &["float dot(genType x, genType y)", "double dot(genDType x, genDType y)"],
+        description: "Returns the dot product of `x` and `y`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "cross",
+        signatures: &["vec3 cross(vec3 x, vec3 y)", "dvec3 cross(dvec3 x, dvec3 y)"],
+        description: "Returns the cross product of `x` and `y`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "normalize",
+        signatures: &["genType normalize(genType x)", "genDType normalize(genDType x)"],
+        description: "Returns a vector in the same direction as `x` but with a length of 1.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "ftransform",
+        signatures: &["vec4 ftransform()"],
+        description: "Returns the vertex position transformed exactly the way the fixed-function pipeline would, i.e. `gl_ModelViewProjectionMatrix * gl_Vertex` with invariance guarantees.",
+        since: 110,
+        removed: Some(140),
+        stages: VERTEX,
+    },
+    BuiltinFunction {
+        name: "faceforward",
+        signatures: &["genType faceforward(genType N, genType I, genType Nref)", "genDType faceforward(genDType N, genDType I, genDType Nref)"],
+        description: "Returns `N` if `dot(Nref, I) < 0`, otherwise `-N`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "reflect",
+        signatures: &["genType reflect(genType I, genType N)", "genDType reflect(genDType I, genDType N)"],
+        description: "For the incident vector `I` and surface orientation `N`, returns the reflection direction `I - 2 * dot(N, I) * N`. `N` should be normalized.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "refract",
+        signatures: &["genType refract(genType I, genType N, float eta)", "genDType refract(genDType I, genDType N, double eta)"],
+        description: "For the incident vector `I`, surface normal `N` and ratio of indices of refraction `eta`, returns the refraction vector. `I` and `N` should be normalized.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    // matrix functions
+    BuiltinFunction {
+        name: "matrixCompMult",
+        signatures: &["mat matrixCompMult(mat x, mat y)"],
+        description: "Multiplies matrix `x` by matrix `y` component-wise. Use the `*` operator for linear algebraic matrix multiplication.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "outerProduct",
+        signatures: &["mat2 outerProduct(vec2 c, vec2 r)", "mat3 outerProduct(vec3 c, vec3 r)", "mat4 outerProduct(vec4 c, vec4 r)"],
+        description: "Treats `c` as a column vector and `r` as a row vector and does a linear algebraic matrix multiply `c * r`.",
+        since: 120,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "transpose",
+        signatures: &["mat2 transpose(mat2 m)", "mat3 transpose(mat3 m)", "mat4 transpose(mat4 m)"],
+        description: "Returns a matrix that is the transpose of `m`.",
+        since: 120,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "determinant",
+        signatures: &["float determinant(mat2 m)", "float determinant(mat3 m)", "float determinant(mat4 m)"],
+        description: "Returns the determinant of `m`.",
+        since: 150,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "inverse",
+        signatures: &["mat2 inverse(mat2 m)", "mat3 inverse(mat3 m)", "mat4 inverse(mat4 m)"],
+        description: "Returns a matrix that is the inverse of `m`. Results are undefined if `m` is singular or poorly conditioned.",
+        since: 140,
+        removed: None,
+        stages: ALL,
+    },
+    // vector relational functions
+    BuiltinFunction {
+        name: "lessThan",
+        signatures: &["bvec lessThan(vec x, vec y)", "bvec lessThan(ivec x, ivec y)", "bvec lessThan(uvec x, uvec y)"],
+        description: "Returns the component-wise compare of `x < y`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "lessThanEqual",
+        signatures: &["bvec lessThanEqual(vec x, vec y)", "bvec lessThanEqual(ivec x, ivec y)", "bvec lessThanEqual(uvec x, uvec y)"],
+        description: "Returns the component-wise compare of `x <= y`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "greaterThan",
+        signatures: &["bvec greaterThan(vec x, vec y)", "bvec greaterThan(ivec x, ivec y)", "bvec greaterThan(uvec x, uvec y)"],
+        description: "Returns the component-wise compare of `x > y`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "greaterThanEqual",
+        signatures: &["bvec greaterThanEqual(vec x, vec y)", "bvec greaterThanEqual(ivec x, ivec y)", "bvec greaterThanEqual(uvec x, uvec y)"],
+        description: "Returns the component-wise compare of `x >= y`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "equal",
+        signatures: &["bvec equal(vec x, vec y)", "bvec equal(ivec x, ivec y)", "bvec equal(uvec x, uvec y)", "bvec equal(bvec x, bvec y)"],
+        description: "Returns the component-wise compare of `x == y`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "notEqual",
+        signatures: &["bvec notEqual(vec x, vec y)", "bvec notEqual(ivec x, ivec y)", "bvec notEqual(uvec x, uvec y)", "bvec notEqual(bvec x, bvec y)"],
+        description: "Returns the component-wise compare of `x != y`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "any",
+        signatures: &["bool any(bvec x)"],
+        description: "Returns true if any component of `x` is true.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "all",
+        signatures: &["bool all(bvec x)"],
+        description: "Returns true only if all components of `x` are true.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "not",
+        signatures: &["bvec not(bvec x)"],
+        description: "Returns the component-wise logical complement of `x`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+    // integer functions
+    BuiltinFunction {
+        name: "uaddCarry",
+        signatures: &["genUType uaddCarry(genUType x, genUType y, out genUType carry)"],
+        description: "Adds 32-bit unsigned integers `x` and `y`, returning the sum modulo 2^32. `carry` is set to 0 if the sum was less than 2^32, otherwise 1.",
+        since: 400,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "usubBorrow",
+        signatures: &["genUType usubBorrow(genUType x, genUType y, out genUType borrow)"],
+        description: "Subtracts the 32-bit unsigned integer `y` from `x`, returning the difference if non-negative, otherwise 2^32 plus the difference. `borrow` is set to 0 if `x >= y`, otherwise 1.",
+        since: 400,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "umulExtended",
+        signatures: &["void umulExtended(genUType x, genUType y, out genUType msb, out genUType lsb)"],
+        description: "Multiplies 32-bit unsigned integers `x` and `y`, producing a 64-bit result split into `msb` and `lsb`.",
+        since: 400,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "imulExtended",
+        signatures: &["void imulExtended(genIType x, genIType y, out genIType msb, out genIType lsb)"],
+        description: "Multiplies 32-bit signed integers `x` and `y`, producing a 64-bit result split into `msb` and `lsb`.",
+        since: 400,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "bitfieldExtract",
+        signatures: &[
+            "genIType bitfieldExtract(genIType value, int offset, int bits)",
+            "genUType bitfieldExtract(genUType value, int offset, int bits)",
+        ],
+        description: "Extracts bits `[offset, offset + bits - 1]` from `value`, returning them in the least significant bits of the result.",
+        since: 400,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "bitfieldInsert",
+        signatures: &[
+            "genIType bitfieldInsert(genIType base, genIType insert, int offset, int bits)",
+            "genUType bitfieldInsert(genUType base, genUType insert, int offset, int bits)",
+        ],
+        description: "Inserts the `bits` least significant bits of `insert` into `base` at `offset`.",
+        since: 400,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "bitfieldReverse",
+        signatures: &["genIType bitfieldReverse(genIType value)", "genUType bitfieldReverse(genUType value)"],
+        description: "Reverses the bits of `value`.",
+        since: 400,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "bitCount",
+        signatures: &["genIType bitCount(genIType value)", "genIType bitCount(genUType value)"],
+        description: "Returns the number of one bits in the binary representation of `value`.",
+        since: 400,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "findLSB",
+        signatures: &["genIType findLSB(genIType value)", "genIType findLSB(genUType value)"],
+        description: "Returns the bit number of the least significant one bit in `value`, or -1 if `value` is zero.",
+        since: 400,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "findMSB",
+        signatures: &["genIType findMSB(genIType value)", "genIType findMSB(genUType value)"],
+        description: "Returns the bit number of the most significant bit in `value`. For positive integers this is the most significant one bit, for negative integers the most significant zero bit. Returns -1 for 0 and -1.",
+        since: 400,
+        removed: None,
+        stages: ALL,
+    },
+    // texture functions
+    BuiltinFunction {
+        name: "textureSize",
+        signatures: &[
+            "int textureSize(gsampler1D sampler, int lod)",
+            "ivec2 textureSize(gsampler2D sampler, int lod)",
+            "ivec3 textureSize(gsampler3D sampler, int lod)",
+            "ivec2 textureSize(gsamplerCube sampler, int lod)",
+            "ivec2 textureSize(sampler2DShadow sampler, int lod)",
+            "ivec3 textureSize(gsampler2DArray sampler, int lod)",
+            "ivec2 textureSize(gsampler2DRect sampler)",
+            "int textureSize(gsamplerBuffer sampler)",
+            "ivec2 textureSize(gsampler2DMS sampler)",
+        ],
+        description: "Returns the dimensions of level `lod` (if present) of the texture bound to `sampler`.",
+        since: 130,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "textureQueryLod",
+        signatures: &[
+            "vec2 textureQueryLod(gsampler1D sampler, float P)",
+            "vec2 textureQueryLod(gsampler2D sampler, vec2 P)",
+            "vec2 textureQueryLod(gsampler3D sampler, vec3 P)",
+            "vec2 textureQueryLod(gsamplerCube sampler, vec3 P)",
+        ],
+        description: "Returns the mipmap array(s) that would be accessed in the x component of the return value, and the computed level of detail relative to the base level in the y component.",
+        since: 400,
+        removed: None,
+        stages: FRAGMENT,
+    },
+    BuiltinFunction {
+        name: "textureQueryLevels",
+        signatures: &[
+            "int textureQueryLevels(gsampler1D sampler)",
+            "int textureQueryLevels(gsampler2D sampler)",
+            "int textureQueryLevels(gsampler3D sampler)",
+            "int textureQueryLevels(gsamplerCube sampler)",
+        ],
+        description: "Returns the number of mipmap levels accessible in the texture associated with `sampler`.",
+        since: 430,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "texture",
+        signatures: &[
+            "gvec4 texture(gsampler1D sampler, float P [, float bias])",
+            "gvec4 texture(gsampler2D sampler, vec2 P [, float bias])",
+            "gvec4 texture(gsampler3D sampler, vec3 P [, float bias])",
+            "gvec4 texture(gsamplerCube sampler, vec3 P [, float bias])",
+            "float texture(sampler2DShadow sampler, vec3 P [, float bias])",
+            "float texture(samplerCubeShadow sampler, vec4 P [, float bias])",
+            "gvec4 texture(gsampler2DArray sampler, vec3 P [, float bias])",
+            "gvec4 texture(gsampler2DRect sampler, vec2 P)",
+        ],
+        description: "Uses the texture coordinate `P` to do a texture lookup in the texture currently bound to `sampler`. For shadow forms, the last component of `P` is used as the depth reference value.",
+        since: 130,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "textureProj",
+        signatures: &[
+            "gvec4 textureProj(gsampler1D sampler, vec2 P [, float bias])",
+            "gvec4 textureProj(gsampler2D sampler, vec3 P [, float bias])",
+            "gvec4 textureProj(gsampler2D sampler, vec4 P [, float bias])",
+            "gvec4 textureProj(gsampler3D sampler, vec4 P [, float bias])",
+            "float textureProj(sampler2DShadow sampler, vec4 P [, float bias])",
+        ],
+        description: "Does a texture lookup with projection. The texture coordinates consumed from `P`, not including the last component, are divided by the last component of `P`.",
+        since: 130,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "textureLod",
+        signatures: &[
+            "gvec4 textureLod(gsampler1D sampler, float P, float lod)",
+            "gvec4 textureLod(gsampler2D sampler, vec2 P, float lod)",
+            "gvec4 textureLod(gsampler3D sampler, vec3 P, float lod)",
+            "gvec4 textureLod(gsamplerCube sampler, vec3 P, float lod)",
+            "float textureLod(sampler2DShadow sampler, vec3 P, float lod)",
+            "gvec4 textureLod(gsampler2DArray sampler, vec3 P, float lod)",
+        ],
+        description: "Does a texture lookup as in `texture` but with an explicit level of detail `lod`.",
+        since: 130,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "textureOffset",
+        signatures: &[
+            "gvec4 textureOffset(gsampler1D sampler, float P, int offset [, float bias])",
+            "gvec4 textureOffset(gsampler2D sampler, vec2 P, ivec2 offset [, float bias])",
+            "gvec4 textureOffset(gsampler3D sampler, vec3 P, ivec3 offset [, float bias])",
+            "float textureOffset(sampler2DShadow sampler, vec3 P, ivec2 offset [, float bias])",
+        ],
+        description: "Does a texture lookup as in `texture` but with `offset` added to the (u, v, w) texel coordinates before looking up each texel.",
+        since: 130,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "texelFetch",
+        signatures: &[
+            "gvec4 texelFetch(gsampler1D sampler, int P, int lod)",
+            "gvec4 texelFetch(gsampler2D sampler, ivec2 P, int lod)",
+            "gvec4 texelFetch(gsampler3D sampler, ivec3 P, int lod)",
+            "gvec4 texelFetch(gsampler2DRect sampler, ivec2 P)",
+            "gvec4 texelFetch(gsampler2DArray sampler, ivec3 P, int lod)",
+            "gvec4 texelFetch(gsamplerBuffer sampler, int P)",
+            "gvec4 texelFetch(gsampler2DMS sampler, ivec2 P, int sample)",
+        ],
+        description: "Uses the integer texture coordinate `P` to look up a single texel from `sampler`. The array layer comes from the last component of `P` for array forms.",
+        since: 130,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "texelFetchOffset",
+        signatures: &[
+            "gvec4 texelFetchOffset(gsampler1D sampler, int P, int lod, int offset)",
+            "gvec4 texelFetchOffset(gsampler2D sampler, ivec2 P, int lod, ivec2 offset)",
+            "gvec4 texelFetchOffset(gsampler3D sampler, ivec3 P, int lod, ivec3 offset)",
+        ],
+        description: "Fetches a single texel as in `texelFetch`, offset by `offset`.",
+        since: 130,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "textureProjOffset",
+        signatures: &[
+            "gvec4 textureProjOffset(gsampler2D sampler, vec3 P, ivec2 offset [, float bias])",
+            "gvec4 textureProjOffset(gsampler2D sampler, vec4 P, ivec2 offset [, float bias])",
+        ],
+        description: "Does a projective texture lookup as described in `textureProj`, offset by `offset`.",
+        since: 130,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "textureLodOffset",
+        signatures: &[
+            "gvec4 textureLodOffset(gsampler2D sampler, vec2 P, float lod, ivec2 offset)",
+            "gvec4 textureLodOffset(gsampler3D sampler, vec3 P, float lod, ivec3 offset)",
+        ],
+        description: "Does an offset texture lookup with explicit level of detail.",
+        since: 130,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "textureProjLod",
+        signatures: &[
+            "gvec4 textureProjLod(gsampler2D sampler, vec3 P, float lod)",
+            "gvec4 textureProjLod(gsampler2D sampler, vec4 P, float lod)",
+        ],
+        description: "Does a projective texture lookup with explicit level of detail.",
+        since: 130,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "textureGrad",
+        signatures: &[
+            "gvec4 textureGrad(gsampler2D sampler, vec2 P, vec2 dPdx, vec2 dPdy)",
+            "gvec4 textureGrad(gsampler3D sampler, vec3 P, vec3 dPdx, vec3 dPdy)",
+            "gvec4 textureGrad(gsamplerCube sampler, vec3 P, vec3 dPdx, vec3 dPdy)",
+            "float textureGrad(sampler2DShadow sampler, vec3 P, vec2 dPdx, vec2 dPdy)",
+        ],
+        description: "Does a texture lookup as in `texture` but with explicit gradients `dPdx` and `dPdy`.",
+        since: 130,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "textureGradOffset",
+        signatures: &["gvec4 textureGradOffset(gsampler2D sampler, vec2 P, vec2 dPdx, vec2 dPdy, ivec2 offset)"],
+        description: "Does a texture lookup with both explicit gradients and offset.",
+        since: 130,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "textureGather",
+        signatures: &[
+            "gvec4 textureGather(gsampler2D sampler, vec2 P [, int comp])",
+            "gvec4 textureGather(gsampler2DArray sampler, vec3 P [, int comp])",
+            "gvec4 textureGather(gsamplerCube sampler, vec3 P [, int comp])",
+            "vec4 textureGather(sampler2DShadow sampler, vec2 P, float refZ)",
+        ],
+        description: "Returns the value `vec4(Sample_i0_j1(P, base).comp, Sample_i1_j1(P, base).comp, Sample_i1_j0(P, base).comp, Sample_i0_j0(P, base).comp)`, i.e. the four texels that would be used for bilinear filtering.",
+        since: 400,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "textureGatherOffset",
+        signatures: &["gvec4 textureGatherOffset(gsampler2D sampler, vec2 P, ivec2 offset [, int comp])"],
+        description: "Performs a texture gather operation as in `textureGather` offset by `offset`.",
+        since: 400,
+        removed: None,
+        stages: ALL,
+    },
+    // deprecated texture functions
+    BuiltinFunction {
+        name: "texture1D",
+        signatures: &["vec4 texture1D(sampler1D sampler, float coord [, float bias])"],
+        description: "Deprecated 1D texture lookup. Use `texture` instead.",
+        since: 110,
+        removed: Some(140),
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "texture2D",
+        signatures: &["vec4 texture2D(sampler2D sampler, vec2 coord [, float bias])"],
+        description: "Deprecated 2D texture lookup. Use `texture` instead.",
+        since: 110,
+        removed: Some(140),
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "texture2DLod",
+        signatures: &["vec4 texture2DLod(sampler2D sampler, vec2 coord, float lod)"],
+        description: "Deprecated 2D texture lookup with explicit level of detail. Use `textureLod` instead.",
+        since: 110,
+        removed: Some(140),
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "texture2DProj",
+        signatures: &["vec4 texture2DProj(sampler2D sampler, vec3 coord [, float bias])", "vec4 texture2DProj(sampler2D sampler, vec4 coord [, float bias])"],
+        description: "Deprecated projective 2D texture lookup. Use `textureProj` instead.",
+        since: 110,
+        removed: Some(140),
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "texture3D",
+        signatures: &["vec4 texture3D(sampler3D sampler, vec3 coord [, float bias])"],
+        description: "Deprecated 3D texture lookup. Use `texture` instead.",
+        since: 110,
+        removed: Some(140),
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "textureCube",
+        signatures: &["vec4 textureCube(samplerCube sampler, vec3 coord [, float bias])"],
+        description: "Deprecated cube map texture lookup. Use `texture` instead.",
+        since: 110,
+        removed: Some(140),
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "shadow2D",
+        signatures: &["vec4 shadow2D(sampler2DShadow sampler, vec3 coord [, float bias])"],
+        description: "Deprecated depth comparison lookup. The third component of `coord` is used as the depth reference value. Use `texture` instead.",
+        since: 110,
+        removed: Some(140),
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "shadow2DLod",
+        signatures: &["vec4 shadow2DLod(sampler2DShadow sampler, vec3 coord, float lod)"],
+        description: "Deprecated depth comparison lookup with explicit level of detail. Use `textureLod` instead.",
+        since: 110,
+        removed: Some(140),
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "shadow2DProj",
+        signatures: &["vec4 shadow2DProj(sampler2DShadow sampler, vec4 coord [, float bias])"],
+        description: "Deprecated projective depth comparison lookup. Use `textureProj` instead.",
+        since: 110,
+        removed: Some(140),
+        stages: ALL,
+    },
+    // fragment processing functions
+    BuiltinFunction {
+        name: "dFdx",
+        signatures: &["genType dFdx(genType p)"],
+        description: "Returns the partial derivative of `p` with respect to the window x coordinate.",
+        since: 110,
+        removed: None,
+        stages: FRAGMENT,
+    },
+    BuiltinFunction {
+        name: "dFdy",
+        signatures: &["genType dFdy(genType p)"],
+        description: "Returns the partial derivative of `p` with respect to the window y coordinate.",
+        since: 110,
+        removed: None,
+        stages: FRAGMENT,
+    },
+    BuiltinFunction {
+        name: "fwidth",
+        signatures: &["genType fwidth(genType p)"],
+        description: "Returns the sum of the absolute derivatives in x and y, i.e. `abs(dFdx(p)) + abs(dFdy(p))`.",
+        since: 110,
+        removed: None,
+        stages: FRAGMENT,
+    },
+    BuiltinFunction {
+        name: "dFdxFine",
+        signatures: &["genType dFdxFine(genType p)"],
+        description: "Returns the partial derivative of `p` with respect to the window x coordinate, computed using local differencing.",
+        since: 450,
+        removed: None,
+        stages: FRAGMENT,
+    },
+    BuiltinFunction {
+        name: "dFdyFine",
+        signatures: &["genType dFdyFine(genType p)"],
+        description: "Returns the partial derivative of `p` with respect to the window y coordinate, computed using local differencing.",
+        since: 450,
+        removed: None,
+        stages: FRAGMENT,
+    },
+    BuiltinFunction {
+        name: "dFdxCoarse",
+        signatures: &["genType dFdxCoarse(genType p)"],
+        description: "Returns the partial derivative of `p` with respect to the window x coordinate, possibly shared between neighbouring fragments.",
+        since: 450,
+        removed: None,
+        stages: FRAGMENT,
+    },
+    BuiltinFunction {
+        name: "dFdyCoarse",
+        signatures: &["genType dFdyCoarse(genType p)"],
+        description: "Returns the partial derivative of `p` with respect to the window y coordinate, possibly shared between neighbouring fragments.",
+        since: 450,
+        removed: None,
+        stages: FRAGMENT,
+    },
+    BuiltinFunction {
+        name: "interpolateAtCentroid",
+        signatures: &["float interpolateAtCentroid(float interpolant)", "vec2 interpolateAtCentroid(vec2 interpolant)", "vec3 interpolateAtCentroid(vec3 interpolant)", "vec4 interpolateAtCentroid(vec4 interpolant)"],
+        description: "Returns the value of the input `interpolant` sampled at a location inside both the pixel and the primitive being processed.",
+        since: 400,
+        removed: None,
+        stages: FRAGMENT,
+    },
+    BuiltinFunction {
+        name: "interpolateAtSample",
+        signatures: &["float interpolateAtSample(float interpolant, int sample)", "vec4 interpolateAtSample(vec4 interpolant, int sample)"],
+        description: "Returns the value of the input `interpolant` at the location of sample number `sample`.",
+        since: 400,
+        removed: None,
+        stages: FRAGMENT,
+    },
+    BuiltinFunction {
+        name: "interpolateAtOffset",
+        signatures: &["float interpolateAtOffset(float interpolant, vec2 offset)", "vec4 interpolateAtOffset(vec4 interpolant, vec2 offset)"],
+        description: "Returns the value of the input `interpolant` sampled at an offset from the center of the pixel specified by `offset`.",
+        since: 400,
+        removed: None,
+        stages: FRAGMENT,
+    },
+    // noise functions
+    BuiltinFunction {
+        name: "noise1",
+        signatures: &["float noise1(genType x)"],
+        description: "Returns a 1D noise value based on `x`. Most drivers return 0.0; these functions are deprecated.",
+        since: 110,
+        removed: Some(440),
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "noise2",
+        signatures: &["vec2 noise2(genType x)"],
+        description: "Returns a 2D noise value based on `x`. Most drivers return 0.0; these functions are deprecated.",
+        since: 110,
+        removed: Some(440),
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "noise3",
+        signatures: &["vec3 noise3(genType x)"],
+        description: "Returns a 3D noise value based on `x`. Most drivers return 0.0; these functions are deprecated.",
+        since: 110,
+        removed: Some(440),
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "noise4",
+        signatures: &["vec4 noise4(genType x)"],
+        description: "Returns a 4D noise value based on `x`. Most drivers return 0.0; these functions are deprecated.",
+        since: 110,
+        removed: Some(440),
+        stages: ALL,
+    },
+    // geometry shader functions
+    BuiltinFunction {
+        name: "EmitVertex",
+        signatures: &["void EmitVertex()"],
+        description: "Emits the current values of output variables to the current output primitive.",
+        since: 150,
+        removed: None,
+        stages: GEOMETRY,
+    },
+    BuiltinFunction {
+        name: "EndPrimitive",
+        signatures: &["void EndPrimitive()"],
+        description: "Completes the current output primitive and starts a new one.",
+        since: 150,
+        removed: None,
+        stages: GEOMETRY,
+    },
+    BuiltinFunction {
+        name: "EmitStreamVertex",
+        signatures: &["void EmitStreamVertex(int stream)"],
+        description: "Emits the current values of output variables to the current output primitive on stream `stream`.",
+        since: 400,
+        removed: None,
+        stages: GEOMETRY,
+    },
+    BuiltinFunction {
+        name: "EndStreamPrimitive",
+        signatures: &["void EndStreamPrimitive(int stream)"],
+        description: "Completes the current output primitive on stream `stream` and starts a new one.",
+        since: 400,
+        removed: None,
+        stages: GEOMETRY,
+    },
+    // image functions
+    BuiltinFunction {
+        name: "imageSize",
+        signatures: &["int imageSize(gimage1D image)", "ivec2 imageSize(gimage2D image)", "ivec3 imageSize(gimage3D image)"],
+        description: "Returns the dimensions of the image bound to `image`.",
+        since: 430,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "imageLoad",
+        signatures: &["gvec4 imageLoad(gimage1D image, int P)", "gvec4 imageLoad(gimage2D image, ivec2 P)", "gvec4 imageLoad(gimage3D image, ivec3 P)"],
+        description: "Loads the texel at the coordinate `P` from the image unit `image`.",
+        since: 420,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "imageStore",
+        signatures: &[
+            "void imageStore(gimage1D image, int P, gvec4 data)",
+            "void imageStore(gimage2D image, ivec2 P, gvec4 data)",
+            "void imageStore(gimage3D image, ivec3 P, gvec4 data)",
+        ],
+        description: "Stores `data` into the texel at the coordinate `P` of the image bound to `image`.",
+        since: 420,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "imageAtomicAdd",
+        signatures: &["uint imageAtomicAdd(gimage2D image, ivec2 P, uint data)", "int imageAtomicAdd(gimage2D image, ivec2 P, int data)"],
+        description: "Atomically adds `data` to the value at `P` in `image`, returning the original value.",
+        since: 420,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "imageAtomicMin",
+        signatures: &["uint imageAtomicMin(gimage2D image, ivec2 P, uint data)", "int imageAtomicMin(gimage2D image, ivec2 P, int data)"],
+        description: "Atomically stores the minimum of `data` and the value at `P` in `image`, returning the original value.",
+        since: 420,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "imageAtomicMax",
+        signatures: &["uint imageAtomicMax(gimage2D image, ivec2 P, uint data)", "int imageAtomicMax(gimage2D image, ivec2 P, int data)"],
+        description: "Atomically stores the maximum of `data` and the value at `P` in `image`, returning the original value.",
+        since: 420,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "imageAtomicExchange",
+        signatures: &["uint imageAtomicExchange(gimage2D image, ivec2 P, uint data)", "float imageAtomicExchange(image2D image, ivec2 P, float data)"],
+        description: "Atomically stores `data` at `P` in `image`, returning the original value.",
+        since: 420,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "imageAtomicCompSwap",
+        signatures: &["uint imageAtomicCompSwap(gimage2D image, ivec2 P, uint compare, uint data)"],
+        description: "Atomically compares `compare` with the value at `P` in `image` and, if equal, stores `data`. Returns the original value.",
+        since: 420,
+        removed: None,
+        stages: ALL,
+    },
+    // atomic counter and memory functions
+    BuiltinFunction {
+        name: "atomicCounterIncrement",
+        signatures: &["uint atomicCounterIncrement(atomic_uint c)"],
+        description: "Atomically increments the counter for `c` and returns its value prior to the increment.",
+        since: 420,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "atomicCounterDecrement",
+        signatures: &["uint atomicCounterDecrement(atomic_uint c)"],
+        description: "Atomically decrements the counter for `c` and returns its value after the decrement.",
+        since: 420,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "atomicCounter",
+        signatures: &["uint atomicCounter(atomic_uint c)"],
+        description: "Returns the counter value for `c`.",
+        since: 420,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "atomicAdd",
+        signatures: &["uint atomicAdd(inout uint mem, uint data)", "int atomicAdd(inout int mem, int data)"],
+        description: "Atomically adds `data` to `mem`, returning the original value of `mem`.",
+        since: 430,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "atomicMin",
+        signatures: &["uint atomicMin(inout uint mem, uint data)", "int atomicMin(inout int mem, int data)"],
+        description: "Atomically stores the minimum of `data` and `mem` into `mem`, returning the original value of `mem`.",
+        since: 430,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "atomicMax",
+        signatures: &["uint atomicMax(inout uint mem, uint data)", "int atomicMax(inout int mem, int data)"],
+        description: "Atomically stores the maximum of `data` and `mem` into `mem`, returning the original value of `mem`.",
+        since: 430,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "atomicAnd",
+        signatures: &["uint atomicAnd(inout uint mem, uint data)", "int atomicAnd(inout int mem, int data)"],
+        description: "Atomically stores the bitwise AND of `data` and `mem` into `mem`, returning the original value of `mem`.",
+        since: 430,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "atomicOr",
+        signatures: &["uint atomicOr(inout uint mem, uint data)", "int atomicOr(inout int mem, int data)"],
+        description: "Atomically stores the bitwise OR of `data` and `mem` into `mem`, returning the original value of `mem`.",
+        since: 430,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "atomicXor",
+        signatures: &["uint atomicXor(inout uint mem, uint data)", "int atomicXor(inout int mem, int data)"],
+        description: "Atomically stores the bitwise XOR of `data` and `mem` into `mem`, returning the original value of `mem`.",
+        since: 430,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "atomicExchange",
+        signatures: &["uint atomicExchange(inout uint mem, uint data)", "int atomicExchange(inout int mem, int data)"],
+        description: "Atomically stores `data` into `mem`, returning the original value of `mem`.",
+        since: 430,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "atomicCompSwap",
+        signatures: &["uint atomicCompSwap(inout uint mem, uint compare, uint data)", "int atomicCompSwap(inout int mem, int compare, int data)"],
+        description: "Atomically compares `compare` with `mem` and, if equal, stores `data` into `mem`. Returns the original value of `mem`.",
+        since: 430,
+        removed: None,
+        stages: ALL,
+    },
+    // shader invocation control and memory barriers
+    BuiltinFunction {
+        name: "barrier",
+        signatures: &["void barrier()"],
+        description: "Synchronizes all invocations of a compute work group; no invocation continues past the barrier until all invocations have reached it.",
+        since: 430,
+        removed: None,
+        stages: COMPUTE,
+    },
+    BuiltinFunction {
+        name: "memoryBarrier",
+        signatures: &["void memoryBarrier()"],
+        description: "Controls the ordering of memory transactions issued by a single shader invocation.",
+        since: 420,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "memoryBarrierAtomicCounter",
+        signatures: &["void memoryBarrierAtomicCounter()"],
+        description: "Controls the ordering of operations on atomic counters issued by a single shader invocation.",
+        since: 430,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "memoryBarrierBuffer",
+        signatures: &["void memoryBarrierBuffer()"],
+        description: "Controls the ordering of memory transactions to buffer variables issued within a single shader invocation.",
+        since: 430,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "memoryBarrierShared",
+        signatures: &["void memoryBarrierShared()"],
+        description: "Controls the ordering of memory transactions to shared variables issued within a single shader invocation.",
+        since: 430,
+        removed: None,
+        stages: COMPUTE,
+    },
+    BuiltinFunction {
+        name: "memoryBarrierImage",
+        signatures: &["void memoryBarrierImage()"],
+        description: "Controls the ordering of memory transactions to images issued within a single shader invocation.",
+        since: 430,
+        removed: None,
+        stages: ALL,
+    },
+    BuiltinFunction {
+        name: "groupMemoryBarrier",
+        signatures: &["void groupMemoryBarrier()"],
+        description: "Controls the ordering of all memory transactions issued within a single shader invocation, as viewed by other invocations in the same work group.",
+        since: 430,
+        removed: None,
+        stages: COMPUTE,
+    },
+];
+
+pub static VARIABLES: &[BuiltinVariable] = &[
+    // vertex shader
+    BuiltinVariable {
+        name: "gl_Position",
+        declaration: "out vec4 gl_Position",
+        description: "The clip-space output position of the current vertex.",
+        since: 110,
+        removed: None,
+        stages: &[TreeType::Vertex, TreeType::Geometry],
+    },
+    BuiltinVariable {
+        name: "gl_PointSize",
+        declaration: "out float gl_PointSize",
+        description: "The size, in pixels, of the point to be rasterized.",
+        since: 110,
+        removed: None,
+        stages: &[TreeType::Vertex, TreeType::Geometry],
+    },
+    BuiltinVariable {
+        name: "gl_ClipDistance",
+        declaration: "out float gl_ClipDistance[]",
+        description: "Distances of the vertex to each user-defined clip plane.",
+        since: 130,
+        removed: None,
+        stages: &[TreeType::Vertex, TreeType::Geometry],
+    },
+    BuiltinVariable {
+        name: "gl_VertexID",
+        declaration: "in int gl_VertexID",
+        description: "The index of the current vertex.",
+        since: 130,
+        removed: None,
+        stages: VERTEX,
+    },
+    BuiltinVariable {
+        name: "gl_InstanceID",
+        declaration: "in int gl_InstanceID",
+        description: "The index of the current instance when doing instanced rendering.",
+        since: 140,
+        removed: None,
+        stages: VERTEX,
+    },
+    BuiltinVariable {
+        name: "gl_Vertex",
+        declaration: "attribute vec4 gl_Vertex",
+        description: "The position of the vertex in model space. Part of the fixed-function state.",
+        since: 110,
+        removed: Some(140),
+        stages: VERTEX,
+    },
+    BuiltinVariable {
+        name: "gl_Normal",
+        declaration: "attribute vec3 gl_Normal",
+        description: "The normal of the vertex in model space. Part of the fixed-function state.",
+        since: 110,
+        removed: Some(140),
+        stages: VERTEX,
+    },
+    BuiltinVariable {
+        name: "gl_Color",
+        declaration: "attribute vec4 gl_Color",
+        description: "The vertex color in the vertex shader, or the interpolated front or back color in the fragment shader.",
+        since: 110,
+        removed: Some(140),
+        stages: &[TreeType::Vertex, TreeType::Fragment],
+    },
+    BuiltinVariable {
+        name: "gl_SecondaryColor",
+        declaration: "attribute vec4 gl_SecondaryColor",
+        description: "The vertex secondary color.",
+        since: 110,
+        removed: Some(140),
+        stages: &[TreeType::Vertex, TreeType::Fragment],
+    },
+    BuiltinVariable {
+        name: "gl_MultiTexCoord0",
+        declaration: "attribute vec4 gl_MultiTexCoord0",
+        description: "Texture coordinate set 0. In Minecraft this is the texture atlas coordinate.",
+        since: 110,
+        removed: Some(140),
+        stages: VERTEX,
+    },
+    BuiltinVariable {
+        name: "gl_MultiTexCoord1",
+        declaration: "attribute vec4 gl_MultiTexCoord1",
+        description: "Texture coordinate set 1. In Minecraft this is the lightmap coordinate.",
+        since: 110,
+        removed: Some(140),
+        stages: VERTEX,
+    },
+    BuiltinVariable {
+        name: "gl_MultiTexCoord2",
+        declaration: "attribute vec4 gl_MultiTexCoord2",
+        description: "Texture coordinate set 2.",
+        since: 110,
+        removed: Some(140),
+        stages: VERTEX,
+    },
+    BuiltinVariable {
+        name: "gl_MultiTexCoord3",
+        declaration: "attribute vec4 gl_MultiTexCoord3",
+        description: "Texture coordinate set 3.",
+        since: 110,
+        removed: Some(140),
+        stages: VERTEX,
+    },
+    BuiltinVariable {
+        name: "gl_FogCoord",
+        declaration: "attribute float gl_FogCoord",
+        description: "The vertex fog coordinate.",
+        since: 110,
+        removed: Some(140),
+        stages: VERTEX,
+    },
+    BuiltinVariable {
+        name: "gl_FrontColor",
+        declaration: "varying vec4 gl_FrontColor",
+        description: "The front-facing color output of the vertex.",
+        since: 110,
+        removed: Some(140),
+        stages: &[TreeType::Vertex, TreeType::Geometry],
+    },
+    BuiltinVariable {
+        name: "gl_BackColor",
+        declaration: "varying vec4 gl_BackColor",
+        description: "The back-facing color output of the vertex.",
+        since: 110,
+        removed: Some(140),
+        stages: &[TreeType::Vertex, TreeType::Geometry],
+    },
+    BuiltinVariable {
+        name: "gl_TexCoord",
+        declaration: "varying vec4 gl_TexCoord[]",
+        description: "The texture coordinates passed from the vertex to the fragment stage.",
+        since: 110,
+        removed: Some(140),
+        stages: ALL,
+    },
+    BuiltinVariable {
+        name: "gl_FogFragCoord",
+        declaration: "varying float gl_FogFragCoord",
+        description: "The fog coordinate passed to the fragment stage.",
+        since: 110,
+        removed: Some(140),
+        stages: ALL,
+    },
+    BuiltinVariable {
+        name: "gl_ClipVertex",
+        declaration: "out vec4 gl_ClipVertex",
+        description: "The coordinate used for user clipping.",
+        since: 110,
+        removed: Some(140),
+        stages: VERTEX,
+    },
+    // fragment shader
+    BuiltinVariable {
+        name: "gl_FragCoord",
+        declaration: "in vec4 gl_FragCoord",
+        description: "The window-relative coordinates of the current fragment. `z` is the fragment depth and `w` is `1 / clip.w`.",
+        since: 110,
+        removed: None,
+        stages: FRAGMENT,
+    },
+    BuiltinVariable {
+        name: "gl_FrontFacing",
+        declaration: "in bool gl_FrontFacing",
+        description: "True if the fragment belongs to a front-facing primitive.",
+        since: 110,
+        removed: None,
+        stages: FRAGMENT,
+    },
+    BuiltinVariable {
+        name: "gl_PointCoord",
+        declaration: "in vec2 gl_PointCoord",
+        description: "The location of the fragment within a point primitive, in [0, 1].",
+        since: 110,
+        removed: None,
+        stages: FRAGMENT,
+    },
+    BuiltinVariable {
+        name: "gl_FragColor",
+        declaration: "out vec4 gl_FragColor",
+        description: "The color written to all draw buffers. OptiFine treats it as `gl_FragData[0]`. Use a user-defined `out` variable in core profiles.",
+        since: 110,
+        removed: Some(140),
+        stages: FRAGMENT,
+    },
+    BuiltinVariable {
+        name: "gl_FragData",
+        declaration: "out vec4 gl_FragData[gl_MaxDrawBuffers]",
+        description: "The colors written to each draw buffer. Which buffers are written is controlled by the `/* DRAWBUFFERS:... */` or `/* RENDERTARGETS: ... */` comment.",
+        since: 110,
+        removed: Some(140),
+        stages: FRAGMENT,
+    },
+    BuiltinVariable {
+        name: "gl_FragDepth",
+        declaration: "out float gl_FragDepth",
+        description: "The depth value written for the current fragment. If not written, the fixed-function depth is used.",
+        since: 110,
+        removed: None,
+        stages: FRAGMENT,
+    },
+    BuiltinVariable {
+        name: "gl_SampleID",
+        declaration: "in int gl_SampleID",
+        description: "The sample number of the sample currently being processed.",
+        since: 400,
+        removed: None,
+        stages: FRAGMENT,
+    },
+    BuiltinVariable {
+        name: "gl_SamplePosition",
+        declaration: "in vec2 gl_SamplePosition",
+        description: "The location of the current sample within the pixel.",
+        since: 400,
+        removed: None,
+        stages: FRAGMENT,
+    },
+    BuiltinVariable {
+        name: "gl_SampleMask",
+        declaration: "out int gl_SampleMask[]",
+        description: "The sample mask for the fragment being processed.",
+        since: 400,
+        removed: None,
+        stages: FRAGMENT,
+    },
+    // geometry shader
+    BuiltinVariable {
+        name: "gl_in",
+        declaration: "in gl_PerVertex { vec4 gl_Position; float gl_PointSize; float gl_ClipDistance[]; } gl_in[]",
+        description: "The per-vertex outputs of the previous stage for each vertex of the input primitive.",
+        since: 150,
+        removed: None,
+        stages: GEOMETRY,
+    },
+    BuiltinVariable {
+        name: "gl_PrimitiveIDIn",
+        declaration: "in int gl_PrimitiveIDIn",
+        description: "The number of primitives processed by the shader since the current set of rendering primitives was started.",
+        since: 150,
+        removed: None,
+        stages: GEOMETRY,
+    },
+    BuiltinVariable {
+        name: "gl_PrimitiveID",
+        declaration: "int gl_PrimitiveID",
+        description: "In the geometry stage, the primitive ID to write out. In the fragment stage, the index of the current primitive.",
+        since: 150,
+        removed: None,
+        stages: &[TreeType::Geometry, TreeType::Fragment],
+    },
+    BuiltinVariable {
+        name: "gl_InvocationID",
+        declaration: "in int gl_InvocationID",
+        description: "The invocation number of the current geometry shader invocation when using instanced geometry shaders.",
+        since: 400,
+        removed: None,
+        stages: GEOMETRY,
+    },
+    BuiltinVariable {
+        name: "gl_Layer",
+        declaration: "int gl_Layer",
+        description: "The layer of a layered framebuffer attachment the primitive is rendered to.",
+        since: 150,
+        removed: None,
+        stages: &[TreeType::Geometry, TreeType::Fragment],
+    },
+    BuiltinVariable {
+        name: "gl_ViewportIndex",
+        declaration: "int gl_ViewportIndex",
+        description: "The viewport the primitive is rendered to.",
+        since: 410,
+        removed: None,
+        stages: &[TreeType::Geometry, TreeType::Fragment],
+    },
+    // compute shader
+    BuiltinVariable {
+        name: "gl_NumWorkGroups",
+        declaration: "in uvec3 gl_NumWorkGroups",
+        description: "The number of work groups that have been dispatched.",
+        since: 430,
+        removed: None,
+        stages: COMPUTE,
+    },
+    BuiltinVariable {
+        name: "gl_WorkGroupSize",
+        declaration: "const uvec3 gl_WorkGroupSize",
+        description: "The size of the work group as declared with `layout(local_size_x = ...) in;`.",
+        since: 430,
+        removed: None,
+        stages: COMPUTE,
+    },
+    BuiltinVariable {
+        name: "gl_WorkGroupID",
+        declaration: "in uvec3 gl_WorkGroupID",
+        description: "The index of the work group currently being operated on.",
+        since: 430,
+        removed: None,
+        stages: COMPUTE,
+    },
+    BuiltinVariable {
+        name: "gl_LocalInvocationID",
+        declaration: "in uvec3 gl_LocalInvocationID",
+        description: "The index of the current invocation within its work group.",
+        since: 430,
+        removed: None,
+        stages: COMPUTE,
+    },
+    BuiltinVariable {
+        name: "gl_GlobalInvocationID",
+        declaration: "in uvec3 gl_GlobalInvocationID",
+        description: "The global index of the current invocation, i.e. `gl_WorkGroupID * gl_WorkGroupSize + gl_LocalInvocationID`.",
+        since: 430,
+        removed: None,
+        stages: COMPUTE,
+    },
+    BuiltinVariable {
+        name: "gl_LocalInvocationIndex",
+        declaration: "in uint gl_LocalInvocationIndex",
+        description: "The 1-dimensional form of `gl_LocalInvocationID`.",
+        since: 430,
+        removed: None,
+        stages: COMPUTE,
+    },
+    // compatibility profile uniform state
+    BuiltinVariable {
+        name: "gl_ModelViewMatrix",
+        declaration: "uniform mat4 gl_ModelViewMatrix",
+        description: "The fixed-function model-view matrix.",
+        since: 110,
+        removed: Some(140),
+        stages: ALL,
+    },
+    BuiltinVariable {
+        name: "gl_ProjectionMatrix",
+        declaration: "uniform mat4 gl_ProjectionMatrix",
+        description: "The fixed-function projection matrix.",
+        since: 110,
+        removed: Some(140),
+        stages: ALL,
+    },
+    BuiltinVariable {
+        name: "gl_ModelViewProjectionMatrix",
+        declaration: "uniform mat4 gl_ModelViewProjectionMatrix",
+        description: "The product of the fixed-function projection and model-view matrices.",
+        since: 110,
+        removed: Some(140),
+        stages: ALL,
+    },
+    BuiltinVariable {
+        name: "gl_ModelViewMatrixInverse",
+        declaration: "uniform mat4 gl_ModelViewMatrixInverse",
+        description: "The inverse of the fixed-function model-view matrix.",
+        since: 110,
+        removed: Some(140),
+        stages: ALL,
+    },
+    BuiltinVariable {
+        name: "gl_ProjectionMatrixInverse",
+        declaration: "uniform mat4 gl_ProjectionMatrixInverse",
+        description: "The inverse of the fixed-function projection matrix.",
+        since: 110,
+        removed: Some(140),
+        stages: ALL,
+    },
+    BuiltinVariable {
+        name: "gl_NormalMatrix",
+        declaration: "uniform mat3 gl_NormalMatrix",
+        description: "The transpose of the inverse of the upper leftmost 3x3 of `gl_ModelViewMatrix`.",
+        since: 110,
+        removed: Some(140),
+        stages: ALL,
+    },
+    BuiltinVariable {
+        name: "gl_TextureMatrix",
+        declaration: "uniform mat4 gl_TextureMatrix[gl_MaxTextureCoords]",
+        description: "The fixed-function texture matrices.",
+        since: 110,
+        removed: Some(140),
+        stages: ALL,
+    },
+    BuiltinVariable {
+        name: "gl_Fog",
+        declaration: "uniform gl_FogParameters gl_Fog",
+        description: "The fixed-function fog parameters: `color`, `density`, `start`, `end` and `scale`.",
+        since: 110,
+        removed: Some(140),
+        stages: ALL,
+    },
+    BuiltinVariable {
+        name: "gl_DepthRange",
+        declaration: "uniform gl_DepthRangeParameters gl_DepthRange",
+        description: "The depth range in window coordinates: `near`, `far` and `diff`.",
+        since: 110,
+        removed: None,
+        stages: ALL,
+    },
+];
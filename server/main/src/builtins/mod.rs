@@ -0,0 +1,258 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::TreeType;
+
+mod glsl;
+mod optifine;
+
+/// A built-in GLSL function, with every overload listed in the notation used by the
+/// GLSL specification (`genType`, `gvec4` etc).
+pub struct BuiltinFunction {
+    pub name: &'static str,
+    pub signatures: &'static [&'static str],
+    pub description: &'static str,
+    pub since: u32,
+    // core profile version from which the function is no longer available. The
+    // compatibility profile keeps it around regardless.
+    pub removed: Option<u32>,
+    // stages the function may be used in. Empty means all stages.
+    pub stages: &'static [TreeType],
+}
+
+/// A built-in GLSL variable or constant, e.g. `gl_FragCoord`.
+pub struct BuiltinVariable {
+    pub name: &'static str,
+    pub declaration: &'static str,
+    pub description: &'static str,
+    pub since: u32,
+    pub removed: Option<u32>,
+    pub stages: &'static [TreeType],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Loader {
+    OptiFine,
+    Iris,
+}
+
+/// A uniform that OptiFine and/or Iris set for shader programs if it is declared.
+pub struct ShaderUniform {
+    pub name: &'static str,
+    pub ty: &'static str,
+    pub description: &'static str,
+    pub range: Option<&'static str>,
+    pub loader: Loader,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    Core,
+    Compatibility,
+}
+
+/// The `#version` a program is compiled with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlslVersion {
+    pub number: u32,
+    pub profile: Profile,
+}
+
+/// Whether a built-in can be used under a given `#version`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Availability {
+    Available,
+    NotYetIntroduced(u32),
+    RemovedInCore(u32),
+}
+
+lazy_static! {
+    static ref RE_VERSION: Regex = Regex::new(r#"^\s*#\s*version\s+(\d+)(?:\s+(core|compatibility))?"#).unwrap();
+    static ref FUNCTIONS: HashMap<&'static str, &'static BuiltinFunction> = glsl::FUNCTIONS.iter().map(|f| (f.name, f)).collect();
+    static ref VARIABLES: HashMap<&'static str, &'static BuiltinVariable> = glsl::VARIABLES.iter().map(|v| (v.name, v)).collect();
+    static ref UNIFORMS: HashMap<&'static str, &'static ShaderUniform> = optifine::UNIFORMS.iter().map(|u| (u.name, u)).collect();
+}
+
+pub fn function(name: &str) -> Option<&'static BuiltinFunction> {
+    FUNCTIONS.get(name).copied()
+}
+
+pub fn variable(name: &str) -> Option<&'static BuiltinVariable> {
+    VARIABLES.get(name).copied()
+}
+
+pub fn uniform(name: &str) -> Option<&'static ShaderUniform> {
+    UNIFORMS.get(name).copied()
+}
+
+/// The `#define`s OptiFine adds to every program.
+pub fn macro_defines() -> Vec<String> {
+    optifine::MACROS
+        .iter()
+        .map(|(name, value)| format!("#define {} {}", name, value))
+        .collect()
+}
+
+impl GlslVersion {
+    /// Finds the first `#version` directive in `source`. Programs without one are compiled
+    /// as GLSL 1.10, same as the driver would.
+    pub fn from_source(source: &str) -> Option<GlslVersion> {
+        source.lines().find_map(|line| {
+            let captures = RE_VERSION.captures(line)?;
+            let number = captures.get(1).unwrap().as_str().parse::<u32>().ok()?;
+            let profile = match captures.get(2).map(|p| p.as_str()) {
+                Some("compatibility") => Profile::Compatibility,
+                Some(_) => Profile::Core,
+                // the profile argument only exists from 1.50 onwards, core being the default
+                None if number >= 150 => Profile::Core,
+                None => Profile::Compatibility,
+            };
+            Some(GlslVersion { number, profile })
+        })
+    }
+
+    pub fn availability(&self, since: u32, removed: Option<u32>) -> Availability {
+        if self.number < since {
+            return Availability::NotYetIntroduced(since);
+        }
+        match removed {
+            Some(removed) if self.profile == Profile::Core && self.number >= removed => Availability::RemovedInCore(removed),
+            _ => Availability::Available,
+        }
+    }
+}
+
+impl Default for GlslVersion {
+    fn default() -> Self {
+        GlslVersion {
+            number: 110,
+            profile: Profile::Compatibility,
+        }
+    }
+}
+
+impl Display for GlslVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.profile {
+            Profile::Core if self.number >= 150 => write!(f, "{} core", self.number),
+            Profile::Compatibility if self.number >= 150 => write!(f, "{} compatibility", self.number),
+            _ => write!(f, "{}", self.number),
+        }
+    }
+}
+
+impl Display for Loader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Loader::OptiFine => f.write_str("OptiFine"),
+            Loader::Iris => f.write_str("Iris"),
+        }
+    }
+}
+
+/// Formats a version number as written in the specification, e.g. `130` as `1.30`.
+pub fn format_version(version: u32) -> String {
+    format!("{}.{:02}", version / 100, version % 100)
+}
+
+pub fn format_stages(stages: &[TreeType]) -> String {
+    stages
+        .iter()
+        .map(|stage| match stage {
+            TreeType::Fragment => "fragment",
+            TreeType::Vertex => "vertex",
+            TreeType::Geometry => "geometry",
+            TreeType::Compute => "compute",
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Finds the label offsets of each parameter in a specification-style signature such as
+/// `vec4 texture2D(sampler2D sampler, vec2 coord [, float bias])`, where optional parameters
+/// are wrapped in brackets.
+pub fn parameter_offsets(signature: &str) -> Vec<[u32; 2]> {
+    let (open, close) = match (signature.find('('), signature.rfind(')')) {
+        (Some(open), Some(close)) if open < close => (open, close),
+        _ => return vec![],
+    };
+
+    let mut offsets = vec![];
+    let mut segment_start = open + 1;
+    for (i, c) in signature[..=close].char_indices().skip_while(|(i, _)| *i <= open) {
+        if c != ',' && i != close {
+            continue;
+        }
+        let segment = &signature[segment_start..i];
+        let trimmed = segment.trim_matches(|c: char| c.is_whitespace() || c == '[' || c == ']');
+        if !trimmed.is_empty() && trimmed != "void" {
+            let start = segment_start + segment.find(trimmed).unwrap();
+            offsets.push([start as u32, (start + trimmed.len()) as u32]);
+        }
+        segment_start = i + 1;
+    }
+    offsets
+}
+
+#[cfg(test)]
+mod builtins_test {
+    use crate::builtins::{self, Availability, GlslVersion, Profile};
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_version_from_source() {
+        let cases = vec![
+            ("#version 120\nvoid main() {}", Some(GlslVersion { number: 120, profile: Profile::Compatibility })),
+            ("// comment\n  #version 330 compatibility", Some(GlslVersion { number: 330, profile: Profile::Compatibility })),
+            ("#version 150", Some(GlslVersion { number: 150, profile: Profile::Core })),
+            ("# version 460 core", Some(GlslVersion { number: 460, profile: Profile::Core })),
+            ("void main() {}", None),
+        ];
+
+        for (source, expected) in cases {
+            assert_eq!(GlslVersion::from_source(source), expected, "{:?}", source);
+        }
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_availability() {
+        let texture2d = builtins::function("texture2D").unwrap();
+        let texel_fetch = builtins::function("texelFetch").unwrap();
+
+        let legacy = GlslVersion { number: 120, profile: Profile::Compatibility };
+        let core = GlslVersion { number: 330, profile: Profile::Core };
+        let compat = GlslVersion { number: 330, profile: Profile::Compatibility };
+
+        assert_eq!(legacy.availability(texture2d.since, texture2d.removed), Availability::Available);
+        assert_eq!(core.availability(texture2d.since, texture2d.removed), Availability::RemovedInCore(140));
+        assert_eq!(compat.availability(texture2d.since, texture2d.removed), Availability::Available);
+        assert_eq!(legacy.availability(texel_fetch.since, texel_fetch.removed), Availability::NotYetIntroduced(130));
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_lookup() {
+        assert!(builtins::function("smoothstep").is_some());
+        assert!(builtins::variable("gl_FragCoord").is_some());
+        assert_eq!(builtins::uniform("worldTime").unwrap().range, Some("0-23999"));
+        assert!(builtins::function("getShadow").is_none());
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_parameter_offsets() {
+        let signature = "vec4 texture2D(sampler2D sampler, vec2 coord [, float bias])";
+        let labels: Vec<&str> = builtins::parameter_offsets(signature)
+            .iter()
+            .map(|[start, end]| &signature[*start as usize..*end as usize])
+            .collect();
+        assert_eq!(labels, vec!["sampler2D sampler", "vec2 coord", "float bias"]);
+
+        assert!(builtins::parameter_offsets("void barrier(void)").is_empty());
+        assert!(builtins::parameter_offsets("void EmitVertex()").is_empty());
+    }
+}
@@ -0,0 +1,837 @@
+use super::{Loader, ShaderUniform};
+
+pub static UNIFORMS: &[ShaderUniform] = &[
+    // held items
+    ShaderUniform {
+        name: "heldItemId",
+        ty: "int",
+        description: "ID of the item held in the main hand, as mapped in `item.properties`.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "heldBlockLightValue",
+        ty: "int",
+        description: "Light value of the item held in the main hand.",
+        range: Some("0-15"),
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "heldItemId2",
+        ty: "int",
+        description: "ID of the item held in the off hand, as mapped in `item.properties`.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "heldBlockLightValue2",
+        ty: "int",
+        description: "Light value of the item held in the off hand.",
+        range: Some("0-15"),
+        loader: Loader::OptiFine,
+    },
+    // fog and sky
+    ShaderUniform {
+        name: "fogMode",
+        ty: "int",
+        description: "Fog mode: `GL_LINEAR` (9729), `GL_EXP` (2048) or `GL_EXP2` (2049).",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "fogDensity",
+        ty: "float",
+        description: "Fog density.",
+        range: Some("0.0-1.0"),
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "fogColor",
+        ty: "vec3",
+        description: "Fog color (r, g, b).",
+        range: Some("0.0-1.0"),
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "skyColor",
+        ty: "vec3",
+        description: "Sky color (r, g, b).",
+        range: Some("0.0-1.0"),
+        loader: Loader::OptiFine,
+    },
+    // time
+    ShaderUniform {
+        name: "worldTime",
+        ty: "int",
+        description: "Time of day in ticks, i.e. `worldTicks % 24000`.",
+        range: Some("0-23999"),
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "worldDay",
+        ty: "int",
+        description: "Number of days passed in the world, i.e. `worldTicks / 24000`.",
+        range: Some("0+"),
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "moonPhase",
+        ty: "int",
+        description: "Current moon phase.",
+        range: Some("0-7"),
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "frameCounter",
+        ty: "int",
+        description: "Frame index. Resets to 0 after 720719.",
+        range: Some("0-720719"),
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "frameTime",
+        ty: "float",
+        description: "Duration of the last frame in seconds.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "frameTimeCounter",
+        ty: "float",
+        description: "Run time in seconds. Resets to 0 after 3600 seconds.",
+        range: Some("0.0-3600.0"),
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "sunAngle",
+        ty: "float",
+        description: "Angle of the sun in the sky: 0.0 is sunrise, 0.25 noon, 0.5 sunset and 0.75 midnight.",
+        range: Some("0.0-1.0"),
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "shadowAngle",
+        ty: "float",
+        description: "Angle of the shadow light source (sun or moon): 0.0-0.5 during the day, 0.5-1.0 at night.",
+        range: Some("0.0-1.0"),
+        loader: Loader::OptiFine,
+    },
+    // weather
+    ShaderUniform {
+        name: "rainStrength",
+        ty: "float",
+        description: "Rain strength.",
+        range: Some("0.0-1.0"),
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "wetness",
+        ty: "float",
+        description: "`rainStrength` smoothed with `wetnessHalfLife` or `drynessHalfLife`.",
+        range: Some("0.0-1.0"),
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "thunderStrength",
+        ty: "float",
+        description: "Thunder strength.",
+        range: Some("0.0-1.0"),
+        loader: Loader::Iris,
+    },
+    // viewport
+    ShaderUniform {
+        name: "aspectRatio",
+        ty: "float",
+        description: "Aspect ratio of the viewport, i.e. `viewWidth / viewHeight`.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "viewWidth",
+        ty: "float",
+        description: "Width of the viewport in pixels.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "viewHeight",
+        ty: "float",
+        description: "Height of the viewport in pixels.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "near",
+        ty: "float",
+        description: "Distance of the near viewing plane.",
+        range: Some("0.05"),
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "far",
+        ty: "float",
+        description: "Distance of the far viewing plane, based on the render distance.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    // positions
+    ShaderUniform {
+        name: "sunPosition",
+        ty: "vec3",
+        description: "Position of the sun in eye space. The length of the vector is 100.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "moonPosition",
+        ty: "vec3",
+        description: "Position of the moon in eye space. The length of the vector is 100.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "shadowLightPosition",
+        ty: "vec3",
+        description: "Position of the shadow light source (sun or moon) in eye space.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "upPosition",
+        ty: "vec3",
+        description: "Up direction in eye space. The length of the vector is 100.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "cameraPosition",
+        ty: "vec3",
+        description: "Position of the camera in world space.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "previousCameraPosition",
+        ty: "vec3",
+        description: "Position of the camera in world space during the previous frame.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "eyePosition",
+        ty: "vec3",
+        description: "Position of the player's eyes in world space.",
+        range: None,
+        loader: Loader::Iris,
+    },
+    ShaderUniform {
+        name: "eyeAltitude",
+        ty: "float",
+        description: "Y position of the view entity.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    // matrices
+    ShaderUniform {
+        name: "gbufferModelView",
+        ty: "mat4",
+        description: "Model-view matrix after setting up the camera transformations.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "gbufferModelViewInverse",
+        ty: "mat4",
+        description: "Inverse of `gbufferModelView`.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "gbufferPreviousModelView",
+        ty: "mat4",
+        description: "`gbufferModelView` of the previous frame.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "gbufferProjection",
+        ty: "mat4",
+        description: "Projection matrix used when the gbuffers were generated.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "gbufferProjectionInverse",
+        ty: "mat4",
+        description: "Inverse of `gbufferProjection`.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "gbufferPreviousProjection",
+        ty: "mat4",
+        description: "`gbufferProjection` of the previous frame.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "shadowProjection",
+        ty: "mat4",
+        description: "Projection matrix used when the shadow map was generated.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "shadowProjectionInverse",
+        ty: "mat4",
+        description: "Inverse of `shadowProjection`.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "shadowModelView",
+        ty: "mat4",
+        description: "Model-view matrix used when the shadow map was generated.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "shadowModelViewInverse",
+        ty: "mat4",
+        description: "Inverse of `shadowModelView`.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "modelViewMatrix",
+        ty: "mat4",
+        description: "Model-view matrix, replacing `gl_ModelViewMatrix` in core profile programs.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "modelViewMatrixInverse",
+        ty: "mat4",
+        description: "Inverse of `modelViewMatrix`.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "projectionMatrix",
+        ty: "mat4",
+        description: "Projection matrix, replacing `gl_ProjectionMatrix` in core profile programs.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "projectionMatrixInverse",
+        ty: "mat4",
+        description: "Inverse of `projectionMatrix`.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "textureMatrix",
+        ty: "mat4",
+        description: "Texture matrix, replacing `gl_TextureMatrix[0]` in core profile programs.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "normalMatrix",
+        ty: "mat3",
+        description: "Normal matrix, replacing `gl_NormalMatrix` in core profile programs.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "chunkOffset",
+        ty: "vec3",
+        description: "Offset of the chunk being rendered, to be added to `vaPosition` in core profile programs.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "alphaTestRef",
+        ty: "float",
+        description: "Alpha test reference value, replacing the fixed-function alpha test in core profile programs.",
+        range: Some("0.0-1.0"),
+        loader: Loader::OptiFine,
+    },
+    // player state
+    ShaderUniform {
+        name: "eyeBrightness",
+        ty: "ivec2",
+        description: "Light level at the player's eyes: `x` is block light, `y` is sky light. Light levels 0-15 map to 0-240.",
+        range: Some("0-240"),
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "eyeBrightnessSmooth",
+        ty: "ivec2",
+        description: "`eyeBrightness` smoothed with `eyeBrightnessHalflife`.",
+        range: Some("0-240"),
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "isEyeInWater",
+        ty: "int",
+        description: "Fluid the camera is in: 0 = none, 1 = water, 2 = lava, 3 = powder snow.",
+        range: Some("0-3"),
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "nightVision",
+        ty: "float",
+        description: "Strength of the night vision effect.",
+        range: Some("0.0-1.0"),
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "blindness",
+        ty: "float",
+        description: "Strength of the blindness effect.",
+        range: Some("0.0-1.0"),
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "darknessFactor",
+        ty: "float",
+        description: "Strength of the darkness effect.",
+        range: Some("0.0-1.0"),
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "darknessLightFactor",
+        ty: "float",
+        description: "Lightmap variation caused by the darkness effect.",
+        range: Some("0.0-1.0"),
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "playerMood",
+        ty: "float",
+        description: "Player mood. Increases the longer the player stays underground.",
+        range: Some("0.0-1.0"),
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "currentPlayerHealth",
+        ty: "float",
+        description: "Current health of the player as a fraction of `maxPlayerHealth`, or -1 when not applicable.",
+        range: Some("0.0-1.0"),
+        loader: Loader::Iris,
+    },
+    ShaderUniform {
+        name: "maxPlayerHealth",
+        ty: "float",
+        description: "Maximum health of the player.",
+        range: None,
+        loader: Loader::Iris,
+    },
+    ShaderUniform {
+        name: "currentPlayerHunger",
+        ty: "float",
+        description: "Current hunger of the player as a fraction of the maximum, or -1 when not applicable.",
+        range: Some("0.0-1.0"),
+        loader: Loader::Iris,
+    },
+    ShaderUniform {
+        name: "currentPlayerAir",
+        ty: "float",
+        description: "Current air supply of the player as a fraction of the maximum, or -1 when not applicable.",
+        range: Some("0.0-1.0"),
+        loader: Loader::Iris,
+    },
+    ShaderUniform {
+        name: "isSpectator",
+        ty: "bool",
+        description: "Whether the player is in spectator mode.",
+        range: None,
+        loader: Loader::Iris,
+    },
+    // rendering state
+    ShaderUniform {
+        name: "screenBrightness",
+        ty: "float",
+        description: "Screen brightness from the video settings.",
+        range: Some("0.0-1.0"),
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "hideGUI",
+        ty: "int",
+        description: "1 if the GUI is hidden (F1), otherwise 0.",
+        range: Some("0-1"),
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "centerDepthSmooth",
+        ty: "float",
+        description: "Depth at the center of the screen, smoothed with `centerDepthHalflife`.",
+        range: Some("0.0-1.0"),
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "atlasSize",
+        ty: "ivec2",
+        description: "Size of the texture atlas. Only set while the atlas texture is bound, otherwise (0, 0).",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "entityColor",
+        ty: "vec4",
+        description: "Entity color multiplier (entity hurt, creeper flashing when exploding).",
+        range: Some("0.0-1.0"),
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "entityId",
+        ty: "int",
+        description: "ID of the entity being rendered, as mapped in `entity.properties`.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "blockEntityId",
+        ty: "int",
+        description: "ID of the block entity being rendered, as mapped in `block.properties`.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "blendFunc",
+        ty: "ivec4",
+        description: "Current blend function (srcRGB, dstRGB, srcAlpha, dstAlpha).",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "instanceId",
+        ty: "int",
+        description: "Instance ID when instancing is enabled (`countInstances > 1`). 0 is the original, 1-N are the copies.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "renderStage",
+        ty: "int",
+        description: "The current render stage, to be compared with the `MC_RENDER_STAGE_*` macros.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "bossBattle",
+        ty: "int",
+        description: "Boss battle in progress: 0 = none, 1 = custom, 2 = ender dragon, 3 = wither, 4 = raid.",
+        range: Some("0-4"),
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "terrainTextureSize",
+        ty: "ivec2",
+        description: "Unused, kept for compatibility.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "terrainIconSize",
+        ty: "int",
+        description: "Unused, kept for compatibility.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    // samplers
+    ShaderUniform {
+        name: "texture",
+        ty: "sampler2D",
+        description: "Texture of the geometry being rendered (gbuffers), or `colortex0` in composite programs.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "gtexture",
+        ty: "sampler2D",
+        description: "Texture of the geometry being rendered. Alias of `texture` that is usable in core profile programs.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "lightmap",
+        ty: "sampler2D",
+        description: "Lightmap texture.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "normals",
+        ty: "sampler2D",
+        description: "Normal map of the geometry being rendered, when the resource pack provides one.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "specular",
+        ty: "sampler2D",
+        description: "Specular map of the geometry being rendered, when the resource pack provides one.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "shadow",
+        ty: "sampler2D",
+        description: "Shadow map depth. `shadowtex1` if `watershadow` is used, otherwise `shadowtex0`.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "watershadow",
+        ty: "sampler2D",
+        description: "Shadow map depth including translucent geometry. Declaring it switches `shadow` to exclude translucents.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "shadowtex0",
+        ty: "sampler2D",
+        description: "Shadow map depth including translucent geometry.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "shadowtex1",
+        ty: "sampler2D",
+        description: "Shadow map depth excluding translucent geometry.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "shadowcolor",
+        ty: "sampler2D",
+        description: "Alias of `shadowcolor0`.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "shadowcolor0",
+        ty: "sampler2D",
+        description: "First shadow color buffer.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "shadowcolor1",
+        ty: "sampler2D",
+        description: "Second shadow color buffer.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "depthtex0",
+        ty: "sampler2D",
+        description: "Depth buffer including everything.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "depthtex1",
+        ty: "sampler2D",
+        description: "Depth buffer excluding translucent geometry.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "depthtex2",
+        ty: "sampler2D",
+        description: "Depth buffer excluding translucent geometry and the hand.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "noisetex",
+        ty: "sampler2D",
+        description: "Noise texture, configured with `texture.noise` or `noiseTextureResolution`.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "gcolor",
+        ty: "sampler2D",
+        description: "Legacy alias of `colortex0`.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "gdepth",
+        ty: "sampler2D",
+        description: "Legacy alias of `colortex1`.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "gnormal",
+        ty: "sampler2D",
+        description: "Legacy alias of `colortex2`.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "composite",
+        ty: "sampler2D",
+        description: "Legacy alias of `colortex3`.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "gaux1",
+        ty: "sampler2D",
+        description: "Legacy alias of `colortex4`.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "gaux2",
+        ty: "sampler2D",
+        description: "Legacy alias of `colortex5`.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "gaux3",
+        ty: "sampler2D",
+        description: "Legacy alias of `colortex6`.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "gaux4",
+        ty: "sampler2D",
+        description: "Legacy alias of `colortex7`.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "colortex0",
+        ty: "sampler2D",
+        description: "Color buffer 0.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "colortex1",
+        ty: "sampler2D",
+        description: "Color buffer 1.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "colortex2",
+        ty: "sampler2D",
+        description: "Color buffer 2.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "colortex3",
+        ty: "sampler2D",
+        description: "Color buffer 3.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "colortex4",
+        ty: "sampler2D",
+        description: "Color buffer 4.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "colortex5",
+        ty: "sampler2D",
+        description: "Color buffer 5.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "colortex6",
+        ty: "sampler2D",
+        description: "Color buffer 6.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "colortex7",
+        ty: "sampler2D",
+        description: "Color buffer 7.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "colortex8",
+        ty: "sampler2D",
+        description: "Color buffer 8.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "colortex9",
+        ty: "sampler2D",
+        description: "Color buffer 9.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "colortex10",
+        ty: "sampler2D",
+        description: "Color buffer 10.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "colortex11",
+        ty: "sampler2D",
+        description: "Color buffer 11.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "colortex12",
+        ty: "sampler2D",
+        description: "Color buffer 12.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "colortex13",
+        ty: "sampler2D",
+        description: "Color buffer 13.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "colortex14",
+        ty: "sampler2D",
+        description: "Color buffer 14.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+    ShaderUniform {
+        name: "colortex15",
+        ty: "sampler2D",
+        description: "Color buffer 15.",
+        range: None,
+        loader: Loader::OptiFine,
+    },
+];
+
+/// Macros OptiFine defines in every program. Their values depend on the game and the settings it's run
+/// with, these match a current setup and can be overridden by the `[preamble]` of the project config.
+pub static MACROS: &[(&str, &str)] = &[
+    ("MC_VERSION", "11902"),
+    ("MC_GL_VERSION", "320"),
+    ("MC_GLSL_VERSION", "150"),
+    ("MC_RENDER_QUALITY", "1.0"),
+    ("MC_SHADOW_QUALITY", "1.0"),
+    ("MC_HAND_DEPTH", "0.125"),
+];
@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use rust_lsp::lsp_types::{CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall, Range, SymbolKind};
+use tree_sitter::{Node, Tree};
+
+use crate::preprocessor;
+use crate::symbols::{self, Declaration, DeclarationKind};
+use crate::url_norm::ToUrl;
+
+/// A call to a function made from within the body of another function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Call {
+    pub caller: String,
+    // range of the calling function's definition, to tell overloads apart.
+    pub caller_range: Range,
+    pub callee: String,
+    // range of the called function's name.
+    pub range: Range,
+    // false if the call is in a preprocessor branch that every program compiles out.
+    pub active: bool,
+}
+
+/// The calls made in a file.
+pub type FileCalls = (PathBuf, Vec<Call>);
+
+/// Collects every call made from a function body in `tree`. A call is active if the conditionals it is
+/// nested in hold with the macros defined by any of the programs in `programs`, or if there are none;
+/// conditions that can't be evaluated are assumed to be true.
+pub fn collect_calls(tree: &Tree, source: &str, programs: &[HashMap<String, String>]) -> Vec<Call> {
+    let mut calls = vec![];
+    let mut cursor = tree.walk();
+    'walk: loop {
+        let node = cursor.node();
+
+        if node.kind() == "call_expression" {
+            let callee = node.child_by_field_name("function").filter(|function| function.kind() == "identifier");
+            if let (Some(callee), Some(caller)) = (callee, enclosing_function(node)) {
+                let caller_name = caller
+                    .child_by_field_name("declarator")
+                    .and_then(symbols::declarator_identifier)
+                    .map(|ident| symbols::text(ident, source));
+                if let Some(caller_name) = caller_name {
+                    calls.push(Call {
+                        caller: caller_name.to_string(),
+                        caller_range: symbols::node_range(caller),
+                        callee: symbols::text(callee, source).to_string(),
+                        range: symbols::node_range(callee),
+                        active: programs.is_empty() || programs.iter().any(|defines| is_active(node, source, defines)),
+                    });
+                }
+            }
+        }
+
+        if cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                break 'walk;
+            }
+        }
+    }
+    calls
+}
+
+fn enclosing_function(node: Node) -> Option<Node> {
+    let mut parent = node.parent();
+    while let Some(current) = parent {
+        if current.kind() == "function_definition" {
+            return Some(current);
+        }
+        parent = current.parent();
+    }
+    None
+}
+
+fn is_active(node: Node, source: &str, defines: &HashMap<String, String>) -> bool {
+    let mut child = node;
+    while let Some(parent) = child.parent() {
+        let condition = match parent.kind() {
+            "preproc_if" | "preproc_elif" => parent
+                .child_by_field_name("condition")
+                .and_then(|condition| preprocessor::evaluate_condition(symbols::text(condition, source), defines)),
+            "preproc_ifdef" => parent.child_by_field_name("name").map(|name| {
+                let defined = defines.contains_key(symbols::text(name, source));
+                let negated = parent.child(0).map_or(false, |directive| symbols::text(directive, source).ends_with("ifndef"));
+                defined != negated
+            }),
+            _ => None,
+        };
+
+        // the body of a conditional needs it to be true, its #elif/#else chain needs it to be false
+        let in_alternative = parent.child_by_field_name("alternative").map(|n| n.id()) == Some(child.id());
+        if condition.map_or(false, |condition| condition == in_alternative) {
+            return false;
+        }
+        child = parent;
+    }
+    true
+}
+
+pub fn item(path: &Path, declaration: &Declaration, active: bool) -> CallHierarchyItem {
+    CallHierarchyItem {
+        name: declaration.name.clone(),
+        kind: SymbolKind::FUNCTION,
+        tags: None,
+        detail: Some(if active {
+            declaration.signature.clone()
+        } else {
+            format!("{} (inactive preprocessor branch)", declaration.signature)
+        }),
+        uri: path.to_url().unwrap(),
+        range: declaration.range,
+        selection_range: declaration.selection_range,
+        data: None,
+    }
+}
+
+/// The definitions in `scope` of the function named `name`, one per overload.
+pub fn prepare(name: &str, scope: &[(PathBuf, Vec<Declaration>)]) -> Vec<CallHierarchyItem> {
+    scope
+        .iter()
+        .flat_map(|(path, declarations)| declarations.iter().map(move |declaration| (path, declaration)))
+        .filter(|(_, declaration)| declaration.kind == DeclarationKind::Function && declaration.name == name)
+        .map(|(path, declaration)| item(path, declaration, true))
+        .collect()
+}
+
+/// Groups the calls to `callee` by the function they're made from. Callers that only call it from
+/// inactive preprocessor branches are marked as such.
+pub fn incoming_calls(
+    callee: &CallHierarchyItem, calls: &[FileCalls], scope: &[(PathBuf, Vec<Declaration>)],
+) -> Vec<CallHierarchyIncomingCall> {
+    let mut incoming = vec![];
+    for (path, file_calls) in calls {
+        let declarations = match scope.iter().find(|(p, _)| p == path) {
+            Some((_, declarations)) => declarations,
+            None => continue,
+        };
+
+        // keyed by the caller's declaration, in order of first call
+        let mut callers: Vec<(&Declaration, Vec<&Call>)> = vec![];
+        for call in file_calls.iter().filter(|call| call.callee == callee.name) {
+            let caller = declarations
+                .iter()
+                .find(|d| d.kind == DeclarationKind::Function && d.name == call.caller && d.range == call.caller_range);
+            let caller = match caller {
+                Some(caller) => caller,
+                None => continue,
+            };
+            match callers.iter_mut().find(|(declaration, _)| std::ptr::eq(*declaration, caller)) {
+                Some((_, calls)) => calls.push(call),
+                None => callers.push((caller, vec![call])),
+            }
+        }
+
+        incoming.extend(callers.into_iter().map(|(caller, calls)| CallHierarchyIncomingCall {
+            from: item(path, caller, calls.iter().any(|call| call.active)),
+            from_ranges: calls.iter().map(|call| call.range).collect(),
+        }));
+    }
+    incoming
+}
+
+/// Groups the calls made from `caller` by the function being called, resolved to
+/// their definition in `scope`. Calls to built-in functions are left out as they have no location.
+pub fn outgoing_calls(
+    caller: &CallHierarchyItem, calls: &[Call], scope: &[(PathBuf, Vec<Declaration>)],
+) -> Vec<CallHierarchyOutgoingCall> {
+    let mut callees: Vec<(&str, Vec<&Call>)> = vec![];
+    for call in calls.iter().filter(|call| call.caller == caller.name && call.caller_range == caller.range) {
+        match callees.iter_mut().find(|(name, _)| *name == call.callee) {
+            Some((_, calls)) => calls.push(call),
+            None => callees.push((&call.callee, vec![call])),
+        }
+    }
+
+    let mut outgoing = vec![];
+    for (name, calls) in callees {
+        let definition = scope
+            .iter()
+            .flat_map(|(path, declarations)| declarations.iter().map(move |declaration| (path, declaration)))
+            .find(|(_, declaration)| declaration.kind == DeclarationKind::Function && declaration.name == name);
+        let (definition_path, definition) = match definition {
+            Some(definition) => definition,
+            None => continue,
+        };
+        outgoing.push(CallHierarchyOutgoingCall {
+            to: item(definition_path, definition, calls.iter().any(|call| call.active)),
+            from_ranges: calls.iter().map(|call| call.range).collect(),
+        });
+    }
+    outgoing
+}
+
+#[cfg(test)]
+mod call_hierarchy_test {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    use tree_sitter::{Parser, Tree};
+
+    use crate::call_hierarchy::{collect_calls, incoming_calls, outgoing_calls, prepare, Call};
+    use crate::symbols::{self, Declaration};
+
+    static SOURCE: &str = r#"#define SHADOWS
+
+float shadow(float depth) { return depth; }
+float fog(float depth) { return max(depth, 0.0) * 0.5; }
+
+float light(float depth) {
+#ifdef SHADOWS
+    return shadow(depth);
+#else
+    return fog(depth);
+#endif
+}
+
+void main() {
+#if MC_VERSION >= 11700
+    light(1.0);
+#endif
+    fog(2.0);
+    light(0.5);
+}
+"#;
+
+    fn parse() -> Tree {
+        let mut parser = Parser::new();
+        parser.set_language(tree_sitter_glsl::language()).unwrap();
+        parser.parse(SOURCE, None).unwrap()
+    }
+
+    fn program(sources: &[&str]) -> HashMap<String, String> {
+        symbols::defines(sources.iter().copied())
+    }
+
+    /// The calls as `(caller, callee, line, active)`.
+    fn summary(calls: &[Call]) -> Vec<(&str, &str, u32, bool)> {
+        calls
+            .iter()
+            .map(|call| (call.caller.as_str(), call.callee.as_str(), call.range.start.line, call.active))
+            .collect()
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_collect_calls() {
+        let tree = parse();
+
+        let calls = collect_calls(&tree, SOURCE, &[program(&["#define MC_VERSION 11902", SOURCE])]);
+        assert_eq!(
+            summary(&calls),
+            vec![
+                ("fog", "max", 3, true),
+                ("light", "shadow", 7, true),
+                ("light", "fog", 9, false),
+                ("main", "light", 15, true),
+                ("main", "fog", 17, true),
+                ("main", "light", 18, true),
+            ]
+        );
+        assert_eq!(calls[1].caller_range.start.line, 5);
+        assert_eq!(calls[1].range.start.character, 11);
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_is_active() {
+        let tree = parse();
+        let active = |programs: &[HashMap<String, String>]| -> Vec<bool> {
+            collect_calls(&tree, SOURCE, programs).iter().map(|call| call.active).collect()
+        };
+
+        // without MC_VERSION and with SHADOWS undefined again
+        let old = program(&[SOURCE, "#undef SHADOWS"]);
+        assert_eq!(active(std::slice::from_ref(&old)), vec![true, false, true, false, true, true]);
+
+        // a call is active if it is in any of the programs
+        let current = program(&["#define MC_VERSION 11902", SOURCE]);
+        assert_eq!(active(&[old, current]), vec![true; 6]);
+
+        // and every call is active without any program to go by
+        assert_eq!(active(&[]), vec![true; 6]);
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_incoming_outgoing() {
+        let tree = parse();
+        let path = PathBuf::from("/shaders/final.fsh");
+        let scope: Vec<(PathBuf, Vec<Declaration>)> = vec![(path.clone(), symbols::collect_declarations(&tree, SOURCE))];
+        let calls = vec![(path, collect_calls(&tree, SOURCE, &[program(&[SOURCE])]))];
+
+        let fog = prepare("fog", &scope);
+        assert_eq!(fog.len(), 1);
+        assert_eq!(fog[0].selection_range.start.line, 3);
+        assert!(prepare("max", &scope).is_empty());
+
+        let incoming = incoming_calls(&fog[0], &calls, &scope);
+        let callers: Vec<_> = incoming
+            .iter()
+            .map(|call| (call.from.name.as_str(), call.from_ranges.len()))
+            .collect();
+        assert_eq!(callers, vec![("light", 1), ("main", 1)]);
+        // light only calls fog when SHADOWS isn't defined
+        assert!(incoming[0].from.detail.as_ref().unwrap().ends_with("(inactive preprocessor branch)"));
+        assert_eq!(incoming[1].from.detail.as_deref(), Some("void main()"));
+
+        // calls to built-in functions have nowhere to go
+        let main = prepare("main", &scope);
+        let outgoing = outgoing_calls(&main[0], &calls[0].1, &scope);
+        let callees: Vec<_> = outgoing
+            .iter()
+            .map(|call| (call.to.name.as_str(), call.from_ranges.len()))
+            .collect();
+        assert_eq!(callees, vec![("light", 2), ("fog", 1)]);
+        assert!(outgoing_calls(&prepare("fog", &scope)[0], &calls[0].1, &scope).is_empty());
+    }
+}
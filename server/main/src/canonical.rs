@@ -0,0 +1,100 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+// the file systems of these are case-insensitive unless set up otherwise.
+const CASE_INSENSITIVE: bool = cfg!(any(windows, target_os = "macos"));
+
+/// The path identifying the file at `path` however it's spelled, with `..` and symlinks resolved and,
+/// on platforms where file names are case-insensitive, lowercased. Only used to tell whether two paths are
+/// the same file, files are still read and shown with the path they were found with.
+///
+/// The part of `path` that doesn't exist, like a missing include or a file in a zipped pack, is kept
+/// as written after resolving `.` and `..`.
+pub fn canonicalize(path: &Path) -> PathBuf {
+    let canonical = path
+        .ancestors()
+        .take_while(|ancestor| !ancestor.as_os_str().is_empty())
+        .find_map(|ancestor| Some((fs::canonicalize(ancestor).ok()?, path.strip_prefix(ancestor).unwrap())))
+        .map_or_else(|| normalize(path), |(existing, rest)| normalize(&existing.join(rest)));
+
+    match CASE_INSENSITIVE {
+        true => PathBuf::from(canonical.to_string_lossy().to_lowercase()),
+        false => canonical,
+    }
+}
+
+/// Resolves `.` and `..` in `path` without looking at the file system.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                // there's nothing above the root
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => normalized.push(".."),
+            },
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod canonical_test {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use tempdir::TempDir;
+
+    use crate::canonical::{canonicalize, normalize};
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_normalize() {
+        assert_eq!(
+            normalize(Path::new("/shaders/lib/../lib/./common.glsl")),
+            PathBuf::from("/shaders/lib/common.glsl")
+        );
+        assert_eq!(normalize(Path::new("/../shaders")), PathBuf::from("/shaders"));
+        assert_eq!(normalize(Path::new("lib/../../common.glsl")), PathBuf::from("../common.glsl"));
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_canonicalize() {
+        let tmp_dir = TempDir::new("mcshader").unwrap();
+        let root = fs::canonicalize(tmp_dir.path()).unwrap();
+        let lib = root.join("shaders").join("lib");
+        fs::create_dir_all(&lib).unwrap();
+        fs::write(lib.join("common.glsl"), "").unwrap();
+
+        let common = canonicalize(&lib.join("common.glsl"));
+        assert_eq!(canonicalize(&lib.join("..").join("lib").join("common.glsl")), common);
+        assert_eq!(
+            canonicalize(&root.join("shaders").join(".").join("lib").join("common.glsl")),
+            common
+        );
+
+        // missing files are still identified by their folder
+        assert_eq!(
+            canonicalize(&lib.join("..").join("missing.glsl")),
+            canonicalize(&root.join("shaders").join("missing.glsl"))
+        );
+        // as are files in zipped packs, which aren't on disk
+        fs::write(root.join("pack.zip"), "").unwrap();
+        assert_eq!(
+            canonicalize(&root.join("pack.zip").join("shaders").join("..").join("shaders").join("final.fsh")),
+            canonicalize(&root.join("pack.zip").join("shaders").join("final.fsh"))
+        );
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&lib, root.join("shaders").join("linked")).unwrap();
+            assert_eq!(canonicalize(&root.join("shaders").join("linked").join("common.glsl")), common);
+        }
+    }
+}
@@ -0,0 +1,554 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Result};
+use path_slash::PathExt;
+use rust_lsp::lsp::LSPEndpoint;
+use rust_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString};
+use serde::Serialize;
+use slog::Level;
+use tree_sitter::Parser;
+
+use crate::archive;
+use crate::opengl::{self, ShaderValidator};
+use crate::url_norm::FromUrl;
+use crate::baseline::{self, Baseline, BASELINE_FILE};
+use crate::configuration::{ProjectConfig, ValidatorBackend, ValidatorConfig};
+use crate::executor::{CancelToken, LintExecutor};
+use crate::{graph, is_top_level, sarif, suppressions, symbol_index, MinecraftShaderLanguageServer};
+
+pub static USAGE: &str = "usage: mcshader-lsp check [--format text|json|sarif] [--glslang <path> | --opengl]
+                         [--baseline <file>] [--update-baseline] [<pack>]
+
+Lints every program of the shader pack at <pack> (the current directory by default), which may also
+be a zipped pack, exiting with 1 if there are any errors. Shaders are compiled with glslang unless
+--opengl is given or the [validator] of the pack's .mcglsl.toml says otherwise.
+
+Diagnostics in the baseline (<pack>/.mcglsl-baseline.json by default) are not reported, and baseline
+entries that no longer occur are reported as fixed. --update-baseline writes the current diagnostics
+to the baseline instead of reporting them.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Sarif,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Validator {
+    // path to the glslangValidator executable.
+    Glslang(PathBuf),
+    OpenGl,
+}
+
+impl Validator {
+    /// The validator set in the project config, glslang from the PATH by default.
+    pub fn from_config(config: &ValidatorConfig) -> Validator {
+        match config.backend {
+            Some(ValidatorBackend::OpenGl) => Validator::OpenGl,
+            _ => Validator::Glslang(config.glslang_path.clone().unwrap_or_else(|| "glslangValidator".into())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckOptions {
+    pub pack: PathBuf,
+    pub format: OutputFormat,
+    // defaults to the one from the project config.
+    pub validator: Option<Validator>,
+    // defaults to the BASELINE_FILE of the pack.
+    pub baseline: Option<PathBuf>,
+    pub update_baseline: bool,
+}
+
+impl CheckOptions {
+    pub fn parse(args: &[String]) -> Result<CheckOptions> {
+        let mut options = CheckOptions {
+            pack: PathBuf::from("."),
+            format: OutputFormat::Text,
+            validator: None,
+            baseline: None,
+            update_baseline: false,
+        };
+
+        let mut pack = None;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--format" => {
+                    options.format = match args.next().map(String::as_str) {
+                        Some("text") => OutputFormat::Text,
+                        Some("json") => OutputFormat::Json,
+                        Some("sarif") => OutputFormat::Sarif,
+                        Some(format) => return Err(anyhow!("unknown output format {:?}", format)),
+                        None => return Err(anyhow!("missing value for --format")),
+                    }
+                }
+                "--glslang" => match args.next() {
+                    Some(path) => options.validator = Some(Validator::Glslang(path.into())),
+                    None => return Err(anyhow!("missing value for --glslang")),
+                },
+                "--opengl" => options.validator = Some(Validator::OpenGl),
+                "--baseline" => match args.next() {
+                    Some(path) => options.baseline = Some(path.into()),
+                    None => return Err(anyhow!("missing value for --baseline")),
+                },
+                "--update-baseline" => options.update_baseline = true,
+                flag if flag.starts_with("--") => return Err(anyhow!("unknown option {:?}", flag)),
+                path if pack.is_none() => pack = Some(PathBuf::from(path)),
+                path => return Err(anyhow!("unexpected argument {:?}", path)),
+            }
+        }
+
+        if let Some(pack) = pack {
+            options.pack = pack;
+        }
+        Ok(options)
+    }
+}
+
+/// A diagnostic found in a pack, with its location relative to the pack root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    pub file: String,
+    // 1-indexed, like compiler output.
+    pub line: u32,
+    pub severity: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+impl Finding {
+    fn new(root: &Path, path: &Path, diagnostic: &Diagnostic) -> Finding {
+        let severity = match diagnostic.severity {
+            Some(DiagnosticSeverity::ERROR) | None => "error",
+            Some(DiagnosticSeverity::WARNING) => "warning",
+            Some(DiagnosticSeverity::INFORMATION) => "info",
+            Some(_) => "hint",
+        };
+        let code = match &diagnostic.code {
+            Some(NumberOrString::String(code)) => Some(code.clone()),
+            Some(NumberOrString::Number(code)) => Some(code.to_string()),
+            None => None,
+        };
+        Finding {
+            file: path.strip_prefix(root).unwrap_or(path).to_slash_lossy(),
+            line: diagnostic.range.start.line + 1,
+            severity,
+            message: diagnostic.message.clone(),
+            code,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == "error"
+    }
+}
+
+/// Runs the `check` subcommand with the arguments following it, returning the exit code.
+pub fn run(args: &[String]) -> i32 {
+    let _guard = logging::set_logger_with_level(Level::Warning);
+
+    let options = match CheckOptions::parse(args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return 2;
+        }
+    };
+
+    let root = match fs::canonicalize(&options.pack) {
+        Ok(root) => root,
+        Err(e) => {
+            eprintln!("error reading {:?}: {}", options.pack, e);
+            return 2;
+        }
+    };
+    let root = if archive::is_archive(&root) {
+        match archive::pack_root(&root) {
+            Some(root) => root,
+            None => {
+                eprintln!("no shader pack found in {:?}", options.pack);
+                return 2;
+            }
+        }
+    } else {
+        root
+    };
+    let config = match ProjectConfig::load(&root) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+
+    let validator = options.validator.clone().unwrap_or_else(|| Validator::from_config(&config.validator));
+    let validator: Arc<dyn ShaderValidator> = match &validator {
+        Validator::Glslang(executable) => match opengl::GlslangValidator::new(executable.clone()) {
+            Ok(validator) => Arc::new(validator),
+            Err(e) => {
+                eprintln!("{}", e);
+                return 2;
+            }
+        },
+        Validator::OpenGl => Arc::new(opengl::OpenGlContext::new()),
+    };
+
+    let (server, mut diagnostics) = match check(root, config, validator) {
+        Ok(checked) => checked,
+        Err(e) => {
+            eprintln!("error checking {:?}: {}", options.pack, e);
+            return 2;
+        }
+    };
+
+    let baseline_path = options.baseline.clone().unwrap_or_else(|| server.root.join(BASELINE_FILE));
+    if options.update_baseline {
+        let baseline = write_baseline(&server.root, &diagnostics);
+        return match baseline.save(&baseline_path) {
+            Ok(_) => {
+                eprintln!("wrote {} entries to {:?}", baseline.entries.len(), baseline_path);
+                0
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                2
+            }
+        };
+    }
+
+    match Baseline::load(&baseline_path) {
+        Ok(Some(baseline)) => apply_baseline(&server.root, &baseline, &mut diagnostics),
+        Ok(None) => {}
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    }
+
+    let has_errors = diagnostics
+        .values()
+        .flatten()
+        .any(|diagnostic| matches!(diagnostic.severity, Some(DiagnosticSeverity::ERROR) | None));
+
+    match options.format {
+        OutputFormat::Text => print!("{}", format_text(&findings(&server.root, diagnostics))),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&findings(&server.root, diagnostics)).unwrap()),
+        OutputFormat::Sarif => {
+            let log = sarif::report(&server.root, &mut server.graph.lock().unwrap(), &diagnostics);
+            println!("{}", serde_json::to_string_pretty(&log).unwrap())
+        }
+    }
+
+    if has_errors {
+        1
+    } else {
+        0
+    }
+}
+
+/// Lints every program of the pack at `root` with `validator`, returning the server that loaded the pack
+/// alongside the diagnostics.
+pub fn check(
+    root: PathBuf, config: ProjectConfig, validator: Arc<dyn ShaderValidator>,
+) -> Result<(MinecraftShaderLanguageServer, HashMap<PathBuf, Vec<Diagnostic>>)> {
+    let mut parser = Parser::new();
+    parser.set_language(tree_sitter_glsl::language()).unwrap();
+
+    let server = offline_server(
+        root,
+        config,
+        Arc::new(Mutex::new(graph::CachedStableGraph::new())),
+        Arc::new(Mutex::new(symbol_index::SymbolIndex::new())),
+        validator,
+        Arc::new(Mutex::new(parser)),
+    );
+    server.build_initial_graph();
+
+    let diagnostics = lint_pack(&server)?;
+    Ok((server, diagnostics))
+}
+
+/// A server that isn't connected to a client, for linting a pack outside of an editor session.
+/// Anything sent to the client is discarded.
+pub fn offline_server(
+    root: PathBuf, config: ProjectConfig, graph: Arc<Mutex<graph::CachedStableGraph>>, symbol_index: Arc<Mutex<symbol_index::SymbolIndex>>,
+    validator: Arc<dyn ShaderValidator>, tree_sitter: Arc<Mutex<Parser>>,
+) -> MinecraftShaderLanguageServer {
+    MinecraftShaderLanguageServer {
+        endpoint: LSPEndpoint::create_lsp_output_with_output_stream(io::sink),
+        graph,
+        symbol_index,
+        root,
+        command_provider: None,
+        opengl_context: validator,
+        tree_sitter,
+        documents: HashMap::new(),
+        document_paths: HashMap::new(),
+        config,
+        log_guard: None,
+        lints: Arc::new(LintExecutor::new()),
+        scan: Mutex::new(None),
+        client_progress: false,
+        pending_rename: Mutex::new(None),
+        baseline: None,
+        notifier: None,
+    }
+}
+
+/// Lints every top-level program in the graph of `server`, merging the diagnostics of files that are
+/// part of several programs. Suppression comments are applied to the merged diagnostics, so that
+/// they're only reported as unused if they don't disable anything in any program.
+pub fn lint_pack(server: &MinecraftShaderLanguageServer) -> Result<HashMap<PathBuf, Vec<Diagnostic>>> {
+    let mut programs: Vec<PathBuf> = {
+        let graph = server.graph.lock().unwrap();
+        graph
+            .graph
+            .node_indices()
+            .map(|node| graph.get_node(node))
+            .filter(|path| path.strip_prefix(&server.root).map_or(false, is_top_level))
+            .collect()
+    };
+    programs.sort();
+
+    let mut merged: HashMap<PathBuf, Vec<Diagnostic>> = HashMap::new();
+    for program in programs {
+        let mut diagnostics = server.lint(&program, &CancelToken::default())?;
+        server.add_include_diagnostics(&mut diagnostics);
+
+        for (uri, diagnostics) in diagnostics {
+            let file = merged.entry(PathBuf::from_url(uri)).or_default();
+            for diagnostic in diagnostics {
+                if !file.contains(&diagnostic) {
+                    file.push(diagnostic);
+                }
+            }
+        }
+    }
+
+    for (path, diagnostics) in merged.iter_mut() {
+        if let Ok(source) = server.document_source(path) {
+            suppressions::apply(&source, diagnostics, true);
+        }
+        server.config.apply_rule_levels(diagnostics);
+    }
+    Ok(merged)
+}
+
+/// Builds a baseline accepting all of `diagnostics`.
+pub fn write_baseline(root: &Path, diagnostics: &HashMap<PathBuf, Vec<Diagnostic>>) -> Baseline {
+    let files: Vec<(String, &[Diagnostic], String)> = diagnostics
+        .iter()
+        .filter(|(_, diagnostics)| !diagnostics.is_empty())
+        .map(|(path, diagnostics)| {
+            let file = path.strip_prefix(root).unwrap_or(path).to_slash_lossy();
+            (file, &diagnostics[..], archive::read_to_string(path).unwrap_or_default())
+        })
+        .collect();
+    Baseline::new(files.iter().map(|(file, diagnostics, source)| (file.clone(), *diagnostics, source.as_str())))
+}
+
+/// Drops the diagnostics of the linted pack that are in `baseline`. As the whole pack was linted, entries
+/// of files without any diagnostics left are fixed too.
+pub fn apply_baseline(root: &Path, baseline: &Baseline, diagnostics: &mut HashMap<PathBuf, Vec<Diagnostic>>) {
+    for entry in &baseline.entries {
+        diagnostics.entry(root.join(&entry.file)).or_default();
+    }
+    for (path, file_diagnostics) in diagnostics.iter_mut() {
+        let file = path.strip_prefix(root).unwrap_or(path).to_slash_lossy();
+        baseline.apply(&file, file_diagnostics, &archive::read_to_string(path).unwrap_or_default(), true);
+    }
+}
+
+/// Flattens `diagnostics` into findings ordered by file and line.
+pub fn findings(root: &Path, diagnostics: HashMap<PathBuf, Vec<Diagnostic>>) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = diagnostics
+        .iter()
+        .flat_map(|(path, diagnostics)| diagnostics.iter().map(move |diagnostic| Finding::new(root, path, diagnostic)))
+        .collect();
+    findings.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+    findings
+}
+
+pub fn format_text(findings: &[Finding]) -> String {
+    let mut out = String::new();
+    for finding in findings {
+        out.push_str(&format!("{}:{}: {}: {}\n", finding.file, finding.line, finding.severity, finding.message));
+    }
+
+    let errors = findings.iter().filter(|finding| finding.is_error()).count();
+    let warnings = findings.iter().filter(|finding| finding.severity == "warning").count();
+    let fixed = findings
+        .iter()
+        .filter(|finding| finding.code.as_deref() == Some(baseline::FIXED_ENTRY_CODE))
+        .count();
+    if fixed > 0 {
+        out.push_str(&format!("{} errors, {} warnings, {} fixed baseline entries\n", errors, warnings, fixed));
+    } else {
+        out.push_str(&format!("{} errors, {} warnings\n", errors, warnings));
+    }
+    out
+}
+
+#[cfg(test)]
+mod cli_test {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    use rust_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range};
+
+    use crate::baseline::{BaselineEntry, FIXED_ENTRY_CODE};
+    use crate::configuration::{ValidatorBackend, ValidatorConfig};
+    use crate::cli::{apply_baseline, findings, format_text, lint_pack, write_baseline, CheckOptions, OutputFormat, Validator};
+    use crate::opengl::MockShaderValidator;
+    use crate::test::{copy_to_and_set_root, new_temp_server};
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_parse_options() {
+        let options = CheckOptions::parse(&args(&[])).unwrap();
+        assert_eq!(options.pack, PathBuf::from("."));
+        assert_eq!(options.format, OutputFormat::Text);
+        assert_eq!(options.validator, None);
+
+        let options = CheckOptions::parse(&args(&["--format", "json", "--glslang", "/opt/glslang", "packs/sildurs"])).unwrap();
+        assert_eq!(options.pack, PathBuf::from("packs/sildurs"));
+        assert_eq!(options.format, OutputFormat::Json);
+        assert_eq!(options.validator, Some(Validator::Glslang("/opt/glslang".into())));
+
+        let options = CheckOptions::parse(&args(&["--opengl", "--format", "sarif"])).unwrap();
+        assert_eq!(options.format, OutputFormat::Sarif);
+        assert_eq!(options.validator, Some(Validator::OpenGl));
+        assert_eq!(options.baseline, None);
+        assert!(!options.update_baseline);
+
+        let options = CheckOptions::parse(&args(&["--baseline", "ci/baseline.json", "--update-baseline"])).unwrap();
+        assert_eq!(options.baseline, Some(PathBuf::from("ci/baseline.json")));
+        assert!(options.update_baseline);
+
+        assert_eq!(
+            Validator::from_config(&ValidatorConfig::default()),
+            Validator::Glslang("glslangValidator".into())
+        );
+        assert_eq!(
+            Validator::from_config(&ValidatorConfig {
+                backend: Some(ValidatorBackend::OpenGl),
+                glslang_path: None
+            }),
+            Validator::OpenGl
+        );
+
+        assert!(CheckOptions::parse(&args(&["--format", "xml"])).is_err());
+        assert!(CheckOptions::parse(&args(&["--format"])).is_err());
+        assert!(CheckOptions::parse(&args(&["a", "b"])).is_err());
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_format_text() {
+        let root = PathBuf::from("/pack");
+        let mut diagnostics = HashMap::new();
+        diagnostics.insert(
+            root.join("shaders").join("final.fsh"),
+            vec![
+                Diagnostic {
+                    range: Range::new(Position::new(11, 0), Position::new(11, 1000)),
+                    severity: Some(DiagnosticSeverity::WARNING),
+                    message: "unused variable".into(),
+                    ..Diagnostic::default()
+                },
+                Diagnostic {
+                    range: Range::new(Position::new(4, 0), Position::new(4, 1000)),
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    message: "undeclared identifier".into(),
+                    ..Diagnostic::default()
+                },
+            ],
+        );
+
+        assert_eq!(
+            format_text(&findings(&root, diagnostics)),
+            "shaders/final.fsh:5: error: undeclared identifier\nshaders/final.fsh:12: warning: unused variable\n1 errors, 1 warnings\n"
+        );
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_lint_pack() {
+        let mut mockgl = MockShaderValidator::new();
+        mockgl.expect_vendor().returning(|| "Mesa".into());
+        mockgl
+            .expect_validate()
+            .returning(|_, _| Some("ERROR: 0:8: 'x' : undeclared identifier\n".into()));
+        let mut server = new_temp_server(Some(Box::new(mockgl)));
+        server.tree_sitter.lock().unwrap().set_language(tree_sitter_glsl::language()).unwrap();
+
+        let (_tmp_dir, tmp_path) = copy_to_and_set_root("./testdata/05", &mut server);
+        server.build_initial_graph();
+
+        let findings = findings(&tmp_path, lint_pack(&server).unwrap());
+        let errors: Vec<_> = findings.iter().filter(|finding| finding.is_error()).collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].file, "shaders/final.fsh");
+        assert_eq!(errors[0].line, 8);
+
+        // none of the included functions are called
+        let unused: Vec<_> = findings
+            .iter()
+            .filter(|finding| finding.code.as_deref() == Some("unused-include"))
+            .map(|finding| (finding.file.as_str(), finding.line))
+            .collect();
+        assert_eq!(unused, vec![("shaders/final.fsh", 3), ("shaders/final.fsh", 4), ("shaders/test/banana.glsl", 1)]);
+
+        server.endpoint.request_shutdown();
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_baseline() {
+        let mut mockgl = MockShaderValidator::new();
+        mockgl.expect_vendor().returning(|| "Mesa".into());
+        mockgl
+            .expect_validate()
+            .returning(|_, _| Some("ERROR: 0:8: 'x' : undeclared identifier\n".into()));
+        let mut server = new_temp_server(Some(Box::new(mockgl)));
+        server.tree_sitter.lock().unwrap().set_language(tree_sitter_glsl::language()).unwrap();
+
+        let (_tmp_dir, tmp_path) = copy_to_and_set_root("./testdata/05", &mut server);
+        server.build_initial_graph();
+
+        let mut baseline = write_baseline(&tmp_path, &lint_pack(&server).unwrap());
+        assert_eq!(baseline.entries.len(), 4);
+
+        let mut diagnostics = lint_pack(&server).unwrap();
+        apply_baseline(&tmp_path, &baseline, &mut diagnostics);
+        assert!(findings(&tmp_path, diagnostics).is_empty());
+
+        baseline.entries.push(BaselineEntry {
+            file: "shaders/common.glsl".into(),
+            line: 2,
+            message: "'y' : undeclared identifier".into(),
+            fingerprint: "0000000000000000".into(),
+        });
+        let mut diagnostics = lint_pack(&server).unwrap();
+        apply_baseline(&tmp_path, &baseline, &mut diagnostics);
+        let findings = findings(&tmp_path, diagnostics);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].file, "shaders/common.glsl");
+        assert_eq!(findings[0].code.as_deref(), Some(FIXED_ENTRY_CODE));
+        assert!(format_text(&findings).ends_with("0 errors, 0 warnings, 1 fixed baseline entries\n"));
+
+        server.endpoint.request_shutdown();
+    }
+}
@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use lazy_static::lazy_static;
+use path_slash::PathExt;
+use regex::Regex;
+use rust_lsp::lsp_types::{Command, Diagnostic, Position, Range, TextEdit, Url, WorkspaceEdit};
+
+use crate::builtins::{self, GlslVersion, Profile};
+use crate::TreeType;
+
+/// Client side command that applies the `WorkspaceEdit` passed as its only argument.
+pub static APPLY_EDIT_COMMAND: &str = "mcglsl.applyWorkspaceEdit";
+
+lazy_static! {
+    static ref RE_UNDECLARED: Regex =
+        Regex::new(r#"(?i)undeclared|undefined|no matching overloaded function|unable to find compatible overloaded function|no function with name"#)
+            .unwrap();
+    static ref RE_QUOTED_IDENTIFIER: Regex = Regex::new(r#"["'`]([A-Za-z_]\w*)"#).unwrap();
+    static ref RE_IDENTIFIER: Regex = Regex::new(r#"[A-Za-z_]\w*"#).unwrap();
+    static ref RE_STORAGE_QUALIFIER: Regex =
+        Regex::new(r#"^\s*(?:(?:flat|smooth|noperspective|centroid|invariant)\s+)*(varying|attribute)\b"#).unwrap();
+    // errors about the qualifiers themselves, like "'varying' : no longer supported in core profile".
+    static ref RE_STORAGE_QUALIFIER_ERROR: Regex = Regex::new(r#"\b(varying|attribute)\b"#).unwrap();
+    static ref RE_DIRECTIVE: Regex = Regex::new(r#"^\s*#\s*(\w+)(?:\s+(\w+))?"#).unwrap();
+}
+
+/// A fix for a diagnostic, offered to the client as a command applying its edit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fix {
+    pub title: String,
+    pub edit: WorkspaceEdit,
+}
+
+impl Fix {
+    pub fn new(title: String, uri: &Url, edits: Vec<TextEdit>) -> Fix {
+        let mut changes = HashMap::new();
+        changes.insert(uri.clone(), edits);
+        Fix {
+            title,
+            edit: WorkspaceEdit::new(changes),
+        }
+    }
+
+    pub fn into_command(self) -> Command {
+        Command {
+            title: self.title,
+            command: APPLY_EDIT_COMMAND.into(),
+            arguments: Some(vec![serde_json::to_value(self.edit).unwrap()]),
+        }
+    }
+}
+
+/// The identifiers an "undeclared identifier" style diagnostic is about. Drivers don't agree on whether
+/// the name is part of the message, so all the identifiers on the offending line are returned if it isn't.
+pub fn undeclared_names(diagnostic: &Diagnostic, source: &str) -> Vec<String> {
+    if !RE_UNDECLARED.is_match(&diagnostic.message) {
+        return vec![];
+    }
+
+    let quoted: Vec<String> = RE_QUOTED_IDENTIFIER
+        .captures_iter(&diagnostic.message)
+        .map(|captures| captures[1].to_string())
+        .collect();
+    if !quoted.is_empty() {
+        return quoted;
+    }
+
+    let line = source.lines().nth(diagnostic.range.start.line as usize).unwrap_or_default();
+    let code = line.split("//").next().unwrap_or_default();
+    let mut names: Vec<String> = vec![];
+    for name in RE_IDENTIFIER.find_iter(code).map(|m| m.as_str().to_string()) {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// Declares the OptiFine/Iris uniform `name` at the top of the file.
+pub fn declare_uniform(uri: &Url, source: &str, name: &str) -> Option<Fix> {
+    let uniform = builtins::uniform(name)?;
+    let declaration = format!("uniform {} {};", uniform.ty, uniform.name);
+    Some(Fix::new(
+        format!("Declare `{}`", declaration),
+        uri,
+        vec![insert_line(header_end(source), declaration)],
+    ))
+}
+
+/// Includes the file at `include` (as written in the directive) to bring in the definition of `function`.
+pub fn add_include(uri: &Url, source: &str, include: &str, function: &str) -> Fix {
+    let directive = format!("#include \"{}\"", include);
+    Fix::new(
+        format!("Add `{}` for `{}`", directive, function),
+        uri,
+        vec![insert_line(header_end(source), directive)],
+    )
+}
+
+/// The path to use in an `#include` of `file`, relative to the first include root (the `shaders` folder
+/// unless configured otherwise) containing it as that resolves the same from every file in the pack.
+pub fn include_path(include_roots: &[PathBuf], file: &Path) -> Option<String> {
+    let relative = include_roots.iter().find_map(|include_root| file.strip_prefix(include_root).ok())?;
+    Some(format!("/{}", relative.to_slash()?))
+}
+
+/// Converts the `varying`/`attribute` declaration `diagnostic` is on to `in`/`out`, if the diagnostic is
+/// about that qualifier and it's compiled under a core profile where they no longer exist.
+pub fn convert_storage_qualifier(
+    uri: &Url, source: &str, diagnostic: &Diagnostic, version: GlslVersion, stage: Option<TreeType>,
+) -> Option<Fix> {
+    let is_core = version.number == 140 || (version.number >= 150 && version.profile == Profile::Core);
+    if !is_core {
+        return None;
+    }
+
+    let line = diagnostic.range.start.line;
+    let text = source.lines().nth(line as usize)?;
+    let qualifier = RE_STORAGE_QUALIFIER.captures(text)?.get(1).unwrap();
+    let about_qualifier = RE_STORAGE_QUALIFIER_ERROR
+        .captures_iter(&diagnostic.message)
+        .any(|captures| &captures[1] == qualifier.as_str());
+    if !about_qualifier {
+        return None;
+    }
+    let replacement = match (qualifier.as_str(), stage?) {
+        ("attribute", TreeType::Vertex) => "in",
+        ("varying", TreeType::Vertex) => "out",
+        ("varying", TreeType::Fragment) => "in",
+        _ => return None,
+    };
+
+    let range = Range::new(
+        Position::new(line, qualifier.start() as u32),
+        Position::new(line, qualifier.end() as u32),
+    );
+    Some(Fix::new(
+        format!("Replace `{}` with `{}`", qualifier.as_str(), replacement),
+        uri,
+        vec![TextEdit {
+            range,
+            new_text: replacement.into(),
+        }],
+    ))
+}
+
+/// Adds a `#version` to the top-level file of a program that doesn't have one, either the version
+/// most packs are written against or a modern one with the compatibility profile.
+pub fn insert_version(uri: &Url) -> Vec<Fix> {
+    ["120", "330 compatibility"]
+        .iter()
+        .map(|version| {
+            let directive = format!("#version {}", version);
+            Fix::new(format!("Insert `{}`", directive), uri, vec![insert_line(0, directive)])
+        })
+        .collect()
+}
+
+pub fn insert_line(line: u32, text: String) -> TextEdit {
+    TextEdit {
+        range: Range::new(Position::new(line, 0), Position::new(line, 0)),
+        new_text: format!("{}\n", text),
+    }
+}
+
+/// The line following the `#version`, `#extension` and `#include` directives of a file, where new
+/// declarations and includes are inserted. Directives inside conditionals are passed over, apart
+/// from an include guard wrapping the whole file which new lines are inserted into.
+pub fn header_end(source: &str) -> u32 {
+    let mut directives = source.lines().enumerate().filter_map(|(i, line)| {
+        let captures = RE_DIRECTIVE.captures(line)?;
+        Some((i, captures.get(1).unwrap().as_str(), captures.get(2).map(|name| name.as_str())))
+    });
+
+    let mut insert_at = 0;
+    let mut top_depth = 0;
+    let mut depth = 0;
+
+    let code_lines = source
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with("//"));
+    let guard: Vec<(usize, &str)> = code_lines.take(2).collect();
+    if let [(_, first), (second_line, second)] = guard[..] {
+        let first = RE_DIRECTIVE.captures(first);
+        let second = RE_DIRECTIVE.captures(second);
+        if let (Some(first), Some(second)) = (first, second) {
+            if &first[1] == "ifndef" && &second[1] == "define" && first.get(2).map(|n| n.as_str()) == second.get(2).map(|n| n.as_str()) {
+                top_depth = 1;
+                insert_at = second_line + 1;
+            }
+        }
+    }
+
+    for (i, directive, _) in &mut directives {
+        match directive {
+            "if" | "ifdef" | "ifndef" => depth += 1,
+            "endif" => depth = (depth as usize).saturating_sub(1) as i32,
+            "version" | "extension" | "include" if depth == top_depth => insert_at = i + 1,
+            _ => {}
+        }
+    }
+
+    insert_at as u32
+}
+
+#[cfg(test)]
+mod code_actions_test {
+    use std::path::{Path, PathBuf};
+
+    use rust_lsp::lsp_types::{Diagnostic, Position, Range, TextEdit, Url};
+
+    use crate::builtins::{GlslVersion, Profile};
+    use crate::code_actions::{convert_storage_qualifier, declare_uniform, header_end, include_path, undeclared_names};
+    use crate::TreeType;
+
+    fn diagnostic(line: u32, message: &str) -> Diagnostic {
+        Diagnostic {
+            range: Range::new(Position::new(line, 0), Position::new(line, 1000)),
+            message: message.into(),
+            ..Diagnostic::default()
+        }
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_undeclared_names() {
+        let source = "void main() {\n    vec3 pos = cameraPosition + foo; // bar\n}";
+
+        assert_eq!(undeclared_names(&diagnostic(1, "undefined variable \"cameraPosition\""), source), vec!["cameraPosition"]);
+        assert_eq!(
+            undeclared_names(&diagnostic(1, "undeclared identifier"), source),
+            vec!["vec3", "pos", "cameraPosition", "foo"]
+        );
+        assert!(undeclared_names(&diagnostic(1, "syntax error"), source).is_empty());
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_header_end() {
+        assert_eq!(header_end("void main() {}"), 0);
+        assert_eq!(header_end("#version 120\n\nvoid main() {}"), 1);
+        assert_eq!(
+            header_end("#version 120\n#include \"/lib/a.glsl\"\n#ifdef SHADOWS\n#include \"/lib/b.glsl\"\n#endif\nvoid main() {}"),
+            2
+        );
+        assert_eq!(header_end("// shadows\n#ifndef SHADOWS_GLSL\n#define SHADOWS_GLSL\nfloat f;\n#endif"), 3);
+        assert_eq!(
+            header_end("#ifndef SHADOWS_GLSL\n#define SHADOWS_GLSL\n#include \"common.glsl\"\nfloat f;\n#endif"),
+            3
+        );
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_fixes() {
+        let uri = Url::parse("file:///shaders/lib/a.glsl").unwrap();
+
+        let fix = declare_uniform(&uri, "#version 120\nvoid main() {}", "cameraPosition").unwrap();
+        assert_eq!(fix.title, "Declare `uniform vec3 cameraPosition;`");
+        assert_eq!(
+            fix.edit.changes.unwrap()[&uri],
+            vec![TextEdit {
+                range: Range::new(Position::new(1, 0), Position::new(1, 0)),
+                new_text: "uniform vec3 cameraPosition;\n".into(),
+            }]
+        );
+        assert!(declare_uniform(&uri, "", "notAUniform").is_none());
+
+        let core = GlslVersion {
+            number: 330,
+            profile: Profile::Core,
+        };
+        let source = "varying vec2 texcoord;\nflat varying int id;\nattribute vec4 mc_Entity;";
+        let error = "'varying' : no longer supported in core profile";
+        let fix = convert_storage_qualifier(&uri, source, &diagnostic(1, error), core, Some(TreeType::Fragment)).unwrap();
+        assert_eq!(
+            fix.edit.changes.unwrap()[&uri],
+            vec![TextEdit {
+                range: Range::new(Position::new(1, 5), Position::new(1, 12)),
+                new_text: "in".into(),
+            }]
+        );
+        let error = "'attribute' : no longer supported in core profile";
+        assert!(convert_storage_qualifier(&uri, source, &diagnostic(2, error), core, Some(TreeType::Vertex)).is_some());
+        let error = "'varying' : no longer supported in core profile";
+        assert!(convert_storage_qualifier(&uri, source, &diagnostic(0, error), GlslVersion::default(), Some(TreeType::Vertex)).is_none());
+        // other errors on the line, and ones about the other qualifier
+        assert!(convert_storage_qualifier(&uri, source, &diagnostic(0, "'texcoord' : redefinition"), core, Some(TreeType::Vertex)).is_none());
+        let error = "'attribute' : no longer supported in core profile";
+        assert!(convert_storage_qualifier(&uri, source, &diagnostic(0, error), core, Some(TreeType::Vertex)).is_none());
+
+        let include_roots = [PathBuf::from("/pack/shaders/lib"), PathBuf::from("/pack/shaders")];
+        assert_eq!(
+            include_path(&include_roots, Path::new("/pack/shaders/lib/shadows.glsl")),
+            Some("/shadows.glsl".into())
+        );
+        assert_eq!(
+            include_path(&include_roots, Path::new("/pack/shaders/world0/common.glsl")),
+            Some("/world0/common.glsl".into())
+        );
+        assert_eq!(include_path(&include_roots, Path::new("/elsewhere/a.glsl")), None);
+    }
+}
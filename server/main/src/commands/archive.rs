@@ -0,0 +1,37 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{format_err, Result};
+use serde_json::Value;
+
+use crate::archive;
+use crate::url_norm::FromJson;
+
+use super::Invokeable;
+
+/// Lists the files in the zip archive given as the argument, for the client to pick one to open.
+pub struct ArchiveFiles;
+
+impl Invokeable for ArchiveFiles {
+    fn run_command(&self, _: &Path, arguments: &[Value]) -> Result<Value> {
+        let path = PathBuf::from_json(arguments.first().ok_or_else(|| format_err!("expected the path of an archive"))?)?;
+        if !archive::is_archive(&path) {
+            return Err(format_err!("{:?} is not a zip archive", path));
+        }
+
+        let files = archive::files(&path)
+            .into_iter()
+            .map(|file| Value::String(file.to_string_lossy().into()))
+            .collect();
+        Ok(Value::Array(files))
+    }
+}
+
+/// Reads a file from a zipped pack, which the client shows as a read-only document.
+pub struct ArchiveFile;
+
+impl Invokeable for ArchiveFile {
+    fn run_command(&self, _: &Path, arguments: &[Value]) -> Result<Value> {
+        let path = PathBuf::from_json(arguments.first().ok_or_else(|| format_err!("expected the path of a file"))?)?;
+        Ok(Value::String(archive::read_to_string(&path)?))
+    }
+}
@@ -0,0 +1,34 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use slog_scope::{info, warn};
+
+use crate::graph::CachedStableGraph;
+
+use super::Invokeable;
+
+/// Checks that the lookup maps of the include graph agree with the graph itself, reporting the node
+/// and edge counts along with every mismatch found.
+pub struct GraphCheckCommand {
+    pub graph: Arc<Mutex<CachedStableGraph>>,
+}
+
+impl Invokeable for GraphCheckCommand {
+    fn run_command(&self, _: &Path, _: &[Value]) -> Result<Value> {
+        let graph = self.graph.lock().unwrap();
+        let mismatches = graph.check();
+
+        match mismatches.is_empty() {
+            true => info!("include graph is consistent"; "nodes" => graph.graph.node_count()),
+            false => warn!("include graph is inconsistent"; "mismatches" => format!("{:?}", mismatches)),
+        }
+
+        Ok(json!({
+            "nodes": graph.graph.node_count(),
+            "edges": graph.graph.edge_count(),
+            "mismatches": mismatches,
+        }))
+    }
+}
@@ -1,52 +1,45 @@
-use std::cell::RefCell;
-use std::fs::OpenOptions;
-use std::io::prelude::*;
-use std::path::Path;
-use std::rc::Rc;
-
-use petgraph::dot::Config;
-use serde_json::Value;
-
-use petgraph::dot;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
 use anyhow::{format_err, Result};
+use serde_json::Value;
 use slog_scope::info;
 
+use crate::archive;
 use crate::graph::CachedStableGraph;
+use crate::graph_export::{GraphExport, Scope};
+use crate::url_norm::FromJson;
 
 use super::Invokeable;
 
+/// Writes the include graph of the pack as a Graphviz DOT file, to the path given as the second argument,
+/// else to the `graph_output` of the project config, else to `graph.dot`. Returns the path written to.
 pub struct GraphDotCommand {
-    pub graph: Rc<RefCell<CachedStableGraph>>,
+    pub graph: Arc<Mutex<CachedStableGraph>>,
+    // the `graph_output` of the project config.
+    pub graph_output: Option<PathBuf>,
 }
 
 impl Invokeable for GraphDotCommand {
-    fn run_command(&self, root: &Path, _: &[Value]) -> Result<Value> {
-        let filepath = root.join("graph.dot");
+    fn run_command(&self, root: &Path, arguments: &[Value]) -> Result<Value> {
+        let filepath = match arguments.get(1) {
+            Some(path) => PathBuf::from_json(path)?,
+            None => archive::output_dir(root).join(self.graph_output.as_deref().unwrap_or_else(|| Path::new("graph.dot"))),
+        };
 
         info!("generating dot file"; "path" => filepath.as_os_str().to_str());
 
-        let mut file = OpenOptions::new().truncate(true).write(true).create(true).open(filepath).unwrap();
-
-        let mut write_data_closure = || -> Result<(), std::io::Error> {
-            let graph = self.graph.as_ref();
-
-            file.seek(std::io::SeekFrom::Start(0))?;
-            file.write_all("digraph {\n\tgraph [splines=ortho]\n\tnode [shape=box]\n".as_bytes())?;
-            file.write_all(
-                dot::Dot::with_config(&graph.borrow().graph, &[Config::GraphContentOnly])
-                    .to_string()
-                    .as_bytes(),
-            )?;
-            file.write_all("\n}".as_bytes())?;
-            file.flush()?;
-            file.seek(std::io::SeekFrom::Start(0))?;
-            Ok(())
+        let dot = GraphExport::new(&self.graph.lock().unwrap(), root, &Scope::Pack, archive::exists)?.to_dot();
+        let write = || -> std::io::Result<()> {
+            if let Some(parent) = filepath.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&filepath, dot)
         };
-
-        match write_data_closure() {
+        match write() {
             Err(err) => Err(format_err!("error generating graphviz data: {}", err)),
-            _ => Ok(Value::Null),
+            _ => Ok(Value::String(filepath.to_string_lossy().into())),
         }
     }
 }
@@ -0,0 +1,43 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{format_err, Result};
+use serde_json::Value;
+use slog_scope::info;
+
+use crate::archive;
+use crate::graph::CachedStableGraph;
+use crate::graph_export::{GraphExport, Scope};
+use crate::url_norm::FromJson;
+
+use super::Invokeable;
+
+/// Returns the include graph as JSON or a Mermaid diagram. Takes the target the command was run for,
+/// then optionally the format, `"json"` (the default) or `"mermaid"`, and a `{"file": path}` or
+/// `{"program": path}` object limiting the output to the subgraph around a file or program.
+pub struct GraphExportCommand {
+    pub graph: Arc<Mutex<CachedStableGraph>>,
+}
+
+impl Invokeable for GraphExportCommand {
+    fn run_command(&self, root: &Path, arguments: &[Value]) -> Result<Value> {
+        let scope = match arguments.get(2) {
+            None | Some(Value::Null) => Scope::Pack,
+            Some(Value::Object(scope)) => match (scope.get("file"), scope.get("program")) {
+                (Some(file), None) => Scope::File(PathBuf::from_json(file)?),
+                (None, Some(program)) => Scope::Program(PathBuf::from_json(program)?),
+                _ => return Err(format_err!("expected either a file or a program to export the graph around")),
+            },
+            Some(scope) => return Err(format_err!("expected a file or program to export the graph around, got {}", scope)),
+        };
+
+        info!("exporting include graph"; "scope" => format!("{:?}", scope));
+
+        let export = GraphExport::new(&self.graph.lock().unwrap(), root, &scope, archive::exists)?;
+        match arguments.get(1).and_then(Value::as_str) {
+            None | Some("json") => Ok(serde_json::to_value(export)?),
+            Some("mermaid") => Ok(Value::String(export.to_mermaid())),
+            Some(format) => Err(format_err!("unknown graph format {:?}, expected json or mermaid", format)),
+        }
+    }
+}
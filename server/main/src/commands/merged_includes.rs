@@ -1,5 +1,4 @@
-use std::cell::RefCell;
-use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 use std::{
     collections::HashMap,
     path::{Path, PathBuf},
@@ -11,8 +10,7 @@ use petgraph::graph::NodeIndex;
 
 use anyhow::{format_err, Result};
 
-use std::fs;
-
+use crate::archive;
 use crate::dfs;
 use crate::merge_views::FilialTuple;
 use crate::source_mapper::SourceMapper;
@@ -21,17 +19,17 @@ use crate::{graph::CachedStableGraph, merge_views, url_norm::FromJson};
 use super::Invokeable;
 
 pub struct VirtualMergedDocument {
-    pub graph: Rc<RefCell<CachedStableGraph>>,
+    pub graph: Arc<Mutex<CachedStableGraph>>,
 }
 
 impl VirtualMergedDocument {
     // TODO: DUPLICATE CODE
     fn get_file_toplevel_ancestors(&self, uri: &Path) -> Result<Option<Vec<petgraph::stable_graph::NodeIndex>>> {
-        let curr_node = match self.graph.borrow_mut().find_node(uri) {
+        let curr_node = match self.graph.lock().unwrap().find_node(uri) {
             Some(n) => n,
             None => return Err(format_err!("node not found {:?}", uri)),
         };
-        let roots = self.graph.borrow().collect_root_ancestors(curr_node);
+        let roots = self.graph.lock().unwrap().collect_root_ancestors(curr_node);
         if roots.is_empty() {
             return Ok(None);
         }
@@ -39,7 +37,7 @@ impl VirtualMergedDocument {
     }
 
     pub fn get_dfs_for_node(&self, root: NodeIndex) -> Result<Vec<FilialTuple>, dfs::error::CycleError> {
-        let graph_ref = self.graph.borrow();
+        let graph_ref = self.graph.lock().unwrap();
 
         let dfs = dfs::Dfs::new(&graph_ref, root);
 
@@ -50,14 +48,14 @@ impl VirtualMergedDocument {
         let mut sources = HashMap::new();
 
         for node in nodes {
-            let graph = self.graph.borrow();
+            let graph = self.graph.lock().unwrap();
             let path = graph.get_node(node.child);
 
             if sources.contains_key(&path) {
                 continue;
             }
 
-            let source = match fs::read_to_string(&path) {
+            let source = match archive::read_to_string(&path) {
                 Ok(s) => s,
                 Err(e) => return Err(format_err!("error reading {:?}: {}", path, e)),
             };
@@ -81,7 +79,7 @@ impl Invokeable for VirtualMergedDocument {
             Err(e) => return Err(e),
         };
 
-        //info!("ancestors for {}:\n\t{:?}", path, file_ancestors.iter().map(|e| self.graph.borrow().graph.node_weight(*e).unwrap().clone()).collect::<Vec<String>>());
+        //info!("ancestors for {}:\n\t{:?}", path, file_ancestors.iter().map(|e| self.graph.lock().unwrap().graph.node_weight(*e).unwrap().clone()).collect::<Vec<String>>());
 
         // the set of all filepath->content. TODO: change to Url?
         let mut all_sources: HashMap<PathBuf, String> = HashMap::new();
@@ -89,7 +87,7 @@ impl Invokeable for VirtualMergedDocument {
         // if we are a top-level file (this has to be one of the set defined by Optifine, right?)
         if file_ancestors.is_empty() {
             // gather the list of all descendants
-            let root = self.graph.borrow_mut().find_node(&path).unwrap();
+            let root = self.graph.lock().unwrap().find_node(&path).unwrap();
             let tree = match self.get_dfs_for_node(root) {
                 Ok(tree) => tree,
                 Err(e) => return Err(e.into()),
@@ -102,7 +100,7 @@ impl Invokeable for VirtualMergedDocument {
             all_sources.extend(sources);
 
             let mut source_mapper = SourceMapper::new(all_sources.len());
-            let graph = self.graph.borrow();
+            let graph = self.graph.lock().unwrap();
             let view = merge_views::MergeViewBuilder::new(&tree, &all_sources, &graph, &mut source_mapper).build();
             return Ok(serde_json::value::Value::String(view));
         }
@@ -5,9 +5,13 @@ use serde_json::Value;
 use anyhow::{format_err, Result};
 use slog_scope::info;
 
+pub mod archive;
+pub mod graph_check;
 pub mod graph_dot;
+pub mod graph_export;
 pub mod merged_includes;
 pub mod parse_tree;
+pub mod sarif_report;
 
 pub struct CustomCommandProvider {
     commands: HashMap<String, Box<dyn Invokeable>>,
@@ -31,6 +35,6 @@ impl CustomCommandProvider {
     }
 }
 
-pub trait Invokeable {
+pub trait Invokeable: Send + Sync {
     fn run_command(&self, root: &Path, arguments: &[Value]) -> Result<Value>;
 }
@@ -1,8 +1,6 @@
 use std::{
-    cell::RefCell,
-    fs,
     path::{Path, PathBuf},
-    rc::Rc,
+    sync::{Arc, Mutex},
 };
 
 use anyhow::{format_err, Result};
@@ -10,12 +8,13 @@ use serde_json::Value;
 use slog_scope::warn;
 use tree_sitter::{Parser, TreeCursor};
 
+use crate::archive;
 use crate::url_norm::FromJson;
 
 use super::Invokeable;
 
 pub struct TreeSitterSExpr {
-    pub tree_sitter: Rc<RefCell<Parser>>,
+    pub tree_sitter: Arc<Mutex<Parser>>,
 }
 
 impl Invokeable for TreeSitterSExpr {
@@ -24,9 +23,9 @@ impl Invokeable for TreeSitterSExpr {
 
         warn!("parsing"; "path" => path.to_str().unwrap().to_string());
 
-        let source = fs::read_to_string(path)?;
+        let source = archive::read_to_string(&path)?;
 
-        let tree = match self.tree_sitter.borrow_mut().parse(source, None) {
+        let tree = match self.tree_sitter.lock().unwrap().parse(source, None) {
             Some(tree) => tree,
             None => return Err(format_err!("tree-sitter parsing resulted in no parse tree")),
         };
@@ -0,0 +1,53 @@
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{format_err, Result};
+use serde_json::Value;
+use slog_scope::info;
+use tree_sitter::Parser;
+
+use crate::archive;
+use crate::baseline::{Baseline, BASELINE_FILE};
+use crate::configuration::ProjectConfig;
+use crate::graph::CachedStableGraph;
+use crate::opengl::ShaderValidator;
+use crate::symbol_index::SymbolIndex;
+use crate::{cli, sarif};
+
+use super::Invokeable;
+
+pub struct SarifReportCommand {
+    pub graph: Arc<Mutex<CachedStableGraph>>,
+    pub symbol_index: Arc<Mutex<SymbolIndex>>,
+    pub opengl_context: Arc<dyn ShaderValidator>,
+    pub tree_sitter: Arc<Mutex<Parser>>,
+}
+
+impl Invokeable for SarifReportCommand {
+    fn run_command(&self, root: &Path, _: &[Value]) -> Result<Value> {
+        let filepath = archive::output_dir(root).join("diagnostics.sarif");
+
+        info!("generating sarif file"; "path" => filepath.as_os_str().to_str());
+
+        // lints the files as saved on disk, the same as CI would see them.
+        let server = cli::offline_server(
+            root.to_path_buf(),
+            ProjectConfig::load(root)?,
+            self.graph.clone(),
+            self.symbol_index.clone(),
+            self.opengl_context.clone(),
+            self.tree_sitter.clone(),
+        );
+        let mut diagnostics = cli::lint_pack(&server)?;
+        if let Some(baseline) = Baseline::load(&root.join(BASELINE_FILE))? {
+            cli::apply_baseline(root, &baseline, &mut diagnostics);
+        }
+        let log = sarif::report(root, &mut self.graph.lock().unwrap(), &diagnostics);
+
+        match fs::write(&filepath, serde_json::to_string_pretty(&log)?) {
+            Err(err) => Err(format_err!("error writing sarif file: {}", err)),
+            _ => Ok(Value::Null),
+        }
+    }
+}
@@ -1,12 +1,244 @@
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
+use anyhow::{anyhow, Result};
+use rust_lsp::lsp_types::{Diagnostic, DiagnosticSeverity};
+use serde::Deserialize;
 use slog::Level;
-use slog_scope::error;
+use serde_json::Value;
+use slog_scope::{error, info};
 
+use crate::archive;
+use crate::diagnostics_parser::rule_id;
+use crate::formatter::BraceStyle;
+
+pub static PROJECT_CONFIG_FILE: &str = ".mcglsl.toml";
 
 pub fn handle_log_level_change<F: FnOnce(Level)>(log_level: String, callback: F) {
     match Level::from_str(log_level.as_str()) {
         Ok(level) => callback(level),
         Err(_) => error!("got unexpected log level from config"; "level" => log_level),
     };
-}
\ No newline at end of file
+}
+
+/// Applies the `mcglsl` section of the editor settings, replacing the logger held by `log_guard` when
+/// the log level changed.
+pub fn handle_settings_change(settings: &Value, log_guard: &mut Option<slog_scope::GlobalLoggerGuard>) {
+    #[derive(Deserialize)]
+    struct Configuration {
+        #[serde(alias = "logLevel")]
+        log_level: String,
+    }
+
+    if let Some(settings) = settings.as_object().unwrap().get("mcglsl") {
+        let config: Configuration = serde_json::from_value(settings.to_owned()).unwrap();
+
+        info!("got updated configuration"; "config" => settings.to_string());
+
+        handle_log_level_change(config.log_level, |level| {
+            log_guard.take(); // set to None so Drop is invoked
+            *log_guard = Some(logging::set_logger_with_level(level));
+        })
+    }
+}
+
+/// Settings shared by everyone working on a pack, from the `.mcglsl.toml` in the workspace root.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ProjectConfig {
+    // extensions of includable files on top of the built-in ones, without the leading dot.
+    pub extensions: Vec<String>,
+    // folders, relative to the pack root, that `#include "/..."` is looked up in. `shaders` if empty.
+    pub include_roots: Vec<PathBuf>,
+    // profile from shaders.properties whose option values programs are compiled with.
+    pub profile: Option<String>,
+    pub validator: ValidatorConfig,
+    // extra defines inserted after the `#version` of every program.
+    pub preamble: BTreeMap<String, toml::Value>,
+    // severity overrides keyed by diagnostic code, see `diagnostics_parser::rule_id`.
+    pub rules: BTreeMap<String, RuleLevel>,
+    pub format: FormatConfig,
+    // where the include graph is written as a DOT file, relative to the pack root. `graph.dot` if unset.
+    pub graph_output: Option<PathBuf>,
+}
+
+/// The `[validator]` table, choosing what programs are compiled with.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct ValidatorConfig {
+    pub backend: Option<ValidatorBackend>,
+    // defaults to glslangValidator from the PATH.
+    pub glslang_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValidatorBackend {
+    OpenGl,
+    Glslang,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleLevel {
+    Off,
+    Hint,
+    Info,
+    Warning,
+    Error,
+}
+
+/// The `[format]` table. Anything left out falls back to the editor's settings or the formatter defaults.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct FormatConfig {
+    pub indent_width: Option<usize>,
+    pub use_tabs: Option<bool>,
+    pub brace_style: Option<BraceStyle>,
+    pub max_width: Option<usize>,
+    pub max_blank_lines: Option<usize>,
+}
+
+impl ProjectConfig {
+    /// Reads the project config of the workspace at `root`, which is entirely optional.
+    pub fn load(root: &Path) -> Result<ProjectConfig> {
+        let path = root.join(PROJECT_CONFIG_FILE);
+        match archive::read_to_string(&path) {
+            Ok(contents) => ProjectConfig::parse(&contents).map_err(|e| anyhow!("error parsing {:?}: {}", path, e)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(ProjectConfig::default()),
+            Err(e) => Err(anyhow!("error reading {:?}: {}", path, e)),
+        }
+    }
+
+    pub fn parse(contents: &str) -> Result<ProjectConfig> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    /// The absolute folders that `#include "/..."` is looked up in, in order.
+    pub fn include_roots(&self, root: &Path) -> Vec<PathBuf> {
+        if self.include_roots.is_empty() {
+            return vec![root.join("shaders")];
+        }
+        self.include_roots.iter().map(|include_root| root.join(include_root)).collect()
+    }
+
+    pub fn is_source_extension(&self, extension: &str) -> bool {
+        ["vsh", "fsh", "gsh", "csh", "glsl", "inc"].contains(&extension)
+            || self.extensions.iter().any(|ext| ext.trim_start_matches('.') == extension)
+    }
+
+    /// Changes the severity of the diagnostics with a configured rule level, dropping the ones turned off.
+    pub fn apply_rule_levels(&self, diagnostics: &mut Vec<Diagnostic>) {
+        if self.rules.is_empty() {
+            return;
+        }
+        diagnostics.retain_mut(|diagnostic| {
+            let severity = match self.rules.get(&rule_id(diagnostic)) {
+                Some(RuleLevel::Off) => return false,
+                Some(RuleLevel::Hint) => DiagnosticSeverity::HINT,
+                Some(RuleLevel::Info) => DiagnosticSeverity::INFORMATION,
+                Some(RuleLevel::Warning) => DiagnosticSeverity::WARNING,
+                Some(RuleLevel::Error) => DiagnosticSeverity::ERROR,
+                None => return true,
+            };
+            diagnostic.severity = Some(severity);
+            true
+        });
+    }
+}
+
+#[cfg(test)]
+mod configuration_test {
+    use std::path::{Path, PathBuf};
+
+    use rust_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString};
+
+    use crate::configuration::{FormatConfig, ProjectConfig, RuleLevel, ValidatorBackend};
+    use crate::formatter::BraceStyle;
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_parse_project_config() {
+        assert_eq!(ProjectConfig::parse("").unwrap(), ProjectConfig::default());
+
+        let config = ProjectConfig::parse(
+            r#"
+[format]
+indent_width = 2
+brace_style = "next_line"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.format,
+            FormatConfig {
+                indent_width: Some(2),
+                brace_style: Some(BraceStyle::NextLine),
+                ..FormatConfig::default()
+            }
+        );
+
+        assert!(ProjectConfig::parse("[format]\nbrace_style = \"sideways\"").is_err());
+
+        let config = ProjectConfig::parse(
+            r#"
+extensions = ["h", ".hlsl"]
+include_roots = ["shaders", "shaders/lib"]
+profile = "HIGH"
+graph_output = "docs/includes.dot"
+
+[validator]
+backend = "glslang"
+glslang_path = "/opt/glslangValidator"
+
+[preamble]
+MC_VERSION = 11902
+
+[rules]
+unused-include = "off"
+compiler-warning = "error"
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.profile.as_deref(), Some("HIGH"));
+        assert_eq!(config.graph_output, Some(PathBuf::from("docs/includes.dot")));
+        assert_eq!(config.validator.backend, Some(ValidatorBackend::Glslang));
+        assert_eq!(config.validator.glslang_path, Some(PathBuf::from("/opt/glslangValidator")));
+        assert_eq!(config.preamble["MC_VERSION"], toml::Value::Integer(11902));
+        assert_eq!(config.rules["unused-include"], RuleLevel::Off);
+        assert!(config.is_source_extension("h") && config.is_source_extension("hlsl") && config.is_source_extension("fsh"));
+        assert!(!config.is_source_extension("txt"));
+        assert_eq!(
+            config.include_roots(Path::new("/pack")),
+            vec![PathBuf::from("/pack/shaders"), PathBuf::from("/pack/shaders/lib")]
+        );
+        assert_eq!(ProjectConfig::default().include_roots(Path::new("/pack")), vec![PathBuf::from("/pack/shaders")]);
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_apply_rule_levels() {
+        let config = ProjectConfig::parse("[rules]\nunused-include = \"off\"\ncompiler-warning = \"error\"").unwrap();
+        let mut diagnostics = vec![
+            Diagnostic {
+                severity: Some(DiagnosticSeverity::WARNING),
+                code: Some(NumberOrString::String("unused-include".into())),
+                ..Diagnostic::default()
+            },
+            Diagnostic {
+                severity: Some(DiagnosticSeverity::WARNING),
+                ..Diagnostic::default()
+            },
+            Diagnostic {
+                severity: Some(DiagnosticSeverity::ERROR),
+                ..Diagnostic::default()
+            },
+        ];
+
+        config.apply_rule_levels(&mut diagnostics);
+        let severities: Vec<_> = diagnostics.iter().map(|diagnostic| diagnostic.severity).collect();
+        assert_eq!(severities, vec![Some(DiagnosticSeverity::ERROR), Some(DiagnosticSeverity::ERROR)]);
+    }
+}
@@ -1,7 +1,8 @@
-use std::{collections::HashMap, cell::OnceCell, path::Path};
+use std::{collections::HashMap, cell::OnceCell, path::{Path, PathBuf}};
 
 use regex::Regex;
-use rust_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range};
+use rust_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+use serde::{Deserialize, Serialize};
 use slog_scope::debug;
 use url::Url;
 
@@ -10,8 +11,31 @@ use crate::{
     graph::CachedStableGraph,
     opengl,
     source_mapper::{SourceMapper, SourceNum},
+    url_norm::ToUrl,
 };
 
+/// Attached to compiler diagnostics as their `data`, so that code actions know which program the
+/// diagnostic was reported for when it comes from an included file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiagnosticData {
+    // the top-level file of the program.
+    pub program: PathBuf,
+}
+
+/// The code of a diagnostic, or one for its severity for compiler diagnostics as they don't come
+/// with a code of their own.
+pub fn rule_id(diagnostic: &Diagnostic) -> String {
+    match &diagnostic.code {
+        Some(NumberOrString::String(code)) => code.clone(),
+        Some(NumberOrString::Number(code)) => code.to_string(),
+        None => match diagnostic.severity {
+            Some(DiagnosticSeverity::ERROR) | None => "compiler-error".into(),
+            Some(DiagnosticSeverity::WARNING) => "compiler-warning".into(),
+            Some(_) => "compiler-note".into(),
+        },
+    }
+}
+
 pub struct DiagnosticsParser<'a, T: opengl::ShaderValidator + ?Sized> {
     line_offset: OnceCell<u32>,
     line_regex: OnceCell<Regex>,
@@ -52,6 +76,11 @@ impl<'a, T: opengl::ShaderValidator + ?Sized> DiagnosticsParser<'a, T> {
 
         debug!("diagnostics regex selected"; "regex" => self.get_line_regex() .as_str());
 
+        // the top-level file is always the first source in the merged view
+        let data = DiagnosticData {
+            program: graph.get_node(source_mapper.get_node(0.into())),
+        };
+
         for line in output_lines {
             let diagnostic_capture = match self.get_line_regex().captures(line) {
                 Some(d) => d,
@@ -103,10 +132,10 @@ impl<'a, T: opengl::ShaderValidator + ?Sized> DiagnosticsParser<'a, T> {
                 related_information: None,
                 tags: None,
                 code_description: Option::None,
-                data: Option::None,
+                data: serde_json::to_value(&data).ok(),
             };
 
-            let origin_url = Url::from_file_path(origin).unwrap();
+            let origin_url = Path::new(&origin).to_url().unwrap();
             match diagnostics.get_mut(&origin_url) {
                 Some(d) => d.push(diagnostic),
                 None => {
@@ -126,7 +155,7 @@ mod diagnostics_test {
     use url::Url;
 
     use crate::{
-        diagnostics_parser::DiagnosticsParser, opengl::MockShaderValidator, source_mapper::SourceMapper, test::new_temp_server,
+        diagnostics_parser::{DiagnosticData, DiagnosticsParser}, opengl::MockShaderValidator, source_mapper::SourceMapper, test::new_temp_server,
     };
 
     #[test]
@@ -145,16 +174,18 @@ mod diagnostics_test {
             let path: PathBuf = "c:\\home\\noah\\.minecraft\\shaderpacks\\test\\shaders\\final.fsh".into();
 
             let mut source_mapper = SourceMapper::new(0);
-            source_mapper.get_num(server.graph.borrow_mut().add_node(&path));
+            source_mapper.get_num(server.graph.lock().unwrap().add_node(&path));
 
             let parser = DiagnosticsParser::new(server.opengl_context.as_ref());
 
             let results =
-                parser.parse_diagnostics_output(output.to_string(), path.parent().unwrap(), &source_mapper, &server.graph.borrow());
+                parser.parse_diagnostics_output(output.to_string(), path.parent().unwrap(), &source_mapper, &server.graph.lock().unwrap());
 
             assert_eq!(results.len(), 1);
             let first = results.into_iter().next().unwrap();
-            assert_eq!(first.0, Url::from_file_path(path).unwrap());
+            assert_eq!(first.0, Url::from_file_path(&path).unwrap());
+            let data: DiagnosticData = serde_json::from_value(first.1[0].data.clone().unwrap()).unwrap();
+            assert_eq!(data.program, path);
             server.endpoint.request_shutdown();
         });
     }
@@ -178,12 +209,12 @@ ERROR: 0:15: 'varying' : syntax error: syntax error
             let path: PathBuf = "c:\\home\\noah\\.minecraft\\shaderpacks\\test\\shaders\\final.fsh".into();
 
             let mut source_mapper = SourceMapper::new(0);
-            source_mapper.get_num(server.graph.borrow_mut().add_node(&path));
+            source_mapper.get_num(server.graph.lock().unwrap().add_node(&path));
 
             let parser = DiagnosticsParser::new(server.opengl_context.as_ref());
 
             let results =
-                parser.parse_diagnostics_output(output.to_string(), path.parent().unwrap(), &source_mapper, &server.graph.borrow());
+                parser.parse_diagnostics_output(output.to_string(), path.parent().unwrap(), &source_mapper, &server.graph.lock().unwrap());
 
             assert_eq!(results.len(), 1);
             let first = results.into_iter().next().unwrap();
@@ -0,0 +1,220 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+use std::thread;
+
+use rust_lsp::lsp_types::NumberOrString;
+use slog_scope::{debug, error};
+use thiserror::Error;
+
+/// Returned by work that stopped early because it was cancelled.
+#[derive(Debug, Error)]
+#[error("cancelled")]
+pub struct Cancelled;
+
+/// Tells a lint that it was cancelled, so that it can stop between programs rather than compiling
+/// every one of them only for the result to be thrown away.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed)
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Errors with `Cancelled` if the work was cancelled.
+    pub fn check(&self) -> Result<(), Cancelled> {
+        match self.is_cancelled() {
+            true => Err(Cancelled),
+            false => Ok(()),
+        }
+    }
+}
+
+type Run = Box<dyn FnOnce(&NumberOrString, &CancelToken) + Send>;
+
+struct Job {
+    token: NumberOrString,
+    path: PathBuf,
+    cancel: CancelToken,
+    run: Run,
+}
+
+// the jobs that are queued or running, and signalled whenever one is done.
+type Pending = (Mutex<Vec<(NumberOrString, PathBuf, CancelToken)>>, Condvar);
+
+/// Runs lints one at a time on a thread of their own, so that requests are answered while programs
+/// compile. A lint is cancelled by a newer lint of the same file, by an edit to the file or by a
+/// `window/workDoneProgress/cancel` with its token.
+pub struct LintExecutor {
+    jobs: Mutex<mpsc::Sender<Job>>,
+    pending: Arc<Pending>,
+    next: AtomicU64,
+}
+
+impl LintExecutor {
+    pub fn new() -> LintExecutor {
+        let (jobs, receiver) = mpsc::channel::<Job>();
+        let pending: Arc<Pending> = Arc::new((Mutex::new(vec![]), Condvar::new()));
+
+        let worker_pending = pending.clone();
+        thread::Builder::new()
+            .name("lint".into())
+            .spawn(move || {
+                for Job { token, path, cancel, run } in receiver {
+                    if cancel.is_cancelled() {
+                        debug!("skipping cancelled lint"; "path" => path.to_str().unwrap());
+                    } else if panic::catch_unwind(AssertUnwindSafe(|| run(&token, &cancel))).is_err() {
+                        error!("linting panicked"; "path" => path.to_str().unwrap());
+                    }
+
+                    let (pending, done) = &*worker_pending;
+                    pending.lock().unwrap().retain(|(pending, _, _)| *pending != token);
+                    done.notify_all();
+                }
+            })
+            .unwrap();
+
+        LintExecutor {
+            jobs: Mutex::new(jobs),
+            pending,
+            next: AtomicU64::new(0),
+        }
+    }
+
+    /// Queues `run` to lint `path`, cancelling the lints of `path` queued before it. `run` is given the
+    /// token of the lint, which is also returned, and is expected to stop once it's cancelled.
+    pub fn submit(&self, path: &Path, run: impl FnOnce(&NumberOrString, &CancelToken) + Send + 'static) -> NumberOrString {
+        self.cancel_file(path);
+
+        let token = NumberOrString::String(format!("mcglsl/lint/{}", self.next.fetch_add(1, Ordering::Relaxed)));
+        let cancel = CancelToken::default();
+        self.pending
+            .0
+            .lock()
+            .unwrap()
+            .push((token.clone(), path.to_path_buf(), cancel.clone()));
+        self.jobs
+            .lock()
+            .unwrap()
+            .send(Job {
+                token: token.clone(),
+                path: path.to_path_buf(),
+                cancel,
+                run: Box::new(run),
+            })
+            .unwrap();
+        token
+    }
+
+    /// Cancels the queued and running lints of `path`, returning whether there were any.
+    pub fn cancel_file(&self, path: &Path) -> bool {
+        self.cancel_where(|_, file| file == path)
+    }
+
+    /// Cancels the lint with `token`, returning whether it was still queued or running.
+    pub fn cancel(&self, token: &NumberOrString) -> bool {
+        self.cancel_where(|pending, _| pending == token)
+    }
+
+    pub fn cancel_all(&self) {
+        self.cancel_where(|_, _| true);
+    }
+
+    fn cancel_where(&self, matches: impl Fn(&NumberOrString, &Path) -> bool) -> bool {
+        let pending = self.pending.0.lock().unwrap();
+        let mut cancelled = false;
+        for (token, path, cancel) in pending.iter() {
+            if matches(token, path) && !cancel.is_cancelled() {
+                debug!("cancelling lint"; "path" => path.to_str().unwrap(), "token" => format!("{:?}", token));
+                cancel.cancel();
+                cancelled = true;
+            }
+        }
+        cancelled
+    }
+
+    /// Blocks until every queued lint has run or was skipped.
+    pub fn wait(&self) {
+        let (pending, done) = &*self.pending;
+        let mut pending = pending.lock().unwrap();
+        while !pending.is_empty() {
+            pending = done.wait(pending).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod executor_test {
+    use std::path::Path;
+    use std::sync::{mpsc, Arc, Mutex};
+
+    use crate::executor::LintExecutor;
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_supersede() {
+        let executor = LintExecutor::new();
+        let ran = Arc::new(Mutex::new(vec![]));
+        let (started, running) = mpsc::channel();
+        let (release, blocked) = mpsc::channel::<()>();
+
+        let first_ran = ran.clone();
+        executor.submit(Path::new("/shaders/final.fsh"), move |_, cancel| {
+            started.send(()).unwrap();
+            blocked.recv().unwrap();
+            first_ran.lock().unwrap().push(("first", cancel.is_cancelled()));
+        });
+        running.recv().unwrap();
+
+        // queued behind the first lint, and cancelled before it gets to run
+        let second_ran = ran.clone();
+        executor.submit(Path::new("/shaders/composite.fsh"), move |_, _| {
+            second_ran.lock().unwrap().push(("second", false))
+        });
+        let third_ran = ran.clone();
+        executor.submit(Path::new("/shaders/composite.fsh"), move |_, cancel| {
+            third_ran.lock().unwrap().push(("third", cancel.is_cancelled()))
+        });
+
+        // an edit to the running lint's file supersedes it
+        assert!(executor.cancel_file(Path::new("/shaders/final.fsh")));
+        release.send(()).unwrap();
+        executor.wait();
+
+        assert_eq!(*ran.lock().unwrap(), vec![("first", true), ("third", false)]);
+        assert!(!executor.cancel_file(Path::new("/shaders/final.fsh")));
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_cancel() {
+        let executor = LintExecutor::new();
+        let ran = Arc::new(Mutex::new(vec![]));
+        let (release, blocked) = mpsc::channel::<()>();
+
+        let first = executor.submit(Path::new("/shaders/final.fsh"), move |_, _| blocked.recv().unwrap());
+        let second_ran = ran.clone();
+        let second = executor.submit(Path::new("/shaders/composite.fsh"), move |token, _| {
+            second_ran.lock().unwrap().push(token.clone())
+        });
+        let third_ran = ran.clone();
+        let third = executor.submit(Path::new("/shaders/deferred.fsh"), move |token, _| {
+            third_ran.lock().unwrap().push(token.clone())
+        });
+        assert_ne!(first, second);
+
+        assert!(executor.cancel(&second));
+        release.send(()).unwrap();
+        executor.wait();
+
+        assert_eq!(*ran.lock().unwrap(), vec![third.clone()]);
+        // done lints can't be cancelled anymore
+        assert!(!executor.cancel(&third));
+    }
+}
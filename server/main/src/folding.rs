@@ -0,0 +1,260 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use rust_lsp::lsp_types::{FoldingRange, FoldingRangeKind};
+use tree_sitter::Tree;
+
+lazy_static! {
+    static ref RE_DIRECTIVE: Regex = Regex::new(r#"^\s*#\s*(if|ifdef|ifndef|elif|else|endif|include)\b"#).unwrap();
+}
+
+/// Computes the folding ranges of a file: preprocessor conditionals, runs of `#include`s and block
+/// comments from a line scan, function, struct and block bodies from the tree.
+pub fn folding_ranges(source: &str, tree: Option<&Tree>) -> Vec<FoldingRange> {
+    let mut ranges = scan_directives(source);
+    if let Some(tree) = tree {
+        ranges.extend(body_ranges(tree));
+    }
+    ranges.sort_by_key(|range| (range.start_line, range.end_line));
+    ranges.dedup_by(|a, b| a.start_line == b.start_line && a.end_line == b.end_line);
+    ranges
+}
+
+fn range(start_line: usize, end_line: usize, kind: Option<FoldingRangeKind>) -> FoldingRange {
+    FoldingRange {
+        start_line: start_line as u32,
+        end_line: end_line as u32,
+        kind,
+        ..FoldingRange::default()
+    }
+}
+
+/// Scans the raw lines for conditional blocks, `#include` runs and block comments. The grammar recovers
+/// poorly from conditionals that split up declarations or functions, so these aren't taken from the tree.
+/// A conditional branch folds up to the line before the next `#elif`/`#else`/`#endif` so that it stays visible.
+fn scan_directives(source: &str) -> Vec<FoldingRange> {
+    let mut ranges = vec![];
+
+    // start lines of the currently open conditional branches
+    let mut branches: Vec<usize> = vec![];
+    let mut include_run: Option<(usize, usize)> = None;
+    let mut comment_start: Option<usize> = None;
+    let mut continued = false;
+
+    for (line_num, line) in source.lines().enumerate() {
+        // directives don't count in the middle of a block comment or a continued line
+        let in_comment = comment_start.is_some();
+        let is_continuation = continued;
+        continued = line.ends_with('\\');
+
+        if let Some(start) = comment_start {
+            if line.contains("*/") {
+                if line_num > start {
+                    ranges.push(range(start, line_num, Some(FoldingRangeKind::Comment)));
+                }
+                comment_start = None;
+            }
+        } else if let Some(start) = block_comment_start(line) {
+            if !line[start..].contains("*/") {
+                comment_start = Some(line_num);
+            }
+        }
+
+        let directive = match (in_comment || is_continuation, RE_DIRECTIVE.captures(line)) {
+            (false, Some(captures)) => captures.get(1).unwrap().as_str(),
+            _ => "",
+        };
+
+        if directive == "include" {
+            include_run = Some(include_run.map_or((line_num, line_num), |(start, _)| (start, line_num)));
+            continue;
+        } else if !line.trim().is_empty() || in_comment {
+            if let Some((start, end)) = include_run.take() {
+                if end > start {
+                    ranges.push(range(start, end, Some(FoldingRangeKind::Imports)));
+                }
+            }
+        }
+
+        match directive {
+            "if" | "ifdef" | "ifndef" => branches.push(line_num),
+            "elif" | "else" => {
+                if let Some(start) = branches.pop() {
+                    close_branch(start, line_num, &mut ranges);
+                    branches.push(line_num);
+                }
+            }
+            "endif" => {
+                if let Some(start) = branches.pop() {
+                    close_branch(start, line_num, &mut ranges);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some((start, end)) = include_run {
+        if end > start {
+            ranges.push(range(start, end, Some(FoldingRangeKind::Imports)));
+        }
+    }
+
+    ranges
+}
+
+fn close_branch(start: usize, line: usize, ranges: &mut Vec<FoldingRange>) {
+    if line > start + 1 {
+        ranges.push(range(start, line - 1, Some(FoldingRangeKind::Region)));
+    }
+}
+
+// the offset of a `/*` that isn't part of a line comment.
+fn block_comment_start(line: &str) -> Option<usize> {
+    let block = line.find("/*")?;
+    match line.find("//") {
+        Some(comment) if comment < block => None,
+        _ => Some(block),
+    }
+}
+
+fn body_ranges(tree: &Tree) -> Vec<FoldingRange> {
+    let mut ranges = vec![];
+    let mut cursor = tree.walk();
+    'walk: loop {
+        let node = cursor.node();
+        let is_body = matches!(node.kind(), "compound_statement" | "field_declaration_list");
+
+        // fold up to the line before the closing brace so that it stays visible
+        let (start, end) = (node.start_position().row, node.end_position().row);
+        if is_body && end > start + 1 {
+            ranges.push(range(start, end - 1, None));
+        }
+
+        if cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                break 'walk;
+            }
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod folding_test {
+    use rust_lsp::lsp_types::FoldingRangeKind;
+
+    use tree_sitter::Parser;
+
+    use crate::folding::folding_ranges;
+
+    fn ranges(source: &str) -> Vec<(u32, u32, Option<FoldingRangeKind>)> {
+        folding_ranges(source, None)
+            .into_iter()
+            .map(|range| (range.start_line, range.end_line, range.kind))
+            .collect()
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_bodies() {
+        let source = r#"struct Light {
+    vec3 color;
+    float strength;
+};
+vec3 shade(Light light, float shadow) {
+    vec3 color = light.color;
+    if (shadow > 0.5) {
+        color *= shadow;
+        color += 0.1;
+    } else {
+        color = vec3(0.0);
+    }
+    return color;
+}
+float luma(vec3 color) { return dot(color, vec3(0.2126, 0.7152, 0.0722)); }"#;
+
+        let mut parser = Parser::new();
+        parser.set_language(tree_sitter_glsl::language()).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let ranges: Vec<(u32, u32, Option<FoldingRangeKind>)> = folding_ranges(source, Some(&tree))
+            .into_iter()
+            .map(|range| (range.start_line, range.end_line, range.kind))
+            .collect();
+
+        // up to the line before the closing brace, and nothing for bodies on a single line
+        assert_eq!(ranges, vec![(0, 2, None), (4, 12, None), (6, 8, None), (9, 10, None)]);
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_conditionals() {
+        let source = r#"#ifdef SHADOWS
+    #if SHADOW_QUALITY > 1
+        float a;
+    #elif SHADOW_QUALITY == 1
+        float b;
+    #else
+        float c;
+    #endif
+    float d;
+#else
+    float e;
+    float f;
+#endif"#;
+
+        assert_eq!(
+            ranges(source),
+            vec![
+                (0, 8, Some(FoldingRangeKind::Region)),
+                (1, 2, Some(FoldingRangeKind::Region)),
+                (3, 4, Some(FoldingRangeKind::Region)),
+                (5, 6, Some(FoldingRangeKind::Region)),
+                (9, 11, Some(FoldingRangeKind::Region)),
+            ]
+        );
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_unbalanced_and_commented_directives() {
+        let source = r#"#endif
+/*
+#ifdef COMMENTED
+*/
+#ifdef A
+    float a;
+#define LONG_MACRO(x) x \
+#endif
+    float b;
+#endif
+#ifdef NEVER_CLOSED
+    float c;
+    float d;"#;
+
+        assert_eq!(
+            ranges(source),
+            vec![(1, 3, Some(FoldingRangeKind::Comment)), (4, 8, Some(FoldingRangeKind::Region))]
+        );
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_includes() {
+        let source = r#"#version 120
+#include "/lib/settings.glsl"
+#include "/lib/common.glsl"
+
+#include "/lib/shadows.glsl"
+void main() {}
+#include "/lib/single.glsl"
+#include "/lib/end1.glsl"
+#include "/lib/end2.glsl""#;
+
+        assert_eq!(
+            ranges(source),
+            vec![(1, 4, Some(FoldingRangeKind::Imports)), (6, 8, Some(FoldingRangeKind::Imports))]
+        );
+    }
+}
@@ -0,0 +1,655 @@
+use std::collections::BTreeSet;
+
+use anyhow::{anyhow, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+use rust_lsp::lsp_types::{FormattingOptions, Position, Range, TextEdit};
+use serde::Deserialize;
+use tree_sitter::{Node, Tree};
+
+use crate::configuration::FormatConfig;
+
+lazy_static! {
+    // lines that are handed to OptiFine/Iris as is: preprocessor directives and option comments
+    static ref RE_VERBATIM: Regex = Regex::new(r#"^\s*#|//\s*\[.*\]"#).unwrap();
+}
+
+const CONTROL_KEYWORDS: &[&str] = &["if", "for", "while", "switch", "return", "do"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BraceStyle {
+    // `void main() {`
+    SameLine,
+    // `void main()` with the brace on the line below
+    NextLine,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatStyle {
+    pub indent_width: usize,
+    pub use_tabs: bool,
+    pub brace_style: BraceStyle,
+    // argument and parameter lists that would go past this column are put one per line.
+    pub max_width: usize,
+    pub max_blank_lines: usize,
+}
+
+impl Default for FormatStyle {
+    fn default() -> Self {
+        FormatStyle {
+            indent_width: 4,
+            use_tabs: false,
+            brace_style: BraceStyle::SameLine,
+            max_width: 100,
+            max_blank_lines: 1,
+        }
+    }
+}
+
+impl FormatStyle {
+    /// The style from the project config, falling back to the editor's indentation settings.
+    pub fn new(options: &FormattingOptions, config: &FormatConfig) -> FormatStyle {
+        let default = FormatStyle::default();
+        FormatStyle {
+            indent_width: config.indent_width.unwrap_or(options.tab_size as usize),
+            use_tabs: config.use_tabs.unwrap_or(!options.insert_spaces),
+            brace_style: config.brace_style.unwrap_or(default.brace_style),
+            max_width: config.max_width.unwrap_or(default.max_width),
+            max_blank_lines: config.max_blank_lines.unwrap_or(default.max_blank_lines),
+        }
+    }
+
+    fn indent(&self, level: usize) -> String {
+        if self.use_tabs {
+            "\t".repeat(level)
+        } else {
+            " ".repeat(level * self.indent_width)
+        }
+    }
+}
+
+/// Formats the whole document, returning a single edit replacing it or none if it is already formatted.
+pub fn format_document(tree: &Tree, source: &str, style: &FormatStyle) -> Result<Vec<TextEdit>> {
+    let lines: Vec<&str> = source.lines().collect();
+    if lines.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut formatted = format_rows(tree, source, 0, lines.len() - 1, style)?;
+    if source.ends_with('\n') {
+        formatted.push('\n');
+    }
+    if formatted == source {
+        return Ok(vec![]);
+    }
+
+    let end = if source.ends_with('\n') {
+        Position::new(lines.len() as u32, 0)
+    } else {
+        Position::new((lines.len() - 1) as u32, lines[lines.len() - 1].len() as u32)
+    };
+    Ok(vec![TextEdit {
+        range: Range::new(Position::new(0, 0), end),
+        new_text: formatted,
+    }])
+}
+
+/// Formats the top-level declarations that overlap `range`, as a function can only be formatted as a whole.
+pub fn format_range(tree: &Tree, source: &str, range: Range, style: &FormatStyle) -> Result<Vec<TextEdit>> {
+    let (mut first, mut last) = (range.start.line as usize, range.end.line as usize);
+    let root = tree.root_node();
+    loop {
+        let mut cursor = root.walk();
+        let overlapping: Vec<Node> = root
+            .children(&mut cursor)
+            .filter(|node| node.end_position().row >= first && node.start_position().row <= last)
+            .collect();
+        let (start, end) = match (overlapping.first(), overlapping.last()) {
+            (Some(start), Some(end)) => (start.start_position().row.min(first), end.end_position().row.max(last)),
+            _ => break,
+        };
+        if (start, end) == (first, last) {
+            break;
+        }
+        first = start;
+        last = end;
+    }
+
+    let lines: Vec<&str> = source.lines().collect();
+    if first >= lines.len() {
+        return Ok(vec![]);
+    }
+    let last = last.min(lines.len() - 1);
+
+    let formatted = format_rows(tree, source, first, last, style)?;
+    if formatted == lines[first..=last].join("\n") {
+        return Ok(vec![]);
+    }
+
+    Ok(vec![TextEdit {
+        range: Range::new(Position::new(first as u32, 0), Position::new(last as u32, lines[last].len() as u32)),
+        new_text: formatted,
+    }])
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Word,
+    Open,
+    // the opening paren of an argument or parameter list
+    OpenList,
+    Close,
+    Comma,
+    Semicolon,
+    Dot,
+    Prefix,
+    Postfix,
+    Binary,
+    BlockOpen,
+    BlockClose,
+    // braces of an initializer list
+    InlineOpen,
+    InlineClose,
+    CaseColon,
+    LineComment,
+    BlockComment,
+    // a line that is kept exactly as written
+    Verbatim,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    kind: Kind,
+    parent_kind: &'static str,
+    start_row: usize,
+    end_row: usize,
+}
+
+fn format_rows(tree: &Tree, source: &str, first: usize, last: usize, style: &FormatStyle) -> Result<String> {
+    let lines: Vec<&str> = source.lines().collect();
+    let tokens = tokenize(tree, source, &lines, first, last);
+    let formatted = Renderer::new(style, &tokens).render();
+
+    // the formatter may only ever touch whitespace, bail if anything else would change
+    let strip = |s: &str| s.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+    if strip(&formatted) != strip(&lines[first..=last].join("\n")) {
+        return Err(anyhow!("unable to format without changing the meaning of the code"));
+    }
+    Ok(formatted)
+}
+
+fn tokenize<'a>(tree: &Tree, source: &'a str, lines: &[&'a str], first: usize, last: usize) -> Vec<Token<'a>> {
+    let leaves: Vec<Node> = leaves(tree)
+        .into_iter()
+        .filter(|leaf| leaf.start_position().row >= first && leaf.start_position().row <= last)
+        .collect();
+
+    // rows inside a multi-line comment aren't lines of their own, whatever they look like
+    let mut inside_leaf = BTreeSet::new();
+    for leaf in &leaves {
+        inside_leaf.extend(leaf.start_position().row + 1..=leaf.end_position().row);
+    }
+
+    let mut verbatim = BTreeSet::new();
+    let mut continued = false;
+    for (row, line) in lines.iter().enumerate().take(last + 1).skip(first) {
+        if continued || (!inside_leaf.contains(&row) && RE_VERBATIM.is_match(line)) {
+            verbatim.insert(row);
+            continued = line.ends_with('\\');
+        }
+    }
+    // a token starting on a verbatim line drags the lines it runs over along with it
+    for leaf in &leaves {
+        if verbatim.contains(&leaf.start_position().row) {
+            verbatim.extend(leaf.start_position().row..=leaf.end_position().row.min(last));
+        }
+    }
+
+    let verbatim_token = |row: usize| Token {
+        text: lines[row],
+        kind: Kind::Verbatim,
+        parent_kind: "",
+        start_row: row,
+        end_row: row,
+    };
+
+    let mut tokens = vec![];
+    let mut pending = verbatim.iter().copied().peekable();
+    for leaf in leaves {
+        let row = leaf.start_position().row;
+        if verbatim.contains(&row) {
+            continue;
+        }
+        while let Some(verbatim_row) = pending.next_if(|r| *r < row) {
+            tokens.push(verbatim_token(verbatim_row));
+        }
+        tokens.push(Token {
+            text: &source[leaf.start_byte()..leaf.end_byte()],
+            kind: classify(leaf, source),
+            parent_kind: leaf.parent().map_or("", |parent| parent.kind()),
+            start_row: row,
+            end_row: leaf.end_position().row,
+        });
+    }
+    tokens.extend(pending.map(verbatim_token));
+    tokens
+}
+
+fn leaves(tree: &Tree) -> Vec<Node<'_>> {
+    let mut leaves = vec![];
+    let mut cursor = tree.walk();
+    'walk: loop {
+        let node = cursor.node();
+        let atomic = matches!(
+            node.kind(),
+            "comment" | "string_literal" | "char_literal" | "number_literal" | "system_lib_string"
+        );
+        if !atomic && cursor.goto_first_child() {
+            continue;
+        }
+        if node.end_byte() > node.start_byte() && (atomic || node.child_count() == 0) {
+            leaves.push(node);
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                break 'walk;
+            }
+        }
+    }
+    leaves
+}
+
+fn classify(leaf: Node, source: &str) -> Kind {
+    let text = &source[leaf.start_byte()..leaf.end_byte()];
+    let parent = leaf.parent();
+    let parent_kind = parent.map_or("", |parent| parent.kind());
+
+    match text {
+        "{" if parent_kind == "initializer_list" => Kind::InlineOpen,
+        "}" if parent_kind == "initializer_list" => Kind::InlineClose,
+        "{" => Kind::BlockOpen,
+        "}" => Kind::BlockClose,
+        "(" if matches!(parent_kind, "argument_list" | "parameter_list") => Kind::OpenList,
+        "(" | "[" => Kind::Open,
+        ")" | "]" => Kind::Close,
+        "," => Kind::Comma,
+        ";" => Kind::Semicolon,
+        "." => Kind::Dot,
+        ":" if parent_kind == "case_statement" => Kind::CaseColon,
+        _ if leaf.kind() == "comment" && text.starts_with("//") => Kind::LineComment,
+        _ if leaf.kind() == "comment" => Kind::BlockComment,
+        _ if !leaf.is_named() && text.chars().all(is_operator_char) => match parent_kind {
+            "unary_expression" | "pointer_expression" => Kind::Prefix,
+            "update_expression" if parent.and_then(|parent| parent.child(0)).map(|n| n.id()) == Some(leaf.id()) => Kind::Prefix,
+            "update_expression" => Kind::Postfix,
+            _ => Kind::Binary,
+        },
+        _ => Kind::Word,
+    }
+}
+
+fn is_operator_char(c: char) -> bool {
+    "+-*/%<>=!&|^~?:".contains(c)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Whether there should be a space between two tokens on the same line.
+fn space_between(prev: &Token, token: &Token) -> bool {
+    let space = match (prev.kind, token.kind) {
+        (_, Kind::LineComment | Kind::BlockComment) | (Kind::BlockComment, _) => true,
+        (_, Kind::Comma | Kind::Semicolon | Kind::Close | Kind::Dot | Kind::CaseColon | Kind::Postfix | Kind::InlineClose) => false,
+        (Kind::Open | Kind::OpenList | Kind::Dot | Kind::Prefix | Kind::InlineOpen, _) => false,
+        (Kind::Word, Kind::Open | Kind::OpenList) => CONTROL_KEYWORDS.contains(&prev.text),
+        (Kind::Close, Kind::Open | Kind::OpenList) => false,
+        (_, Kind::Open) if token.text == "[" => !matches!(prev.kind, Kind::Word | Kind::Close | Kind::Postfix),
+        _ => true,
+    };
+
+    // never glue together tokens that would lex differently, e.g. `- -x` or `a b`
+    let (last, first) = match (prev.text.chars().last(), token.text.chars().next()) {
+        (Some(last), Some(first)) => (last, first),
+        _ => return space,
+    };
+    space || (is_word_char(last) && is_word_char(first)) || (is_operator_char(last) && is_operator_char(first))
+}
+
+struct Wrap {
+    // index of the closing paren of the wrapped list
+    close: usize,
+    level: usize,
+    paren_depth: usize,
+}
+
+struct Renderer<'a, 's> {
+    style: &'a FormatStyle,
+    tokens: &'a [Token<'s>],
+    lines: Vec<String>,
+    line: String,
+    line_level: usize,
+    depth: usize,
+    paren_depth: usize,
+    // whether the statements at each brace depth are inside a `case` label
+    in_case: Vec<bool>,
+    statement_start: bool,
+    wraps: Vec<Wrap>,
+    // index of the closing paren of the list being kept on one line
+    flat_until: Option<usize>,
+}
+
+impl<'a, 's> Renderer<'a, 's> {
+    fn new(style: &'a FormatStyle, tokens: &'a [Token<'s>]) -> Self {
+        Renderer {
+            style,
+            tokens,
+            lines: vec![],
+            line: String::new(),
+            line_level: 0,
+            depth: 0,
+            paren_depth: 0,
+            in_case: vec![false],
+            statement_start: true,
+            wraps: vec![],
+            flat_until: None,
+        }
+    }
+
+    fn render(mut self) -> String {
+        for (i, token) in self.tokens.iter().enumerate() {
+            let prev = if i > 0 { Some(&self.tokens[i - 1]) } else { None };
+
+            match token.kind {
+                Kind::BlockClose => {
+                    self.depth = self.depth.saturating_sub(1);
+                    if self.in_case.len() > 1 {
+                        self.in_case.pop();
+                    }
+                }
+                Kind::Word if token.parent_kind == "case_statement" && self.statement_start => {
+                    *self.in_case.last_mut().unwrap() = false;
+                }
+                _ => {}
+            }
+
+            match prev {
+                Some(prev) if self.breaks_line(i, prev, token) => {
+                    self.newline();
+                    let blank_lines = token.start_row.saturating_sub(prev.end_row + 1).min(self.style.max_blank_lines);
+                    if !matches!(prev.kind, Kind::BlockOpen) && !matches!(token.kind, Kind::BlockClose) {
+                        for _ in 0..blank_lines {
+                            self.lines.push(String::new());
+                        }
+                    }
+                }
+                Some(prev) if space_between(prev, token) => self.line.push(' '),
+                _ => {}
+            }
+
+            if token.kind == Kind::Verbatim {
+                self.lines.push(token.text.to_string());
+                continue;
+            }
+
+            if self.line.is_empty() {
+                self.line_level = self.level(i, token);
+                self.line.push_str(&self.style.indent(self.line_level));
+            }
+
+            if token.kind == Kind::OpenList && self.flat_until.is_none() {
+                self.fit_list(i);
+            }
+
+            self.line.push_str(token.text);
+            self.update_state(i, token);
+        }
+
+        self.newline();
+        self.lines.join("\n")
+    }
+
+    fn breaks_line(&self, i: usize, prev: &Token, token: &Token) -> bool {
+        let same_line = token.start_row == prev.end_row;
+        let trailing_comment = same_line && matches!(token.kind, Kind::LineComment | Kind::BlockComment);
+
+        if matches!(token.kind, Kind::Verbatim) || matches!(prev.kind, Kind::Verbatim | Kind::LineComment) {
+            return true;
+        }
+        if let Some(wrap) = self.wraps.last() {
+            if i == wrap.close || (self.paren_depth == wrap.paren_depth && matches!(prev.kind, Kind::OpenList | Kind::Comma)) {
+                return !trailing_comment;
+            }
+        }
+
+        match (prev.kind, token.kind) {
+            (_, Kind::BlockClose) => true,
+            (_, Kind::BlockOpen) => self.style.brace_style == BraceStyle::NextLine,
+            (Kind::BlockOpen, _) => !trailing_comment,
+            (Kind::BlockClose, Kind::Semicolon | Kind::Comma) => false,
+            (Kind::BlockClose, Kind::Word) if token.text == "else" => self.style.brace_style == BraceStyle::NextLine,
+            (Kind::BlockClose, Kind::Word) if token.text == "while" || prev.parent_kind == "field_declaration_list" => !same_line,
+            (Kind::BlockClose, _) => !trailing_comment,
+            (Kind::Semicolon, _) if self.paren_depth == 0 => !trailing_comment,
+            (Kind::CaseColon, _) => !trailing_comment,
+            _ if self.flat_until.is_some() => false,
+            _ => !same_line,
+        }
+    }
+
+    fn level(&self, i: usize, token: &Token) -> usize {
+        if let Some(wrap) = self.wraps.last() {
+            return if i == wrap.close {
+                wrap.level
+            } else if self.paren_depth > wrap.paren_depth {
+                wrap.level + 2
+            } else {
+                wrap.level + 1
+            };
+        }
+
+        let level = self.depth + *self.in_case.last().unwrap() as usize;
+        let continuation = !self.statement_start && !matches!(token.kind, Kind::BlockOpen | Kind::BlockClose);
+        level + continuation as usize
+    }
+
+    // decides whether the list opened at `open` is kept on one line or has its arguments put one per line
+    fn fit_list(&mut self, open: usize) {
+        let mut depth = 0;
+        let mut close = None;
+        for (i, token) in self.tokens.iter().enumerate().skip(open) {
+            match token.kind {
+                Kind::Verbatim | Kind::LineComment | Kind::BlockComment => return,
+                Kind::Open | Kind::OpenList if token.text == "(" => depth += 1,
+                Kind::Close if token.text == ")" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        close = Some(i);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        let close = match close {
+            Some(close) => close,
+            None => return,
+        };
+
+        let list = &self.tokens[open..=close];
+        let width: usize = list.iter().map(|token| token.text.len()).sum::<usize>()
+            + list.windows(2).filter(|pair| space_between(&pair[0], &pair[1])).count();
+        let column = self.line.chars().map(|c| if c == '\t' { self.style.indent_width } else { 1 }).sum::<usize>();
+
+        if column + width <= self.style.max_width || close == open + 1 {
+            self.flat_until = Some(close);
+        } else {
+            self.wraps.push(Wrap {
+                close,
+                level: self.line_level,
+                paren_depth: self.paren_depth + 1,
+            });
+        }
+    }
+
+    fn update_state(&mut self, i: usize, token: &Token) {
+        match token.kind {
+            Kind::Open | Kind::OpenList if token.text == "(" => self.paren_depth += 1,
+            Kind::Close if token.text == ")" => self.paren_depth = self.paren_depth.saturating_sub(1),
+            Kind::BlockOpen => {
+                self.depth += 1;
+                self.in_case.push(false);
+            }
+            Kind::CaseColon => *self.in_case.last_mut().unwrap() = true,
+            _ => {}
+        }
+
+        if self.flat_until == Some(i) {
+            self.flat_until = None;
+        }
+        if self.wraps.last().map_or(false, |wrap| wrap.close == i) {
+            self.wraps.pop();
+        }
+
+        match token.kind {
+            Kind::Semicolon if self.paren_depth == 0 => self.statement_start = true,
+            Kind::BlockOpen | Kind::BlockClose | Kind::CaseColon => self.statement_start = true,
+            Kind::LineComment | Kind::BlockComment | Kind::Verbatim => {}
+            _ => self.statement_start = false,
+        }
+    }
+
+    fn newline(&mut self) {
+        if !self.line.is_empty() {
+            let line = std::mem::take(&mut self.line);
+            self.lines.push(line.trim_end().to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod formatter_test {
+    use tree_sitter::Parser;
+
+    use crate::formatter::{format_document, format_range, BraceStyle, FormatStyle};
+    use rust_lsp::lsp_types::{Position, Range};
+
+    fn format(source: &str, style: &FormatStyle) -> String {
+        let mut parser = Parser::new();
+        parser.set_language(tree_sitter_glsl::language()).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        match format_document(&tree, source, style).unwrap().pop() {
+            Some(edit) => edit.new_text,
+            None => source.to_string(),
+        }
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_format_document() {
+        let source = r#"#version 120
+#define SHADOW_QUALITY 2 // [1 2 3]
+uniform sampler2D texture;
+const int shadowMapResolution=2048;   // [1024 2048 4096]
+vec2 texcoord ;
+
+
+float getShadow(vec3 pos){
+float x=pos.x*2.0+ -pos.y;
+  #ifdef   SHADOWS
+    x++ ;
+  #endif
+for(int i=0;i<4;i++){ x+=texture2D(texture,vec2(i,0)).r; }
+if(x>1.0) return 1.0; else {return x;}
+}
+void main() {
+	gl_FragColor = vec4(getShadow(vec3(texcoord, 0.0)));	// trailing
+}
+"#;
+
+        let expected = r#"#version 120
+#define SHADOW_QUALITY 2 // [1 2 3]
+uniform sampler2D texture;
+const int shadowMapResolution=2048;   // [1024 2048 4096]
+vec2 texcoord;
+
+float getShadow(vec3 pos) {
+    float x = pos.x * 2.0 + -pos.y;
+  #ifdef   SHADOWS
+    x++;
+  #endif
+    for (int i = 0; i < 4; i++) {
+        x += texture2D(texture, vec2(i, 0)).r;
+    }
+    if (x > 1.0) return 1.0;
+    else {
+        return x;
+    }
+}
+void main() {
+    gl_FragColor = vec4(getShadow(vec3(texcoord, 0.0))); // trailing
+}
+"#;
+
+        assert_eq!(format(source, &FormatStyle::default()), expected);
+        assert_eq!(format(expected, &FormatStyle::default()), expected);
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_brace_style_and_wrapping() {
+        let source = r#"vec3 shade(vec3 albedo, vec3 normal) {
+    if (true) {
+        return computeLighting(albedo, normal, lightDirection, shadowFactor, ambientColor);
+    } else {
+        return albedo;
+    }
+}
+"#;
+
+        let style = FormatStyle {
+            use_tabs: true,
+            brace_style: BraceStyle::NextLine,
+            max_width: 60,
+            ..FormatStyle::default()
+        };
+        let expected = "vec3 shade(vec3 albedo, vec3 normal)
+{
+\tif (true)
+\t{
+\t\treturn computeLighting(
+\t\t\talbedo,
+\t\t\tnormal,
+\t\t\tlightDirection,
+\t\t\tshadowFactor,
+\t\t\tambientColor
+\t\t);
+\t}
+\telse
+\t{
+\t\treturn albedo;
+\t}
+}
+";
+        assert_eq!(format(source, &style), expected);
+        assert_eq!(format(expected, &style), expected);
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_format_range() {
+        let source = "float a(){return 1.0;}\n\nfloat b(){\nreturn 2.0;}\n";
+
+        let mut parser = Parser::new();
+        parser.set_language(tree_sitter_glsl::language()).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+
+        let range = Range::new(Position::new(3, 0), Position::new(3, 1));
+        let edits = format_range(&tree, source, range, &FormatStyle::default()).unwrap();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].range, Range::new(Position::new(2, 0), Position::new(3, 12)));
+        assert_eq!(edits[0].new_text, "float b() {\n    return 2.0;\n}");
+    }
+}
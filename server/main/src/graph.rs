@@ -1,24 +1,30 @@
 use petgraph::stable_graph::EdgeIndex;
 use petgraph::stable_graph::NodeIndex;
 use petgraph::stable_graph::StableDiGraph;
-use petgraph::visit::EdgeRef;
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
 use petgraph::Direction;
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
     path::{Path, PathBuf},
     str::FromStr,
 };
 
 use super::IncludePosition;
+use crate::canonical;
 
 /// Wraps a `StableDiGraph` with caching behaviour for node search by maintaining
 /// an index for node value to node index and a reverse index.
-/// This allows for **O(1)** lookup for a value if it exists, else **O(n)**.
+/// This allows for **O(1)** lookup for a value.
+///
+/// Nodes are identified by the canonical path of their file, so that every spelling of a file's path
+/// finds the same node. Nodes keep the path they were first added with.
+#[derive(Clone)]
 pub struct CachedStableGraph {
     // StableDiGraph is used as it allows for String node values, essential for
     // generating the GraphViz DOT render.
     pub graph: StableDiGraph<String, IncludePosition>,
+    // keyed by canonical path.
     cache: HashMap<PathBuf, NodeIndex>,
     // Maps a node index to its abstracted string representation.
     // Mainly used as the graph is based on NodeIndex.
@@ -35,23 +41,10 @@ impl CachedStableGraph {
         }
     }
 
-    /// Returns the `NodeIndex` for a given graph node with the value of `name`
-    /// and caches the result in the `HashMap`. Complexity is **O(1)** if the value
-    /// is cached (which should always be the case), else **O(n)** where **n** is
-    /// the number of node indices, as an exhaustive search must be done.
-    pub fn find_node(&mut self, name: &Path) -> Option<NodeIndex> {
-        match self.cache.get(name) {
-            Some(n) => Some(*n),
-            None => {
-                // If the string is not in cache, O(n) search the graph (i know...) and then cache the NodeIndex
-                // for later
-                let n = self.graph.node_indices().find(|n| self.graph[*n] == name.to_str().unwrap());
-                if let Some(n) = n {
-                    self.cache.insert(name.into(), n);
-                }
-                n
-            }
-        }
+    /// Returns the `NodeIndex` for a given graph node with the value of `name`, however its path is spelled.
+    /// Every node is cached when it's added, so this is **O(1)**.
+    pub fn find_node(&self, name: &Path) -> Option<NodeIndex> {
+        self.cache.get(&canonical::canonicalize(name)).copied()
     }
 
     // Returns the `PathBuf` for a given `NodeIndex`
@@ -90,11 +83,12 @@ impl CachedStableGraph {
     }
 
     pub fn add_node(&mut self, name: &Path) -> NodeIndex {
-        if let Some(idx) = self.cache.get(name) {
+        let canonical = canonical::canonicalize(name);
+        if let Some(idx) = self.cache.get(&canonical) {
             return *idx;
         }
         let idx = self.graph.add_node(name.to_str().unwrap().to_string());
-        self.cache.insert(name.to_owned(), idx);
+        self.cache.insert(canonical, idx);
         self.reverse_index.insert(idx, name.to_owned());
         idx
     }
@@ -111,10 +105,143 @@ impl CachedStableGraph {
             .and_then(|edge| self.graph.remove_edge(edge));
     }
 
+    /// Moves `node` to `name` in place, keeping its edges.
+    pub fn rename_node(&mut self, node: NodeIndex, name: &Path) {
+        // the old path may not resolve the same way anymore once the file is moved
+        self.cache.retain(|_, n| *n != node);
+        self.reverse_index.insert(node, name.to_owned());
+        self.cache.insert(canonical::canonicalize(name), node);
+        self.graph[node] = name.to_str().unwrap().to_string();
+    }
+
+    /// Removes `node` along with its edges, returning its path.
+    pub fn remove_node(&mut self, node: NodeIndex) -> PathBuf {
+        // the path may not resolve to the key it was cached with anymore if the file is gone
+        self.cache.retain(|_, n| *n != node);
+        self.graph.remove_node(node);
+        self.reverse_index.remove(&node).unwrap()
+    }
+
+    /// Removes the nodes that nothing includes and whose file doesn't exist according to `exists`, like
+    /// includes of a missing file once they're removed. Their includes are removed too if that leaves them
+    /// orphaned in turn. Returns the paths of the removed nodes.
+    pub fn collect_orphans(&mut self, exists: impl Fn(&Path) -> bool) -> Vec<PathBuf> {
+        let mut removed = vec![];
+        let mut candidates: Vec<NodeIndex> = self.graph.node_indices().collect();
+        while let Some(node) = candidates.pop() {
+            if !self.graph.contains_node(node)
+                || self.graph.neighbors_directed(node, Direction::Incoming).next().is_some()
+                || exists(&self.get_node(node))
+            {
+                continue;
+            }
+            candidates.extend(self.graph.neighbors(node));
+            removed.push(self.remove_node(node));
+        }
+        removed
+    }
+
+    /// Checks that `cache` and `reverse_index` agree with the graph, returning a description of every
+    /// mismatch found. An empty result means the graph is consistent.
+    pub fn check(&self) -> Vec<String> {
+        let mut mismatches = vec![];
+        let cached: HashSet<NodeIndex> = self.cache.values().copied().collect();
+
+        for node in self.graph.node_indices() {
+            match self.reverse_index.get(&node) {
+                None => mismatches.push(format!("node {} ({}) is missing from the reverse index", node.index(), self.graph[node])),
+                Some(path) if path.to_str() != Some(self.graph[node].as_str()) => mismatches.push(format!(
+                    "node {} is {} but {} in the reverse index",
+                    node.index(),
+                    self.graph[node],
+                    path.display()
+                )),
+                Some(_) => {}
+            }
+            if !cached.contains(&node) {
+                mismatches.push(format!("node {} ({}) is missing from the cache", node.index(), self.graph[node]));
+            }
+        }
+
+        for (node, path) in &self.reverse_index {
+            if !self.graph.contains_node(*node) {
+                mismatches.push(format!("reverse index has {} for removed node {}", path.display(), node.index()));
+            }
+        }
+
+        let mut keys: HashMap<NodeIndex, usize> = HashMap::new();
+        for (key, node) in &self.cache {
+            if !self.graph.contains_node(*node) {
+                mismatches.push(format!("cache has {} for removed node {}", key.display(), node.index()));
+                continue;
+            }
+            *keys.entry(*node).or_default() += 1;
+            if canonical::canonicalize(&self.get_node(*node)) != *key {
+                mismatches.push(format!("cache has {} for node {} ({})", key.display(), node.index(), self.graph[*node]));
+            }
+        }
+        for (node, count) in keys.into_iter().filter(|(_, count)| *count > 1) {
+            mismatches.push(format!("node {} ({}) is cached under {} paths", node.index(), self.graph[node], count));
+        }
+
+        mismatches
+    }
+
+    /// Returns the includes leading from `from` to `to` as `(parent, IncludePosition)` pairs, starting at `from`.
+    /// The shortest chain is picked if there are several. An empty chain means `from` and `to` are the same node.
+    pub fn include_chain(&self, from: NodeIndex, to: NodeIndex) -> Option<Vec<(NodeIndex, IncludePosition)>> {
+        let mut previous: HashMap<NodeIndex, (NodeIndex, IncludePosition)> = HashMap::new();
+        let mut visited = HashSet::from([from]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(node) = queue.pop_front() {
+            if node == to {
+                let mut chain = vec![];
+                let mut current = to;
+                while let Some((parent, position)) = previous.get(&current) {
+                    chain.push((*parent, *position));
+                    current = *parent;
+                }
+                chain.reverse();
+                return Some(chain);
+            }
+            for (child, position) in self.get_all_child_positions(node) {
+                if visited.insert(child) {
+                    previous.insert(child, (node, position));
+                    queue.push_back(child);
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns every `(parent, child, IncludePosition)` edge in the graph.
+    pub fn all_edges(&self) -> impl Iterator<Item = (NodeIndex, NodeIndex, IncludePosition)> + '_ {
+        self.graph.edge_references().map(|edge| (edge.source(), edge.target(), *edge.weight()))
+    }
+
     pub fn child_node_indexes(&self, node: NodeIndex) -> impl Iterator<Item = NodeIndex> + '_ {
         self.graph.neighbors(node)
     }
 
+    /// Whether adding an include of `child` to `parent` would make a file (indirectly) include itself.
+    pub fn would_create_cycle(&self, parent: NodeIndex, child: NodeIndex) -> bool {
+        parent == child || petgraph::algo::has_path_connecting(&self.graph, child, parent, None)
+    }
+
+    /// Every file that includes the file at `node`, directly or through other includes, nearest first.
+    pub fn collect_ancestors(&self, node: NodeIndex) -> Vec<NodeIndex> {
+        let mut ancestors = vec![];
+        let mut queue: VecDeque<NodeIndex> = self.parent_node_indexes(node).into();
+        while let Some(ancestor) = queue.pop_front() {
+            if ancestor == node || ancestors.contains(&ancestor) {
+                continue;
+            }
+            ancestors.push(ancestor);
+            queue.extend(self.parent_node_indexes(ancestor));
+        }
+        ancestors
+    }
+
     pub fn collect_root_ancestors(&self, node: NodeIndex) -> Vec<NodeIndex> {
         let mut visited = HashSet::new();
         self.get_root_ancestors(node, node, &mut visited)
@@ -165,20 +292,15 @@ impl CachedStableGraph {
             .map(|n| self.reverse_index.get(&n).unwrap().clone())
             .collect()
     }
-
-    fn remove_node(&mut self, name: &Path) {
-        let idx = self.cache.remove(name);
-        if let Some(idx) = idx {
-            self.graph.remove_node(idx);
-        }
-    }
 }
 
 #[cfg(test)]
 mod graph_test {
-    use std::path::PathBuf;
+    use std::fs;
+    use std::path::{Path, PathBuf};
 
     use petgraph::graph::NodeIndex;
+    use tempdir::TempDir;
 
     use crate::{graph::CachedStableGraph, IncludePosition};
 
@@ -217,7 +339,7 @@ mod graph_test {
         let ancestors = graph.collect_root_ancestors(idx1);
         assert_eq!(ancestors.len(), 0);
 
-        graph.remove_node(&PathBuf::from("sample"));
+        graph.remove_node(idx1);
         assert_eq!(graph.graph.node_count(), 1);
         assert!(graph.find_node(&PathBuf::from("sample")).is_none());
 
@@ -247,6 +369,199 @@ mod graph_test {
         assert_eq!(Some(IncludePosition { line: 4, start: 0, end: 0 }), edge_metas.next());
     }
 
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_would_create_cycle() {
+        let mut graph = CachedStableGraph::new();
+
+        let idx0 = graph.add_node(&PathBuf::from("0"));
+        let idx1 = graph.add_node(&PathBuf::from("1"));
+        let idx2 = graph.add_node(&PathBuf::from("2"));
+        let idx3 = graph.add_node(&PathBuf::from("3"));
+
+        graph.add_edge(idx0, idx1, IncludePosition { line: 2, start: 0, end: 0 });
+        graph.add_edge(idx1, idx2, IncludePosition { line: 3, start: 0, end: 0 });
+
+        //   0
+        //   |
+        //   1
+        //   |
+        //   2   3
+
+        assert!(graph.would_create_cycle(idx2, idx0));
+        assert!(graph.would_create_cycle(idx1, idx1));
+        assert!(!graph.would_create_cycle(idx0, idx2));
+        assert!(!graph.would_create_cycle(idx2, idx3));
+        assert!(!graph.would_create_cycle(idx3, idx0));
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_include_chain() {
+        let mut graph = CachedStableGraph::new();
+
+        let idx0 = graph.add_node(&PathBuf::from("0"));
+        let idx1 = graph.add_node(&PathBuf::from("1"));
+        let idx2 = graph.add_node(&PathBuf::from("2"));
+        let idx3 = graph.add_node(&PathBuf::from("3"));
+
+        graph.add_edge(idx0, idx1, IncludePosition { line: 2, start: 0, end: 0 });
+        graph.add_edge(idx1, idx2, IncludePosition { line: 3, start: 0, end: 0 });
+        graph.add_edge(idx0, idx3, IncludePosition { line: 4, start: 0, end: 0 });
+
+        //     0
+        //    / \
+        //   1   3
+        //   |
+        //   2
+
+        assert_eq!(
+            graph.include_chain(idx0, idx2),
+            Some(vec![
+                (idx0, IncludePosition { line: 2, start: 0, end: 0 }),
+                (idx1, IncludePosition { line: 3, start: 0, end: 0 })
+            ])
+        );
+        assert_eq!(graph.include_chain(idx0, idx0), Some(vec![]));
+        assert_eq!(graph.include_chain(idx3, idx2), None);
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_rename_node() {
+        let mut graph = CachedStableGraph::new();
+
+        let idx0 = graph.add_node(&PathBuf::from("/shaders/final.fsh"));
+        let idx1 = graph.add_node(&PathBuf::from("/shaders/lib/common.glsl"));
+        graph.add_edge(idx0, idx1, IncludePosition { line: 2, start: 10, end: 26 });
+
+        graph.rename_node(idx1, &PathBuf::from("/shaders/utils/common.glsl"));
+
+        assert_eq!(graph.find_node(&PathBuf::from("/shaders/lib/common.glsl")), None);
+        assert_eq!(graph.find_node(&PathBuf::from("/shaders/utils/common.glsl")), Some(idx1));
+        assert_eq!(graph.get_node(idx1), PathBuf::from("/shaders/utils/common.glsl"));
+        assert_eq!(graph.child_node_names(idx0), vec![PathBuf::from("/shaders/utils/common.glsl")]);
+        assert_eq!(
+            graph.all_edges().collect::<Vec<_>>(),
+            vec![(idx0, idx1, IncludePosition { line: 2, start: 10, end: 26 })]
+        );
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_canonical_nodes() {
+        let tmp_dir = TempDir::new("mcshader").unwrap();
+        let shaders = tmp_dir.path().join("shaders");
+        fs::create_dir_all(shaders.join("lib")).unwrap();
+        fs::write(shaders.join("final.fsh"), "").unwrap();
+        fs::write(shaders.join("lib").join("common.glsl"), "").unwrap();
+
+        let mut graph = CachedStableGraph::new();
+        let final_fsh = graph.add_node(&shaders.join("final.fsh"));
+        let common = graph.add_node(&shaders.join("lib").join("common.glsl"));
+        assert_eq!(graph.add_node(&shaders.join("lib").join("..").join("lib").join("common.glsl")), common);
+        assert_eq!(graph.find_node(&shaders.join(".").join("lib").join("common.glsl")), Some(common));
+        assert_eq!(graph.get_node(common), shaders.join("lib").join("common.glsl"));
+        assert_eq!(graph.graph.node_count(), 2);
+
+        // a cycle through another spelling of the file is still a cycle
+        graph.add_edge(final_fsh, common, IncludePosition { line: 2, start: 10, end: 26 });
+        let including = graph.find_node(&shaders.join("lib").join("..").join("final.fsh")).unwrap();
+        assert!(graph.would_create_cycle(common, including));
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_collect_orphans() {
+        let mut graph = CachedStableGraph::new();
+
+        let idx0 = graph.add_node(&PathBuf::from("/shaders/final.fsh"));
+        let idx1 = graph.add_node(&PathBuf::from("/shaders/lib/missing.glsl"));
+        let idx2 = graph.add_node(&PathBuf::from("/shaders/lib/common.glsl"));
+        let idx3 = graph.add_node(&PathBuf::from("/shaders/lib/deleted.glsl"));
+        let idx4 = graph.add_node(&PathBuf::from("/shaders/lib/nested.glsl"));
+        graph.add_edge(idx0, idx1, IncludePosition { line: 2, start: 10, end: 27 });
+        graph.add_edge(idx3, idx4, IncludePosition { line: 1, start: 10, end: 26 });
+        graph.add_edge(idx3, idx2, IncludePosition { line: 2, start: 10, end: 26 });
+
+        //   0     3
+        //   |    / \
+        //   1   4   2
+
+        let exists = |path: &Path| !path.ends_with("missing.glsl") && !path.ends_with("deleted.glsl") && !path.ends_with("nested.glsl");
+
+        // still included, so kept even though it's missing
+        let mut removed = graph.collect_orphans(exists);
+        removed.sort();
+        assert_eq!(
+            removed,
+            vec![PathBuf::from("/shaders/lib/deleted.glsl"), PathBuf::from("/shaders/lib/nested.glsl")]
+        );
+        assert_eq!(graph.find_node(&PathBuf::from("/shaders/lib/missing.glsl")), Some(idx1));
+        assert_eq!(graph.find_node(&PathBuf::from("/shaders/lib/common.glsl")), Some(idx2));
+        assert_eq!(graph.find_node(&PathBuf::from("/shaders/lib/deleted.glsl")), None);
+
+        graph.remove_edge(idx0, idx1, IncludePosition { line: 2, start: 10, end: 27 });
+        assert_eq!(graph.collect_orphans(exists), vec![PathBuf::from("/shaders/lib/missing.glsl")]);
+        assert_eq!(graph.graph.node_count(), 2);
+        assert!(graph.check().is_empty());
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_check() {
+        let mut graph = CachedStableGraph::new();
+
+        let idx0 = graph.add_node(&PathBuf::from("/shaders/final.fsh"));
+        let idx1 = graph.add_node(&PathBuf::from("/shaders/lib/common.glsl"));
+        graph.add_edge(idx0, idx1, IncludePosition { line: 2, start: 10, end: 26 });
+        graph.rename_node(idx1, &PathBuf::from("/shaders/utils/common.glsl"));
+        assert!(graph.check().is_empty());
+
+        graph.reverse_index.insert(idx1, PathBuf::from("/shaders/lib/common.glsl"));
+        graph.cache.insert(PathBuf::from("/shaders/composite.fsh"), idx0);
+        graph.graph.remove_node(idx0);
+
+        let mut mismatches = graph.check();
+        mismatches.sort();
+        assert_eq!(
+            mismatches,
+            vec![
+                "cache has /shaders/composite.fsh for removed node 0",
+                "cache has /shaders/final.fsh for removed node 0",
+                "node 1 is /shaders/utils/common.glsl but /shaders/lib/common.glsl in the reverse index",
+                "reverse index has /shaders/final.fsh for removed node 0",
+            ]
+        );
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_collect_ancestors() {
+        let mut graph = CachedStableGraph::new();
+
+        let idx0 = graph.add_node(&PathBuf::from("0"));
+        let idx1 = graph.add_node(&PathBuf::from("1"));
+        let idx2 = graph.add_node(&PathBuf::from("2"));
+        let idx3 = graph.add_node(&PathBuf::from("3"));
+
+        graph.add_edge(idx0, idx1, IncludePosition { line: 2, start: 0, end: 0 });
+        graph.add_edge(idx1, idx2, IncludePosition { line: 3, start: 0, end: 0 });
+        graph.add_edge(idx3, idx1, IncludePosition { line: 4, start: 0, end: 0 });
+        graph.add_edge(idx0, idx2, IncludePosition { line: 5, start: 0, end: 0 });
+
+        //       0  3
+        //       |\/
+        //       | 1
+        //       |/
+        //       2
+
+        let mut ancestors = graph.collect_ancestors(idx2);
+        ancestors.sort();
+        assert_eq!(ancestors, vec![idx0, idx1, idx3]);
+        assert!(graph.collect_ancestors(idx0).is_empty());
+    }
+
     #[test]
     #[logging_macro::log_scope]
     fn test_collect_root_ancestors() {
@@ -0,0 +1,245 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::archive;
+use crate::fnv;
+use crate::symbol_index::IndexedFile;
+use crate::IncludePosition;
+
+// bumped whenever the format changes or includes are resolved differently.
+static GRAPH_CACHE_VERSION: u32 = 3;
+
+/// The user's cache folder, following the XDG base directory spec outside of windows and macOS.
+fn cache_dir() -> Option<PathBuf> {
+    let absolute = |var: &str| env::var_os(var).map(PathBuf::from).filter(|path| path.is_absolute());
+    if cfg!(windows) {
+        absolute("LOCALAPPDATA")
+    } else if cfg!(target_os = "macos") {
+        absolute("HOME").map(|home| home.join("Library").join("Caches"))
+    } else {
+        absolute("XDG_CACHE_HOME").or_else(|| absolute("HOME").map(|home| home.join(".cache")))
+    }
+}
+
+/// Where the graph cache of the pack at `root` is kept, which is in the user's cache folder so that packs
+/// aren't cluttered with it. `None` if there's no cache folder, in which case the graph isn't cached.
+pub fn cache_path(root: &Path) -> Option<PathBuf> {
+    let name = format!("graph-{}.json", fnv::hash(root.to_string_lossy().as_bytes()));
+    Some(cache_dir()?.join("mcshader-lsp").join(name))
+}
+
+/// What a file looked like when it was scanned. A file is only read again if its modification time or
+/// size changed, and only scanned again if its content did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    // modification time, in nanoseconds since the unix epoch.
+    modified: u128,
+    size: u64,
+}
+
+impl FileStamp {
+    /// The stamp of the file at `path`, or of its archive if it's in a zipped pack.
+    pub fn of(path: &Path) -> io::Result<FileStamp> {
+        let on_disk = archive::split(path).map_or_else(|| path.to_path_buf(), |(archive, _)| archive);
+        let metadata = fs::metadata(on_disk)?;
+        let modified = metadata.modified()?.duration_since(UNIX_EPOCH).unwrap_or_default();
+        Ok(FileStamp {
+            modified: modified.as_nanos(),
+            size: metadata.len(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedFile {
+    stamp: FileStamp,
+    hash: String,
+    pub includes: Vec<(PathBuf, IncludePosition)>,
+    pub symbols: IndexedFile,
+}
+
+/// The includes and symbols of every file of a pack as they were last scanned, saved between
+/// sessions so that opening a pack only has to scan the files that changed since.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphCache {
+    version: u32,
+    server_version: String,
+    root: PathBuf,
+    // includes resolve differently with other include roots.
+    include_roots: Vec<PathBuf>,
+    files: BTreeMap<PathBuf, CachedFile>,
+}
+
+impl GraphCache {
+    pub fn new(root: &Path, include_roots: &[PathBuf]) -> GraphCache {
+        GraphCache {
+            version: GRAPH_CACHE_VERSION,
+            server_version: env!("CARGO_PKG_VERSION").into(),
+            root: root.to_path_buf(),
+            include_roots: include_roots.to_vec(),
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// Reads the cache at `path`, or `None` if there isn't one. A cache that can't be read, was written
+    /// by another version or for another pack or include roots is an error, which means the graph has
+    /// to be built from scratch.
+    pub fn load(path: &Path, root: &Path, include_roots: &[PathBuf]) -> Result<Option<GraphCache>> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(anyhow!("error reading graph cache {:?}: {}", path, e)),
+        };
+        let cache: GraphCache = serde_json::from_str(&contents).map_err(|e| anyhow!("error parsing graph cache {:?}: {}", path, e))?;
+
+        if cache.version != GRAPH_CACHE_VERSION || cache.server_version != env!("CARGO_PKG_VERSION") {
+            return Err(anyhow!(
+                "graph cache {:?} was written by version {} of the server",
+                path,
+                cache.server_version
+            ));
+        }
+        if cache.root != root || cache.include_roots != include_roots {
+            return Err(anyhow!("graph cache {:?} was written for another pack or include roots", path));
+        }
+        Ok(Some(cache))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents = serde_json::to_string(self)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| anyhow!("error creating graph cache folder {:?}: {}", parent, e))?;
+        }
+        fs::write(path, contents).map_err(|e| anyhow!("error writing graph cache {:?}: {}", path, e))
+    }
+
+    pub fn insert(&mut self, path: PathBuf, file: CachedFile) {
+        self.files.insert(path, file);
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+}
+
+/// The includes and symbols of the file at `path`, taken from `cache` if the file didn't change since
+/// and found with `analyze` on its content otherwise. Also returns whether the cache was used.
+pub fn scan(
+    cache: Option<&GraphCache>, path: &Path, analyze: impl FnOnce(&[u8]) -> Result<(Vec<(PathBuf, IncludePosition)>, IndexedFile)>,
+) -> Result<(CachedFile, bool)> {
+    let stamp = FileStamp::of(path)?;
+    let cached = cache.and_then(|cache| cache.files.get(path));
+    if let Some(cached) = cached.filter(|cached| cached.stamp == stamp) {
+        return Ok((cached.clone(), true));
+    }
+
+    let contents = archive::read(path)?;
+    let hash = fnv::hash(&contents);
+    if let Some(cached) = cached.filter(|cached| cached.hash == hash) {
+        // touched but not changed, like after switching branches back and forth
+        return Ok((CachedFile { stamp, ..cached.clone() }, true));
+    }
+
+    let (includes, symbols) = analyze(&contents)?;
+    Ok((
+        CachedFile {
+            stamp,
+            hash,
+            includes,
+            symbols,
+        },
+        false,
+    ))
+}
+
+#[cfg(test)]
+mod graph_cache_test {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use tempdir::TempDir;
+
+    use crate::graph_cache::{scan, GraphCache};
+    use crate::symbol_index::IndexedFile;
+    use crate::IncludePosition;
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_scan() {
+        let tmp_dir = TempDir::new("mcshader").unwrap();
+        let root = tmp_dir.path().to_path_buf();
+        let file = root.join("final.fsh");
+        fs::write(&file, "#include \"/lib/common.glsl\"\n").unwrap();
+
+        let include = (
+            root.join("lib").join("common.glsl"),
+            IncludePosition {
+                line: 0,
+                start: 10,
+                end: 26,
+            },
+        );
+        let analyze = |_: &[u8]| Ok((vec![include.clone()], IndexedFile::default()));
+
+        let (scanned, cached) = scan(None, &file, analyze).unwrap();
+        assert!(!cached);
+        assert_eq!(scanned.includes, vec![include.clone()]);
+
+        let mut cache = GraphCache::new(&root, &[root.join("shaders")]);
+        cache.insert(file.clone(), scanned.clone());
+        let cache_path = root.join("cache").join("graph.json");
+        cache.save(&cache_path).unwrap();
+        let cache = GraphCache::load(&cache_path, &root, &[root.join("shaders")]).unwrap().unwrap();
+
+        // unchanged files aren't scanned again
+        let (_, cached) = scan(Some(&cache), &file, |_| panic!("scanned an unchanged file")).unwrap();
+        assert!(cached);
+
+        // neither are files that were only touched
+        let mut touched = cache.clone();
+        touched.files.get_mut(&file).unwrap().stamp.modified -= 1;
+        let (rescanned, cached) = scan(Some(&touched), &file, |_| panic!("scanned an unchanged file")).unwrap();
+        assert!(cached);
+        assert_eq!(rescanned, scanned);
+
+        fs::write(&file, "#version 120\n").unwrap();
+        let (rescanned, cached) = scan(Some(&cache), &file, |_| Ok((vec![], IndexedFile::default()))).unwrap();
+        assert!(!cached);
+        assert!(rescanned.includes.is_empty());
+
+        assert!(scan(Some(&cache), &root.join("missing.fsh"), analyze).is_err());
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_load() {
+        let tmp_dir = TempDir::new("mcshader").unwrap();
+        let root = tmp_dir.path().to_path_buf();
+        let cache_path = root.join("graph.json");
+        let include_roots = vec![root.join("shaders")];
+
+        assert_eq!(GraphCache::load(&cache_path, &root, &include_roots).unwrap(), None);
+
+        let mut cache = GraphCache::new(&root, &include_roots);
+        cache.save(&cache_path).unwrap();
+        assert_eq!(GraphCache::load(&cache_path, &root, &include_roots).unwrap(), Some(cache.clone()));
+
+        // another pack or other include roots
+        assert!(GraphCache::load(&cache_path, &PathBuf::from("/other"), &include_roots).is_err());
+        assert!(GraphCache::load(&cache_path, &root, &[root.join("shaders"), root.join("lib")]).is_err());
+
+        cache.server_version = "0.0.1".into();
+        cache.save(&cache_path).unwrap();
+        assert!(GraphCache::load(&cache_path, &root, &include_roots).is_err());
+
+        fs::write(&cache_path, "{\"version\": 1, \"files\": ").unwrap();
+        assert!(GraphCache::load(&cache_path, &root, &include_roots).is_err());
+    }
+}
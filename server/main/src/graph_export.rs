@@ -0,0 +1,434 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{format_err, Result};
+use path_slash::PathExt;
+use petgraph::stable_graph::NodeIndex;
+use petgraph::Direction;
+use serde::Serialize;
+
+use crate::graph::CachedStableGraph;
+use crate::{is_top_level, IncludePosition, TreeType};
+
+/// The part of the include graph to export.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scope {
+    Pack,
+    /// The file, the files it includes and the files that include it.
+    File(PathBuf),
+    /// The top-level program and the files it includes.
+    Program(PathBuf),
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct Node {
+    pub id: usize,
+    // relative to the pack root, unless the file is outside of it.
+    pub path: String,
+    pub top_level: bool,
+    pub stage: Option<TreeType>,
+    // whether the file doesn't exist, like a mistyped include.
+    pub missing: bool,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub position: IncludePosition,
+    // whether the include is part of an include cycle.
+    pub cycle: bool,
+}
+
+/// A snapshot of (part of) the include graph, with nodes ordered by path and edges by file and line.
+#[derive(Debug, Serialize)]
+pub struct GraphExport {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+impl GraphExport {
+    /// Exports the part of `graph` in `scope`, with files that don't exist according to `exists` marked missing.
+    pub fn new(graph: &CachedStableGraph, root: &Path, scope: &Scope, exists: impl Fn(&Path) -> bool) -> Result<GraphExport> {
+        let included: HashSet<NodeIndex> = match scope {
+            Scope::Pack => graph.graph.node_indices().collect(),
+            Scope::File(path) => {
+                let node = graph
+                    .find_node(path)
+                    .ok_or_else(|| format_err!("{:?} is not in the include graph", path))?;
+                let mut nodes = reachable(graph, node, Direction::Outgoing);
+                nodes.extend(reachable(graph, node, Direction::Incoming));
+                nodes
+            }
+            Scope::Program(path) => {
+                let node = graph
+                    .find_node(path)
+                    .ok_or_else(|| format_err!("{:?} is not in the include graph", path))?;
+                if !path.strip_prefix(root).is_ok_and(is_top_level) {
+                    return Err(format_err!("{:?} is not a top-level program", path));
+                }
+                reachable(graph, node, Direction::Outgoing)
+            }
+        };
+
+        let mut nodes: Vec<Node> = included
+            .iter()
+            .map(|node| {
+                let path = graph.get_node(*node);
+                let relative = path.strip_prefix(root).ok();
+                let top_level = relative.is_some_and(is_top_level);
+                Node {
+                    id: node.index(),
+                    path: relative.unwrap_or(&path).to_slash_lossy(),
+                    top_level,
+                    stage: top_level.then(|| TreeType::from_path(&path)).flatten(),
+                    missing: !exists(&path),
+                }
+            })
+            .collect();
+        nodes.sort_by(|a, b| a.path.cmp(&b.path));
+
+        // includes within a strongly connected component lead back to where they started.
+        let components: HashMap<NodeIndex, usize> = petgraph::algo::tarjan_scc(&graph.graph)
+            .into_iter()
+            .enumerate()
+            .flat_map(|(i, component)| component.into_iter().map(move |node| (node, i)))
+            .collect();
+        let mut edges: Vec<Edge> = graph
+            .all_edges()
+            .filter(|(parent, child, _)| included.contains(parent) && included.contains(child))
+            .map(|(parent, child, position)| Edge {
+                from: parent.index(),
+                to: child.index(),
+                position,
+                cycle: components[&parent] == components[&child],
+            })
+            .collect();
+        let order: HashMap<usize, usize> = nodes.iter().enumerate().map(|(i, node)| (node.id, i)).collect();
+        edges.sort_by_key(|edge| (order[&edge.from], edge.position.line, edge.position.start));
+
+        Ok(GraphExport { nodes, edges })
+    }
+
+    /// Renders the graph as a Mermaid flowchart, with top-level programs drawn as stadiums and edges
+    /// labelled with the (1-indexed) line of the include.
+    pub fn to_mermaid(&self) -> String {
+        let mut mermaid = "flowchart LR\n".to_string();
+        for node in &self.nodes {
+            let label = node.path.replace('"', "#quot;");
+            match node.top_level {
+                true => writeln!(mermaid, "    n{}([\"{}\"])", node.id, label).unwrap(),
+                false => writeln!(mermaid, "    n{}[\"{}\"]", node.id, label).unwrap(),
+            }
+        }
+        for edge in &self.edges {
+            writeln!(mermaid, "    n{} -->|\"line {}\"| n{}", edge.from, edge.position.line + 1, edge.to).unwrap();
+        }
+        mermaid
+    }
+
+    /// Renders the graph in the Graphviz DOT format. Top-level programs are filled in by stage, files of
+    /// `world*` dimension folders are grouped in a cluster per folder, edges are labelled with the
+    /// (1-indexed) line of the include and missing files and includes forming a cycle are drawn in red.
+    pub fn to_dot(&self) -> String {
+        let mut dot = "digraph {\n\tnode [shape=box]\n".to_string();
+
+        let mut worlds: BTreeMap<&str, Vec<&Node>> = BTreeMap::new();
+        for node in &self.nodes {
+            worlds.entry(world_folder(&node.path).unwrap_or_default()).or_default().push(node);
+        }
+        for (world, nodes) in &worlds {
+            let indent = match world.is_empty() {
+                true => "\t",
+                false => {
+                    writeln!(dot, "\tsubgraph \"cluster_{}\" {{", escape(world)).unwrap();
+                    writeln!(dot, "\t\tlabel=\"{}\"", escape(world.rsplit('/').next().unwrap())).unwrap();
+                    "\t\t"
+                }
+            };
+            for node in nodes {
+                let mut attributes = vec![format!("label=\"{}\"", escape(&node.path))];
+                let mut styles = vec![];
+                if let Some(stage) = node.stage {
+                    attributes.push(format!("fillcolor=\"{}\"", stage_color(stage)));
+                    styles.push("filled");
+                }
+                if node.missing {
+                    attributes.push("color=red fontcolor=red".into());
+                    styles.push("dashed");
+                }
+                if !styles.is_empty() {
+                    attributes.push(format!("style=\"{}\"", styles.join(",")));
+                }
+                writeln!(dot, "{}n{} [{}]", indent, node.id, attributes.join(" ")).unwrap();
+            }
+            if !world.is_empty() {
+                writeln!(dot, "\t}}").unwrap();
+            }
+        }
+
+        for edge in &self.edges {
+            let color = match edge.cycle {
+                true => " color=red fontcolor=red",
+                false => "",
+            };
+            writeln!(
+                dot,
+                "\tn{} -> n{} [label=\"{}\"{}]",
+                edge.from,
+                edge.to,
+                edge.position.line + 1,
+                color
+            )
+            .unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+// the `shaders/world*` folder that the file at `path`, relative to the pack root, is in.
+fn world_folder(path: &str) -> Option<&str> {
+    let mut parts = path.splitn(3, '/');
+    let (shaders, world) = (parts.next()?, parts.next()?);
+    parts.next()?;
+    let dimension = world.strip_prefix("world")?;
+    match shaders == "shaders" && dimension.parse::<i32>().is_ok() {
+        true => Some(&path[..shaders.len() + 1 + world.len()]),
+        false => None,
+    }
+}
+
+fn stage_color(stage: TreeType) -> &'static str {
+    match stage {
+        TreeType::Fragment => "#a6cee3",
+        TreeType::Vertex => "#b2df8a",
+        TreeType::Geometry => "#fdbf6f",
+        TreeType::Compute => "#cab2d6",
+    }
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// the nodes reachable from `node` following edges in `direction`, including `node` itself.
+fn reachable(graph: &CachedStableGraph, node: NodeIndex, direction: Direction) -> HashSet<NodeIndex> {
+    let mut visited = HashSet::from([node]);
+    let mut queue = VecDeque::from([node]);
+    while let Some(node) = queue.pop_front() {
+        for next in graph.graph.neighbors_directed(node, direction) {
+            if visited.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+    visited
+}
+
+#[cfg(test)]
+mod graph_export_test {
+    use std::path::PathBuf;
+
+    use crate::graph::CachedStableGraph;
+    use crate::graph_export::{GraphExport, Scope};
+    use crate::{IncludePosition, TreeType};
+
+    fn pack() -> CachedStableGraph {
+        let mut graph = CachedStableGraph::new();
+        let final_fsh = graph.add_node(&PathBuf::from("/pack/shaders/final.fsh"));
+        let composite_vsh = graph.add_node(&PathBuf::from("/pack/shaders/composite.vsh"));
+        let common = graph.add_node(&PathBuf::from("/pack/shaders/lib/common.glsl"));
+        let utils = graph.add_node(&PathBuf::from("/pack/shaders/lib/utils.glsl"));
+        let noise = graph.add_node(&PathBuf::from("/pack/shaders/lib/noise.glsl"));
+        graph.add_edge(
+            final_fsh,
+            common,
+            IncludePosition {
+                line: 2,
+                start: 10,
+                end: 26,
+            },
+        );
+        graph.add_edge(
+            common,
+            utils,
+            IncludePosition {
+                line: 0,
+                start: 10,
+                end: 21,
+            },
+        );
+        graph.add_edge(
+            composite_vsh,
+            noise,
+            IncludePosition {
+                line: 4,
+                start: 10,
+                end: 21,
+            },
+        );
+        graph.add_edge(
+            composite_vsh,
+            common,
+            IncludePosition {
+                line: 3,
+                start: 10,
+                end: 26,
+            },
+        );
+        graph
+    }
+
+    fn paths(export: &GraphExport) -> Vec<&str> {
+        export.nodes.iter().map(|node| node.path.as_str()).collect()
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_export_pack() {
+        let export = GraphExport::new(&pack(), &PathBuf::from("/pack"), &Scope::Pack, |_| true).unwrap();
+
+        assert_eq!(
+            paths(&export),
+            vec![
+                "shaders/composite.vsh",
+                "shaders/final.fsh",
+                "shaders/lib/common.glsl",
+                "shaders/lib/noise.glsl",
+                "shaders/lib/utils.glsl"
+            ]
+        );
+        assert_eq!(export.nodes[0].stage, Some(TreeType::Vertex));
+        assert!(export.nodes[1].top_level);
+        assert!(!export.nodes[2].top_level);
+        assert_eq!(export.nodes[2].stage, None);
+
+        let json = serde_json::to_value(&export).unwrap();
+        assert_eq!(json["nodes"][1]["stage"], "fragment");
+        assert_eq!(json["edges"][0]["position"]["line"], 3);
+
+        assert_eq!(
+            export.to_mermaid(),
+            "flowchart LR\n    \
+                n1([\"shaders/composite.vsh\"])\n    \
+                n0([\"shaders/final.fsh\"])\n    \
+                n2[\"shaders/lib/common.glsl\"]\n    \
+                n4[\"shaders/lib/noise.glsl\"]\n    \
+                n3[\"shaders/lib/utils.glsl\"]\n    \
+                n1 -->|\"line 4\"| n2\n    \
+                n1 -->|\"line 5\"| n4\n    \
+                n0 -->|\"line 3\"| n2\n    \
+                n2 -->|\"line 1\"| n3\n"
+        );
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_export_subgraph() {
+        let graph = pack();
+        let root = PathBuf::from("/pack");
+
+        let export = GraphExport::new(&graph, &root, &Scope::File(PathBuf::from("/pack/shaders/lib/common.glsl")), |_| {
+            true
+        })
+        .unwrap();
+        assert_eq!(
+            paths(&export),
+            vec![
+                "shaders/composite.vsh",
+                "shaders/final.fsh",
+                "shaders/lib/common.glsl",
+                "shaders/lib/utils.glsl"
+            ]
+        );
+        assert_eq!(export.edges.len(), 3);
+
+        let export = GraphExport::new(&graph, &root, &Scope::Program(PathBuf::from("/pack/shaders/final.fsh")), |_| true).unwrap();
+        assert_eq!(
+            paths(&export),
+            vec!["shaders/final.fsh", "shaders/lib/common.glsl", "shaders/lib/utils.glsl"]
+        );
+        assert_eq!(export.edges.len(), 2);
+
+        assert!(GraphExport::new(
+            &graph,
+            &root,
+            &Scope::Program(PathBuf::from("/pack/shaders/lib/common.glsl")),
+            |_| true
+        )
+        .is_err());
+        assert!(GraphExport::new(&graph, &root, &Scope::File(PathBuf::from("/pack/shaders/missing.glsl")), |_| true).is_err());
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_export_dot() {
+        let mut graph = CachedStableGraph::new();
+        let final_fsh = graph.add_node(&PathBuf::from("/pack/shaders/final.fsh"));
+        let end_fsh = graph.add_node(&PathBuf::from("/pack/shaders/world1/gbuffers_water.fsh"));
+        let a = graph.add_node(&PathBuf::from("/pack/shaders/lib/a.glsl"));
+        let b = graph.add_node(&PathBuf::from("/pack/shaders/lib/b.glsl"));
+        let missing = graph.add_node(&PathBuf::from("/pack/shaders/lib/missing.glsl"));
+        graph.add_edge(
+            final_fsh,
+            a,
+            IncludePosition {
+                line: 2,
+                start: 10,
+                end: 19,
+            },
+        );
+        graph.add_edge(
+            a,
+            b,
+            IncludePosition {
+                line: 0,
+                start: 10,
+                end: 19,
+            },
+        );
+        graph.add_edge(
+            b,
+            a,
+            IncludePosition {
+                line: 1,
+                start: 10,
+                end: 19,
+            },
+        );
+        graph.add_edge(
+            end_fsh,
+            missing,
+            IncludePosition {
+                line: 4,
+                start: 10,
+                end: 25,
+            },
+        );
+
+        let export = GraphExport::new(&graph, &PathBuf::from("/pack"), &Scope::Pack, |path| {
+            !path.ends_with("missing.glsl")
+        })
+        .unwrap();
+        assert_eq!(
+            export.to_dot(),
+            "digraph {\n\
+                \tnode [shape=box]\n\
+                \tn0 [label=\"shaders/final.fsh\" fillcolor=\"#a6cee3\" style=\"filled\"]\n\
+                \tn2 [label=\"shaders/lib/a.glsl\"]\n\
+                \tn3 [label=\"shaders/lib/b.glsl\"]\n\
+                \tn4 [label=\"shaders/lib/missing.glsl\" color=red fontcolor=red style=\"dashed\"]\n\
+                \tsubgraph \"cluster_shaders/world1\" {\n\
+                \t\tlabel=\"world1\"\n\
+                \t\tn1 [label=\"shaders/world1/gbuffers_water.fsh\" fillcolor=\"#a6cee3\" style=\"filled\"]\n\
+                \t}\n\
+                \tn0 -> n2 [label=\"3\"]\n\
+                \tn2 -> n3 [label=\"1\" color=red fontcolor=red]\n\
+                \tn3 -> n2 [label=\"2\" color=red fontcolor=red]\n\
+                \tn1 -> n4 [label=\"5\"]\n\
+            }\n"
+        );
+    }
+}
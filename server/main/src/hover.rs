@@ -0,0 +1,324 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use rust_lsp::lsp_types::{Hover, HoverContents, MarkupContent, MarkupKind, Position, Range};
+use tree_sitter::{Node, Point, Tree};
+
+use crate::builtins::{self, Availability, GlslVersion};
+use crate::preprocessor;
+use crate::symbols::{self, Declaration, DeclarationKind};
+use crate::TreeType;
+
+/// Everything needed to answer a hover request for a file: the file itself and the declarations
+/// of every file that is part of the same program(s), in include order.
+pub struct HoverContext<'a> {
+    pub path: &'a Path,
+    pub source: &'a str,
+    pub tree: &'a Tree,
+    pub scope: &'a [(PathBuf, Vec<Declaration>)],
+    // the macros defined by each program, of which the first resolves `#define` values.
+    pub defines: &'a [HashMap<String, String>],
+    // the `#version` of the top-level program the file is included in, if known.
+    pub version: Option<GlslVersion>,
+    pub stage: Option<TreeType>,
+}
+
+impl<'a> HoverContext<'a> {
+    pub fn hover(&self, position: Position) -> Option<Hover> {
+        let (word, range) = symbols::word_at(self.source, position)?;
+
+        let node = self.identifier_at(range, &word);
+
+        let sections = match node {
+            Some(node) if node.kind() == "field_identifier" => self.project_hover(&word, true),
+            Some(node) => self.local_hover(node, &word).or_else(|| self.project_hover(&word, false)),
+            None => self.project_hover(&word, false),
+        }
+        .or_else(|| self.builtin_hover(&word))?;
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: sections.join("\n\n---\n\n"),
+            }),
+            range: Some(range),
+        })
+    }
+
+    fn identifier_at(&self, range: Range, word: &str) -> Option<Node<'a>> {
+        let start = Point::new(range.start.line as usize, range.start.character as usize);
+        let end = Point::new(range.end.line as usize, range.end.character as usize);
+        self.tree
+            .root_node()
+            .named_descendant_for_point_range(start, end)
+            .filter(|node| symbols::text(*node, self.source) == word)
+    }
+
+    fn local_hover(&self, node: Node, word: &str) -> Option<Vec<String>> {
+        let declaration = symbols::local_declaration(node, word, self.source)?;
+        let label = if declaration.kind() == "parameter_declaration" {
+            "(parameter) "
+        } else {
+            "(local) "
+        };
+        Some(vec![format!(
+            "```glsl\n{}{}\n```",
+            label,
+            symbols::collapse_whitespace(symbols::text(declaration, self.source)).trim_end_matches(';')
+        )])
+    }
+
+    fn project_hover(&self, word: &str, fields: bool) -> Option<Vec<String>> {
+        // declarations in the hovered file take precedence over ones pulled in from elsewhere
+        let ordered = self
+            .scope
+            .iter()
+            .filter(|(path, _)| path == self.path)
+            .chain(self.scope.iter().filter(|(path, _)| path != self.path));
+
+        let mut matches: Vec<(&Path, &Declaration)> = vec![];
+        for (path, declarations) in ordered {
+            for declaration in declarations {
+                if declaration.name != word || (declaration.kind == DeclarationKind::Field) != fields {
+                    continue;
+                }
+                // only functions can be overloaded
+                if matches.iter().any(|(_, d)| d.kind != DeclarationKind::Function || declaration.kind != DeclarationKind::Function) {
+                    continue;
+                }
+                matches.push((path, declaration));
+            }
+        }
+
+        if matches.is_empty() {
+            return None;
+        }
+
+        let sections = matches
+            .into_iter()
+            .map(|(path, declaration)| {
+                let mut section = match &declaration.container {
+                    Some(container) => format!("```glsl\n{} // in struct {}\n```", declaration.signature, container),
+                    None => format!("```glsl\n{}\n```", declaration.signature),
+                };
+
+                if matches!(declaration.kind, DeclarationKind::Define) {
+                    if let Some(resolved) = self.resolve_define(declaration) {
+                        section.push_str(&format!("\n\nResolves to `{}`", resolved));
+                    }
+                }
+
+                if let Some(doc) = &declaration.documentation {
+                    section.push_str("\n\n");
+                    section.push_str(doc);
+                }
+
+                if path != self.path {
+                    let file = path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+                    section.push_str(&format!("\n\n*Declared in `{}` on line {}*", file, declaration.range.start.line + 1));
+                }
+
+                if declaration.kind == DeclarationKind::Uniform {
+                    if let Some(uniform) = builtins::uniform(&declaration.name) {
+                        section.push_str("\n\n");
+                        section.push_str(&uniform_markdown(uniform, false));
+                    }
+                }
+
+                section
+            })
+            .collect();
+
+        Some(sections)
+    }
+
+    fn resolve_define(&self, declaration: &Declaration) -> Option<String> {
+        let value = declaration.value.as_ref()?;
+
+        let defines = self.defines.first()?;
+        let expanded = symbols::collapse_whitespace(&preprocessor::expand(value, defines));
+        let evaluated = preprocessor::evaluate(&expanded).map(|v| v.to_string());
+
+        match evaluated {
+            Some(result) if result != *value => Some(result),
+            Some(_) => None,
+            None if expanded != *value => Some(expanded),
+            None => None,
+        }
+    }
+
+    fn builtin_hover(&self, word: &str) -> Option<Vec<String>> {
+        if let Some(function) = builtins::function(word) {
+            let mut section = format!("```glsl\n{}\n```\n\n{}", function.signatures.join("\n"), function.description);
+            section.push_str(&self.availability_notes(function.since, function.removed, function.stages));
+            return Some(vec![section]);
+        }
+
+        if let Some(variable) = builtins::variable(word) {
+            let mut section = format!("```glsl\n{}\n```\n\n{}", variable.declaration, variable.description);
+            section.push_str(&self.availability_notes(variable.since, variable.removed, variable.stages));
+            return Some(vec![section]);
+        }
+
+        builtins::uniform(word).map(|uniform| vec![uniform_markdown(uniform, true)])
+    }
+
+    fn availability_notes(&self, since: u32, removed: Option<u32>, stages: &[TreeType]) -> String {
+        let mut notes = String::new();
+
+        if let Some(version) = self.version {
+            match version.availability(since, removed) {
+                Availability::Available => {}
+                Availability::NotYetIntroduced(since) => notes.push_str(&format!(
+                    "\n\n**Not available in `#version {}`**, requires GLSL {}",
+                    version,
+                    builtins::format_version(since)
+                )),
+                Availability::RemovedInCore(removed) => notes.push_str(&format!(
+                    "\n\n**Not available in `#version {}`**, removed from the core profile in GLSL {}",
+                    version,
+                    builtins::format_version(removed)
+                )),
+            }
+        } else if since > 110 {
+            notes.push_str(&format!("\n\n*Requires GLSL {}*", builtins::format_version(since)));
+        }
+
+        if !stages.is_empty() {
+            match self.stage {
+                Some(stage) if !stages.contains(&stage) => notes.push_str(&format!(
+                    "\n\n**Not available in this stage**, only in {} shaders",
+                    builtins::format_stages(stages)
+                )),
+                _ => notes.push_str(&format!("\n\n*Only available in {} shaders*", builtins::format_stages(stages))),
+            }
+        }
+
+        notes
+    }
+}
+
+fn uniform_markdown(uniform: &builtins::ShaderUniform, with_declaration: bool) -> String {
+    let mut section = String::new();
+    if with_declaration {
+        section.push_str(&format!("```glsl\nuniform {} {};\n```\n\n", uniform.ty, uniform.name));
+    }
+    section.push_str(&format!("**{} uniform** — {}", uniform.loader, uniform.description));
+    if let Some(range) = uniform.range {
+        section.push_str(&format!("\n\nRange: `{}`", range));
+    }
+    if with_declaration {
+        section.push_str("\n\n*Needs to be declared to be used*");
+    }
+    section
+}
+
+#[cfg(test)]
+mod hover_test {
+    use std::path::{Path, PathBuf};
+
+    use rust_lsp::lsp_types::{HoverContents, Position};
+    use tree_sitter::Parser;
+
+    use crate::builtins::GlslVersion;
+    use crate::hover::HoverContext;
+    use crate::symbols;
+    use crate::TreeType;
+
+    const SOURCE: &str = "#define SHADOW_RES 1024
+#define SHADOW_SIZE (SHADOW_RES * 2)
+// Returns the luminance of the color.
+float luma(vec3 color) {
+    return dot(color, vec3(0.2126, 0.7152, 0.0722));
+}
+void main() {
+    float brightness = luma(texture2D(gcolor, texcoord).rgb) * noise(texcoord);
+    EmitVertex();
+}
+";
+    const COMMON: &str = "float noise(vec2 p) {
+    return fract(sin(dot(p, vec2(12.9898, 78.233))) * 43758.5453);
+}
+";
+
+    fn hover(context: &HoverContext, line: u32, character: u32) -> String {
+        match context.hover(Position::new(line, character)).unwrap().contents {
+            HoverContents::Markup(markup) => markup.value,
+            contents => panic!("unexpected hover contents {:?}", contents),
+        }
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_hover() {
+        let mut parser = Parser::new();
+        parser.set_language(tree_sitter_glsl::language()).unwrap();
+        let tree = parser.parse(SOURCE, None).unwrap();
+        let common = parser.parse(COMMON, None).unwrap();
+
+        let path = Path::new("/shaders/final.fsh");
+        let scope = vec![
+            (
+                PathBuf::from("/shaders/lib/common.glsl"),
+                symbols::collect_declarations(&common, COMMON),
+            ),
+            (path.to_path_buf(), symbols::collect_declarations(&tree, SOURCE)),
+        ];
+        let defines = vec![symbols::defines([COMMON, SOURCE])];
+        let mut context = HoverContext {
+            path,
+            source: SOURCE,
+            tree: &tree,
+            scope: &scope,
+            defines: &defines,
+            version: None,
+            stage: None,
+        };
+
+        // a builtin
+        let dot = hover(&context, 4, 12);
+        assert!(dot.starts_with("```glsl\nfloat dot("), "{}", dot);
+        assert!(!dot.contains("Requires GLSL"), "{}", dot);
+
+        // project declarations, from the file itself and from an include
+        let luma = hover(&context, 7, 23);
+        assert!(luma.starts_with("```glsl\nfloat luma(vec3 color)\n```"), "{}", luma);
+        assert!(luma.contains("Returns the luminance of the color."), "{}", luma);
+        assert!(!luma.contains("*Declared in"), "{}", luma);
+        let noise = hover(&context, 7, 63);
+        assert!(noise.starts_with("```glsl\nfloat noise(vec2 p)\n```"), "{}", noise);
+        assert!(noise.ends_with("*Declared in `common.glsl` on line 1*"), "{}", noise);
+
+        // a #define, resolved through the one it refers to
+        let define = hover(&context, 1, 8);
+        assert!(define.contains("Resolves to `2048`"), "{}", define);
+        // the comment after it is about the function
+        assert!(!define.contains("luminance"), "{}", define);
+
+        // without a known #version or stage, only what the builtin needs is mentioned
+        let emit = hover(&context, 8, 4);
+        assert!(emit.contains("*Requires GLSL 1.50*"), "{}", emit);
+        assert!(emit.contains("*Only available in geometry shaders*"), "{}", emit);
+
+        context.version = GlslVersion::from_source("#version 330 core");
+        context.stage = Some(TreeType::Fragment);
+        let texture2d = hover(&context, 7, 28);
+        assert!(
+            texture2d.contains("**Not available in `#version 330 core`**, removed from the core profile in GLSL 1.40"),
+            "{}",
+            texture2d
+        );
+        let emit = hover(&context, 8, 4);
+        assert!(
+            emit.contains("**Not available in this stage**, only in geometry shaders"),
+            "{}",
+            emit
+        );
+
+        context.version = GlslVersion::from_source("#version 120");
+        let emit = hover(&context, 8, 4);
+        assert!(emit.contains("**Not available in `#version 120`**, requires GLSL 1.50"), "{}", emit);
+        let texture2d = hover(&context, 7, 28);
+        assert!(!texture2d.contains("Not available"), "{}", texture2d);
+    }
+}
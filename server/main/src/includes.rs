@@ -0,0 +1,420 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader};
+use std::path::{Component, Path, PathBuf};
+
+use lazy_static::lazy_static;
+use path_slash::PathBufExt;
+use regex::Regex;
+use rust_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, DiagnosticTag, NumberOrString, Position, Range, TextEdit, Url};
+use tree_sitter::Tree;
+
+use crate::archive;
+use crate::code_actions::{self, Fix};
+use crate::consts;
+use crate::symbol_index::IndexedFile;
+use crate::symbols::{self, DeclarationKind};
+use crate::IncludePosition;
+
+pub static UNUSED_INCLUDE_CODE: &str = "unused-include";
+
+lazy_static! {
+    static ref RE_INCLUDE: Regex = Regex::new(r#"^(?:\s)*?(?:#include) "(.+)"\r?"#).unwrap();
+    static ref RE_IDENTIFIER: Regex = Regex::new(r#"[A-Za-z_]\w*"#).unwrap();
+    static ref RE_INCLUDE_LINE: Regex = Regex::new(r#"^\s*#include\s+""#).unwrap();
+}
+
+/// An `#include` made directly by a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectInclude {
+    pub path: PathBuf,
+    pub position: IncludePosition,
+    // the included file followed by every file it includes in turn.
+    pub files: Vec<PathBuf>,
+}
+
+/// Which of the symbols used by a file are brought in by which of its includes.
+pub struct IncludeUsage<'a> {
+    includes: &'a [DirectInclude],
+    files: &'a HashMap<PathBuf, IndexedFile>,
+    // the symbols used by the file that it doesn't declare itself, with the included files declaring them.
+    needed: Vec<(String, Vec<&'a Path>)>,
+    // the included files declaring symbols that a later include relies on without including them itself,
+    // which packs commonly do with a shared header of settings.
+    needed_by_siblings: HashSet<&'a Path>,
+    // the included files declaring symbols used by the files including this one, for shared headers
+    // that include things on behalf of the programs including them.
+    needed_by_includers: HashSet<&'a Path>,
+}
+
+impl<'a> IncludeUsage<'a> {
+    /// Works out the usage of `includes` by `file`, which is included by `includers`. `files` holds every
+    /// file reachable through `includes`.
+    pub fn new(
+        file: &IndexedFile, includers: &[&IndexedFile], includes: &'a [DirectInclude], files: &'a HashMap<PathBuf, IndexedFile>,
+    ) -> IncludeUsage<'a> {
+        let own: HashSet<&str> = file.declarations.iter().map(|d| d.name.as_str()).collect();
+
+        let mut used: Vec<String> = file.used.iter().filter(|name| !own.contains(name.as_str())).cloned().collect();
+        used.sort();
+
+        let mut reachable: Vec<&Path> = vec![];
+        for file in includes.iter().flat_map(|include| &include.files) {
+            if !reachable.contains(&file.as_path()) {
+                reachable.push(file);
+            }
+        }
+
+        let needed = used
+            .into_iter()
+            .map(|name| {
+                let providers: Vec<&Path> = reachable.iter().copied().filter(|file| provides(files, file, &name)).collect();
+                (name, providers)
+            })
+            .filter(|(_, providers)| !providers.is_empty())
+            .collect();
+
+        let mut needed_by_siblings = HashSet::new();
+        for (i, include) in includes.iter().enumerate() {
+            let earlier: Vec<&Path> = includes[..i].iter().flat_map(|earlier| &earlier.files).map(PathBuf::as_path).collect();
+            for name in include.files.iter().filter_map(|file| files.get(file)).flat_map(|file| &file.used) {
+                if include.files.iter().any(|file| provides(files, file, name)) {
+                    continue;
+                }
+                needed_by_siblings.extend(earlier.iter().copied().filter(|file| provides(files, file, name)));
+            }
+        }
+
+        let needed_by_includers = reachable
+            .iter()
+            .copied()
+            .filter(|file| includers.iter().flat_map(|includer| &includer.used).any(|name| provides(files, file, name)))
+            .collect();
+
+        IncludeUsage {
+            includes,
+            files,
+            needed,
+            needed_by_siblings,
+            needed_by_includers,
+        }
+    }
+
+    // whether the include is needed by something other than the file itself.
+    fn needed_elsewhere(&self, include: &DirectInclude) -> bool {
+        include
+            .files
+            .iter()
+            .any(|file| self.needed_by_siblings.contains(file.as_path()) || self.needed_by_includers.contains(file.as_path()))
+    }
+
+    /// The includes that don't bring in any symbol the file uses, not even indirectly.
+    pub fn unused(&self) -> Vec<&'a DirectInclude> {
+        self.includes
+            .iter()
+            .filter(|include| !self.needed_elsewhere(include))
+            .filter(|include| {
+                !self
+                    .needed
+                    .iter()
+                    .any(|(_, providers)| providers.iter().any(|p| include.files.iter().any(|f| f == p)))
+            })
+            .collect()
+    }
+
+    /// The files declaring symbols that the file uses but only gets through another include, in the
+    /// order they're first reached.
+    pub fn missing(&self) -> Vec<&'a Path> {
+        let mut missing: Vec<&Path> = vec![];
+        for (name, providers) in &self.needed {
+            if self.includes.iter().any(|include| provides(self.files, &include.path, name)) {
+                continue;
+            }
+            if !missing.contains(&providers[0]) {
+                missing.push(providers[0]);
+            }
+        }
+        missing
+    }
+
+    /// The includes that are no longer needed once `added` are included directly: every symbol used
+    /// from them is then declared by another direct include. Of several includes declaring the same
+    /// symbol, the first one is kept.
+    pub fn dead(&self, added: &[&Path]) -> Vec<&'a DirectInclude> {
+        let mut dead: Vec<&DirectInclude> = vec![];
+        for include in self.includes.iter().rev().filter(|include| !self.needed_elsewhere(include)) {
+            let still_provided = self
+                .needed
+                .iter()
+                .filter(|(_, providers)| providers.iter().any(|p| include.files.iter().any(|f| f == p)))
+                .all(|(name, _)| {
+                    let kept = self
+                        .includes
+                        .iter()
+                        .filter(|other| *other != include && !dead.contains(other))
+                        .map(|other| other.path.as_path());
+                    kept.chain(added.iter().copied()).any(|file| provides(self.files, file, name))
+                });
+            if still_provided {
+                dead.push(include);
+            }
+        }
+        dead.reverse();
+        dead
+    }
+}
+
+fn provides(files: &HashMap<PathBuf, IndexedFile>, file: &Path, name: &str) -> bool {
+    files.get(file).map_or(false, |file| {
+        file.declarations
+            .iter()
+            .any(|d| d.name == name && !matches!(d.kind, DeclarationKind::Field | DeclarationKind::DisabledOption))
+    })
+}
+
+/// Every identifier and type name referred to in the file, including the ones in macro bodies and
+/// preprocessor conditions.
+pub fn used_names(tree: &Tree, source: &str) -> HashSet<String> {
+    let mut names = HashSet::new();
+    let mut cursor = tree.walk();
+    'walk: loop {
+        let node = cursor.node();
+        match node.kind() {
+            "identifier" | "type_identifier" => {
+                names.insert(symbols::text(node, source).to_string());
+            }
+            "preproc_arg" => names.extend(RE_IDENTIFIER.find_iter(symbols::text(node, source)).map(|m| m.as_str().to_string())),
+            _ => {}
+        }
+
+        if cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                break 'walk;
+            }
+        }
+    }
+    names
+}
+
+/// The file that `include`, as written in an `#include` in `file`, refers to. Paths starting with `/`
+/// are relative to the first of `include_roots` they exist in (the `shaders` folder unless configured
+/// otherwise), anything else to the folder of the including file.
+pub fn resolve(include_roots: &[PathBuf], file: &Path, include: &str) -> PathBuf {
+    match include.strip_prefix('/') {
+        Some(include) => {
+            let include = PathBuf::from_slash(include);
+            include_roots
+                .iter()
+                .map(|include_root| include_root.join(&include))
+                .find(|path| archive::exists(path))
+                .unwrap_or_else(|| include_roots[0].join(&include))
+        }
+        None => file.parent().unwrap().join(PathBuf::from_slash(include)),
+    }
+}
+
+/// The files included by `file`, given its content, along with where they're included.
+pub fn find(include_roots: &[PathBuf], file: &Path, contents: &[u8]) -> Vec<(PathBuf, IncludePosition)> {
+    let mut includes = Vec::default();
+
+    let buf = BufReader::new(contents);
+    buf.lines()
+        .enumerate()
+        .filter_map(|line| match line.1 {
+            Ok(t) => Some((line.0, t)),
+            Err(_e) => None,
+        })
+        .filter(|line| RE_INCLUDE.is_match(line.1.as_str()))
+        .for_each(|line| {
+            let cap = RE_INCLUDE.captures(line.1.as_str()).unwrap().get(1).unwrap();
+
+            let start = cap.start();
+            let end = cap.end();
+            let full_include = resolve(include_roots, file, cap.as_str());
+
+            includes.push((full_include, IncludePosition { line: line.0, start, end }));
+        });
+
+    includes
+}
+
+/// How an `#include` in `file` refers to `target`, either from one of the `include_roots` (if `absolute`)
+/// or relative to the folder of `file`.
+pub fn include_text(include_roots: &[PathBuf], file: &Path, target: &Path, absolute: bool) -> Option<String> {
+    if absolute {
+        return code_actions::include_path(include_roots, target);
+    }
+    relative_path(file.parent()?, target).to_slash().map(|path| path.to_string())
+}
+
+fn relative_path(from: &Path, to: &Path) -> PathBuf {
+    let from: Vec<Component> = from.components().collect();
+    let to: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut path = PathBuf::new();
+    for _ in common..from.len() {
+        path.push("..");
+    }
+    for component in &to[common..] {
+        path.push(component);
+    }
+    path
+}
+
+pub fn unused_include_diagnostic(include: &DirectInclude, source: &str) -> Diagnostic {
+    let line = include.position.line as u32;
+    let include_text = source
+        .lines()
+        .nth(include.position.line)
+        .and_then(|text| text.get(include.position.start..include.position.end))
+        .unwrap_or_default();
+    Diagnostic {
+        range: Range::new(
+            Position::new(line, include.position.start as u32),
+            Position::new(line, include.position.end as u32),
+        ),
+        severity: Some(DiagnosticSeverity::WARNING),
+        code: Some(NumberOrString::String(UNUSED_INCLUDE_CODE.into())),
+        source: Some(consts::SOURCE.into()),
+        message: format!("`{}` is included but none of its symbols are used", include_text),
+        tags: Some(vec![DiagnosticTag::UNNECESSARY]),
+        ..Diagnostic::default()
+    }
+}
+
+/// Deletes the whole line of the include on `line`, unless the unsaved document moved it elsewhere.
+fn remove_line(source: &str, line: usize) -> Option<TextEdit> {
+    if !RE_INCLUDE_LINE.is_match(source.lines().nth(line)?) {
+        return None;
+    }
+    Some(TextEdit {
+        range: Range::new(Position::new(line as u32, 0), Position::new(line as u32 + 1, 0)),
+        new_text: "".into(),
+    })
+}
+
+pub fn remove_include(uri: &Url, source: &str, line: usize) -> Option<Fix> {
+    Some(Fix::new("Remove unused include".into(), uri, vec![remove_line(source, line)?]))
+}
+
+/// Adds the includes in `added` (as written in their directive) and removes the `dead` ones in one go.
+pub fn organize_includes(uri: &Url, source: &str, added: &[String], dead: &[&DirectInclude]) -> Option<Fix> {
+    let mut edits: Vec<TextEdit> = dead.iter().filter_map(|include| remove_line(source, include.position.line)).collect();
+    let insert_at = code_actions::header_end(source);
+    edits.extend(added.iter().map(|include| code_actions::insert_line(insert_at, format!("#include \"{}\"", include))));
+
+    if edits.is_empty() {
+        return None;
+    }
+    Some(Fix::new("Organize includes".into(), uri, edits))
+}
+
+#[cfg(test)]
+mod includes_test {
+    use std::collections::HashMap;
+    use std::path::{Path, PathBuf};
+
+    use tree_sitter::Parser;
+
+    use crate::includes::{include_text, resolve, DirectInclude, IncludeUsage};
+    use crate::symbol_index::IndexedFile;
+    use crate::IncludePosition;
+
+    fn include(line: usize, files: &[&str]) -> DirectInclude {
+        DirectInclude {
+            path: files[0].into(),
+            position: IncludePosition { line, start: 10, end: 20 },
+            files: files.iter().map(PathBuf::from).collect(),
+        }
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_include_usage() {
+        let mut parser = Parser::new();
+        parser.set_language(tree_sitter_glsl::language()).unwrap();
+
+        let sources = [
+            ("/shaders/lib/shadows.glsl", "float getShadow(vec3 pos) { return sampleDepth(pos); }"),
+            ("/shaders/lib/depth.glsl", "float sampleDepth(vec3 pos) { return pos.z; }"),
+            ("/shaders/lib/fog.glsl", "vec3 applyFog(vec3 color) { return color; }"),
+            ("/shaders/lib/noise.glsl", "float noise(vec2 p) { return p.x; }"),
+            ("/shaders/lib/noise_copy.glsl", "float noise(vec2 p) { return p.y; }"),
+        ];
+        let mut files = HashMap::new();
+        for (path, source) in sources {
+            let tree = parser.parse(source, None).unwrap();
+            files.insert(PathBuf::from(path), IndexedFile::new(&tree, source));
+        }
+
+        let includes = vec![
+            include(0, &["/shaders/lib/shadows.glsl", "/shaders/lib/depth.glsl"]),
+            include(1, &["/shaders/lib/fog.glsl"]),
+            include(2, &["/shaders/lib/noise.glsl"]),
+            include(3, &["/shaders/lib/noise_copy.glsl"]),
+        ];
+        let source = "void main() {\n    float depth = sampleDepth(vec3(0.0)) + noise(vec2(0.0));\n}";
+        let file = IndexedFile::new(&parser.parse(source, None).unwrap(), source);
+        let usage = IncludeUsage::new(&file, &[], &includes, &files);
+
+        let unused: Vec<usize> = usage.unused().iter().map(|include| include.position.line).collect();
+        assert_eq!(unused, vec![1]);
+
+        assert_eq!(usage.missing(), vec![Path::new("/shaders/lib/depth.glsl")]);
+
+        let dead: Vec<usize> = usage.dead(&usage.missing()).iter().map(|include| include.position.line).collect();
+        assert_eq!(dead, vec![0, 1, 3]);
+
+        // without the direct include the shadows one is still needed for sampleDepth
+        let dead: Vec<usize> = usage.dead(&[]).iter().map(|include| include.position.line).collect();
+        assert_eq!(dead, vec![1, 3]);
+
+        // fog isn't used by the file itself, but by the include after it
+        let includes = vec![
+            include(0, &["/shaders/lib/fog.glsl"]),
+            include(1, &["/shaders/lib/sky.glsl"]),
+        ];
+        let sky = "vec3 sky(vec3 color) { return applyFog(color); }";
+        files.insert("/shaders/lib/sky.glsl".into(), IndexedFile::new(&parser.parse(sky, None).unwrap(), sky));
+        let source = "void main() {\n    gl_FragColor = vec4(sky(vec3(0.0)), 1.0);\n}";
+        let file = IndexedFile::new(&parser.parse(source, None).unwrap(), source);
+        let usage = IncludeUsage::new(&file, &[], &includes, &files);
+        assert!(usage.unused().is_empty());
+        assert!(usage.dead(&[]).is_empty());
+
+        // a shared header including fog for the programs including it
+        let includes = vec![include(0, &["/shaders/lib/fog.glsl"])];
+        let source = "const float FOG_DENSITY = 0.5;";
+        let header = IndexedFile::new(&parser.parse(source, None).unwrap(), source);
+        let usage = IncludeUsage::new(&header, &[], &includes, &files);
+        assert_eq!(usage.unused().len(), 1);
+
+        let source = "void main() {\n    gl_FragColor = vec4(applyFog(vec3(FOG_DENSITY)), 1.0);\n}";
+        let program = IndexedFile::new(&parser.parse(source, None).unwrap(), source);
+        let usage = IncludeUsage::new(&header, &[&program], &includes, &files);
+        assert!(usage.unused().is_empty());
+        assert!(usage.dead(&[]).is_empty());
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_include_paths() {
+        let root = &[PathBuf::from("/pack/shaders")];
+        let file = Path::new("/pack/shaders/world0/composite.fsh");
+
+        assert_eq!(resolve(root, file, "/lib/common.glsl"), PathBuf::from("/pack/shaders/lib/common.glsl"));
+        assert_eq!(resolve(root, file, "../lib/common.glsl"), PathBuf::from("/pack/shaders/world0/../lib/common.glsl"));
+
+        let target = Path::new("/pack/shaders/lib/utils/common.glsl");
+        assert_eq!(include_text(root, file, target, true), Some("/lib/utils/common.glsl".into()));
+        assert_eq!(include_text(root, file, target, false), Some("../lib/utils/common.glsl".into()));
+        assert_eq!(
+            include_text(root, Path::new("/pack/shaders/lib/a.glsl"), Path::new("/pack/shaders/lib/b.glsl"), false),
+            Some("b.glsl".into())
+        );
+        assert_eq!(include_text(root, file, Path::new("/elsewhere/a.glsl"), true), None);
+    }
+}
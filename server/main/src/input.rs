@@ -0,0 +1,174 @@
+use std::io::{self, BufRead, Read};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use serde_json::json;
+use slog_scope::error;
+
+/// Notification the server sends itself once a pack has been scanned in the background.
+pub static SCAN_FINISHED: &str = "mcglsl/scanFinished";
+
+/// The messages the server reads: the ones the client sends over stdin, along with the notifications the
+/// server sends itself from other threads. This way work finished in the background is picked up by the
+/// main loop right away, rather than whenever the client happens to send the next message.
+pub struct Input {
+    messages: Receiver<Vec<u8>>,
+    current: Vec<u8>,
+    read: usize,
+}
+
+/// Sends notifications to the main loop from other threads.
+#[derive(Clone)]
+pub struct Notifier(Sender<Vec<u8>>);
+
+impl Notifier {
+    pub fn notify(&self, method: &str) {
+        let body = json!({ "jsonrpc": "2.0", "method": method, "params": {} }).to_string();
+        // only fails once the main loop is gone
+        let _ = self.0.send(frame(body.as_bytes()));
+    }
+}
+
+fn frame(body: &[u8]) -> Vec<u8> {
+    let mut message = format!("Content-Length: {}\r\n\r\n", body.len()).into_bytes();
+    message.extend_from_slice(body);
+    message
+}
+
+/// Reads the messages of the client from `client` on a thread of its own. Messages are passed on whole,
+/// so that notifications are never sent in the middle of one.
+pub fn channel(mut client: impl BufRead + Send + 'static) -> (Input, Notifier) {
+    let (sender, messages) = mpsc::channel();
+    let client_sender = sender.clone();
+    thread::spawn(move || {
+        loop {
+            match read_message(&mut client) {
+                Ok(Some(body)) => {
+                    if client_sender.send(frame(&body)).is_err() {
+                        return;
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    error!("error reading message from client"; "error" => format!("{:?}", e));
+                    break;
+                }
+            }
+        }
+        // an empty message ends the input, as the notifiers keep the channel open
+        let _ = client_sender.send(vec![]);
+    });
+
+    let input = Input {
+        messages,
+        current: vec![],
+        read: 0,
+    };
+    (input, Notifier(sender))
+}
+
+/// The body of the next message, or `None` once the client closed its end.
+fn read_message(client: &mut impl BufRead) -> io::Result<Option<Vec<u8>>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if client.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let length = length.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "message without a Content-Length"))?;
+    let mut body = vec![0; length];
+    client.read_exact(&mut body)?;
+    Ok(Some(body))
+}
+
+impl Read for Input {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for Input {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.read == self.current.len() {
+            // a closed channel or an empty message is the end of the input
+            self.current = self.messages.recv().unwrap_or_default();
+            self.read = 0;
+        }
+        Ok(&self.current[self.read..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.read = (self.read + amt).min(self.current.len());
+    }
+}
+
+#[cfg(test)]
+mod input_test {
+    use std::io::{self, BufRead, BufReader, Read};
+    use std::sync::mpsc::{self, Receiver};
+
+    use crate::input::{channel, frame, Input, SCAN_FINISHED};
+
+    // a client that sends whatever the test hands it, and closes its end once the test drops the sender.
+    struct Client(Receiver<Vec<u8>>);
+
+    impl Read for Client {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let chunk = self.0.recv().unwrap_or_default();
+            buf[..chunk.len()].copy_from_slice(&chunk);
+            Ok(chunk.len())
+        }
+    }
+
+    fn next_message(input: &mut Input) -> Option<String> {
+        let mut header = String::new();
+        if input.read_line(&mut header).unwrap() == 0 {
+            return None;
+        }
+        let length: usize = header.trim_start_matches("Content-Length: ").trim().parse().unwrap();
+        input.read_line(&mut header).unwrap();
+        let mut body = vec![0; length];
+        input.read_exact(&mut body).unwrap();
+        Some(String::from_utf8(body).unwrap())
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_channel() {
+        let (client, client_input) = mpsc::channel();
+        let (mut input, notifier) = channel(BufReader::new(Client(client_input)));
+
+        let body = r#"{"jsonrpc":"2.0","method":"initialized","params":{}}"#;
+        let mut message = format!("Content-Length: {}\r\nContent-Type: application/vscode-jsonrpc\r\n\r\n", body.len());
+        message.push_str(body);
+        // split up, as it may be read off stdin
+        let (first, second) = message.split_at(30);
+        client.send(first.as_bytes().to_vec()).unwrap();
+        client.send(second.as_bytes().to_vec()).unwrap();
+        assert_eq!(next_message(&mut input).unwrap(), body);
+
+        notifier.notify(SCAN_FINISHED);
+        let notification: serde_json::Value = serde_json::from_str(&next_message(&mut input).unwrap()).unwrap();
+        assert_eq!(notification["method"], SCAN_FINISHED);
+
+        // the input ends with the client's end, even though the notifier is still around
+        drop(client);
+        assert_eq!(next_message(&mut input), None);
+        assert_eq!(frame(b"{}"), b"Content-Length: 2\r\n\r\n{}");
+    }
+}
@@ -1,4 +1,12 @@
-use rust_lsp::lsp_types::notification::Notification;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use rust_lsp::jsonrpc::Endpoint;
+use rust_lsp::lsp_types::notification::{self, Notification};
+use rust_lsp::lsp_types::request::{Request, WorkDoneProgressCreate};
+use rust_lsp::lsp_types::{
+    ClientCapabilities, NumberOrString, ProgressParams, ProgressParamsValue, WorkDoneProgress, WorkDoneProgressBegin,
+    WorkDoneProgressCreateParams, WorkDoneProgressEnd, WorkDoneProgressReport,
+};
 use serde::{Deserialize, Serialize};
 
 pub enum Status {}
@@ -14,3 +22,96 @@ pub struct StatusParams {
     pub message: Option<String>,
     pub icon: Option<String>,
 }
+
+/// Updates the status bar item of the client.
+pub fn set_status(endpoint: &Endpoint, status: impl Into<String>, message: impl Into<String>, icon: impl Into<String>) {
+    endpoint
+        .send_notification(
+            Status::METHOD,
+            StatusParams {
+                status: status.into(),
+                message: Some(message.into()),
+                icon: Some(icon.into()),
+            },
+        )
+        .unwrap_or(());
+}
+
+pub fn supports_work_done_progress(capabilities: &ClientCapabilities) -> bool {
+    capabilities
+        .window
+        .as_ref()
+        .and_then(|window| window.work_done_progress)
+        .unwrap_or(false)
+}
+
+/// Work done by the server in the background, which the client shows a progress notification for
+/// until it's ended.
+pub struct Progress {
+    endpoint: Endpoint,
+    token: NumberOrString,
+    cancellable: bool,
+    // last reported, to only notify the client when it changes.
+    percentage: AtomicU32,
+}
+
+impl Progress {
+    /// Asks the client to show the progress of the work identified by `token`. Only clients that
+    /// announced support for work done progress may be sent any of it. Cancellable work gets a
+    /// `window/workDoneProgress/cancel` with `token` when the user cancels it.
+    pub fn begin(endpoint: &Endpoint, token: NumberOrString, title: impl Into<String>, cancellable: bool) -> Progress {
+        let mut endpoint = endpoint.clone();
+        endpoint
+            .send_request::<_, (), (), _>(
+                WorkDoneProgressCreate::METHOD,
+                WorkDoneProgressCreateParams { token: token.clone() },
+                |_| {},
+            )
+            .unwrap_or(());
+
+        let progress = Progress {
+            endpoint,
+            token,
+            cancellable,
+            percentage: AtomicU32::new(0),
+        };
+        progress.send(WorkDoneProgress::Begin(WorkDoneProgressBegin {
+            title: title.into(),
+            cancellable: Some(cancellable),
+            message: None,
+            percentage: Some(0),
+        }));
+        progress
+    }
+
+    /// Reports that `done` out of `total` steps are done.
+    pub fn report(&self, done: usize, total: usize) {
+        let percentage = (done * 100 / total.max(1)) as u32;
+        if self.percentage.swap(percentage, Ordering::Relaxed) == percentage {
+            return;
+        }
+        self.send(WorkDoneProgress::Report(WorkDoneProgressReport {
+            cancellable: Some(self.cancellable),
+            message: Some(format!("{}/{}", done, total)),
+            percentage: Some(percentage),
+        }));
+    }
+
+    pub fn end(self, message: impl Into<String>) {
+        self.send(WorkDoneProgress::End(WorkDoneProgressEnd {
+            message: Some(message.into()),
+        }));
+    }
+
+    fn send(&self, value: WorkDoneProgress) {
+        self.endpoint
+            .send_notification(
+                notification::Progress::METHOD,
+                ProgressParams {
+                    token: self.token.clone(),
+                    value: ProgressParamsValue::WorkDone(value),
+                },
+            )
+            .unwrap_or(());
+    }
+}
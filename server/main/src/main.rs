@@ -2,63 +2,107 @@
 #![feature(option_get_or_insert_default)]
 
 use merge_views::FilialTuple;
+use rust_lsp::jsonrpc::jsonrpc_common::error_JSON_RPC_MethodNotFound;
+use rust_lsp::jsonrpc::jsonrpc_request::RequestParams;
 use rust_lsp::jsonrpc::{method_types::*, *};
 use rust_lsp::lsp::*;
+use rust_lsp::lsp_types::request::{self, Request as _};
 use rust_lsp::lsp_types::{notification::*, *};
 
 use petgraph::stable_graph::NodeIndex;
 use path_slash::PathExt;
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::{from_value, Value};
 
 use tree_sitter::Parser;
-use url_norm::FromUrl;
+use symbol_index::IndexedFile;
+use url_norm::{FromUrl, ToUrl};
 
-use walkdir::WalkDir;
 
 use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 use std::fmt::{Debug, Display, Formatter};
-use std::fs;
-use std::io::{stdin, stdout, BufRead, BufReader};
-use std::iter::{Extend, FromIterator};
-use std::rc::Rc;
+use std::io::{stdin, stdout, BufReader};
+use std::iter::Extend;
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::thread;
 
-use std::{
-    cell::RefCell,
-    path::{Path, PathBuf},
-};
+use std::path::{Path, PathBuf};
 
 use slog::Level;
 use slog_scope::{debug, error, info, warn};
 
-use path_slash::PathBufExt;
-
 use anyhow::{anyhow, Result};
 
 use regex::Regex;
 
 use lazy_static::lazy_static;
 
+mod archive;
+mod baseline;
+mod builtins;
+mod call_hierarchy;
+mod canonical;
+mod cli;
+mod code_actions;
 mod commands;
 mod configuration;
 mod consts;
 mod dfs;
 mod diagnostics_parser;
+mod executor;
+mod fnv;
+mod folding;
+mod formatter;
 mod graph;
+mod graph_cache;
+mod graph_export;
+mod hover;
+mod includes;
+mod input;
 mod linemap;
 mod lsp_ext;
 mod merge_views;
 mod navigation;
 mod opengl;
+mod preamble;
+mod preprocessor;
+mod sarif;
+mod scan;
+mod semantic_tokens;
+mod signature_help;
 mod source_mapper;
+mod suppressions;
+mod symbol_index;
+mod symbols;
 mod url_norm;
+mod workspace;
 
 #[cfg(test)]
 mod test;
 
+/// Where `path` ends up after `renames`, which may move it or a folder holding it.
+fn moved_to(renames: &[(PathBuf, PathBuf)], path: &Path) -> PathBuf {
+    for (old, new) in renames {
+        if let Ok(rest) = path.strip_prefix(old) {
+            return if rest.as_os_str().is_empty() { new.clone() } else { new.join(rest) };
+        }
+    }
+    path.to_path_buf()
+}
+
+/// The old and new paths of the files in a `workspace/willRenameFiles` or `workspace/didRenameFiles` message.
+fn file_renames(params: &RenameFilesParams) -> Vec<(PathBuf, PathBuf)> {
+    params
+        .files
+        .iter()
+        .filter_map(|rename| Some((Url::parse(&rename.old_uri).ok()?, Url::parse(&rename.new_uri).ok()?)))
+        .map(|(old, new)| (PathBuf::from_url(old), PathBuf::from_url(new)))
+        .collect()
+}
+
 pub fn is_top_level(path: &Path) -> bool {
     let path = path.to_slash().unwrap();
     if !RE_WORLD_FOLDER.is_match(&path) {
@@ -69,8 +113,10 @@ pub fn is_top_level(path: &Path) -> bool {
     (len == 3 || len == 2) && TOPLEVEL_FILES.contains(parts[len - 1])
 }
 
+// upper bound on workspace symbol results, clients filter further as the query is refined.
+const MAX_WORKSPACE_SYMBOLS: usize = 256;
+
 lazy_static! {
-    static ref RE_INCLUDE: Regex = Regex::new(r#"^(?:\s)*?(?:#include) "(.+)"\r?"#).unwrap();
     static ref RE_WORLD_FOLDER: Regex = Regex::new(r#"^shaders(/world-?\d+)?"#).unwrap();
     static ref TOPLEVEL_FILES: HashSet<String> = {
         let mut set = HashSet::with_capacity(1716);
@@ -135,60 +181,63 @@ lazy_static! {
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("check") {
+        std::process::exit(cli::run(&args[2..]));
+    }
+
     let guard = logging::set_logger_with_level(Level::Info);
 
     let endpoint_output = LSPEndpoint::create_lsp_output_with_output_stream(stdout);
 
-    let cache_graph = graph::CachedStableGraph::new();
-
     let mut parser = Parser::new();
     parser.set_language(tree_sitter_glsl::language()).unwrap();
 
-    let mut langserver = MinecraftShaderLanguageServer {
-        endpoint: endpoint_output.clone(),
-        graph: Rc::new(RefCell::new(cache_graph)),
-        root: "".into(),
-        command_provider: None,
-        opengl_context: Rc::new(opengl::OpenGlContext::new()),
-        tree_sitter: Rc::new(RefCell::new(parser)),
-        log_guard: Some(guard),
-    };
+    let (mut input, notifier) = input::channel(BufReader::new(stdin()));
 
-    langserver.command_provider = Some(commands::CustomCommandProvider::new(vec![
-        (
-            "graphDot",
-            Box::new(commands::graph_dot::GraphDotCommand {
-                graph: langserver.graph.clone(),
-            }),
-        ),
-        (
-            "virtualMerge",
-            Box::new(commands::merged_includes::VirtualMergedDocument {
-                graph: langserver.graph.clone(),
-            }),
-        ),
-        (
-            "parseTree",
-            Box::new(commands::parse_tree::TreeSitterSExpr {
-                tree_sitter: langserver.tree_sitter.clone(),
-            }),
-        ),
-    ]));
+    let workspace = workspace::Workspace::new(
+        endpoint_output.clone(),
+        Arc::new(opengl::OpenGlContext::new()),
+        Arc::new(Mutex::new(parser)),
+        Some(guard),
+        Some(notifier),
+    );
 
-    LSPEndpoint::run_server_from_input(&mut stdin().lock(), endpoint_output, langserver);
+    LSPEndpoint::run_server_from_input(&mut input, endpoint_output, workspace);
 }
 
+// renames with the includes rewritten for them, as `(parent, child, old position, new position)`.
+type PendingRename = (Vec<(PathBuf, PathBuf)>, Vec<(PathBuf, PathBuf, IncludePosition, IncludePosition)>);
+
 pub struct MinecraftShaderLanguageServer {
     endpoint: Endpoint,
-    graph: Rc<RefCell<graph::CachedStableGraph>>,
+    graph: Arc<Mutex<graph::CachedStableGraph>>,
+    symbol_index: Arc<Mutex<symbol_index::SymbolIndex>>,
     root: PathBuf,
     command_provider: Option<commands::CustomCommandProvider>,
-    opengl_context: Rc<dyn opengl::ShaderValidator>,
-    tree_sitter: Rc<RefCell<Parser>>,
+    opengl_context: Arc<dyn opengl::ShaderValidator>,
+    tree_sitter: Arc<Mutex<Parser>>,
+    // contents of open documents, which may not have been saved yet.
+    documents: HashMap<PathBuf, String>,
+    // the path each open document was opened with, keyed by its canonical path.
+    document_paths: HashMap<PathBuf, PathBuf>,
+    config: configuration::ProjectConfig,
     log_guard: Option<slog_scope::GlobalLoggerGuard>,
+    // runs lints off the main thread, shared by every pack of the workspace.
+    lints: Arc<executor::LintExecutor>,
+    // the scan building the graph, while it runs.
+    scan: Mutex<Option<thread::JoinHandle<scan::PackScan>>>,
+    // whether the client shows progress for work done by the server.
+    client_progress: bool,
+    // the renames asked about in the last `workspace/willRenameFiles`, with the includes rewritten for them.
+    pending_rename: Mutex<Option<PendingRename>>,
+    // the pack's baseline, read when the pack is opened and again whenever it changes.
+    baseline: Option<Arc<baseline::Baseline>>,
+    // tells the main loop when the scan is done, so its results don't wait for the next message.
+    notifier: Option<input::Notifier>,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct IncludePosition {
     // the 0-indexed line on which the include lives.
     line: usize,
@@ -210,7 +259,8 @@ impl Display for IncludePosition {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum TreeType {
     Fragment,
     Vertex,
@@ -218,6 +268,18 @@ pub enum TreeType {
     Compute,
 }
 
+impl TreeType {
+    pub fn from_path(path: &Path) -> Option<TreeType> {
+        match path.extension()?.to_str()? {
+            "fsh" => Some(TreeType::Fragment),
+            "vsh" => Some(TreeType::Vertex),
+            "gsh" => Some(TreeType::Geometry),
+            "csh" => Some(TreeType::Compute),
+            _ => None,
+        }
+    }
+}
+
 impl MinecraftShaderLanguageServer {
     pub fn error_not_available<DATA>(data: DATA) -> MethodError<DATA> {
         let msg = "Functionality not implemented.".to_string();
@@ -228,48 +290,274 @@ impl MinecraftShaderLanguageServer {
         }
     }
 
-    fn build_initial_graph(&self) {
+    /// What the server supports, which is the same for every pack.
+    pub fn capabilities() -> ServerCapabilities {
+        ServerCapabilities {
+            definition_provider: Some(OneOf::Left(true)),
+            references_provider: Some(OneOf::Left(true)),
+            document_highlight_provider: Some(OneOf::Left(true)),
+            document_symbol_provider: Some(OneOf::Left(true)),
+            workspace_symbol_provider: Some(OneOf::Left(true)),
+            folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+            document_formatting_provider: Some(OneOf::Left(true)),
+            document_range_formatting_provider: Some(OneOf::Left(true)),
+            call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
+            code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+            workspace: Some(WorkspaceServerCapabilities {
+                workspace_folders: Some(WorkspaceFoldersServerCapabilities {
+                    supported: Some(true),
+                    change_notifications: Some(OneOf::Left(true)),
+                }),
+                file_operations: Some(WorkspaceFileOperationsServerCapabilities {
+                    will_rename: Some(FileOperationRegistrationOptions {
+                        filters: vec![
+                            FileOperationFilter {
+                                scheme: Some("file".into()),
+                                pattern: FileOperationPattern {
+                                    glob: "**/*.{vsh,fsh,gsh,csh,glsl,inc}".into(),
+                                    matches: Some(FileOperationPatternKind::File),
+                                    options: None,
+                                },
+                            },
+                            FileOperationFilter {
+                                scheme: Some("file".into()),
+                                pattern: FileOperationPattern {
+                                    glob: "**".into(),
+                                    matches: Some(FileOperationPatternKind::Folder),
+                                    options: None,
+                                },
+                            },
+                        ],
+                    }),
+                    did_rename: Some(FileOperationRegistrationOptions {
+                        filters: vec![
+                            FileOperationFilter {
+                                scheme: Some("file".into()),
+                                pattern: FileOperationPattern {
+                                    glob: "**/*.{vsh,fsh,gsh,csh,glsl,inc}".into(),
+                                    matches: Some(FileOperationPatternKind::File),
+                                    options: None,
+                                },
+                            },
+                            FileOperationFilter {
+                                scheme: Some("file".into()),
+                                pattern: FileOperationPattern {
+                                    glob: "**".into(),
+                                    matches: Some(FileOperationPatternKind::Folder),
+                                    options: None,
+                                },
+                            },
+                        ],
+                    }),
+                    ..WorkspaceFileOperationsServerCapabilities::default()
+                }),
+            }),
+            semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(SemanticTokensOptions {
+                work_done_progress_options: WorkDoneProgressOptions { work_done_progress: None },
+                legend: semantic_tokens::legend(),
+                range: Some(true),
+                full: Some(SemanticTokensFullOptions::Bool(true)),
+            })),
+            hover_provider: Some(HoverProviderCapability::Simple(true)),
+            signature_help_provider: Some(SignatureHelpOptions {
+                trigger_characters: Some(vec!["(".into(), ",".into()]),
+                retrigger_characters: None,
+                work_done_progress_options: WorkDoneProgressOptions { work_done_progress: None },
+            }),
+            document_link_provider: Some(DocumentLinkOptions {
+                resolve_provider: None,
+                work_done_progress_options: WorkDoneProgressOptions { work_done_progress: None },
+            }),
+            execute_command_provider: Some(ExecuteCommandOptions {
+                commands: vec!["graphDot".into()],
+                work_done_progress_options: WorkDoneProgressOptions { work_done_progress: None },
+            }),
+            text_document_sync: Some(TextDocumentSyncCapability::Options(TextDocumentSyncOptions {
+                open_close: Some(true),
+                will_save: None,
+                will_save_wait_until: None,
+                change: Some(TextDocumentSyncKind::FULL),
+                save: Some(TextDocumentSyncSaveOptions::SaveOptions(SaveOptions { include_text: Some(true) })),
+            })),
+            ..ServerCapabilities::default()
+        }
+    }
+
+    /// The commands run through `workspace/executeCommand`, which hold on to the validator in use.
+    fn build_command_provider(&self) -> commands::CustomCommandProvider {
+        commands::CustomCommandProvider::new(vec![
+            (
+                "graphDot",
+                Box::new(commands::graph_dot::GraphDotCommand {
+                    graph: self.graph.clone(),
+                    graph_output: self.config.graph_output.clone(),
+                }),
+            ),
+            (
+                "graphExport",
+                Box::new(commands::graph_export::GraphExportCommand {
+                    graph: self.graph.clone(),
+                }),
+            ),
+            (
+                "graphCheck",
+                Box::new(commands::graph_check::GraphCheckCommand {
+                    graph: self.graph.clone(),
+                }),
+            ),
+            (
+                "virtualMerge",
+                Box::new(commands::merged_includes::VirtualMergedDocument {
+                    graph: self.graph.clone(),
+                }),
+            ),
+            (
+                "parseTree",
+                Box::new(commands::parse_tree::TreeSitterSExpr {
+                    tree_sitter: self.tree_sitter.clone(),
+                }),
+            ),
+            (
+                "sarifReport",
+                Box::new(commands::sarif_report::SarifReportCommand {
+                    graph: self.graph.clone(),
+                    symbol_index: self.symbol_index.clone(),
+                    opengl_context: self.opengl_context.clone(),
+                    tree_sitter: self.tree_sitter.clone(),
+                }),
+            ),
+            ("archiveFiles", Box::new(commands::archive::ArchiveFiles)),
+            ("archiveFile", Box::new(commands::archive::ArchiveFile)),
+        ])
+    }
+
+    /// Sets the server up for the pack at `root`, loading its project config and building its graph.
+    fn open_pack(&mut self, root: PathBuf) {
+        self.root = root;
+
+        match configuration::ProjectConfig::load(&self.root) {
+            Ok(config) => self.config = config,
+            Err(e) => {
+                error!("error loading project config"; "error" => format!("{:?}", e));
+                self.show_error(format!("Failed to load {}: {}", configuration::PROJECT_CONFIG_FILE, e));
+            }
+        }
+        self.update_validator(None);
+        self.command_provider = Some(self.build_command_provider());
+        self.load_baseline();
+
+        self.start_scan();
+    }
+
+    /// Starts scanning the files of the pack in the background, reporting progress to the client if it
+    /// supports it. Requests that need the graph wait for it to finish with `finish_scan`, otherwise it's
+    /// merged once the main loop gets `input::SCAN_FINISHED`.
+    fn start_scan(&self) {
         info!("generating graph for current root"; "root" => self.root.to_str().unwrap());
 
-        // filter directories and files not ending in any of the 3 extensions
-        WalkDir::new(&self.root)
-            .into_iter()
-            .filter_map(|entry| {
-                if entry.is_err() {
-                    return None;
-                }
+        let root = self.root.clone();
+        let config = self.config.clone();
+        let progress = match self.client_progress {
+            true => Some(lsp_ext::Progress::begin(
+                &self.endpoint,
+                NumberOrString::String(format!("mcglsl/scan/{}", root.to_string_lossy())),
+                "Scanning shader pack",
+                false,
+            )),
+            false => None,
+        };
+        let notifier = self.notifier.clone();
 
-                let entry = entry.unwrap();
-                let path = entry.path();
-                if path.is_dir() {
-                    return None;
+        let scan = thread::spawn(move || {
+            let cache_path = graph_cache::cache_path(&root);
+            let scan = scan::scan_pack(&root, &config, cache_path.as_deref(), |done, total| {
+                if let Some(progress) = &progress {
+                    progress.report(done, total)
                 }
+            });
+            if let Some(progress) = progress {
+                progress.end(format!("Scanned {} files", scan.files.len()));
+            }
+            if let Some(notifier) = notifier {
+                notifier.notify(input::SCAN_FINISHED);
+            }
+            scan
+        });
+        *self.scan.lock().unwrap() = Some(scan);
+    }
 
-                let ext = match path.extension() {
-                    Some(e) => e,
-                    None => return None,
-                };
+    /// Waits for the scan started by `start_scan` and builds the graph and symbol index from it, returning
+    /// whether there was one running.
+    fn wait_for_scan(&self) -> bool {
+        let scan = match self.scan.lock().unwrap().take() {
+            Some(scan) => scan,
+            None => return false,
+        };
+        let scan = match scan.join() {
+            Ok(scan) => scan,
+            Err(_) => {
+                error!("scanning the pack failed"; "root" => self.root.to_str().unwrap());
+                return true;
+            }
+        };
 
-                // TODO: include user added extensions with a set
-                if ext != "vsh" && ext != "fsh" && ext  != "csh" && ext != "gsh" && ext != "glsl" && ext != "inc" {
-                    return None;
-                }
+        let (files, cached) = (scan.files.len(), scan.cached);
+        for file in scan.files {
+            self.add_file_with_includes(&file.path, file.file.includes);
+            self.symbol_index.lock().unwrap().update(&file.path, file.file.symbols);
+        }
+        // documents opened or edited in the meantime may not match the files on disk
+        let open: Vec<PathBuf> = self.documents.keys().cloned().collect();
+        for path in open {
+            self.update_includes(&path);
+            self.index_symbols(&path);
+        }
+        info!("finished building project include graph"; "files" => files, "cached" => cached);
+        true
+    }
 
-                Some(entry.into_path())
-            })
-            .for_each(|path| {
-                // iterate all valid found files, search for includes, add a node into the graph for each
-                // file and add a file->includes KV into the map
-                self.add_file_and_includes_to_graph(&path);
-            });
+    /// Makes sure the graph is complete before handling a request that needs it. Documents opened while
+    /// the pack was being scanned are linted now that it is.
+    fn finish_scan(&self) {
+        if !self.wait_for_scan() {
+            return;
+        }
+        self.lint_open_documents();
+    }
+
+    fn lint_open_documents(&self) {
+        let mut open: Vec<PathBuf> = self.documents.keys().cloned().collect();
+        open.sort();
+        for path in open {
+            self.lint_and_publish(&path);
+        }
+    }
+
+    /// Finishes the scan if it's done, without waiting for it. Requests that don't need the graph are
+    /// answered while the scan runs, and the scan thread notifies the main loop to call this once done.
+    fn poll_scan(&self) {
+        let finished = self.scan.lock().unwrap().as_ref().map_or(false, |scan| scan.is_finished());
+        if finished {
+            self.finish_scan();
+        }
+    }
+
+    fn scanning(&self) -> bool {
+        self.scan.lock().unwrap().is_some()
+    }
 
-        info!("finished building project include graph");
+    /// Builds the graph of the pack, waiting for it to be done.
+    fn build_initial_graph(&self) {
+        self.start_scan();
+        self.wait_for_scan();
     }
 
     fn add_file_and_includes_to_graph(&self, path: &Path) {
-        let includes = self.find_includes(path);
+        self.add_file_with_includes(path, self.find_includes(path));
+    }
 
-        let idx = self.graph.borrow_mut().add_node(path);
+    fn add_file_with_includes(&self, path: &Path, includes: Vec<(PathBuf, IncludePosition)>) {
+        let idx = self.graph.lock().unwrap().add_node(path);
 
         debug!("adding includes for new file"; "file" => path.to_str().unwrap(), "includes" => format!("{:?}", includes));
         for include in includes {
@@ -277,40 +565,35 @@ impl MinecraftShaderLanguageServer {
         }
     }
 
+    /// (Re-)parses `path` and updates its entry in the workspace symbol index.
+    fn index_symbols(&self, path: &Path) {
+        let source = match self.document_source(path) {
+            Ok(s) => s,
+            Err(e) => {
+                warn!("error reading file for symbol index"; "path" => path.to_str().unwrap(), "error" => format!("{:?}", e));
+                self.symbol_index.lock().unwrap().remove(path);
+                return;
+            }
+        };
+        let tree = match self.tree_sitter.lock().unwrap().parse(&source, None) {
+            Some(tree) => tree,
+            None => return,
+        };
+        self.symbol_index.lock().unwrap().update(path, IndexedFile::new(&tree, &source));
+    }
+
     fn add_include(&self, include: (PathBuf, IncludePosition), node: NodeIndex) {
-        let child = self.graph.borrow_mut().add_node(&include.0);
-        self.graph.borrow_mut().add_edge(node, child, include.1);
+        let child = self.graph.lock().unwrap().add_node(&include.0);
+        self.graph.lock().unwrap().add_edge(node, child, include.1);
     }
 
+    /// The includes of `file`, from the unsaved contents of the document if it is open.
     pub fn find_includes(&self, file: &Path) -> Vec<(PathBuf, IncludePosition)> {
-        let mut includes = Vec::default();
-
-        let buf = BufReader::new(std::fs::File::open(file).unwrap());
-        buf.lines()
-            .enumerate()
-            .filter_map(|line| match line.1 {
-                Ok(t) => Some((line.0, t)),
-                Err(_e) => None,
-            })
-            .filter(|line| RE_INCLUDE.is_match(line.1.as_str()))
-            .for_each(|line| {
-                let cap = RE_INCLUDE.captures(line.1.as_str()).unwrap().get(1).unwrap();
-
-                let start = cap.start();
-                let end = cap.end();
-                let mut path: String = cap.as_str().into();
-
-                let full_include = if path.starts_with('/') {
-                    path = path.strip_prefix('/').unwrap().to_string();
-                    self.root.join("shaders").join(PathBuf::from_slash(&path))
-                } else {
-                    file.parent().unwrap().join(PathBuf::from_slash(&path))
-                };
-
-                includes.push((full_include, IncludePosition { line: line.0, start, end }));
-            });
-
-        includes
+        let contents = match self.open_document(file) {
+            Some((_, source)) => source.clone().into_bytes(),
+            None => archive::read(file).unwrap(),
+        };
+        includes::find(&self.config.include_roots(&self.root), file, &contents)
     }
 
     fn update_includes(&self, file: &Path) {
@@ -318,14 +601,14 @@ impl MinecraftShaderLanguageServer {
 
         info!("includes found for file"; "file" => file.to_str().unwrap(), "includes" => format!("{:?}", includes));
 
-        let idx = match self.graph.borrow_mut().find_node(file) {
+        let idx = match self.graph.lock().unwrap().find_node(file) {
             None => return,
             Some(n) => n,
         };
 
-        let prev_children: HashSet<_> = HashSet::from_iter(self.graph.borrow().get_all_child_positions(idx).map(|tup| {
-            (self.graph.borrow().get_node(tup.0), tup.1)
-        }));
+        let graph = self.graph.lock().unwrap();
+        let prev_children: HashSet<_> = graph.get_all_child_positions(idx).map(|(child, position)| (graph.get_node(child), position)).collect();
+        drop(graph);
         let new_children: HashSet<_> = includes.iter().cloned().collect();
 
         let to_be_added = new_children.difference(&prev_children);
@@ -337,17 +620,42 @@ impl MinecraftShaderLanguageServer {
             "for addition" => format!("{:?}", to_be_added)
         );
 
+        let removed_any = to_be_removed.clone().next().is_some();
         for removal in to_be_removed {
-            let child = self.graph.borrow_mut().find_node(&removal.0).unwrap();
-            self.graph.borrow_mut().remove_edge(idx, child, removal.1);
+            let child = self.graph.lock().unwrap().find_node(&removal.0).unwrap();
+            self.graph.lock().unwrap().remove_edge(idx, child, removal.1);
         }
 
         for insertion in to_be_added {
             self.add_include(includes.iter().find(|f| f.0 == *insertion.0).unwrap().clone(), idx);
         }
+
+        if removed_any {
+            self.collect_orphans();
+        }
+    }
+
+    /// Removes the files that are gone and that nothing includes anymore from the graph and the symbol index.
+    /// Open documents are kept, even if they aren't saved.
+    fn collect_orphans(&self) {
+        let removed = self
+            .graph
+            .lock()
+            .unwrap()
+            .collect_orphans(|path| archive::exists(path) || self.open_document(path).is_some());
+        if removed.is_empty() {
+            return;
+        }
+
+        info!("removed orphaned files from graph"; "files" => format!("{:?}", removed));
+        let mut symbol_index = self.symbol_index.lock().unwrap();
+        for path in &removed {
+            symbol_index.remove(path);
+        }
     }
 
-    pub fn lint(&self, uri: &Path) -> Result<HashMap<Url, Vec<Diagnostic>>> {
+    /// Compiles the programs `uri` is part of, stopping before the next one once `cancel` is cancelled.
+    pub fn lint(&self, uri: &Path, cancel: &executor::CancelToken) -> Result<HashMap<Url, Vec<Diagnostic>>> {
         // get all top level ancestors of this file
         let file_ancestors = match self.get_file_toplevel_ancestors(uri) {
             Ok(opt) => match opt {
@@ -363,7 +671,7 @@ impl MinecraftShaderLanguageServer {
             "ancestors" => format!("{:?}", file_ancestors
                 .iter()
                 .map(|e| PathBuf::from_str(
-                    &self.graph.borrow().graph[*e].clone()
+                    &self.graph.lock().unwrap().graph[*e].clone()
                 )
                 .unwrap())
                 .collect::<Vec<PathBuf>>())
@@ -377,35 +685,37 @@ impl MinecraftShaderLanguageServer {
         // we want to backfill the diagnostics map with all linked sources
         let back_fill = |all_sources: &HashMap<PathBuf, String>, diagnostics: &mut HashMap<Url, Vec<Diagnostic>>| {
             for path in all_sources.keys() {
-                diagnostics.entry(Url::from_file_path(path).unwrap()).or_default();
+                diagnostics.entry(path.to_url().unwrap()).or_default();
             }
         };
 
         // if we are a top-level file (this has to be one of the set defined by Optifine, right?)
         if file_ancestors.is_empty() {
             // gather the list of all descendants
-            let root = self.graph.borrow_mut().find_node(uri).unwrap();
+            let root = self.graph.lock().unwrap().find_node(uri).unwrap();
             let tree = match self.get_dfs_for_node(root) {
                 Ok(tree) => tree,
                 Err(e) => {
-                    diagnostics.insert(Url::from_file_path(uri).unwrap(), vec![e.into()]);
+                    diagnostics.insert(uri.to_url().unwrap(), vec![e.into()]);
                     return Ok(diagnostics);
                 }
             };
 
-            all_sources.extend(self.load_sources(&tree)?);
+            let mut sources = self.load_sources(&tree)?;
+            preamble::apply_options_to_all(&mut sources, &self.profile_options());
+            all_sources.extend(sources);
 
             let mut source_mapper = source_mapper::SourceMapper::new(all_sources.len());
 
             let view = {
-                let graph = self.graph.borrow();
+                let graph = self.graph.lock().unwrap();
                 let merged_string = {
                     merge_views::MergeViewBuilder::new(&tree, &all_sources, &graph, &mut source_mapper).build()
                 };
                 merged_string
             };
 
-            let root_path = self.graph.borrow().get_node(root);
+            let root_path = self.graph.lock().unwrap().get_node(root);
             let ext = match root_path.extension() {
                 Some(ext) => ext.to_str().unwrap(),
                 None => {
@@ -432,6 +742,7 @@ impl MinecraftShaderLanguageServer {
                 unreachable!();
             };
 
+            cancel.check()?;
             let stdout = match self.compile_shader_source(&view, tree_type, &root_path) {
                 Some(s) => s,
                 None => {
@@ -442,7 +753,7 @@ impl MinecraftShaderLanguageServer {
 
             let diagnostics_parser = diagnostics_parser::DiagnosticsParser::new(self.opengl_context.as_ref());
 
-            diagnostics.extend(diagnostics_parser.parse_diagnostics_output(stdout, uri, &source_mapper, &self.graph.borrow()));
+            diagnostics.extend(diagnostics_parser.parse_diagnostics_output(stdout, uri, &source_mapper, &self.graph.lock().unwrap()));
         } else {
             let mut all_trees: Vec<(TreeType, Vec<FilialTuple>)> = Vec::new();
 
@@ -450,13 +761,13 @@ impl MinecraftShaderLanguageServer {
                 let nodes = match self.get_dfs_for_node(*root) {
                     Ok(nodes) => nodes,
                     Err(e) => {
-                        diagnostics.insert(Url::from_file_path(uri).unwrap(), vec![e.into()]);
+                        diagnostics.insert(uri.to_url().unwrap(), vec![e.into()]);
                         back_fill(&all_sources, &mut diagnostics); // TODO: confirm
                         return Ok(diagnostics);
                     }
                 };
 
-                let root_path = self.graph.borrow().get_node(*root).clone();
+                let root_path = self.graph.lock().unwrap().get_node(*root).clone();
                 let ext = match root_path.extension() {
                     Some(ext) => ext.to_str().unwrap(),
                     None => continue,
@@ -479,23 +790,25 @@ impl MinecraftShaderLanguageServer {
                     unreachable!();
                 };
 
-                let sources = self.load_sources(&nodes)?;
+                let mut sources = self.load_sources(&nodes)?;
+                preamble::apply_options_to_all(&mut sources, &self.profile_options());
                 all_trees.push((tree_type, nodes));
                 all_sources.extend(sources);
             }
 
             for tree in all_trees {
+                cancel.check()?;
                 // bit over-zealous in allocation but better than having to resize
                 let mut source_mapper = source_mapper::SourceMapper::new(all_sources.len());
                 let view = {
-                    let graph = self.graph.borrow();
+                    let graph = self.graph.lock().unwrap();
                     let merged_string = {
                         merge_views::MergeViewBuilder::new(&tree.1, &all_sources, &graph, &mut source_mapper).build()
                     };
                     merged_string
                 };
 
-                let root_path = self.graph.borrow().get_node(tree.1.first().unwrap().child);
+                let root_path = self.graph.lock().unwrap().get_node(tree.1.first().unwrap().child);
                 let stdout = match self.compile_shader_source(&view, tree.0, &root_path) {
                     Some(s) => s,
                     None => continue,
@@ -503,7 +816,7 @@ impl MinecraftShaderLanguageServer {
 
                 let diagnostics_parser = diagnostics_parser::DiagnosticsParser::new(self.opengl_context.as_ref());
 
-                diagnostics.extend(diagnostics_parser.parse_diagnostics_output(stdout, uri, &source_mapper, &self.graph.borrow()));
+                diagnostics.extend(diagnostics_parser.parse_diagnostics_output(stdout, uri, &source_mapper, &self.graph.lock().unwrap()));
             }
         };
 
@@ -511,8 +824,24 @@ impl MinecraftShaderLanguageServer {
         Ok(diagnostics)
     }
 
+    /// The option values of the profile set in the project config, if any.
+    fn profile_options(&self) -> HashMap<String, preamble::OptionValue> {
+        let profile = match &self.config.profile {
+            Some(profile) => profile,
+            None => return HashMap::new(),
+        };
+        match preamble::load_profile(&self.root, profile) {
+            Ok(options) => options,
+            Err(e) => {
+                warn!("error loading profile"; "profile" => profile, "error" => format!("{:?}", e));
+                HashMap::new()
+            }
+        }
+    }
+
     fn compile_shader_source(&self, source: &str, tree_type: TreeType, path: &Path) -> Option<String> {
-        let result = self.opengl_context.clone().validate(tree_type, source);
+        let source = preamble::insert_defines(source, &preamble::preamble_defines(&self.config.preamble));
+        let result = self.opengl_context.clone().validate(tree_type, &source);
         match &result {
             Some(output) => {
                 info!("compilation errors reported"; "errors" => format!("`{}`", output.replace('\n', "\\n")), "tree_root" => path.to_str().unwrap())
@@ -523,7 +852,7 @@ impl MinecraftShaderLanguageServer {
     }
 
     pub fn get_dfs_for_node(&self, root: NodeIndex) -> Result<Vec<FilialTuple>, dfs::error::CycleError> {
-        let graph_ref = self.graph.borrow();
+        let graph_ref = self.graph.lock().unwrap();
 
         let dfs = dfs::Dfs::new(&graph_ref, root);
 
@@ -534,14 +863,14 @@ impl MinecraftShaderLanguageServer {
         let mut sources = HashMap::new();
 
         for node in nodes {
-            let graph = self.graph.borrow();
+            let graph = self.graph.lock().unwrap();
             let path = graph.get_node(node.child);
 
             if sources.contains_key(&path) {
                 continue;
             }
 
-            let source = match fs::read_to_string(&path) {
+            let source = match archive::read_to_string(&path) {
                 Ok(s) => s,
                 Err(e) => return Err(anyhow!("error reading {:?}: {}", path, e)),
             };
@@ -553,20 +882,25 @@ impl MinecraftShaderLanguageServer {
     }
 
     fn get_file_toplevel_ancestors(&self, uri: &Path) -> Result<Option<Vec<petgraph::stable_graph::NodeIndex>>> {
-        let curr_node = match self.graph.borrow_mut().find_node(uri) {
+        let curr_node = match self.graph.lock().unwrap().find_node(uri) {
             Some(n) => n,
             None => return Err(anyhow!("node not found {:?}", uri)),
         };
-        let roots = self.graph.borrow().collect_root_ancestors(curr_node);
+        let roots = self.graph.lock().unwrap().collect_root_ancestors(curr_node);
         if roots.is_empty() {
             return Ok(None);
         }
         Ok(Some(roots))
     }
 
+    /// Publishes `diagnostics`, against the path a file was opened with if it's open under another one.
     pub fn publish_diagnostic(&self, diagnostics: HashMap<Url, Vec<Diagnostic>>, document_version: Option<i32>) {
         // info!("DIAGNOSTICS:\n{:?}", diagnostics);
         for (uri, diagnostics) in diagnostics {
+            let uri = match self.open_document(&PathBuf::from_url(uri.clone())) {
+                Some((opened, _)) => opened.to_url().unwrap_or(uri),
+                None => uri,
+            };
             self.endpoint
                 .send_notification(
                     PublishDiagnostics::METHOD,
@@ -580,17 +914,676 @@ impl MinecraftShaderLanguageServer {
         }
     }
 
-    fn set_status(&self, status: impl Into<String>, message: impl Into<String>, icon: impl Into<String>) {
+    /// The path and contents of the open document that is the file at `path`, which may have been opened
+    /// with another path to it, like through a symlinked folder.
+    fn open_document(&self, path: &Path) -> Option<(&PathBuf, &String)> {
+        if let Some(document) = self.documents.get_key_value(path) {
+            return Some(document);
+        }
+        let opened = self.document_paths.get(&canonical::canonicalize(path))?;
+        self.documents.get_key_value(opened)
+    }
+
+    /// Returns the contents of `path`, preferring the unsaved contents of the document if it is open.
+    fn document_source(&self, path: &Path) -> Result<String> {
+        match self.open_document(path) {
+            Some((_, source)) => Ok(source.clone()),
+            None => match archive::read_to_string(path) {
+                Ok(s) => Ok(s.replace("\r\n", "\n")),
+                Err(e) => Err(anyhow!("error reading {:?}: {}", path, e)),
+            },
+        }
+    }
+
+    /// Parses every file that shares a program with `path` and collects their declarations, in include order.
+    /// Files that aren't part of any program only see themselves and their own includes.
+    fn program_scope(&self, path: &Path, parser: &mut Parser) -> Result<symbols::ProgramScope> {
+        let roots = match self.get_file_toplevel_ancestors(path)? {
+            Some(roots) => roots,
+            None => vec![self.graph.lock().unwrap().find_node(path).unwrap()],
+        };
+
+        let mut files: Vec<PathBuf> = vec![];
+        let mut programs: Vec<Vec<PathBuf>> = vec![];
+        for root in &roots {
+            match self.get_dfs_for_node(*root) {
+                Ok(nodes) => {
+                    let graph = self.graph.lock().unwrap();
+                    let program: Vec<PathBuf> = nodes.iter().map(|node| graph.get_node(node.child)).collect();
+                    for child in &program {
+                        if !files.contains(child) {
+                            files.push(child.clone());
+                        }
+                    }
+                    programs.push(program);
+                }
+                Err(e) => warn!("include cycle while collecting program scope"; "error" => format!("{:?}", e)),
+            }
+        }
+        if !files.iter().any(|f| f == path) {
+            files.insert(0, path.to_path_buf());
+        }
+
+        let root_path = self.graph.lock().unwrap().get_node(roots[0]);
+        let mut version = None;
+        let mut sources = HashMap::new();
+        let mut declarations = Vec::with_capacity(files.len());
+        for file in files {
+            let source = match self.document_source(&file) {
+                Ok(s) => s,
+                Err(e) => {
+                    warn!("error reading file in program scope"; "path" => file.to_str().unwrap(), "error" => format!("{:?}", e));
+                    continue;
+                }
+            };
+            if file == root_path {
+                version = builtins::GlslVersion::from_source(&source);
+            }
+            let tree = parser.parse(&source, None).unwrap();
+            declarations.push((file.clone(), symbols::collect_declarations(&tree, &source)));
+            sources.insert(file, source);
+        }
+
+        let mut preamble = builtins::macro_defines();
+        preamble.extend(preamble::preamble_defines(&self.config.preamble));
+        let preamble = preamble.join("\n");
+        let defines = programs
+            .iter()
+            .map(|program| {
+                let program_sources = program.iter().filter_map(|file| sources.get(file)).map(String::as_str);
+                symbols::defines(std::iter::once(preamble.as_str()).chain(program_sources))
+            })
+            .collect();
+
+        Ok(symbols::ProgramScope {
+            files: declarations,
+            version: version.or_else(|| TreeType::from_path(&root_path).map(|_| builtins::GlslVersion::default())),
+            stage: TreeType::from_path(&root_path),
+            defines,
+        })
+    }
+
+    fn semantic_tokens(&self, path: &Path, range: Option<Range>) -> Result<Vec<SemanticToken>> {
+        if self.graph.lock().unwrap().find_node(path) == None {
+            self.add_file_and_includes_to_graph(path);
+        }
+
+        let source = self.document_source(path)?;
+        let parser = &mut self.tree_sitter.lock().unwrap();
+        let scope = self.program_scope(path, parser)?;
+        let tree = match parser.parse(&source, None) {
+            Some(tree) => tree,
+            None => return Err(anyhow!("error parsing {:?}", path)),
+        };
+
+        let tokens = semantic_tokens::tokenize(&tree, &source, &scope.files, range);
+        debug!("classified semantic tokens"; "path" => path.to_str().unwrap(), "count" => tokens.len());
+
+        Ok(semantic_tokens::encode(tokens))
+    }
+
+    /// Collects the calls made in every file that shares a program with `path`, along with the scope
+    /// they were collected in.
+    fn program_calls(&self, path: &Path) -> Result<(symbols::ProgramScope, Vec<call_hierarchy::FileCalls>)> {
+        if self.graph.lock().unwrap().find_node(path) == None {
+            self.add_file_and_includes_to_graph(path);
+        }
+
+        let parser = &mut self.tree_sitter.lock().unwrap();
+        let scope = self.program_scope(path, parser)?;
+
+        let mut calls = Vec::with_capacity(scope.files.len());
+        for (file, _) in &scope.files {
+            let source = match self.document_source(file) {
+                Ok(s) => s,
+                Err(e) => {
+                    warn!("error reading file for call hierarchy"; "path" => file.to_str().unwrap(), "error" => format!("{:?}", e));
+                    continue;
+                }
+            };
+            let tree = parser.parse(&source, None).unwrap();
+            calls.push((file.clone(), call_hierarchy::collect_calls(&tree, &source, &scope.defines)));
+        }
+
+        Ok((scope, calls))
+    }
+
+    /// Formats the whole of `path`, or only the declarations overlapping `range`, in the style from the
+    /// project config.
+    fn format(&self, path: &Path, options: &FormattingOptions, range: Option<Range>) -> Result<Vec<TextEdit>> {
+        let style = formatter::FormatStyle::new(options, &self.config.format);
+
+        let source = self.document_source(path)?;
+        let tree = match self.tree_sitter.lock().unwrap().parse(&source, None) {
+            Some(tree) => tree,
+            None => return Err(anyhow!("error parsing {:?}", path)),
+        };
+
+        match range {
+            Some(range) => formatter::format_range(&tree, &source, range, &style),
+            None => formatter::format_document(&tree, &source, &style),
+        }
+    }
+
+    /// The includes made directly by `path`, along with every file they bring in. Includes of files that
+    /// aren't in the symbol index, like ones that can't be read, are left out as there is nothing to say
+    /// about their usage.
+    fn direct_includes(&self, path: &Path, files: &HashMap<PathBuf, IndexedFile>) -> Result<Vec<includes::DirectInclude>> {
+        let node = match self.graph.lock().unwrap().find_node(path) {
+            Some(n) => n,
+            None => return Err(anyhow!("node not found {:?}", path)),
+        };
+        let children: Vec<_> = self.graph.lock().unwrap().get_all_child_positions(node).collect();
+
+        let mut direct = Vec::with_capacity(children.len());
+        for (child, position) in children {
+            let nodes = match self.get_dfs_for_node(child) {
+                Ok(nodes) => nodes,
+                Err(e) => {
+                    warn!("include cycle while collecting includes"; "error" => format!("{:?}", e));
+                    continue;
+                }
+            };
+
+            let mut reachable: Vec<PathBuf> = vec![];
+            for node in nodes {
+                let file = self.graph.lock().unwrap().get_node(node.child);
+                if !reachable.contains(&file) {
+                    reachable.push(file);
+                }
+            }
+
+            if files.contains_key(&reachable[0]) {
+                direct.push(includes::DirectInclude {
+                    path: reachable[0].clone(),
+                    position,
+                    files: reachable,
+                });
+            }
+        }
+
+        Ok(direct)
+    }
+
+    /// Works out the usage of the includes of `path`, taking the files including it into account, from the
+    /// symbol index in `files` and the current contents of `path`.
+    fn include_usage<'a>(
+        &self, path: &Path, source: &str, direct: &'a [includes::DirectInclude], files: &'a HashMap<PathBuf, IndexedFile>,
+    ) -> includes::IncludeUsage<'a> {
+        let file = IndexedFile::new(&self.tree_sitter.lock().unwrap().parse(source, None).unwrap(), source);
+        let includers: Vec<&IndexedFile> = {
+            let graph = self.graph.lock().unwrap();
+            let ancestors = graph.find_node(path).map(|node| graph.collect_ancestors(node)).unwrap_or_default();
+            ancestors.into_iter().filter_map(|ancestor| files.get(&graph.get_node(ancestor))).collect()
+        };
+        includes::IncludeUsage::new(&file, &includers, direct, files)
+    }
+
+    /// Adds a warning for every unused include of the files in `diagnostics`, which holds an entry for
+    /// every file that had its diagnostics refreshed.
+    fn add_include_diagnostics(&self, diagnostics: &mut HashMap<Url, Vec<Diagnostic>>) {
+        let index = self.symbol_index.lock().unwrap();
+        for (uri, file_diagnostics) in diagnostics.iter_mut() {
+            let path = PathBuf::from_url(uri.clone());
+            let direct = match self.direct_includes(&path, index.files()) {
+                Ok(includes) => includes,
+                Err(e) => {
+                    warn!("error collecting includes"; "path" => path.to_str().unwrap(), "error" => format!("{:?}", e));
+                    continue;
+                }
+            };
+            if direct.is_empty() {
+                continue;
+            }
+
+            let source = match self.document_source(&path) {
+                Ok(source) => source,
+                Err(_) => continue,
+            };
+            let usage = self.include_usage(&path, &source, &direct, index.files());
+            file_diagnostics.extend(usage.unused().into_iter().map(|include| includes::unused_include_diagnostic(include, &source)));
+        }
+    }
+
+    /// Queues a lint of the programs `path` is part of, which runs in the background on a snapshot of the
+    /// server so that requests are answered in the meantime.
+    fn lint_and_publish(&self, path: &Path) {
+        let server = self.snapshot();
+        let path = path.to_path_buf();
+        self.lints.submit(&path.clone(), move |token, cancel| {
+            logging::slog_with_trace_id(|| server.publish_lint(&path, token, cancel))
+        });
+    }
+
+    /// Lints the programs `path` is part of and publishes the diagnostics left after suppressions, the
+    /// rule levels from the project config and the baseline are applied, unless it was cancelled.
+    fn publish_lint(&self, path: &Path, token: &NumberOrString, cancel: &executor::CancelToken) {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let progress = match self.client_progress {
+            true => Some(lsp_ext::Progress::begin(&self.endpoint, token.clone(), format!("Linting {}", file_name), true)),
+            false => None,
+        };
+
+        let result = self.lint(path, cancel).map(|mut diagnostics| {
+            self.add_include_diagnostics(&mut diagnostics);
+            self.apply_suppressions(path, &mut diagnostics);
+            for file_diagnostics in diagnostics.values_mut() {
+                self.config.apply_rule_levels(file_diagnostics);
+            }
+            self.apply_baseline(&mut diagnostics);
+            diagnostics
+        });
+        let message = match result {
+            // a newer lint may have started on the same files already
+            _ if cancel.is_cancelled() => {
+                info!("lint cancelled"; "path" => path.to_str().unwrap());
+                "Cancelled"
+            }
+            Ok(diagnostics) => {
+                self.publish_diagnostic(diagnostics, None);
+                "Done"
+            }
+            Err(e) => {
+                error!("error linting"; "error" => format!("{:?}", e), "path" => path.to_str().unwrap());
+                "Failed"
+            }
+        };
+        if let Some(progress) = progress {
+            progress.end(message);
+        }
+    }
+
+    /// A copy of the server to lint with off the main thread. The graph, documents and config are copied
+    /// so that they can't change under a running lint, and it gets a parser of its own.
+    fn snapshot(&self) -> MinecraftShaderLanguageServer {
+        let mut parser = Parser::new();
+        parser.set_language(tree_sitter_glsl::language()).unwrap();
+
+        MinecraftShaderLanguageServer {
+            endpoint: self.endpoint.clone(),
+            graph: Arc::new(Mutex::new(self.graph.lock().unwrap().clone())),
+            symbol_index: self.symbol_index.clone(),
+            root: self.root.clone(),
+            command_provider: None,
+            opengl_context: self.opengl_context.clone(),
+            tree_sitter: Arc::new(Mutex::new(parser)),
+            documents: self.documents.clone(),
+            document_paths: self.document_paths.clone(),
+            config: self.config.clone(),
+            log_guard: None,
+            lints: self.lints.clone(),
+            scan: Mutex::new(None),
+            client_progress: self.client_progress,
+            pending_rename: Mutex::new(None),
+            baseline: self.baseline.clone(),
+            notifier: self.notifier.clone(),
+        }
+    }
+
+    /// Reloads the project config after it changed, rebuilding the graph if the files it's made of
+    /// may have changed and relinting the open documents.
+    fn reload_config(&mut self) {
+        let config = match configuration::ProjectConfig::load(&self.root) {
+            Ok(config) => config,
+            Err(e) => {
+                error!("error loading project config"; "error" => format!("{:?}", e));
+                self.show_error(format!("Failed to load {}: {}", configuration::PROJECT_CONFIG_FILE, e));
+                return;
+            }
+        };
+        if config == self.config {
+            return;
+        }
+        info!("project config changed"; "config" => format!("{:?}", config));
+
+        let previous = std::mem::replace(&mut self.config, config);
+        if previous.validator != self.config.validator {
+            self.update_validator(previous.validator.backend);
+        }
+        if previous.graph_output != self.config.graph_output {
+            self.command_provider = Some(self.build_command_provider());
+        }
+        if previous.extensions != self.config.extensions || previous.include_roots != self.config.include_roots {
+            *self.graph.lock().unwrap() = graph::CachedStableGraph::new();
+            *self.symbol_index.lock().unwrap() = symbol_index::SymbolIndex::new();
+            self.build_initial_graph();
+        }
+
+        self.lint_open_documents();
+    }
+
+    /// Switches to the validator backend of the project config, after it was `previous`.
+    fn update_validator(&mut self, previous: Option<configuration::ValidatorBackend>) {
+        let validator: Arc<dyn opengl::ShaderValidator> = match self.config.validator.backend {
+            Some(configuration::ValidatorBackend::Glslang) => {
+                let executable = self.config.validator.glslang_path.clone().unwrap_or_else(|| "glslangValidator".into());
+                match opengl::GlslangValidator::new(executable) {
+                    Ok(validator) => Arc::new(validator),
+                    Err(e) => {
+                        error!("error setting up glslang"; "error" => format!("{:?}", e));
+                        self.show_error(format!("Failed to set up glslang: {}", e));
+                        return;
+                    }
+                }
+            }
+            _ if previous == Some(configuration::ValidatorBackend::Glslang) => Arc::new(opengl::OpenGlContext::new()),
+            _ => return,
+        };
+        self.opengl_context = validator;
+        self.command_provider = Some(self.build_command_provider());
+    }
+
+    fn show_error(&self, message: String) {
         self.endpoint
             .send_notification(
-                lsp_ext::Status::METHOD,
-                lsp_ext::StatusParams {
-                    status: status.into(),
-                    message: Some(message.into()),
-                    icon: Some(icon.into()),
+                ShowMessage::METHOD,
+                ShowMessageParams {
+                    typ: MessageType::ERROR,
+                    message,
                 },
             )
-            .unwrap_or(());
+            .expect("failed to send popup/show message notification");
+    }
+
+    /// Drops the diagnostics disabled by suppression comments in the file they were reported in,
+    /// whichever program they were reported for. As the lint of `linted` only compiled the programs
+    /// it is part of, unused suppressions are only reported in files that aren't part of any other.
+    fn apply_suppressions(&self, linted: &Path, diagnostics: &mut HashMap<Url, Vec<Diagnostic>>) {
+        let linted_programs = self.programs_of(linted);
+        for (uri, file_diagnostics) in diagnostics.iter_mut() {
+            let path = PathBuf::from_url(uri.clone());
+            if let Ok(source) = self.document_source(&path) {
+                let report_unused = self.programs_of(&path).is_subset(&linted_programs);
+                suppressions::apply(&source, file_diagnostics, report_unused);
+            }
+        }
+    }
+
+    /// The top-level files of the programs `path` is part of, which is only itself if nothing includes it.
+    fn programs_of(&self, path: &Path) -> HashSet<NodeIndex> {
+        let graph = self.graph.lock().unwrap();
+        let node = match graph.find_node(path) {
+            Some(node) => node,
+            None => return HashSet::new(),
+        };
+        match graph.collect_root_ancestors(node) {
+            roots if roots.is_empty() => HashSet::from([node]),
+            roots => roots.into_iter().collect(),
+        }
+    }
+
+    /// Reads the pack's baseline, if it has one, which is applied to every lint until it changes.
+    fn load_baseline(&mut self) {
+        self.baseline = match baseline::Baseline::load(&self.root.join(baseline::BASELINE_FILE)) {
+            Ok(baseline) => baseline.map(Arc::new),
+            Err(e) => {
+                warn!("error loading baseline"; "error" => format!("{:?}", e));
+                None
+            }
+        };
+    }
+
+    /// Drops the diagnostics that are in the pack's baseline, if it has one. Entries that no longer
+    /// occur are left to `check`, as they may belong to programs that weren't linted.
+    fn apply_baseline(&self, diagnostics: &mut HashMap<Url, Vec<Diagnostic>>) {
+        let baseline = match &self.baseline {
+            Some(baseline) => baseline,
+            None => return,
+        };
+
+        for (uri, file_diagnostics) in diagnostics.iter_mut() {
+            let path = PathBuf::from_url(uri.clone());
+            let source = match self.document_source(&path) {
+                Ok(source) => source,
+                Err(_) => continue,
+            };
+            let file = path.strip_prefix(&self.root).unwrap_or(&path).to_slash_lossy();
+            baseline.apply(&file, file_diagnostics, &source, false);
+        }
+    }
+
+    /// Adds the includes of symbols `path` only gets through another include and removes the ones
+    /// that aren't needed after that. Includes that would make a file include itself are never added.
+    fn organize_includes(&self, path: &Path) -> Result<Option<code_actions::Fix>> {
+        if self.graph.lock().unwrap().find_node(path) == None {
+            self.add_file_and_includes_to_graph(path);
+        }
+
+        let index = self.symbol_index.lock().unwrap();
+        let direct = self.direct_includes(path, index.files())?;
+        let source = self.document_source(path)?;
+        let usage = self.include_usage(path, &source, &direct, index.files());
+
+        let include_roots = self.config.include_roots(&self.root);
+        let mut added: Vec<&Path> = vec![];
+        let mut added_includes: Vec<String> = vec![];
+        for file in usage.missing() {
+            let include = match code_actions::include_path(&include_roots, file) {
+                Some(include) => include,
+                None => continue,
+            };
+            let graph = self.graph.lock().unwrap();
+            let would_cycle = match (graph.find_node(path), graph.find_node(file)) {
+                (Some(parent), Some(child)) => graph.would_create_cycle(parent, child),
+                _ => true,
+            };
+            if !would_cycle {
+                added.push(file);
+                added_includes.push(include);
+            }
+        }
+
+        let uri = path.to_url().unwrap();
+        Ok(includes::organize_includes(&uri, &source, &added_includes, &usage.dead(&added)))
+    }
+
+    /// Rewrites the includes to and from the files or folders being renamed so they keep pointing at the
+    /// same files, keeping them absolute or relative as they were. Nothing is changed until the files are
+    /// renamed, as the client may still cancel the rename; the includes are remembered for
+    /// `did_rename_files` to move in the graph.
+    fn will_rename_files(&self, renames: &[(PathBuf, PathBuf)]) -> Result<WorkspaceEdit> {
+        let include_roots = self.config.include_roots(&self.root);
+        let edges: Vec<_> = self.graph.lock().unwrap().all_edges().collect();
+        let mut sources: HashMap<PathBuf, Option<String>> = HashMap::new();
+        let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+        let mut moved_includes = vec![];
+        for (parent, child, position) in edges {
+            let (parent_path, child_path) = {
+                let graph = self.graph.lock().unwrap();
+                (graph.get_node(parent), graph.get_node(child))
+            };
+            let (new_parent, new_child) = (moved_to(renames, &parent_path), moved_to(renames, &child_path));
+            if new_parent == parent_path && new_child == child_path {
+                continue;
+            }
+
+            let source = sources.entry(parent_path.clone()).or_insert_with(|| match self.document_source(&parent_path) {
+                Ok(source) => Some(source),
+                Err(e) => {
+                    warn!("error reading file, not updating its includes"; "path" => parent_path.to_str().unwrap(), "error" => format!("{:?}", e));
+                    None
+                }
+            });
+            let include = match source {
+                Some(source) => source
+                    .lines()
+                    .nth(position.line)
+                    .and_then(|line| line.get(position.start..position.end))
+                    .unwrap_or_default(),
+                None => continue,
+            };
+            // the unsaved document may have moved the include since the graph was last updated
+            if includes::resolve(&include_roots, &parent_path, include) != child_path {
+                warn!("include moved since last save, not updating"; "path" => parent_path.to_str().unwrap(), "line" => position.line);
+                continue;
+            }
+
+            let new_include = match includes::include_text(&include_roots, &new_parent, &new_child, include.starts_with('/')) {
+                Some(new_include) if new_include != include => new_include,
+                _ => continue,
+            };
+            let range = Range::new(
+                Position::new(position.line as u32, position.start as u32),
+                Position::new(position.line as u32, position.end as u32),
+            );
+            changes.entry(parent_path.to_url().unwrap()).or_default().push(TextEdit {
+                range,
+                new_text: new_include.clone(),
+            });
+            moved_includes.push((parent_path, child_path, position, IncludePosition {
+                end: position.start + new_include.len(),
+                ..position
+            }));
+        }
+
+        info!("updating includes for renamed files"; "renames" => format!("{:?}", renames), "files changed" => changes.len());
+        *self.pending_rename.lock().unwrap() = Some((renames.to_vec(), moved_includes));
+        Ok(WorkspaceEdit::new(changes))
+    }
+
+    /// Moves the renamed files in the graph and the symbol index, along with the includes rewritten for them
+    /// by `will_rename_files` if it was asked about the same rename.
+    fn did_rename_files(&self, renames: &[(PathBuf, PathBuf)]) {
+        let moved_includes = match self.pending_rename.lock().unwrap().take() {
+            Some((pending, moved_includes)) if pending == renames => moved_includes,
+            _ => vec![],
+        };
+
+        let mut graph = self.graph.lock().unwrap();
+        for (parent, child, old, new) in moved_includes {
+            if let (Some(parent), Some(child)) = (graph.find_node(&parent), graph.find_node(&child)) {
+                graph.remove_edge(parent, child, old);
+                graph.add_edge(parent, child, new);
+            }
+        }
+        let nodes: Vec<_> = graph.graph.node_indices().collect();
+        for node in nodes {
+            let path = graph.get_node(node);
+            let new_path = moved_to(renames, &path);
+            if new_path != path {
+                graph.rename_node(node, &new_path);
+                self.symbol_index.lock().unwrap().rename(&path, &new_path);
+            }
+        }
+
+        info!("moved renamed files"; "renames" => format!("{:?}", renames));
+    }
+
+    /// Works out fixes for the compiler diagnostics reported for `path`. Edits always target the file the
+    /// diagnostic was mapped back to, never the merged source that was compiled.
+    fn quick_fixes(&self, path: &Path, diagnostics: &[Diagnostic]) -> Result<Vec<code_actions::Fix>> {
+        if self.graph.lock().unwrap().find_node(path) == None {
+            self.add_file_and_includes_to_graph(path);
+        }
+
+        let uri = path.to_url().unwrap();
+        let source = self.document_source(path)?;
+        let scope = self.program_scope(path, &mut self.tree_sitter.lock().unwrap())?;
+        let include_roots = self.config.include_roots(&self.root);
+
+        let mut fixes: Vec<code_actions::Fix> = vec![];
+        for diagnostic in diagnostics.iter().filter(|d| d.source.as_deref() == Some(consts::SOURCE)) {
+            if diagnostic.code == Some(NumberOrString::String(includes::UNUSED_INCLUDE_CODE.into())) {
+                fixes.extend(includes::remove_include(&uri, &source, diagnostic.range.start.line as usize));
+                continue;
+            }
+            if diagnostic.code == Some(NumberOrString::String(suppressions::UNUSED_SUPPRESSION_CODE.into())) {
+                fixes.extend(suppressions::remove_suppression(&uri, &source, diagnostic.range));
+                continue;
+            }
+
+            let program = diagnostic
+                .data
+                .clone()
+                .and_then(|data| from_value::<diagnostics_parser::DiagnosticData>(data).ok())
+                .map(|data| data.program)
+                .unwrap_or_else(|| path.to_path_buf());
+
+            for name in code_actions::undeclared_names(diagnostic, &source) {
+                let declared = scope.files.iter().any(|(_, declarations)| declarations.iter().any(|d| d.name == name));
+                if declared {
+                    continue;
+                }
+
+                if let Some(fix) = code_actions::declare_uniform(&uri, &source, &name) {
+                    fixes.push(fix);
+                    continue;
+                }
+
+                let index = self.symbol_index.lock().unwrap();
+                for (file, _) in index.definitions(&name) {
+                    let include = match code_actions::include_path(&include_roots, file) {
+                        Some(include) => include,
+                        None => continue,
+                    };
+                    let graph = self.graph.lock().unwrap();
+                    let would_cycle = match (graph.find_node(path), graph.find_node(file)) {
+                        (Some(parent), Some(child)) => graph.would_create_cycle(parent, child),
+                        _ => false,
+                    };
+                    if !would_cycle {
+                        fixes.push(code_actions::add_include(&uri, &source, &include, &name));
+                    }
+                }
+            }
+
+            if let Some(version) = scope.version {
+                let stage = TreeType::from_path(&program);
+                if let Some(fix) = code_actions::convert_storage_qualifier(&uri, &source, diagnostic, version, stage) {
+                    fixes.push(fix);
+                }
+            }
+
+            if TreeType::from_path(&program).is_some() {
+                let program_source = self.document_source(&program)?;
+                if builtins::GlslVersion::from_source(&program_source).is_none() {
+                    fixes.extend(code_actions::insert_version(&program.to_url().unwrap()));
+                }
+            }
+        }
+
+        let mut seen = HashSet::new();
+        fixes.retain(|fix| seen.insert(fix.title.clone()));
+        Ok(fixes)
+    }
+
+    /// The symbols of the pack matching `query`, for `workspace/symbol`.
+    fn workspace_symbol_search(&self, query: &str) -> Vec<SymbolInformation> {
+        #[allow(deprecated)]
+        self.symbol_index
+            .lock()
+            .unwrap()
+            .search(query, MAX_WORKSPACE_SYMBOLS)
+            .into_iter()
+            .filter_map(|(path, declaration)| {
+                let uri = match path.to_url() {
+                    Ok(uri) => uri,
+                    Err(e) => {
+                        error!("error converting into url"; "path" => path.to_str().unwrap(), "error" => format!("{:?}", e));
+                        return None;
+                    }
+                };
+                let kind = match declaration.kind {
+                    _ if declaration.is_option() => SymbolKind::PROPERTY,
+                    symbols::DeclarationKind::Function => SymbolKind::FUNCTION,
+                    symbols::DeclarationKind::Struct => SymbolKind::STRUCT,
+                    symbols::DeclarationKind::Uniform => SymbolKind::VARIABLE,
+                    _ => SymbolKind::CONSTANT,
+                };
+                Some(SymbolInformation {
+                    name: declaration.name.clone(),
+                    kind,
+                    tags: None,
+                    deprecated: None,
+                    location: Location::new(uri, declaration.selection_range),
+                    container_name: path.strip_prefix(&self.root).ok().map(|p| p.to_slash_lossy()),
+                })
+            })
+            .collect()
+    }
+
+    fn set_status(&self, status: impl Into<String>, message: impl Into<String>, icon: impl Into<String>) {
+        lsp_ext::set_status(&self.endpoint, status, message, icon)
     }
 }
 
@@ -599,28 +1592,6 @@ impl LanguageServerHandling for MinecraftShaderLanguageServer {
         logging::slog_with_trace_id(|| {
             info!("starting server...");
 
-            let capabilities = ServerCapabilities {
-                definition_provider: Some(OneOf::Left(true)),
-                references_provider: Some(OneOf::Left(true)),
-                document_symbol_provider: Some(OneOf::Left(true)),
-                document_link_provider: Some(DocumentLinkOptions {
-                    resolve_provider: None,
-                    work_done_progress_options: WorkDoneProgressOptions { work_done_progress: None },
-                }),
-                execute_command_provider: Some(ExecuteCommandOptions {
-                    commands: vec!["graphDot".into()],
-                    work_done_progress_options: WorkDoneProgressOptions { work_done_progress: None },
-                }),
-                text_document_sync: Some(TextDocumentSyncCapability::Options(TextDocumentSyncOptions {
-                    open_close: Some(true),
-                    will_save: None,
-                    will_save_wait_until: None,
-                    change: Some(TextDocumentSyncKind::FULL),
-                    save: Some(TextDocumentSyncSaveOptions::SaveOptions(SaveOptions { include_text: Some(true) })),
-                })),
-                ..ServerCapabilities::default()
-            };
-
             let root = match params.root_uri {
                 Some(uri) => PathBuf::from_url(uri),
                 None => {
@@ -633,17 +1604,15 @@ impl LanguageServerHandling for MinecraftShaderLanguageServer {
                 }
             };
 
+            self.client_progress = lsp_ext::supports_work_done_progress(&params.capabilities);
             completable.complete(Ok(InitializeResult {
-                capabilities,
+                capabilities: Self::capabilities(),
                 server_info: None,
             }));
 
             self.set_status("loading", "Building dependency graph...", "$(loading~spin)");
 
-            self.root = root;
-
-
-            self.build_initial_graph();
+            self.open_pack(root);
 
             self.set_status("ready", "Project initialized", "$(check)");
         });
@@ -659,64 +1628,82 @@ impl LanguageServerHandling for MinecraftShaderLanguageServer {
     }
 
     fn workspace_change_configuration(&mut self, params: DidChangeConfigurationParams) {
-        logging::slog_with_trace_id(|| {
-            #[derive(Deserialize)]
-            struct Configuration {
-                #[serde(alias = "logLevel")]
-                log_level: String,
-            }
-
-            if let Some(settings) = params.settings.as_object().unwrap().get("mcglsl") {
-                let config: Configuration = from_value(settings.to_owned()).unwrap();
-
-                info!("got updated configuration"; "config" => params.settings.as_object().unwrap().get("mcglsl").unwrap().to_string());
-
-                configuration::handle_log_level_change(config.log_level, |level| {
-                    self.log_guard = None; // set to None so Drop is invoked
-                    self.log_guard = Some(logging::set_logger_with_level(level));
-                })
-            }
-        });
+        logging::slog_with_trace_id(|| configuration::handle_settings_change(&params.settings, &mut self.log_guard));
     }
 
     fn did_open_text_document(&mut self, params: DidOpenTextDocumentParams) {
+        self.poll_scan();
         logging::slog_with_trace_id(|| {
             //info!("opened doc {}", params.text_document.uri);
             let path = PathBuf::from_url(params.text_document.uri);
             if !path.starts_with(&self.root) {
                 return;
             }
+            self.documents.insert(path.clone(), params.text_document.text.replace("\r\n", "\n"));
+            self.document_paths.insert(canonical::canonicalize(&path), path.clone());
+            // linted once the scan is done
+            if self.scanning() {
+                return;
+            }
 
-            if self.graph.borrow_mut().find_node(&path) == None {
+            if self.graph.lock().unwrap().find_node(&path) == None {
                 self.add_file_and_includes_to_graph(&path);
+                self.index_symbols(&path);
             }
-            match self.lint(&path) {
-                Ok(diagnostics) => self.publish_diagnostic(diagnostics, None),
-                Err(e) => error!("error linting"; "error" => format!("{:?}", e), "path" => path.to_str().unwrap()),
-            }
+            self.lint_and_publish(&path);
         });
     }
 
-    fn did_change_text_document(&mut self, _: DidChangeTextDocumentParams) {}
+    fn did_change_text_document(&mut self, params: DidChangeTextDocumentParams) {
+        self.poll_scan();
+        let path = PathBuf::from_url(params.text_document.uri);
+        if !path.starts_with(&self.root) {
+            return;
+        }
+        // diagnostics of the previous content would point at text that's gone
+        self.lints.cancel_file(&path);
+        // full document sync, the last change holds the whole document
+        if let Some(change) = params.content_changes.into_iter().last() {
+            self.documents.insert(path, change.text.replace("\r\n", "\n"));
+        }
+    }
 
-    fn did_close_text_document(&mut self, _: DidCloseTextDocumentParams) {}
+    fn did_close_text_document(&mut self, params: DidCloseTextDocumentParams) {
+        let path = PathBuf::from_url(params.text_document.uri);
+        self.documents.remove(&path);
+        self.document_paths.retain(|_, opened| *opened != path);
+    }
 
     fn did_save_text_document(&mut self, params: DidSaveTextDocumentParams) {
+        self.finish_scan();
         logging::slog_with_trace_id(|| {
             let path = PathBuf::from_url(params.text_document.uri);
             if !path.starts_with(&self.root) {
                 return;
             }
             self.update_includes(&path);
+            self.index_symbols(&path);
 
-            match self.lint(&path) {
-                Ok(diagnostics) => self.publish_diagnostic(diagnostics, None),
-                Err(e) => error!("error linting"; "error" => format!("{:?}", e), "path" => path.to_str().unwrap()),
-            }
+            self.lint_and_publish(&path);
         });
     }
 
-    fn did_change_watched_files(&mut self, _: DidChangeWatchedFilesParams) {}
+    fn did_change_watched_files(&mut self, params: DidChangeWatchedFilesParams) {
+        self.finish_scan();
+        logging::slog_with_trace_id(|| {
+            let changed = |path: PathBuf| params.changes.iter().any(|change| PathBuf::from_url(change.uri.clone()) == path);
+            if changed(self.root.join(configuration::PROJECT_CONFIG_FILE)) {
+                self.reload_config();
+            }
+            if changed(self.root.join(baseline::BASELINE_FILE)) {
+                self.load_baseline();
+                self.lint_open_documents();
+            }
+            if params.changes.iter().any(|change| change.typ == FileChangeType::DELETED) {
+                self.collect_orphans();
+            }
+        });
+    }
 
     fn completion(&mut self, _: TextDocumentPositionParams, completable: LSCompletable<CompletionList>) {
         completable.complete(Err(Self::error_not_available(())));
@@ -726,17 +1713,64 @@ impl LanguageServerHandling for MinecraftShaderLanguageServer {
         completable.complete(Err(Self::error_not_available(())));
     }
 
-    fn hover(&mut self, _: TextDocumentPositionParams, _: LSCompletable<Hover>) {
-        /* completable.complete(Ok(Hover{
-            contents: HoverContents::Markup(MarkupContent{
-                kind: MarkupKind::Markdown,
-                value: String::from("# Hello World"),
-            }),
-            range: None,
-        })); */
+    fn hover(&mut self, params: TextDocumentPositionParams, completable: LSCompletable<Hover>) {
+        self.finish_scan();
+        logging::slog_with_trace_id(|| {
+            let path = PathBuf::from_url(params.text_document.uri);
+            if !path.starts_with(&self.root) {
+                return completable.complete(Ok(Hover {
+                    contents: HoverContents::Array(vec![]),
+                    range: None,
+                }));
+            }
+            if self.graph.lock().unwrap().find_node(&path) == None {
+                self.add_file_and_includes_to_graph(&path);
+            }
+
+            let parser = &mut self.tree_sitter.lock().unwrap();
+            let scope = match self.program_scope(&path, parser) {
+                Ok(scope) => scope,
+                Err(e) => {
+                    return completable.complete(Err(MethodError {
+                        code: 42069,
+                        message: format!("error collecting declarations: error={}, path={:?}", e, path),
+                        data: (),
+                    }))
+                }
+            };
+
+            let source = match self.document_source(&path) {
+                Ok(s) => s,
+                Err(e) => {
+                    return completable.complete(Err(MethodError {
+                        code: 42069,
+                        message: format!("error reading file: error={}, path={:?}", e, path),
+                        data: (),
+                    }))
+                }
+            };
+            let tree = parser.parse(&source, None).unwrap();
+
+            let ctx = hover::HoverContext {
+                path: &path,
+                source: &source,
+                tree: &tree,
+                scope: &scope.files,
+                defines: &scope.defines,
+                version: scope.version,
+                stage: scope.stage,
+            };
+
+            let hover = ctx.hover(params.position).unwrap_or(Hover {
+                contents: HoverContents::Array(vec![]),
+                range: None,
+            });
+            completable.complete(Ok(hover));
+        });
     }
 
     fn execute_command(&mut self, params: ExecuteCommandParams, completable: LSCompletable<Option<Value>>) {
+        self.finish_scan();
         logging::slog_with_trace_id(|| {
             match self
                 .command_provider
@@ -774,17 +1808,64 @@ impl LanguageServerHandling for MinecraftShaderLanguageServer {
         });
     }
 
-    fn signature_help(&mut self, _: TextDocumentPositionParams, completable: LSCompletable<SignatureHelp>) {
-        completable.complete(Err(Self::error_not_available(())));
+    fn signature_help(&mut self, params: TextDocumentPositionParams, completable: LSCompletable<SignatureHelp>) {
+        self.finish_scan();
+        logging::slog_with_trace_id(|| {
+            let path = PathBuf::from_url(params.text_document.uri);
+            let empty = SignatureHelp {
+                signatures: vec![],
+                active_signature: None,
+                active_parameter: None,
+            };
+            if !path.starts_with(&self.root) {
+                return completable.complete(Ok(empty));
+            }
+
+            let source = match self.document_source(&path) {
+                Ok(s) => s,
+                Err(e) => {
+                    return completable.complete(Err(MethodError {
+                        code: 42069,
+                        message: format!("error reading file: error={}, path={:?}", e, path),
+                        data: (),
+                    }))
+                }
+            };
+            let offset = linemap::LineMap::new(&source).offset_for_position(params.position);
+            let call = match signature_help::call_context(&source, offset) {
+                Some(call) => call,
+                None => return completable.complete(Ok(empty)),
+            };
+
+            if self.graph.lock().unwrap().find_node(&path) == None {
+                self.add_file_and_includes_to_graph(&path);
+            }
+            let parser = &mut self.tree_sitter.lock().unwrap();
+            let scope = match self.program_scope(&path, parser) {
+                Ok(scope) => scope,
+                Err(e) => {
+                    return completable.complete(Err(MethodError {
+                        code: 42069,
+                        message: format!("error collecting declarations: error={}, path={:?}", e, path),
+                        data: (),
+                    }))
+                }
+            };
+
+            debug!("found call for signature help"; "function" => &call.function, "active_parameter" => call.active_parameter);
+
+            completable.complete(Ok(signature_help::signature_help(&call, &scope.files).unwrap_or(empty)));
+        });
     }
 
     fn goto_definition(&mut self, params: TextDocumentPositionParams, completable: LSCompletable<Vec<Location>>) {
+        self.finish_scan();
         logging::slog_with_trace_id(|| {
             let path = PathBuf::from_url(params.text_document.uri);
             if !path.starts_with(&self.root) {
                 return;
             }
-            let parser = &mut self.tree_sitter.borrow_mut();
+            let parser = &mut self.tree_sitter.lock().unwrap();
             let parser_ctx = match navigation::ParserContext::new(parser, &path) {
                 Ok(ctx) => ctx,
                 Err(e) => {
@@ -808,12 +1889,13 @@ impl LanguageServerHandling for MinecraftShaderLanguageServer {
     }
 
     fn references(&mut self, params: ReferenceParams, completable: LSCompletable<Vec<Location>>) {
+        self.finish_scan();
         logging::slog_with_trace_id(|| {
             let path = PathBuf::from_url(params.text_document_position.text_document.uri);
             if !path.starts_with(&self.root) {
                 return;
             }
-            let parser = &mut self.tree_sitter.borrow_mut();
+            let parser = &mut self.tree_sitter.lock().unwrap();
             let parser_ctx = match navigation::ParserContext::new(parser, &path) {
                 Ok(ctx) => ctx,
                 Err(e) => {
@@ -836,17 +1918,58 @@ impl LanguageServerHandling for MinecraftShaderLanguageServer {
         });
     }
 
-    fn document_highlight(&mut self, _: TextDocumentPositionParams, completable: LSCompletable<Vec<DocumentHighlight>>) {
-        completable.complete(Err(Self::error_not_available(())));
+    fn document_highlight(&mut self, params: TextDocumentPositionParams, completable: LSCompletable<Vec<DocumentHighlight>>) {
+        self.poll_scan();
+        logging::slog_with_trace_id(|| {
+            let path = PathBuf::from_url(params.text_document.uri);
+            if !path.starts_with(&self.root) {
+                return completable.complete(Ok(vec![]));
+            }
+            if self.graph.lock().unwrap().find_node(&path) == None {
+                self.add_file_and_includes_to_graph(&path);
+            }
+
+            let parser = &mut self.tree_sitter.lock().unwrap();
+            // functions from the rest of the program are needed to tell which arguments are `out` parameters
+            let functions: Vec<symbols::Declaration> = match self.program_scope(&path, parser) {
+                Ok(scope) => scope.files.into_iter().flat_map(|(_, declarations)| declarations).collect(),
+                Err(e) => {
+                    warn!("error collecting declarations"; "error" => format!("{:?}", e), "path" => path.to_str().unwrap());
+                    vec![]
+                }
+            };
+
+            let source = match self.document_source(&path) {
+                Ok(s) => s,
+                Err(e) => {
+                    return completable.complete(Err(MethodError {
+                        code: 42069,
+                        message: format!("error reading file: error={}, path={:?}", e, path),
+                        data: (),
+                    }))
+                }
+            };
+            let parser_ctx = navigation::ParserContext::from_source(parser, source);
+
+            match parser_ctx.find_highlights(params.position, &functions) {
+                Ok(highlights) => completable.complete(Ok(highlights.unwrap_or_default())),
+                Err(e) => completable.complete(Err(MethodError {
+                    code: 42069,
+                    message: format!("error finding highlights: error={}, path={:?}", e, path),
+                    data: (),
+                })),
+            }
+        });
     }
 
     fn document_symbols(&mut self, params: DocumentSymbolParams, completable: LSCompletable<DocumentSymbolResponse>) {
+        self.poll_scan();
         logging::slog_with_trace_id(|| {
             let path = PathBuf::from_url(params.text_document.uri);
             if !path.starts_with(&self.root) {
                 return;
             }
-            let parser = &mut self.tree_sitter.borrow_mut();
+            let parser = &mut self.tree_sitter.lock().unwrap();
             let parser_ctx = match navigation::ParserContext::new(parser, &path) {
                 Ok(ctx) => ctx,
                 Err(e) => {
@@ -871,12 +1994,36 @@ impl LanguageServerHandling for MinecraftShaderLanguageServer {
         });
     }
 
-    fn workspace_symbols(&mut self, _: WorkspaceSymbolParams, completable: LSCompletable<DocumentSymbolResponse>) {
-        completable.complete(Err(Self::error_not_available(())));
+    fn workspace_symbols(&mut self, params: WorkspaceSymbolParams, completable: LSCompletable<DocumentSymbolResponse>) {
+        self.finish_scan();
+        logging::slog_with_trace_id(|| {
+            let symbols = self.workspace_symbol_search(&params.query);
+
+            info!("workspace symbol search results"; "query" => &params.query, "count" => symbols.len());
+            completable.complete(Ok(DocumentSymbolResponse::Flat(symbols)));
+        });
     }
 
-    fn code_action(&mut self, _: CodeActionParams, completable: LSCompletable<Vec<Command>>) {
-        completable.complete(Err(Self::error_not_available(())));
+    fn code_action(&mut self, params: CodeActionParams, completable: LSCompletable<Vec<Command>>) {
+        self.finish_scan();
+        logging::slog_with_trace_id(|| {
+            let path = PathBuf::from_url(params.text_document.uri);
+            let fixes = self.quick_fixes(&path, &params.context.diagnostics).and_then(|mut fixes| {
+                fixes.extend(self.organize_includes(&path)?);
+                Ok(fixes)
+            });
+            match fixes {
+                Ok(fixes) => {
+                    info!("found quick fixes"; "path" => path.to_str().unwrap(), "count" => fixes.len());
+                    completable.complete(Ok(fixes.into_iter().map(code_actions::Fix::into_command).collect()))
+                }
+                Err(e) => completable.complete(Err(MethodError {
+                    code: 42069,
+                    message: format!("error finding quick fixes: error={}, path={:?}", e, path),
+                    data: (),
+                })),
+            }
+        });
     }
 
     fn code_lens(&mut self, _: CodeLensParams, completable: LSCompletable<Vec<CodeLens>>) {
@@ -888,10 +2035,11 @@ impl LanguageServerHandling for MinecraftShaderLanguageServer {
     }
 
     fn document_link(&mut self, params: DocumentLinkParams, completable: LSCompletable<Vec<DocumentLink>>) {
+        self.poll_scan();
         logging::slog_with_trace_id(|| {
             // node for current document
             let curr_doc = PathBuf::from_url(params.text_document.uri);
-            let node = match self.graph.borrow_mut().find_node(&curr_doc) {
+            let node = match self.graph.lock().unwrap().find_node(&curr_doc) {
                 Some(n) => n,
                 None => {
                     warn!("document not found in graph"; "path" => curr_doc.to_str().unwrap());
@@ -900,15 +2048,13 @@ impl LanguageServerHandling for MinecraftShaderLanguageServer {
                 }
             };
 
-            let edges: Vec<DocumentLink> = self
-                .graph
-                .borrow()
+            let graph = self.graph.lock().unwrap();
+            let edges: Vec<DocumentLink> = graph
                 .child_node_indexes(node)
                 .filter_map::<Vec<DocumentLink>, _>(|child| {
-                    let graph = self.graph.borrow();
                     graph.get_child_positions(node, child).map(|value| {
                         let path = graph.get_node(child);
-                        let url = match Url::from_file_path(&path) {
+                        let url = match path.to_url() {
                             Ok(url) => url,
                             Err(e) => {
                                 error!("error converting into url"; "path" => path.to_str().unwrap(), "error" => format!("{:?}", e));
@@ -941,12 +2087,34 @@ impl LanguageServerHandling for MinecraftShaderLanguageServer {
         completable.complete(Err(Self::error_not_available(())));
     }
 
-    fn formatting(&mut self, _: DocumentFormattingParams, completable: LSCompletable<Vec<TextEdit>>) {
-        completable.complete(Err(Self::error_not_available(())));
+    fn formatting(&mut self, params: DocumentFormattingParams, completable: LSCompletable<Vec<TextEdit>>) {
+        self.poll_scan();
+        logging::slog_with_trace_id(|| {
+            let path = PathBuf::from_url(params.text_document.uri);
+            match self.format(&path, &params.options, None) {
+                Ok(edits) => completable.complete(Ok(edits)),
+                Err(e) => completable.complete(Err(MethodError {
+                    code: 42069,
+                    message: format!("error formatting document: error={}, path={:?}", e, path),
+                    data: (),
+                })),
+            }
+        });
     }
 
-    fn range_formatting(&mut self, _: DocumentRangeFormattingParams, completable: LSCompletable<Vec<TextEdit>>) {
-        completable.complete(Err(Self::error_not_available(())));
+    fn range_formatting(&mut self, params: DocumentRangeFormattingParams, completable: LSCompletable<Vec<TextEdit>>) {
+        self.poll_scan();
+        logging::slog_with_trace_id(|| {
+            let path = PathBuf::from_url(params.text_document.uri);
+            match self.format(&path, &params.options, Some(params.range)) {
+                Ok(edits) => completable.complete(Ok(edits)),
+                Err(e) => completable.complete(Err(MethodError {
+                    code: 42069,
+                    message: format!("error formatting range: error={}, path={:?}", e, path),
+                    data: (),
+                })),
+            }
+        });
     }
 
     fn on_type_formatting(&mut self, _: DocumentOnTypeFormattingParams, completable: LSCompletable<Vec<TextEdit>>) {
@@ -956,4 +2124,138 @@ impl LanguageServerHandling for MinecraftShaderLanguageServer {
     fn rename(&mut self, _: RenameParams, completable: LSCompletable<WorkspaceEdit>) {
         completable.complete(Err(Self::error_not_available(())));
     }
+
+    fn handle_other_method(&mut self, method_name: &str, params: RequestParams, completable: ResponseCompletable) {
+        // highlighting and folding only need the document itself
+        match method_name {
+            request::SemanticTokensFullRequest::METHOD | request::SemanticTokensRangeRequest::METHOD | request::FoldingRangeRequest::METHOD => {
+                self.poll_scan()
+            }
+            _ => self.finish_scan(),
+        }
+        logging::slog_with_trace_id(|| match method_name {
+            request::SemanticTokensFullRequest::METHOD => {
+                completable.handle_request_with(params, |params: SemanticTokensParams, completable: LSCompletable<Option<SemanticTokensResult>>| {
+                    let path = PathBuf::from_url(params.text_document.uri);
+                    match self.semantic_tokens(&path, None) {
+                        Ok(data) => completable.complete(Ok(Some(SemanticTokensResult::Tokens(SemanticTokens { result_id: None, data })))),
+                        Err(e) => completable.complete(Err(MethodError {
+                            code: 42069,
+                            message: format!("error computing semantic tokens: error={}, path={:?}", e, path),
+                            data: (),
+                        })),
+                    }
+                })
+            }
+            request::SemanticTokensRangeRequest::METHOD => {
+                completable.handle_request_with(params, |params: SemanticTokensRangeParams, completable: LSCompletable<Option<SemanticTokensRangeResult>>| {
+                    let path = PathBuf::from_url(params.text_document.uri);
+                    match self.semantic_tokens(&path, Some(params.range)) {
+                        Ok(data) => completable.complete(Ok(Some(SemanticTokensRangeResult::Tokens(SemanticTokens { result_id: None, data })))),
+                        Err(e) => completable.complete(Err(MethodError {
+                            code: 42069,
+                            message: format!("error computing semantic tokens: error={}, path={:?}", e, path),
+                            data: (),
+                        })),
+                    }
+                })
+            }
+            request::FoldingRangeRequest::METHOD => {
+                completable.handle_request_with(params, |params: FoldingRangeParams, completable: LSCompletable<Option<Vec<FoldingRange>>>| {
+                    let path = PathBuf::from_url(params.text_document.uri);
+                    let source = match self.document_source(&path) {
+                        Ok(source) => source,
+                        Err(e) => {
+                            return completable.complete(Err(MethodError {
+                                code: 42069,
+                                message: format!("error reading file: error={}, path={:?}", e, path),
+                                data: (),
+                            }))
+                        }
+                    };
+                    let tree = self.tree_sitter.lock().unwrap().parse(&source, None);
+                    completable.complete(Ok(Some(folding::folding_ranges(&source, tree.as_ref()))))
+                })
+            }
+            request::WillRenameFiles::METHOD => {
+                completable.handle_request_with(params, |params: RenameFilesParams, completable: LSCompletable<Option<WorkspaceEdit>>| {
+                    let renames = file_renames(&params);
+                    match self.will_rename_files(&renames) {
+                        Ok(edit) => completable.complete(Ok(Some(edit))),
+                        Err(e) => completable.complete(Err(MethodError {
+                            code: 42069,
+                            message: format!("error updating includes: error={}, renames={:?}", e, renames),
+                            data: (),
+                        })),
+                    }
+                })
+            }
+            notification::DidRenameFiles::METHOD => {
+                completable.handle_notification_with(params, |params: RenameFilesParams| self.did_rename_files(&file_renames(&params)))
+            }
+            request::CallHierarchyPrepare::METHOD => {
+                completable.handle_request_with(params, |params: CallHierarchyPrepareParams, completable: LSCompletable<Option<Vec<CallHierarchyItem>>>| {
+                    let path = PathBuf::from_url(params.text_document_position_params.text_document.uri);
+                    let position = params.text_document_position_params.position;
+                    let items = self.document_source(&path).and_then(|source| {
+                        let name = match symbols::word_at(&source, position) {
+                            Some((name, _)) => name,
+                            None => return Ok(vec![]),
+                        };
+                        if self.graph.lock().unwrap().find_node(&path) == None {
+                            self.add_file_and_includes_to_graph(&path);
+                        }
+                        let scope = self.program_scope(&path, &mut self.tree_sitter.lock().unwrap())?;
+                        Ok(call_hierarchy::prepare(&name, &scope.files))
+                    });
+                    match items {
+                        Ok(items) if items.is_empty() => completable.complete(Ok(None)),
+                        Ok(items) => completable.complete(Ok(Some(items))),
+                        Err(e) => completable.complete(Err(MethodError {
+                            code: 42069,
+                            message: format!("error preparing call hierarchy: error={}, path={:?}", e, path),
+                            data: (),
+                        })),
+                    }
+                })
+            }
+            request::CallHierarchyIncomingCalls::METHOD => {
+                completable.handle_request_with(params, |params: CallHierarchyIncomingCallsParams, completable: LSCompletable<Option<Vec<CallHierarchyIncomingCall>>>| {
+                    let path = PathBuf::from_url(params.item.uri.clone());
+                    match self.program_calls(&path) {
+                        Ok((scope, calls)) => {
+                            let incoming = call_hierarchy::incoming_calls(&params.item, &calls, &scope.files);
+                            completable.complete(Ok(Some(incoming)))
+                        }
+                        Err(e) => completable.complete(Err(MethodError {
+                            code: 42069,
+                            message: format!("error finding incoming calls: error={}, path={:?}", e, path),
+                            data: (),
+                        })),
+                    }
+                })
+            }
+            request::CallHierarchyOutgoingCalls::METHOD => {
+                completable.handle_request_with(params, |params: CallHierarchyOutgoingCallsParams, completable: LSCompletable<Option<Vec<CallHierarchyOutgoingCall>>>| {
+                    let path = PathBuf::from_url(params.item.uri.clone());
+                    match self.program_calls(&path) {
+                        Ok((scope, calls)) => {
+                            let file_calls = calls.iter().find(|(file, _)| *file == path).map_or(&[][..], |(_, calls)| calls);
+                            let outgoing = call_hierarchy::outgoing_calls(&params.item, file_calls, &scope.files);
+                            completable.complete(Ok(Some(outgoing)))
+                        }
+                        Err(e) => completable.complete(Err(MethodError {
+                            code: 42069,
+                            message: format!("error finding outgoing calls: error={}, path={:?}", e, path),
+                            data: (),
+                        })),
+                    }
+                })
+            }
+            _ => {
+                debug!("unhandled method"; "method" => method_name);
+                completable.complete_with_error(error_JSON_RPC_MethodNotFound())
+            }
+        });
+    }
 }
@@ -340,18 +340,19 @@ mod merge_view_test {
         let (_tmp_dir, tmp_path) = copy_to_and_set_root("./testdata/01", &mut server);
         server.endpoint.request_shutdown();
 
-        let final_idx = server.graph.borrow_mut().add_node(&tmp_path.join("shaders").join("final.fsh"));
-        let common_idx = server.graph.borrow_mut().add_node(&tmp_path.join("shaders").join("common.glsl"));
+        let final_idx = server.graph.lock().unwrap().add_node(&tmp_path.join("shaders").join("final.fsh"));
+        let common_idx = server.graph.lock().unwrap().add_node(&tmp_path.join("shaders").join("common.glsl"));
 
         server
             .graph
-            .borrow_mut()
+            .lock()
+            .unwrap()
             .add_edge(final_idx, common_idx, IncludePosition { line: 2, start: 0, end: 0 });
 
         let nodes = server.get_dfs_for_node(final_idx).unwrap();
         let sources = server.load_sources(&nodes).unwrap();
 
-        let graph_borrow = server.graph.borrow();
+        let graph_borrow = server.graph.lock().unwrap();
         let mut source_mapper = SourceMapper::new(0);
         let result = MergeViewBuilder::new(&nodes, &sources, &graph_borrow, &mut source_mapper).build();
 
@@ -384,37 +385,43 @@ mod merge_view_test {
         let (_tmp_dir, tmp_path) = copy_to_and_set_root("./testdata/02", &mut server);
         server.endpoint.request_shutdown();
 
-        let final_idx = server.graph.borrow_mut().add_node(&tmp_path.join("shaders").join("final.fsh"));
+        let final_idx = server.graph.lock().unwrap().add_node(&tmp_path.join("shaders").join("final.fsh"));
         let test_idx = server
             .graph
-            .borrow_mut()
+            .lock()
+            .unwrap()
             .add_node(&tmp_path.join("shaders").join("utils").join("test.glsl"));
         let burger_idx = server
             .graph
-            .borrow_mut()
+            .lock()
+            .unwrap()
             .add_node(&tmp_path.join("shaders").join("utils").join("burger.glsl"));
         let sample_idx = server
             .graph
-            .borrow_mut()
+            .lock()
+            .unwrap()
             .add_node(&tmp_path.join("shaders").join("utils").join("sample.glsl"));
 
         server
             .graph
-            .borrow_mut()
+            .lock()
+            .unwrap()
             .add_edge(final_idx, sample_idx, IncludePosition { line: 2, start: 0, end: 0 });
         server
             .graph
-            .borrow_mut()
+            .lock()
+            .unwrap()
             .add_edge(sample_idx, burger_idx, IncludePosition { line: 4, start: 0, end: 0 });
         server
             .graph
-            .borrow_mut()
+            .lock()
+            .unwrap()
             .add_edge(sample_idx, test_idx, IncludePosition { line: 6, start: 0, end: 0 });
 
         let nodes = server.get_dfs_for_node(final_idx).unwrap();
         let sources = server.load_sources(&nodes).unwrap();
 
-        let graph_borrow = server.graph.borrow();
+        let graph_borrow = server.graph.lock().unwrap();
         let mut source_mapper = SourceMapper::new(0);
         let result = MergeViewBuilder::new(&nodes, &sources, &graph_borrow, &mut source_mapper).build();
 
@@ -459,37 +466,43 @@ mod merge_view_test {
         let (_tmp_dir, tmp_path) = copy_to_and_set_root("./testdata/03", &mut server);
         server.endpoint.request_shutdown();
 
-        let final_idx = server.graph.borrow_mut().add_node(&tmp_path.join("shaders").join("final.fsh"));
+        let final_idx = server.graph.lock().unwrap().add_node(&tmp_path.join("shaders").join("final.fsh"));
         let test_idx = server
             .graph
-            .borrow_mut()
+            .lock()
+            .unwrap()
             .add_node(&tmp_path.join("shaders").join("utils").join("test.glsl"));
         let burger_idx = server
             .graph
-            .borrow_mut()
+            .lock()
+            .unwrap()
             .add_node(&tmp_path.join("shaders").join("utils").join("burger.glsl"));
         let sample_idx = server
             .graph
-            .borrow_mut()
+            .lock()
+            .unwrap()
             .add_node(&tmp_path.join("shaders").join("utils").join("sample.glsl"));
 
         server
             .graph
-            .borrow_mut()
+            .lock()
+            .unwrap()
             .add_edge(final_idx, sample_idx, IncludePosition { line: 2, start: 0, end: 0 });
         server
             .graph
-            .borrow_mut()
+            .lock()
+            .unwrap()
             .add_edge(sample_idx, burger_idx, IncludePosition { line: 4, start: 0, end: 0 });
         server
             .graph
-            .borrow_mut()
+            .lock()
+            .unwrap()
             .add_edge(sample_idx, test_idx, IncludePosition { line: 6, start: 0, end: 0 });
 
         let nodes = server.get_dfs_for_node(final_idx).unwrap();
         let sources = server.load_sources(&nodes).unwrap();
 
-        let graph_borrow = server.graph.borrow();
+        let graph_borrow = server.graph.lock().unwrap();
         let mut source_mapper = SourceMapper::new(0);
         let result = MergeViewBuilder::new(&nodes, &sources, &graph_borrow, &mut source_mapper).build();
 
@@ -534,45 +547,53 @@ mod merge_view_test {
         let (_tmp_dir, tmp_path) = copy_to_and_set_root("./testdata/04", &mut server);
         server.endpoint.request_shutdown();
 
-        let final_idx = server.graph.borrow_mut().add_node(&tmp_path.join("shaders").join("final.fsh"));
+        let final_idx = server.graph.lock().unwrap().add_node(&tmp_path.join("shaders").join("final.fsh"));
         let utilities_idx = server
             .graph
-            .borrow_mut()
+            .lock()
+            .unwrap()
             .add_node(&tmp_path.join("shaders").join("utils").join("utilities.glsl"));
         let stuff1_idx = server
             .graph
-            .borrow_mut()
+            .lock()
+            .unwrap()
             .add_node(&tmp_path.join("shaders").join("utils").join("stuff1.glsl"));
         let stuff2_idx = server
             .graph
-            .borrow_mut()
+            .lock()
+            .unwrap()
             .add_node(&tmp_path.join("shaders").join("utils").join("stuff2.glsl"));
         let matrices_idx = server
             .graph
-            .borrow_mut()
+            .lock()
+            .unwrap()
             .add_node(&tmp_path.join("shaders").join("lib").join("matrices.glsl"));
 
         server
             .graph
-            .borrow_mut()
+            .lock()
+            .unwrap()
             .add_edge(final_idx, utilities_idx, IncludePosition { line: 2, start: 0, end: 0 });
         server
             .graph
-            .borrow_mut()
+            .lock()
+            .unwrap()
             .add_edge(utilities_idx, stuff1_idx, IncludePosition { line: 0, start: 0, end: 0 });
         server
             .graph
-            .borrow_mut()
+            .lock()
+            .unwrap()
             .add_edge(utilities_idx, stuff2_idx, IncludePosition { line: 1, start: 0, end: 0 });
         server
             .graph
-            .borrow_mut()
+            .lock()
+            .unwrap()
             .add_edge(final_idx, matrices_idx, IncludePosition { line: 3, start: 0, end: 0 });
 
         let nodes = server.get_dfs_for_node(final_idx).unwrap();
         let sources = server.load_sources(&nodes).unwrap();
 
-        let graph_borrow = server.graph.borrow();
+        let graph_borrow = server.graph.lock().unwrap();
         let mut source_mapper = SourceMapper::new(0);
         let result = MergeViewBuilder::new(&nodes, &sources, &graph_borrow, &mut source_mapper).build();
 
@@ -606,22 +627,24 @@ mod merge_view_test {
         let (_tmp_dir, tmp_path) = copy_to_and_set_root("./testdata/06", &mut server);
         server.endpoint.request_shutdown();
 
-        let final_idx = server.graph.borrow_mut().add_node(&tmp_path.join("shaders").join("final.fsh"));
-        let test_idx = server.graph.borrow_mut().add_node(&tmp_path.join("shaders").join("test.glsl"));
+        let final_idx = server.graph.lock().unwrap().add_node(&tmp_path.join("shaders").join("final.fsh"));
+        let test_idx = server.graph.lock().unwrap().add_node(&tmp_path.join("shaders").join("test.glsl"));
 
         server
             .graph
-            .borrow_mut()
+            .lock()
+            .unwrap()
             .add_edge(final_idx, test_idx, IncludePosition { line: 3, start: 0, end: 0 });
         server
             .graph
-            .borrow_mut()
+            .lock()
+            .unwrap()
             .add_edge(final_idx, test_idx, IncludePosition { line: 5, start: 0, end: 0 });
 
         let nodes = server.get_dfs_for_node(final_idx).unwrap();
         let sources = server.load_sources(&nodes).unwrap();
 
-        let graph_borrow = server.graph.borrow();
+        let graph_borrow = server.graph.lock().unwrap();
         let mut source_mapper = SourceMapper::new(0);
         let result = MergeViewBuilder::new(&nodes, &sources, &graph_borrow, &mut source_mapper).build();
 
@@ -1,12 +1,15 @@
-use std::{collections::HashMap, fs::read_to_string, path::Path, vec};
+use std::{collections::HashMap, path::Path, vec};
 
 use anyhow::Result;
-use rust_lsp::lsp_types::{DocumentSymbol, Location, Position, Range, SymbolKind};
+use rust_lsp::lsp_types::{DocumentHighlight, DocumentHighlightKind, DocumentSymbol, Location, Position, Range, SymbolKind};
 use slog_scope::{debug, info, trace};
 use tree_sitter::{Node, Parser, Point, Query, QueryCursor, Tree};
-use url::Url;
 
+use crate::archive;
+use crate::builtins;
 use crate::linemap::LineMap;
+use crate::symbols::{self, Declaration, DeclarationKind};
+use crate::url_norm::ToUrl;
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq, Default)]
 struct SymbolName(String);
@@ -83,6 +86,21 @@ macro_rules! find_function_refs_str {
     };
 }
 
+macro_rules! find_identifier_str {
+    () => {
+        r#"
+            (
+                [
+                    (identifier)
+                    (field_identifier)
+                    (type_identifier)
+                ] @ident
+                (#eq? @ident "{}")
+            )
+        "#
+    };
+}
+
 macro_rules! find_variable_def_str {
     () => {
         r#"
@@ -147,18 +165,23 @@ pub struct ParserContext<'a> {
 
 impl<'a> ParserContext<'a> {
     pub fn new(parser: &'a mut Parser, path: &Path) -> Result<Self> {
-        let source = read_to_string(path)?;
+        let source = archive::read_to_string(path)?;
+
+        Ok(Self::from_source(parser, source))
+    }
 
+    /// A context over `source` rather than the file on disk, e.g. the unsaved contents of an open document.
+    pub fn from_source(parser: &'a mut Parser, source: String) -> Self {
         let tree = parser.parse(&source, None).unwrap();
 
         let linemap = LineMap::new(&source);
 
-        Ok(ParserContext {
+        ParserContext {
             source,
             tree,
             linemap,
             parser,
-        })
+        }
     }
 
     pub fn list_symbols(&self, _path: &Path) -> Result<Option<Vec<DocumentSymbol>>> {
@@ -298,57 +321,185 @@ impl<'a> ParserContext<'a> {
         Ok(Some(locations))
     }
 
-    fn tree_climbing_search(&self, path: &Path, start_node: Node) -> Result<Vec<Location>> {
-        let mut locations = vec![];
+    /// Finds every occurrence of the symbol at `point` that refers to the same declaration, marking assignments,
+    /// declarations and arguments passed to `out`/`inout` parameters of `functions` or built-ins as writes.
+    pub fn find_highlights(&self, point: Position, functions: &[Declaration]) -> Result<Option<Vec<DocumentHighlight>>> {
+        let current_node = match self.find_node_at_point(point) {
+            Some(node) => node,
+            None => return Ok(None),
+        };
+
+        if !matches!(current_node.kind(), "identifier" | "field_identifier" | "type_identifier") {
+            return Ok(None);
+        }
+
+        let name = current_node.utf8_text(self.source.as_bytes())?;
+
+        let query = Query::new(tree_sitter_glsl::language(), &format!(find_identifier_str!(), name))?;
+        let mut query_cursor = QueryCursor::new();
+        let candidates: Vec<Node> = query_cursor
+            .matches(&query, self.root_node(), self.source.as_bytes())
+            .flat_map(|m| m.captures.iter().map(|capture| capture.node).collect::<Vec<_>>())
+            .filter(|node| node.kind() == current_node.kind())
+            .collect();
+
+        let occurrences: Vec<Node> = if current_node.kind() != "identifier" {
+            // struct fields and types aren't scoped
+            candidates
+        } else if is_function_name(current_node) {
+            candidates.into_iter().filter(|node| is_function_name(*node)).collect()
+        } else {
+            let definition_query = Query::new(tree_sitter_glsl::language(), &format!(find_variable_def_str!(), name))?;
+            let declaration_ids = |node: Node| {
+                self.climb_for_declarations(node, &definition_query)
+                    .iter()
+                    .map(|decl| decl.id())
+                    .collect::<Vec<_>>()
+            };
+
+            let target = declaration_ids(current_node);
+            candidates
+                .into_iter()
+                .filter(|node| !is_function_name(*node) && declaration_ids(*node) == target)
+                .collect()
+        };
+
+        let highlights: Vec<DocumentHighlight> = occurrences
+            .into_iter()
+            .map(|node| DocumentHighlight {
+                range: symbols::node_range(node),
+                kind: Some(if self.is_write(node, functions) {
+                    DocumentHighlightKind::WRITE
+                } else {
+                    DocumentHighlightKind::READ
+                }),
+            })
+            .collect();
+
+        info!("finished searching for highlights"; "count" => highlights.len(), "symbol" => name);
+
+        Ok(Some(highlights))
+    }
+
+    fn is_write(&self, node: Node, functions: &[Declaration]) -> bool {
+        let parent = match node.parent() {
+            Some(parent) => parent,
+            None => return false,
+        };
+
+        match parent.kind() {
+            // `float a, b;` has a declarator for each variable
+            "init_declarator" | "declaration" | "parameter_declaration" | "array_declarator" | "field_declaration" => {
+                let mut cursor = parent.walk();
+                let is_declarator = parent
+                    .children_by_field_name("declarator", &mut cursor)
+                    .any(|decl| decl.id() == node.id());
+                return is_declarator;
+            }
+            "function_declarator" => return true,
+            _ => {}
+        }
+
+        // `a.xyz = ...` and `a[i] = ...` write to `a`
+        let mut target = node;
+        while let Some(parent) = target.parent() {
+            match parent.kind() {
+                "field_expression" | "subscript_expression" if parent.child_by_field_name("argument").map(|a| a.id()) == Some(target.id()) => {
+                    target = parent
+                }
+                "parenthesized_expression" => target = parent,
+                _ => break,
+            }
+        }
+
+        let parent = match target.parent() {
+            Some(parent) => parent,
+            None => return false,
+        };
+
+        match parent.kind() {
+            "assignment_expression" => parent.child_by_field_name("left").map(|left| left.id()) == Some(target.id()),
+            "update_expression" => true,
+            "argument_list" => {
+                let call = match parent.parent().filter(|call| call.kind() == "call_expression") {
+                    Some(call) => call,
+                    None => return false,
+                };
+                let function = match call.child_by_field_name("function") {
+                    Some(function) => function.utf8_text(self.source.as_bytes()).unwrap_or_default(),
+                    None => return false,
+                };
+                let mut cursor = parent.walk();
+                let index = match parent.named_children(&mut cursor).position(|arg| arg.id() == target.id()) {
+                    Some(index) => index,
+                    None => return false,
+                };
+                is_out_parameter(function, index, functions)
+            }
+            _ => false,
+        }
+    }
 
+    fn tree_climbing_search(&self, path: &Path, start_node: Node) -> Result<Vec<Location>> {
         let node_text = start_node.utf8_text(self.source.as_bytes())?;
 
         let query_str = format!(find_variable_def_str!(), node_text);
 
         debug!("built query string"; "query" => &query_str);
 
-        let mut parent = start_node.parent();
+        let query = Query::new(tree_sitter_glsl::language(), &query_str)?;
 
-        loop {
-            if parent.is_none() {
-                trace!("no more parent left, found nothing");
-                break;
-            }
+        let locations = self
+            .climb_for_declarations(start_node, &query)
+            .into_iter()
+            .map(|node| {
+                let start = node.start_position();
+                let end = node.end_position();
 
-            let query = Query::new(tree_sitter_glsl::language(), &query_str)?;
-            let mut query_cursor = QueryCursor::new();
-
-            trace!("running tree-sitter query for node"; "node" => format!("{:?}", parent.unwrap()), "node_text" => parent.unwrap().utf8_text(self.source.as_bytes()).unwrap());
-
-            for m in query_cursor.matches(&query, parent.unwrap(), self.source.as_bytes()) {
-                for capture in m.captures {
-                    let start = capture.node.start_position();
-                    let end = capture.node.end_position();
-
-                    locations.push(Location {
-                        uri: Url::from_file_path(path).unwrap(),
-                        range: Range {
-                            start: Position {
-                                line: start.row as u32,
-                                character: start.column as u32,
-                            },
-                            end: Position {
-                                line: end.row as u32,
-                                character: end.column as u32,
-                            },
+                Location {
+                    uri: path.to_url().unwrap(),
+                    range: Range {
+                        start: Position {
+                            line: start.row as u32,
+                            character: start.column as u32,
+                        },
+                        end: Position {
+                            line: end.row as u32,
+                            character: end.column as u32,
                         },
-                    });
+                    },
                 }
-            }
+            })
+            .collect();
+
+        Ok(locations)
+    }
+
+    /// Climbs up the tree from `start_node`, running the variable definition `query` on every ancestor until
+    /// one is found that declares the variable in its own scope (as opposed to a nested scope).
+    fn climb_for_declarations<'t>(&'t self, start_node: Node<'t>, query: &Query) -> Vec<Node<'t>> {
+        let mut parent = start_node.parent();
+
+        while let Some(scope) = parent {
+            let mut query_cursor = QueryCursor::new();
+
+            trace!("running tree-sitter query for node"; "node" => format!("{:?}", scope), "node_text" => scope.utf8_text(self.source.as_bytes()).unwrap());
+
+            let declarations: Vec<Node> = query_cursor
+                .matches(query, scope, self.source.as_bytes())
+                .flat_map(|m| m.captures.iter().map(|capture| capture.node).collect::<Vec<_>>())
+                .filter(|node| enclosing_scope(*node).map_or(false, |s| s.id() == scope.id()))
+                .collect();
 
-            if !locations.is_empty() {
-                break;
+            if !declarations.is_empty() {
+                return declarations;
             }
 
-            parent = parent.unwrap().parent();
+            parent = scope.parent();
         }
 
-        Ok(locations)
+        trace!("no more parent left, found nothing");
+        vec![]
     }
 
     fn simple_global_search(&self, path: &Path, query_str: &str) -> Result<Vec<Location>> {
@@ -363,7 +514,7 @@ impl<'a> ParserContext<'a> {
                 let end = capture.node.end_position();
 
                 locations.push(Location {
-                    uri: Url::from_file_path(path).unwrap(),
+                    uri: path.to_url().unwrap(),
                     range: Range {
                         start: Position {
                             line: start.row as u32,
@@ -427,3 +578,119 @@ impl<'a> ParserContext<'a> {
         }
     }
 }
+
+// returns the nearest node that variables can be declared in the scope of.
+fn enclosing_scope(node: Node) -> Option<Node> {
+    let mut parent = node.parent();
+    while let Some(p) = parent {
+        if matches!(p.kind(), "compound_statement" | "function_definition" | "for_statement" | "translation_unit") {
+            return Some(p);
+        }
+        parent = p.parent();
+    }
+    None
+}
+
+fn is_function_name(node: Node) -> bool {
+    match node.parent() {
+        Some(parent) if parent.kind() == "function_declarator" => parent.child_by_field_name("declarator").map(|d| d.id()) == Some(node.id()),
+        Some(parent) if parent.kind() == "call_expression" => parent.child_by_field_name("function").map(|f| f.id()) == Some(node.id()),
+        _ => false,
+    }
+}
+
+// whether the `index`th parameter of any overload of `function` is an `out` or `inout` parameter.
+fn is_out_parameter(function: &str, index: usize, functions: &[Declaration]) -> bool {
+    let project = functions
+        .iter()
+        .filter(|decl| decl.kind == DeclarationKind::Function && decl.name == function)
+        .filter_map(|decl| decl.parameters.get(index))
+        .any(|param| matches!(param.qualifier.as_deref(), Some("out") | Some("inout")));
+    if project {
+        return true;
+    }
+
+    builtins::function(function).map_or(false, |builtin| {
+        builtin.signatures.iter().any(|signature| {
+            builtins::parameter_offsets(signature).get(index).map_or(false, |[start, end]| {
+                let param = &signature[*start as usize..*end as usize];
+                param.starts_with("out ") || param.starts_with("inout ")
+            })
+        })
+    })
+}
+
+#[cfg(test)]
+mod navigation_test {
+    use rust_lsp::lsp_types::{DocumentHighlightKind, Position};
+    use tree_sitter::{Parser, Query};
+
+    use crate::navigation::ParserContext;
+
+    const SOURCE: &str = "float a, b;
+void main() {
+    b = a;
+    {
+        float a = 1.0;
+        a += b;
+    }
+    a++;
+    modf(b, a);
+}
+";
+
+    fn highlights(parser: &mut Parser, line: u32, character: u32) -> Vec<(u32, u32, DocumentHighlightKind)> {
+        let context = ParserContext::from_source(parser, SOURCE.into());
+        context
+            .find_highlights(Position::new(line, character), &[])
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|highlight| (highlight.range.start.line, highlight.range.start.character, highlight.kind.unwrap()))
+            .collect()
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_find_highlights() {
+        let mut parser = Parser::new();
+        parser.set_language(tree_sitter_glsl::language()).unwrap();
+
+        const READ: DocumentHighlightKind = DocumentHighlightKind::READ;
+        const WRITE: DocumentHighlightKind = DocumentHighlightKind::WRITE;
+
+        // declared second in `float a, b;`, and passed as the `out` parameter of modf
+        assert_eq!(
+            highlights(&mut parser, 2, 4),
+            vec![(0, 9, WRITE), (2, 4, WRITE), (5, 13, READ), (8, 9, READ)]
+        );
+        // the global, not the one shadowing it in the block
+        assert_eq!(
+            highlights(&mut parser, 2, 8),
+            vec![(0, 6, WRITE), (2, 8, READ), (7, 4, WRITE), (8, 12, WRITE)]
+        );
+        assert_eq!(highlights(&mut parser, 5, 8), vec![(4, 14, WRITE), (5, 8, WRITE)]);
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_climb_for_declarations() {
+        let mut parser = Parser::new();
+        parser.set_language(tree_sitter_glsl::language()).unwrap();
+        let context = ParserContext::from_source(&mut parser, SOURCE.into());
+
+        let query = Query::new(tree_sitter_glsl::language(), &format!(find_variable_def_str!(), "a")).unwrap();
+        let declared_at = |line: u32, character: u32| -> Vec<(usize, usize)> {
+            let node = context.find_node_at_point(Position::new(line, character)).unwrap();
+            context
+                .climb_for_declarations(node, &query)
+                .iter()
+                .map(|declaration| (declaration.start_position().row, declaration.start_position().column))
+                .collect()
+        };
+
+        // declarations in nested scopes aren't visible from outside them
+        assert_eq!(declared_at(7, 4), vec![(0, 6)]);
+        assert_eq!(declared_at(5, 8), vec![(4, 14)]);
+    }
+}
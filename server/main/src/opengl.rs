@@ -1,19 +1,31 @@
 use std::ffi::{CStr, CString};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
 use std::ptr;
+use std::sync::{mpsc, Mutex};
+use std::thread;
 
-use slog_scope::info;
+use anyhow::{anyhow, Result};
+use slog_scope::{error, info};
 
 #[cfg(test)]
 use mockall::automock;
 
 #[cfg_attr(test, automock)]
-pub trait ShaderValidator {
+pub trait ShaderValidator: Send + Sync {
     fn validate(&self, tree_type: super::TreeType, source: &str) -> Option<String>;
     fn vendor(&self) -> String;
 }
 
+// a source to compile and where to send the compiler output.
+type Validation = (super::TreeType, String, mpsc::Sender<Option<String>>);
+
+/// Validates shaders with the driver of the GPU through a headless OpenGL context. A context can only be
+/// used from the thread it was made current on, so it lives on a thread of its own that sources are sent to.
 pub struct OpenGlContext {
-    _ctx: glutin::Context<glutin::PossiblyCurrent>,
+    requests: Mutex<mpsc::Sender<Validation>>,
+    vendor: String,
 }
 
 impl OpenGlContext {
@@ -23,82 +35,150 @@ impl OpenGlContext {
             .build_headless(&*events_loop, glutin::dpi::PhysicalSize::new(1, 1))
             .unwrap();
 
-        let gl_window = unsafe {
-            let gl_window = gl_window.make_current().unwrap();
-            gl::load_with(|symbol| gl_window.get_proc_address(symbol) as *const _);
-            gl_window
-        };
+        let (requests, receiver) = mpsc::channel::<Validation>();
+        let (vendor_sender, vendor) = mpsc::channel();
+        thread::Builder::new()
+            .name("opengl".into())
+            .spawn(move || {
+                let _ctx = unsafe {
+                    let gl_window = gl_window.make_current().unwrap();
+                    gl::load_with(|symbol| gl_window.get_proc_address(symbol) as *const _);
+                    gl_window
+                };
 
-        let gl_ctx = OpenGlContext { _ctx: gl_window };
+                unsafe {
+                    info!(
+                        "OpenGL device";
+                        "vendor" => gl_string(gl::VENDOR),
+                        "version" => gl_string(gl::VERSION),
+                        "renderer" => gl_string(gl::RENDERER)
+                    );
+                    vendor_sender.send(gl_string(gl::VENDOR)).unwrap();
+                }
+
+                for (tree_type, source, result) in receiver {
+                    let _ = result.send(unsafe { compile(tree_type, &source) });
+                }
+            })
+            .unwrap();
 
-        unsafe {
-            info!(
-                "OpenGL device";
-                "vendor" => gl_ctx.vendor(),
-                "version" => String::from_utf8(CStr::from_ptr(gl::GetString(gl::VERSION) as *const _).to_bytes().to_vec()).unwrap(),
-                "renderer" => String::from_utf8(CStr::from_ptr(gl::GetString(gl::RENDERER) as *const _).to_bytes().to_vec()).unwrap()
-            );
+        OpenGlContext {
+            requests: Mutex::new(requests),
+            vendor: vendor.recv().unwrap(),
         }
-        gl_ctx
     }
+}
 
-    unsafe fn compile_and_get_shader_log(&self, shader: gl::types::GLuint, source: &str) -> Option<String> {
-        let mut success = i32::from(gl::FALSE);
-        let c_str_frag = CString::new(source).unwrap();
-        gl::ShaderSource(shader, 1, &c_str_frag.as_ptr(), ptr::null());
-        gl::CompileShader(shader);
-
-        // Check for shader compilation errors
-        gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut success);
-        let result = if success != i32::from(gl::TRUE) {
-            let mut info_len: gl::types::GLint = 0;
-            gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut info_len);
-            let mut info = vec![0u8; info_len as usize];
-            gl::GetShaderInfoLog(
-                shader,
-                info_len as gl::types::GLsizei,
-                ptr::null_mut(),
-                info.as_mut_ptr() as *mut gl::types::GLchar,
-            );
-            info.set_len((info_len - 1) as usize); // ignore null for str::from_utf8
-            Some(String::from_utf8(info).unwrap())
-        } else {
-            None
-        };
-        gl::DeleteShader(shader);
-        result
-    }
+unsafe fn gl_string(name: gl::types::GLenum) -> String {
+    String::from_utf8(CStr::from_ptr(gl::GetString(name) as *const _).to_bytes().to_vec()).unwrap()
+}
+
+unsafe fn compile(tree_type: super::TreeType, source: &str) -> Option<String> {
+    let shader = match tree_type {
+        crate::TreeType::Fragment => gl::CreateShader(gl::FRAGMENT_SHADER),
+        crate::TreeType::Vertex => gl::CreateShader(gl::VERTEX_SHADER),
+        crate::TreeType::Geometry => gl::CreateShader(gl::GEOMETRY_SHADER),
+        crate::TreeType::Compute => gl::CreateShader(gl::COMPUTE_SHADER),
+    };
+
+    let mut success = i32::from(gl::FALSE);
+    let c_str_frag = CString::new(source).unwrap();
+    gl::ShaderSource(shader, 1, &c_str_frag.as_ptr(), ptr::null());
+    gl::CompileShader(shader);
+
+    // Check for shader compilation errors
+    gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut success);
+    let result = if success != i32::from(gl::TRUE) {
+        let mut info_len: gl::types::GLint = 0;
+        gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut info_len);
+        let mut info = vec![0u8; info_len as usize];
+        gl::GetShaderInfoLog(
+            shader,
+            info_len as gl::types::GLsizei,
+            ptr::null_mut(),
+            info.as_mut_ptr() as *mut gl::types::GLchar,
+        );
+        info.set_len((info_len - 1) as usize); // ignore null for str::from_utf8
+        Some(String::from_utf8(info).unwrap())
+    } else {
+        None
+    };
+    gl::DeleteShader(shader);
+    result
 }
 
 impl ShaderValidator for OpenGlContext {
     fn validate(&self, tree_type: super::TreeType, source: &str) -> Option<String> {
-        unsafe {
-            match tree_type {
-                crate::TreeType::Fragment => {
-                    // Fragment shader
-                    let fragment_shader = gl::CreateShader(gl::FRAGMENT_SHADER);
-                    self.compile_and_get_shader_log(fragment_shader, source)
-                }
-                crate::TreeType::Vertex => {
-                    // Vertex shader
-                    let vertex_shader = gl::CreateShader(gl::VERTEX_SHADER);
-                    self.compile_and_get_shader_log(vertex_shader, source)
-                }
-                crate::TreeType::Geometry => {
-                    // Geometry shader
-                    let geometry_shader = gl::CreateShader(gl::GEOMETRY_SHADER);
-                    self.compile_and_get_shader_log(geometry_shader, source)
-                }
-                crate::TreeType::Compute => {
-                    // Compute shader
-                    let compute_shader = gl::CreateShader(gl::COMPUTE_SHADER);
-                    self.compile_and_get_shader_log(compute_shader, source)
-                }
+        let (result, receiver) = mpsc::channel();
+        self.requests.lock().unwrap().send((tree_type, source.into(), result)).unwrap();
+        receiver.recv().unwrap()
+    }
+
+    fn vendor(&self) -> String {
+        self.vendor.clone()
+    }
+}
+
+/// Validates shaders with glslang, the Khronos reference compiler, rather than the GPU driver so that
+/// packs can be checked on machines without a GPU such as CI runners. Its output follows the same
+/// format as Mesa's.
+pub struct GlslangValidator {
+    executable: PathBuf,
+}
+
+impl GlslangValidator {
+    /// Checks that `executable` can be run before using it, as a missing compiler would otherwise
+    /// look like a pack without any errors.
+    pub fn new(executable: PathBuf) -> Result<GlslangValidator> {
+        match Command::new(&executable).arg("--version").stdout(Stdio::null()).stderr(Stdio::null()).status() {
+            Ok(_) => Ok(GlslangValidator { executable }),
+            Err(e) => Err(anyhow!("error running {:?}: {}", executable, e)),
+        }
+    }
+}
+
+impl ShaderValidator for GlslangValidator {
+    fn validate(&self, tree_type: super::TreeType, source: &str) -> Option<String> {
+        let stage = match tree_type {
+            crate::TreeType::Fragment => "frag",
+            crate::TreeType::Vertex => "vert",
+            crate::TreeType::Geometry => "geom",
+            crate::TreeType::Compute => "comp",
+        };
+
+        let child = Command::new(&self.executable)
+            .args(["--stdin", "-S", stage])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                error!("error running glslang"; "executable" => self.executable.to_str().unwrap(), "error" => format!("{:?}", e));
+                return None;
             }
+        };
+
+        // the merged source is written in full before reading any output, glslang only writes once it has read all of stdin
+        if let Err(e) = child.stdin.take().unwrap().write_all(source.as_bytes()) {
+            error!("error writing source to glslang"; "error" => format!("{:?}", e));
+        }
+        let output = match child.wait_with_output() {
+            Ok(output) => output,
+            Err(e) => {
+                error!("error waiting for glslang"; "error" => format!("{:?}", e));
+                return None;
+            }
+        };
+
+        if output.status.success() {
+            return None;
         }
+        Some(String::from_utf8_lossy(&output.stdout).into_owned())
     }
 
     fn vendor(&self) -> String {
-        unsafe { String::from_utf8(CStr::from_ptr(gl::GetString(gl::VENDOR) as *const _).to_bytes().to_vec()).unwrap() }
+        "Khronos glslang".into()
     }
 }
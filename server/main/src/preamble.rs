@@ -0,0 +1,241 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::archive;
+
+lazy_static! {
+    static ref RE_OPTION_DEFINE: Regex = Regex::new(r#"^(\s*)(//\s*)?#define\s+(\w+)(.*)$"#).unwrap();
+    static ref RE_DEFINE_VALUE: Regex = Regex::new(r#"^(\s+)([^\s/]+)(.*)$"#).unwrap();
+}
+
+/// Value of a shader option as set by a profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionValue {
+    Toggle(bool),
+    Value(String),
+}
+
+/// The option values of the profile `name` from the pack's `shaders.properties`, including the values of
+/// the profiles it builds on. Entries are written like OptiFine does, `OPTION` or `!OPTION` to toggle an
+/// option, `OPTION:value` or `OPTION=value` to set it and `profile.OTHER` to include another profile.
+pub fn load_profile(root: &Path, name: &str) -> Result<HashMap<String, OptionValue>> {
+    let path = root.join("shaders").join("shaders.properties");
+    let contents = match archive::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Err(anyhow!("profile {:?} set but {:?} doesn't exist", name, path)),
+        Err(e) => return Err(anyhow!("error reading {:?}: {}", path, e)),
+    };
+
+    let profiles = parse_profiles(&contents);
+    let mut options = HashMap::new();
+    resolve_profile(&profiles, name, &mut options, &mut HashSet::new()).map_err(|e| anyhow!("{} in {:?}", e, path))?;
+    Ok(options)
+}
+
+/// The `profile.NAME` entries of a `shaders.properties`, joining lines continued with `\`.
+fn parse_profiles(contents: &str) -> HashMap<String, Vec<String>> {
+    let mut logical_lines: Vec<String> = vec![];
+    let mut continued = false;
+    for line in contents.lines() {
+        let line = line.trim();
+        let (text, continues) = match line.strip_suffix('\\') {
+            Some(text) => (text, true),
+            None => (line, false),
+        };
+        if continued {
+            logical_lines.last_mut().unwrap().push_str(&format!(" {}", text));
+        } else {
+            logical_lines.push(text.to_string());
+        }
+        continued = continues;
+    }
+
+    logical_lines
+        .iter()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            let name = key.trim().strip_prefix("profile.")?;
+            Some((name.to_string(), value.split_whitespace().map(String::from).collect()))
+        })
+        .collect()
+}
+
+fn resolve_profile<'a>(
+    profiles: &'a HashMap<String, Vec<String>>, name: &'a str, options: &mut HashMap<String, OptionValue>, seen: &mut HashSet<&'a str>,
+) -> Result<()> {
+    if !seen.insert(name) {
+        return Err(anyhow!("profile {:?} includes itself", name));
+    }
+    let entries = profiles.get(name).ok_or_else(|| anyhow!("no profile {:?}", name))?;
+
+    for entry in entries {
+        if let Some(other) = entry.strip_prefix("profile.") {
+            resolve_profile(profiles, other, options, seen)?;
+        } else if let Some(option) = entry.strip_prefix('!') {
+            // disabled programs aren't options
+            if !option.starts_with("program.") {
+                options.insert(option.to_string(), OptionValue::Toggle(false));
+            }
+        } else if let Some((option, value)) = entry.split_once([':', '=']) {
+            options.insert(option.to_string(), OptionValue::Value(value.to_string()));
+        } else {
+            options.insert(entry.to_string(), OptionValue::Toggle(true));
+        }
+    }
+    Ok(())
+}
+
+/// Rewrites the `#define`s of the options in `source` to the values in `options`, the way OptiFine
+/// applies option values. Toggles comment or uncomment the define and values replace the defined value,
+/// so line numbers are left unchanged.
+pub fn apply_options(source: &str, options: &HashMap<String, OptionValue>) -> String {
+    if options.is_empty() {
+        return source.to_string();
+    }
+
+    let result: Vec<String> = source
+        .split('\n')
+        .map(|line| {
+            let captures = match RE_OPTION_DEFINE.captures(line) {
+                Some(captures) => captures,
+                None => return line.to_string(),
+            };
+            let indent = &captures[1];
+            let commented = captures.get(2).is_some();
+            let name = &captures[3];
+            let rest = &captures[4];
+
+            match options.get(name) {
+                Some(OptionValue::Toggle(true)) if commented => format!("{}#define {}{}", indent, name, rest),
+                Some(OptionValue::Toggle(false)) if !commented => format!("{}//#define {}{}", indent, name, rest),
+                Some(OptionValue::Value(value)) if !commented => match RE_DEFINE_VALUE.captures(rest) {
+                    Some(define) => format!("{}#define {}{}{}{}", indent, name, &define[1], value, &define[3]),
+                    None => line.to_string(),
+                },
+                _ => line.to_string(),
+            }
+        })
+        .collect();
+    result.join("\n")
+}
+
+pub fn apply_options_to_all(sources: &mut HashMap<PathBuf, String>, options: &HashMap<String, OptionValue>) {
+    if options.is_empty() {
+        return;
+    }
+    for source in sources.values_mut() {
+        *source = apply_options(source, options);
+    }
+}
+
+/// The `#define`s for the configured preamble values. `true` defines the name without a value and
+/// `false` leaves it undefined.
+pub fn preamble_defines(values: &BTreeMap<String, toml::Value>) -> Vec<String> {
+    values
+        .iter()
+        .filter_map(|(name, value)| match value {
+            toml::Value::Boolean(true) => Some(format!("#define {}", name)),
+            toml::Value::Boolean(false) => None,
+            toml::Value::String(value) => Some(format!("#define {} {}", name, value)),
+            value => Some(format!("#define {} {}", name, value)),
+        })
+        .collect()
+}
+
+/// Inserts `defines` into the merged source of a program, following the `#version` if there is one.
+/// A `#line` directive after them keeps the line numbers of the top-level file as they were.
+pub fn insert_defines(merged: &str, defines: &[String]) -> String {
+    if defines.is_empty() {
+        return merged.to_string();
+    }
+
+    let version_line = merged.lines().position(|line| line.trim_start().starts_with("#version"));
+    let insert_at = version_line.map_or(0, |line| line + 1);
+
+    let mut lines: Vec<&str> = merged.split('\n').collect();
+    let tail = lines.split_off(insert_at.min(lines.len()));
+
+    let mut result = lines.join("\n");
+    if !result.is_empty() {
+        result.push('\n');
+    }
+    for define in defines {
+        result.push_str(define);
+        result.push('\n');
+    }
+    // #line is 1-indexed and refers to the line following it
+    result.push_str(&format!("#line {} 0\n", insert_at + 1));
+    result.push_str(&tail.join("\n"));
+    result
+}
+
+#[cfg(test)]
+mod preamble_test {
+    use std::collections::{BTreeMap, HashMap};
+
+    use crate::preamble::{apply_options, insert_defines, parse_profiles, preamble_defines, resolve_profile, OptionValue};
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_profiles() {
+        let profiles = parse_profiles(
+            r#"
+# comment
+profile.LOW = !SHADOWS shadowMapResolution:1024 !program.composite2
+profile.HIGH = profile.LOW SHADOWS \
+    shadowMapResolution=4096
+profile.LOOP = profile.LOOP
+sliders = shadowMapResolution
+"#,
+        );
+
+        let mut options = HashMap::new();
+        resolve_profile(&profiles, "HIGH", &mut options, &mut Default::default()).unwrap();
+        assert_eq!(options.len(), 2);
+        assert_eq!(options["SHADOWS"], OptionValue::Toggle(true));
+        assert_eq!(options["shadowMapResolution"], OptionValue::Value("4096".into()));
+
+        assert!(resolve_profile(&profiles, "LOOP", &mut HashMap::new(), &mut Default::default()).is_err());
+        assert!(resolve_profile(&profiles, "ULTRA", &mut HashMap::new(), &mut Default::default()).is_err());
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_apply_options() {
+        let source = "//#define SHADOWS\n  #define BLOOM // bloom\n#define shadowMapResolution 2048 // [1024 2048 4096]\n#define OTHER 1\n";
+
+        let mut options = HashMap::new();
+        options.insert("SHADOWS".to_string(), OptionValue::Toggle(true));
+        options.insert("BLOOM".to_string(), OptionValue::Toggle(false));
+        options.insert("shadowMapResolution".to_string(), OptionValue::Value("4096".into()));
+
+        assert_eq!(
+            apply_options(source, &options),
+            "#define SHADOWS\n  //#define BLOOM // bloom\n#define shadowMapResolution 4096 // [1024 2048 4096]\n#define OTHER 1\n"
+        );
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_insert_defines() {
+        let mut values = BTreeMap::new();
+        values.insert("MC_VERSION".to_string(), toml::Value::Integer(11902));
+        values.insert("MC_RENDER_QUALITY".to_string(), toml::Value::Float(1.0));
+        values.insert("IS_IRIS".to_string(), toml::Value::Boolean(true));
+        values.insert("MC_GL_VENDOR_NVIDIA".to_string(), toml::Value::Boolean(false));
+        let defines = preamble_defines(&values);
+        assert_eq!(defines, vec!["#define IS_IRIS", "#define MC_RENDER_QUALITY 1.0", "#define MC_VERSION 11902"]);
+
+        assert_eq!(
+            insert_defines("#version 120\nvoid main() {}\n", &defines[..1]),
+            "#version 120\n#define IS_IRIS\n#line 2 0\nvoid main() {}\n"
+        );
+        assert_eq!(insert_defines("void main() {}", &defines[..1]), "#define IS_IRIS\n#line 1 0\nvoid main() {}");
+    }
+}
@@ -0,0 +1,437 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::iter::Peekable;
+use std::str::Chars;
+
+// guards against runaway expansion of (indirectly) self-referencing macros
+const MAX_EXPANSION_DEPTH: usize = 32;
+
+/// The result of evaluating a constant preprocessor expression.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+}
+
+impl Value {
+    fn as_f64(self) -> f64 {
+        match self {
+            Value::Int(i) => i as f64,
+            Value::Float(f) => f,
+        }
+    }
+
+    fn as_bool(self) -> bool {
+        self.as_f64() != 0.0
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Float(v) if v.fract() == 0.0 && v.abs() < 1e15 => write!(f, "{:.1}", v),
+            Value::Float(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+/// Strips a trailing `//` or `/* */` comment from a `#define` value, returning the value and the comment text.
+pub fn split_trailing_comment(value: &str) -> (&str, Option<&str>) {
+    let start = match (value.find("//"), value.find("/*")) {
+        (Some(a), Some(b)) => a.min(b),
+        (Some(a), None) => a,
+        (None, Some(b)) => b,
+        (None, None) => return (value.trim(), None),
+    };
+    let comment = value[start..].trim_start_matches('/').trim_start_matches('*').trim_end_matches("*/").trim();
+    (value[..start].trim(), Some(comment))
+}
+
+/// Expands all object-like macros in `text` using `defines`, recursively. Identifiers that are being
+/// expanded are not expanded again, same as a C preprocessor would do.
+pub fn expand(text: &str, defines: &HashMap<String, String>) -> String {
+    let mut expanding = HashSet::new();
+    expand_inner(text, defines, &mut expanding, 0)
+}
+
+fn expand_inner(text: &str, defines: &HashMap<String, String>, expanding: &mut HashSet<String>, depth: usize) -> String {
+    if depth > MAX_EXPANSION_DEPTH {
+        return text.to_string();
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if is_ident_start(c) {
+            let ident = take_ident(c, &mut chars);
+            match defines.get(&ident) {
+                Some(value) if !expanding.contains(&ident) => {
+                    expanding.insert(ident.clone());
+                    out.push_str(&expand_inner(value, defines, expanding, depth + 1));
+                    expanding.remove(&ident);
+                }
+                _ => out.push_str(&ident),
+            }
+        } else if c.is_ascii_digit() {
+            // don't treat suffixes such as the `f` in `1.0f` as identifiers
+            out.push(c);
+            while let Some(&next) = chars.peek() {
+                if !(next.is_ascii_alphanumeric() || next == '.' || next == '_') {
+                    break;
+                }
+                out.push(next);
+                chars.next();
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Evaluates an `#if`/`#elif` condition. `defined` checks are resolved against `defines`, remaining macros
+/// are expanded and any identifier left over evaluates to 0, as per the C preprocessor rules. Returns `None`
+/// if the expression cannot be parsed.
+pub fn evaluate_condition(expr: &str, defines: &HashMap<String, String>) -> Option<bool> {
+    let resolved = resolve_defined(expr, defines);
+    let expanded = expand(&resolved, defines);
+    Parser::new(&expanded, true).parse().map(Value::as_bool)
+}
+
+/// Evaluates an already expanded constant expression. Returns `None` if it contains identifiers or is otherwise
+/// not a constant expression.
+pub fn evaluate(expr: &str) -> Option<Value> {
+    Parser::new(expr, false).parse()
+}
+
+fn resolve_defined(expr: &str, defines: &HashMap<String, String>) -> String {
+    let mut out = String::with_capacity(expr.len());
+    let mut chars = expr.chars().peekable();
+    while let Some(c) = chars.next() {
+        if !is_ident_start(c) {
+            out.push(c);
+            continue;
+        }
+
+        let ident = take_ident(c, &mut chars);
+        if ident != "defined" {
+            out.push_str(&ident);
+            continue;
+        }
+
+        skip_whitespace(&mut chars);
+        let parenthesized = chars.peek() == Some(&'(');
+        if parenthesized {
+            chars.next();
+            skip_whitespace(&mut chars);
+        }
+        let name = match chars.next() {
+            Some(c) if is_ident_start(c) => take_ident(c, &mut chars),
+            _ => return expr.to_string(),
+        };
+        if parenthesized {
+            skip_whitespace(&mut chars);
+            if chars.next() != Some(')') {
+                return expr.to_string();
+            }
+        }
+        out.push_str(if defines.contains_key(&name) { "1" } else { "0" });
+    }
+    out
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn take_ident(first: char, chars: &mut Peekable<Chars>) -> String {
+    let mut ident = String::from(first);
+    while let Some(&next) = chars.peek() {
+        if !(next.is_ascii_alphanumeric() || next == '_') {
+            break;
+        }
+        ident.push(next);
+        chars.next();
+    }
+    ident
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.peek().map_or(false, |c| c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(Value),
+    Ident,
+    Op(&'static str),
+    LParen,
+    RParen,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    // whether unresolved identifiers evaluate to 0 (#if semantics) or fail the evaluation
+    idents_as_zero: bool,
+}
+
+const OPERATORS: &[&str] = &[
+    "||", "&&", "==", "!=", "<=", ">=", "<<", ">>", "|", "^", "&", "<", ">", "+", "-", "*", "/", "%", "!", "~",
+];
+
+impl Parser {
+    fn new(expr: &str, idents_as_zero: bool) -> Self {
+        Parser {
+            tokens: Self::tokenize(expr).unwrap_or_default(),
+            pos: 0,
+            idents_as_zero,
+        }
+    }
+
+    fn tokenize(expr: &str) -> Option<Vec<Token>> {
+        let mut tokens = Vec::new();
+        let mut chars = expr.chars().peekable();
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+            } else if c.is_ascii_digit() || c == '.' {
+                let mut literal = String::new();
+                while let Some(&next) = chars.peek() {
+                    let exponent_sign = (next == '-' || next == '+') && literal.ends_with(['e', 'E']) && !literal.starts_with("0x");
+                    if !(next.is_ascii_alphanumeric() || next == '.' || exponent_sign) {
+                        break;
+                    }
+                    literal.push(next);
+                    chars.next();
+                }
+                tokens.push(Token::Number(Self::parse_number(&literal)?));
+            } else if is_ident_start(c) {
+                chars.next();
+                take_ident(c, &mut chars);
+                tokens.push(Token::Ident);
+            } else if c == '(' {
+                chars.next();
+                tokens.push(Token::LParen);
+            } else if c == ')' {
+                chars.next();
+                tokens.push(Token::RParen);
+            } else {
+                let rest: String = chars.clone().take(2).collect();
+                let op = OPERATORS.iter().find(|op| rest.starts_with(**op))?;
+                for _ in 0..op.len() {
+                    chars.next();
+                }
+                tokens.push(Token::Op(op));
+            }
+        }
+        Some(tokens)
+    }
+
+    fn parse_number(literal: &str) -> Option<Value> {
+        let lower = literal.to_ascii_lowercase();
+        if let Some(hex) = lower.strip_prefix("0x") {
+            return i64::from_str_radix(hex.trim_end_matches('u'), 16).ok().map(Value::Int);
+        }
+        let is_float = lower.contains(['.', 'e']) || lower.ends_with('f');
+        let trimmed = lower.trim_end_matches("lf").trim_end_matches(['f', 'u']);
+        if is_float {
+            trimmed.parse::<f64>().ok().map(Value::Float)
+        } else {
+            trimmed.parse::<i64>().ok().map(Value::Int)
+        }
+    }
+
+    fn parse(&mut self) -> Option<Value> {
+        if self.tokens.is_empty() {
+            return None;
+        }
+        let value = self.binary(0)?;
+        if self.pos != self.tokens.len() {
+            return None;
+        }
+        Some(value)
+    }
+
+    fn precedence(op: &str) -> Option<u8> {
+        Some(match op {
+            "||" => 1,
+            "&&" => 2,
+            "|" => 3,
+            "^" => 4,
+            "&" => 5,
+            "==" | "!=" => 6,
+            "<" | ">" | "<=" | ">=" => 7,
+            "<<" | ">>" => 8,
+            "+" | "-" => 9,
+            "*" | "/" | "%" => 10,
+            _ => return None,
+        })
+    }
+
+    fn binary(&mut self, min_precedence: u8) -> Option<Value> {
+        let mut lhs = self.unary()?;
+        loop {
+            let op = match self.tokens.get(self.pos) {
+                Some(Token::Op(op)) => *op,
+                _ => break,
+            };
+            let precedence = match Self::precedence(op) {
+                Some(p) if p > min_precedence => p,
+                _ => break,
+            };
+            self.pos += 1;
+            let rhs = self.binary(precedence)?;
+            lhs = Self::apply(op, lhs, rhs)?;
+        }
+        Some(lhs)
+    }
+
+    fn unary(&mut self) -> Option<Value> {
+        let token = self.tokens.get(self.pos)?.clone();
+        self.pos += 1;
+        match token {
+            Token::Number(v) => Some(v),
+            Token::Ident if self.idents_as_zero => Some(Value::Int(0)),
+            Token::Ident => None,
+            Token::LParen => {
+                let v = self.binary(0)?;
+                match self.tokens.get(self.pos) {
+                    Some(Token::RParen) => {
+                        self.pos += 1;
+                        Some(v)
+                    }
+                    _ => None,
+                }
+            }
+            Token::Op("-") => match self.unary()? {
+                Value::Int(i) => i.checked_neg().map(Value::Int),
+                Value::Float(f) => Some(Value::Float(-f)),
+            },
+            Token::Op("+") => self.unary(),
+            Token::Op("!") => Some(Value::Int(!self.unary()?.as_bool() as i64)),
+            Token::Op("~") => match self.unary()? {
+                Value::Int(i) => Some(Value::Int(!i)),
+                Value::Float(_) => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn apply(op: &str, lhs: Value, rhs: Value) -> Option<Value> {
+        let bool_value = |b: bool| Some(Value::Int(b as i64));
+        match op {
+            "||" => return bool_value(lhs.as_bool() || rhs.as_bool()),
+            "&&" => return bool_value(lhs.as_bool() && rhs.as_bool()),
+            "==" => return bool_value(lhs.as_f64() == rhs.as_f64()),
+            "!=" => return bool_value(lhs.as_f64() != rhs.as_f64()),
+            "<" => return bool_value(lhs.as_f64() < rhs.as_f64()),
+            ">" => return bool_value(lhs.as_f64() > rhs.as_f64()),
+            "<=" => return bool_value(lhs.as_f64() <= rhs.as_f64()),
+            ">=" => return bool_value(lhs.as_f64() >= rhs.as_f64()),
+            _ => {}
+        }
+
+        match (lhs, rhs) {
+            (Value::Int(a), Value::Int(b)) => match op {
+                "+" => a.checked_add(b).map(Value::Int),
+                "-" => a.checked_sub(b).map(Value::Int),
+                "*" => a.checked_mul(b).map(Value::Int),
+                "/" => a.checked_div(b).map(Value::Int),
+                "%" => a.checked_rem(b).map(Value::Int),
+                "|" => Some(Value::Int(a | b)),
+                "^" => Some(Value::Int(a ^ b)),
+                "&" => Some(Value::Int(a & b)),
+                "<<" => u32::try_from(b).ok().and_then(|b| a.checked_shl(b)).map(Value::Int),
+                ">>" => u32::try_from(b).ok().and_then(|b| a.checked_shr(b)).map(Value::Int),
+                _ => None,
+            },
+            (a, b) => {
+                let (a, b) = (a.as_f64(), b.as_f64());
+                match op {
+                    "+" => Some(Value::Float(a + b)),
+                    "-" => Some(Value::Float(a - b)),
+                    "*" => Some(Value::Float(a * b)),
+                    "/" => Some(Value::Float(a / b)),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod preprocessor_test {
+    use std::collections::HashMap;
+
+    use crate::builtins;
+    use crate::preprocessor::{evaluate, evaluate_condition, expand, split_trailing_comment, Value};
+    use crate::symbols;
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_expand() {
+        let defines: HashMap<String, String> = vec![
+            ("SHADOW_RES".to_string(), "2048".to_string()),
+            ("SHADOW_DIST".to_string(), "SHADOW_RES / 16".to_string()),
+            ("LOOP".to_string(), "LOOP + 1".to_string()),
+            ("FLOAT".to_string(), "1.0f".to_string()),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(expand("SHADOW_DIST", &defines), "2048 / 16");
+        assert_eq!(expand("LOOP", &defines), "LOOP + 1");
+        assert_eq!(expand("FLOAT * 2", &defines), "1.0f * 2");
+        assert_eq!(expand("SHADOW_RESOLUTION", &defines), "SHADOW_RESOLUTION");
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_evaluate() {
+        assert_eq!(evaluate("2048 / 16"), Some(Value::Int(128)));
+        assert_eq!(evaluate("(1 + 2) * 3"), Some(Value::Int(9)));
+        assert_eq!(evaluate("1.5f * 2"), Some(Value::Float(3.0)));
+        assert_eq!(evaluate("-0.5 + 1"), Some(Value::Float(0.5)));
+        assert_eq!(evaluate("0x10 | 1"), Some(Value::Int(17)));
+        assert_eq!(evaluate("1 << 4"), Some(Value::Int(16)));
+        assert_eq!(evaluate("1e-3"), Some(Value::Float(0.001)));
+        assert_eq!(evaluate("vec3(1.0)"), None);
+        assert_eq!(evaluate("1 / 0"), None);
+        assert_eq!(evaluate("-(1 << 63)"), None);
+        assert_eq!(evaluate("(1 + 2"), None);
+        assert_eq!(format!("{}", Value::Float(3.0)), "3.0");
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_evaluate_condition() {
+        let optifine = builtins::macro_defines().join("\n");
+        let mut defines = symbols::defines([optifine.as_str(), "#define SHADOWS\n#define QUALITY 2\n#define SAMPLE(x) x"]);
+
+        assert_eq!(evaluate_condition("defined SHADOWS", &defines), Some(true));
+        assert_eq!(evaluate_condition("defined(SHADOWS) && QUALITY > 2", &defines), Some(false));
+        assert_eq!(evaluate_condition("!defined(FOG) || QUALITY == 2", &defines), Some(true));
+        assert_eq!(evaluate_condition("UNDEFINED_MACRO", &defines), Some(false));
+        assert_eq!(evaluate_condition("MC_VERSION >= 11700", &defines), Some(true));
+        assert_eq!(evaluate_condition("QUALITY >", &defines), None);
+
+        // function-like macros are defined, but calls to them aren't evaluated
+        assert_eq!(evaluate_condition("defined(SAMPLE)", &defines), Some(true));
+        assert_eq!(evaluate_condition("SAMPLE(1) > 0", &defines), None);
+
+        defines.remove("MC_VERSION");
+        assert_eq!(evaluate_condition("MC_VERSION >= 11700", &defines), Some(false));
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_split_trailing_comment() {
+        assert_eq!(split_trailing_comment("2048 // [1024 2048 4096]"), ("2048", Some("[1024 2048 4096]")));
+        assert_eq!(split_trailing_comment("0.5 /* strength */"), ("0.5", Some("strength")));
+        assert_eq!(split_trailing_comment("1"), ("1", None));
+    }
+}
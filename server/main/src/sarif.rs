@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use path_slash::PathExt;
+use rust_lsp::lsp_types::{Diagnostic, DiagnosticSeverity};
+use serde_json::{json, Value};
+use url::Url;
+
+use crate::diagnostics_parser::{rule_id, DiagnosticData};
+use crate::graph::CachedStableGraph;
+use crate::includes;
+
+pub static SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+// base id that artifact locations are relative to, set to the pack root.
+static ROOT_BASE_ID: &str = "PACKROOT";
+
+fn rule_description(id: &str) -> &str {
+    match id {
+        "compiler-error" => "The GLSL compiler reported an error",
+        "compiler-warning" => "The GLSL compiler reported a warning",
+        "compiler-note" => "The GLSL compiler reported a note",
+        id if id == includes::UNUSED_INCLUDE_CODE => "Included file provides nothing the including file uses",
+        id => id,
+    }
+}
+
+fn level(diagnostic: &Diagnostic) -> &'static str {
+    match diagnostic.severity {
+        Some(DiagnosticSeverity::ERROR) | None => "error",
+        Some(DiagnosticSeverity::WARNING) => "warning",
+        Some(_) => "note",
+    }
+}
+
+// `line` is 0-indexed, SARIF regions are 1-indexed.
+fn location(root: &Path, path: &Path, line: u32) -> Value {
+    json!({
+        "physicalLocation": {
+            "artifactLocation": {
+                "uri": path.strip_prefix(root).unwrap_or(path).to_slash_lossy(),
+                "uriBaseId": ROOT_BASE_ID,
+            },
+            "region": {
+                "startLine": line + 1,
+            },
+        },
+    })
+}
+
+/// The includes leading from the program a diagnostic was reported for to the file it's in, as a
+/// SARIF code flow ending at the diagnostic itself.
+fn code_flow(root: &Path, graph: &mut CachedStableGraph, path: &Path, diagnostic: &Diagnostic) -> Option<Value> {
+    let data: DiagnosticData = serde_json::from_value(diagnostic.data.clone()?).ok()?;
+    if data.program == path {
+        return None;
+    }
+
+    let program = graph.find_node(&data.program)?;
+    let file = graph.find_node(path)?;
+    let chain = graph.include_chain(program, file)?;
+
+    let mut locations: Vec<Value> = chain
+        .iter()
+        .enumerate()
+        .map(|(i, (parent, position))| {
+            let child = chain.get(i + 1).map_or(file, |(child, _)| *child);
+            let child = graph.get_node(child);
+            let mut location = location(root, &graph.get_node(*parent), position.line as u32);
+            location["message"] = json!({
+                "text": format!("includes {}", child.strip_prefix(root).unwrap_or(&child).to_slash_lossy()),
+            });
+            json!({ "location": location })
+        })
+        .collect();
+
+    let mut last = location(root, path, diagnostic.range.start.line);
+    last["message"] = json!({ "text": diagnostic.message });
+    locations.push(json!({ "location": last }));
+
+    Some(json!({
+        "threadFlows": [{ "locations": locations }],
+    }))
+}
+
+/// Builds a SARIF 2.1.0 log of `diagnostics`, keyed by the file they were reported in. Locations are
+/// relative to `root`, and diagnostics reported for a program from one of its included files carry
+/// the chain of includes leading to that file.
+pub fn report(root: &Path, graph: &mut CachedStableGraph, diagnostics: &HashMap<PathBuf, Vec<Diagnostic>>) -> Value {
+    let mut paths: Vec<&PathBuf> = diagnostics.keys().collect();
+    paths.sort();
+
+    let mut rules: Vec<String> = vec![];
+    let mut results: Vec<Value> = vec![];
+    for path in paths {
+        let mut file_diagnostics: Vec<&Diagnostic> = diagnostics[path].iter().collect();
+        file_diagnostics.sort_by_key(|diagnostic| diagnostic.range.start.line);
+
+        for diagnostic in file_diagnostics {
+            let id = rule_id(diagnostic);
+            let rule_index = match rules.iter().position(|rule| *rule == id) {
+                Some(index) => index,
+                None => {
+                    rules.push(id.clone());
+                    rules.len() - 1
+                }
+            };
+
+            let mut result = json!({
+                "ruleId": id,
+                "ruleIndex": rule_index,
+                "level": level(diagnostic),
+                "message": { "text": diagnostic.message },
+                "locations": [location(root, path, diagnostic.range.start.line)],
+            });
+            if let Some(code_flow) = code_flow(root, graph, path, diagnostic) {
+                result["codeFlows"] = json!([code_flow]);
+            }
+            results.push(result);
+        }
+    }
+
+    let rules: Vec<Value> = rules
+        .iter()
+        .map(|id| {
+            json!({
+                "id": id,
+                "shortDescription": { "text": rule_description(id) },
+            })
+        })
+        .collect();
+
+    let root_uri = Url::from_directory_path(root).map(String::from).unwrap_or_default();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "mcshader-lsp",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/Strum355/mcshader-lsp",
+                    "rules": rules,
+                },
+            },
+            "originalUriBaseIds": {
+                (ROOT_BASE_ID): { "uri": root_uri },
+            },
+            "results": results,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod sarif_test {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    use rust_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+
+    use crate::diagnostics_parser::DiagnosticData;
+    use crate::graph::CachedStableGraph;
+    use crate::sarif::report;
+    use crate::IncludePosition;
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_report() {
+        let root = PathBuf::from("/pack");
+        let program = root.join("shaders/final.fsh");
+        let common = root.join("shaders/lib/common.glsl");
+        let shadows = root.join("shaders/lib/shadows.glsl");
+
+        let mut graph = CachedStableGraph::new();
+        let program_node = graph.add_node(&program);
+        let common_node = graph.add_node(&common);
+        let shadows_node = graph.add_node(&shadows);
+        graph.add_edge(program_node, common_node, IncludePosition { line: 2, start: 0, end: 0 });
+        graph.add_edge(common_node, shadows_node, IncludePosition { line: 5, start: 0, end: 0 });
+
+        let mut diagnostics = HashMap::new();
+        diagnostics.insert(
+            shadows.clone(),
+            vec![Diagnostic {
+                range: Range::new(Position::new(9, 0), Position::new(9, 1000)),
+                severity: Some(DiagnosticSeverity::ERROR),
+                message: "undeclared identifier".into(),
+                data: Some(serde_json::to_value(DiagnosticData { program: program.clone() }).unwrap()),
+                ..Diagnostic::default()
+            }],
+        );
+        diagnostics.insert(
+            program.clone(),
+            vec![Diagnostic {
+                range: Range::new(Position::new(3, 0), Position::new(3, 30)),
+                severity: Some(DiagnosticSeverity::WARNING),
+                code: Some(NumberOrString::String("unused-include".into())),
+                message: "unused include".into(),
+                ..Diagnostic::default()
+            }],
+        );
+
+        let log = report(&root, &mut graph, &diagnostics);
+        let run = &log["runs"][0];
+        assert_eq!(log["version"], "2.1.0");
+        assert_eq!(run["originalUriBaseIds"]["PACKROOT"]["uri"], "file:///pack/");
+
+        let rules: Vec<&str> = run["tool"]["driver"]["rules"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|rule| rule["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(rules, vec!["unused-include", "compiler-error"]);
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["ruleId"], "unused-include");
+        assert_eq!(results[0]["level"], "warning");
+        assert!(results[0].get("codeFlows").is_none());
+
+        let error = &results[1];
+        assert_eq!(error["ruleIndex"], 1);
+        let location = &error["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "shaders/lib/shadows.glsl");
+        assert_eq!(location["region"]["startLine"], 10);
+
+        let flow: Vec<(&str, u64)> = error["codeFlows"][0]["threadFlows"][0]["locations"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|step| {
+                let location = &step["location"]["physicalLocation"];
+                (
+                    location["artifactLocation"]["uri"].as_str().unwrap(),
+                    location["region"]["startLine"].as_u64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            flow,
+            vec![("shaders/final.fsh", 3), ("shaders/lib/common.glsl", 6), ("shaders/lib/shadows.glsl", 10)]
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use slog_scope::{info, warn};
+use tree_sitter::Parser;
+
+use crate::archive;
+use crate::canonical;
+use crate::configuration::ProjectConfig;
+use crate::graph_cache::{self, CachedFile, GraphCache};
+use crate::includes;
+use crate::symbol_index::IndexedFile;
+
+/// A source file of the pack, with its includes and the symbols it declares.
+pub struct ScannedFile {
+    pub path: PathBuf,
+    pub file: CachedFile,
+}
+
+pub struct PackScan {
+    // sorted by path, so that the graph comes out the same every time.
+    pub files: Vec<ScannedFile>,
+    // how many files the graph cache still had the includes and symbols of.
+    pub cached: usize,
+}
+
+/// The source files of the pack at `root`, being every file with one of the shader extensions or ones
+/// added in the project config. Files found through several paths, like through a symlinked folder, are
+/// only listed once.
+fn source_files(root: &Path, config: &ProjectConfig) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = archive::files(root)
+        .into_iter()
+        .filter(|path| match path.extension() {
+            Some(ext) => config.is_source_extension(&ext.to_string_lossy()),
+            None => false,
+        })
+        .collect();
+    files.sort();
+
+    let mut seen = HashSet::new();
+    files.retain(|path| seen.insert(canonical::canonicalize(path)));
+    files
+}
+
+/// Finds the includes and symbols of every source file of the pack at `root`, spreading the files
+/// across a worker thread per core. Files that didn't change since the graph cache at `cache_path` was
+/// written are taken from it instead, after which the cache is updated. `progress` is called with the
+/// number of files scanned so far and the total as files are done.
+pub fn scan_pack(root: &Path, config: &ProjectConfig, cache_path: Option<&Path>, progress: impl Fn(usize, usize) + Sync) -> PackScan {
+    let include_roots = config.include_roots(root);
+    let cache = match cache_path.map(|path| GraphCache::load(path, root, &include_roots)) {
+        Some(Ok(cache)) => cache,
+        None => None,
+        Some(Err(e)) => {
+            warn!("ignoring graph cache, rebuilding the graph"; "error" => format!("{:?}", e));
+            None
+        }
+    };
+
+    let paths = source_files(root, config);
+    let next = AtomicUsize::new(0);
+    let done = AtomicUsize::new(0);
+    let cached = AtomicUsize::new(0);
+    let workers = thread::available_parallelism().map_or(1, |n| n.get()).min(paths.len()).max(1);
+
+    let mut files: Vec<ScannedFile> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut parser = Parser::new();
+                    parser.set_language(tree_sitter_glsl::language()).unwrap();
+
+                    let mut scanned = vec![];
+                    while let Some(path) = paths.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        match scan_file(cache.as_ref(), &include_roots, &mut parser, path) {
+                            Ok((file, was_cached)) => {
+                                cached.fetch_add(was_cached as usize, Ordering::Relaxed);
+                                scanned.push(file);
+                            }
+                            Err(e) => {
+                                warn!("error reading file for include graph"; "path" => path.to_str().unwrap(), "error" => format!("{:?}", e))
+                            }
+                        }
+                        progress(done.fetch_add(1, Ordering::Relaxed) + 1, paths.len());
+                    }
+                    scanned
+                })
+            })
+            .collect();
+        handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect()
+    });
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut new_cache = GraphCache::new(root, &include_roots);
+    for file in &files {
+        new_cache.insert(file.path.clone(), file.file.clone());
+    }
+    if let Some(Err(e)) = cache_path.map(|path| new_cache.save(path)) {
+        warn!("error saving graph cache"; "error" => format!("{:?}", e));
+    }
+
+    let cached = cached.into_inner();
+    info!("scanned pack"; "root" => root.to_str().unwrap(), "files" => files.len(), "cached" => cached, "workers" => workers);
+    PackScan { files, cached }
+}
+
+fn scan_file(
+    cache: Option<&GraphCache>, include_roots: &[PathBuf], parser: &mut Parser, path: &Path,
+) -> anyhow::Result<(ScannedFile, bool)> {
+    let (file, cached) = graph_cache::scan(cache, path, |contents| {
+        let source = std::str::from_utf8(contents)?.replace("\r\n", "\n");
+        let symbols = match parser.parse(&source, None) {
+            Some(tree) => IndexedFile::new(&tree, &source),
+            None => IndexedFile::default(),
+        };
+        Ok((includes::find(include_roots, path, contents), symbols))
+    })?;
+    Ok((
+        ScannedFile {
+            path: path.to_path_buf(),
+            file,
+        },
+        cached,
+    ))
+}
+
+#[cfg(test)]
+mod scan_test {
+    use std::fs;
+    use std::sync::Mutex;
+
+    use tempdir::TempDir;
+
+    use crate::configuration::ProjectConfig;
+    use crate::scan::{scan_pack, PackScan};
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_scan_pack() {
+        let tmp_dir = TempDir::new("mcshader").unwrap();
+        let root = tmp_dir.path();
+        let shaders = root.join("shaders");
+        fs::create_dir_all(shaders.join("lib")).unwrap();
+        fs::write(
+            shaders.join("final.fsh"),
+            "#version 120\n#include \"/lib/common.glsl\"\nvoid main() {}\n",
+        )
+        .unwrap();
+        fs::write(
+            shaders.join("lib").join("common.glsl"),
+            "float luma(vec3 color) { return color.g; }\n",
+        )
+        .unwrap();
+        fs::write(shaders.join("notes.txt"), "#include \"/lib/missing.glsl\"\n").unwrap();
+        let cache_path = root.join("cache").join("graph.json");
+
+        let reported = Mutex::new(vec![]);
+        let scan = scan_pack(root, &ProjectConfig::default(), Some(&cache_path), |done, total| {
+            reported.lock().unwrap().push((done, total))
+        });
+
+        let paths: Vec<_> = scan.files.iter().map(|file| file.path.clone()).collect();
+        assert_eq!(paths, vec![shaders.join("final.fsh"), shaders.join("lib").join("common.glsl")]);
+        assert_eq!(scan.files[0].file.includes.len(), 1);
+        assert_eq!(scan.files[0].file.includes[0].0, shaders.join("lib").join("common.glsl"));
+        let names = |scan: &PackScan| -> Vec<String> {
+            scan.files[1]
+                .file
+                .symbols
+                .declarations
+                .iter()
+                .map(|declaration| declaration.name.clone())
+                .collect()
+        };
+        assert_eq!(names(&scan), vec!["luma"]);
+        assert_eq!(scan.cached, 0);
+
+        let mut reported = reported.into_inner().unwrap();
+        reported.sort();
+        assert_eq!(reported, vec![(1, 2), (2, 2)]);
+
+        // the next scan finds everything in the cache
+        assert!(cache_path.is_file());
+        let scan = scan_pack(root, &ProjectConfig::default(), Some(&cache_path), |_, _| {});
+        assert_eq!(scan.cached, 2);
+        assert_eq!(scan.files[0].file.includes.len(), 1);
+        assert_eq!(names(&scan), vec!["luma"]);
+
+        // nothing is cached without a cache path
+        let scan = scan_pack(root, &ProjectConfig::default(), None, |_, _| {});
+        assert_eq!(scan.cached, 0);
+        assert_eq!(names(&scan), vec!["luma"]);
+    }
+}
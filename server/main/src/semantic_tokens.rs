@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use rust_lsp::lsp_types::{Range, SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokensLegend};
+use tree_sitter::{Node, Tree};
+
+use crate::builtins;
+use crate::symbols::{self, Declaration, DeclarationKind};
+
+// indexes into TOKEN_TYPES
+const FUNCTION: u32 = 0;
+const PARAMETER: u32 = 1;
+const PROPERTY: u32 = 2;
+const MACRO: u32 = 3;
+const VARIABLE: u32 = 4;
+const STRUCT: u32 = 5;
+const UNIFORM: u32 = 6;
+const VARYING: u32 = 7;
+
+const TOKEN_TYPES: &[SemanticTokenType] = &[
+    SemanticTokenType::FUNCTION,
+    SemanticTokenType::PARAMETER,
+    SemanticTokenType::PROPERTY,
+    SemanticTokenType::MACRO,
+    SemanticTokenType::VARIABLE,
+    SemanticTokenType::STRUCT,
+    SemanticTokenType::new("uniform"),
+    SemanticTokenType::new("varying"),
+];
+
+// bit flags for TOKEN_MODIFIERS
+const DECLARATION: u32 = 1 << 0;
+const READONLY: u32 = 1 << 1;
+const DEFAULT_LIBRARY: u32 = 1 << 2;
+const OPTIFINE: u32 = 1 << 3;
+
+const TOKEN_MODIFIERS: &[SemanticTokenModifier] = &[
+    SemanticTokenModifier::DECLARATION,
+    SemanticTokenModifier::READONLY,
+    SemanticTokenModifier::DEFAULT_LIBRARY,
+    // uniforms that are set by OptiFine/Iris, as opposed to user declared globals
+    SemanticTokenModifier::new("optifine"),
+];
+
+pub fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TOKEN_TYPES.to_vec(),
+        token_modifiers: TOKEN_MODIFIERS.to_vec(),
+    }
+}
+
+/// A token with an absolute position, before being delta encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AbsoluteToken {
+    pub line: u32,
+    pub start: u32,
+    pub length: u32,
+    pub token_type: u32,
+    pub modifiers: u32,
+}
+
+/// Classifies the identifiers in `tree`, using the declarations of every file in the program(s)
+/// the file is part of to resolve globals. Only tokens within `range` are returned if given.
+pub fn tokenize(tree: &Tree, source: &str, scope: &[(PathBuf, Vec<Declaration>)], range: Option<Range>) -> Vec<AbsoluteToken> {
+    // first declaration of a name wins, same as for hover
+    let mut globals: HashMap<&str, &Declaration> = HashMap::new();
+    for declaration in scope.iter().flat_map(|(_, declarations)| declarations) {
+        if declaration.kind != DeclarationKind::Field {
+            globals.entry(declaration.name.as_str()).or_insert(declaration);
+        }
+    }
+
+    let mut tokens = vec![];
+    let mut cursor = tree.walk();
+    'walk: loop {
+        let node = cursor.node();
+
+        let in_range = range.map_or(true, |range| {
+            let node_range = symbols::node_range(node);
+            node_range.end >= range.start && node_range.start <= range.end
+        });
+
+        if in_range {
+            if let Some((token_type, modifiers)) = classify(node, source, &globals) {
+                tokens.push(AbsoluteToken {
+                    line: node.start_position().row as u32,
+                    start: node.start_position().column as u32,
+                    length: (node.end_byte() - node.start_byte()) as u32,
+                    token_type,
+                    modifiers,
+                });
+            }
+            if cursor.goto_first_child() {
+                continue;
+            }
+        }
+
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                break 'walk;
+            }
+        }
+    }
+
+    tokens
+}
+
+fn classify(node: Node, source: &str, globals: &HashMap<&str, &Declaration>) -> Option<(u32, u32)> {
+    let name = symbols::text(node, source);
+    let parent = node.parent()?;
+    let is_field = |field: &str| parent.child_by_field_name(field).map(|n| n.id()) == Some(node.id());
+
+    match node.kind() {
+        "field_identifier" => return Some((PROPERTY, if parent.kind() == "field_declaration" { DECLARATION } else { 0 })),
+        "type_identifier" => {
+            return match globals.get(name) {
+                Some(declaration) if declaration.kind == DeclarationKind::Struct => {
+                    Some((STRUCT, if parent.kind() == "struct_specifier" { DECLARATION } else { 0 }))
+                }
+                _ => None,
+            }
+        }
+        "identifier" => {}
+        _ => return None,
+    }
+
+    match parent.kind() {
+        "preproc_def" | "preproc_function_def" if is_field("name") => return Some((MACRO, DECLARATION)),
+        "preproc_ifdef" | "preproc_defined" => return Some((MACRO, 0)),
+        "function_declarator" if is_field("declarator") => return Some((FUNCTION, DECLARATION)),
+        "call_expression" if is_field("function") => {
+            return match globals.get(name) {
+                Some(declaration) if matches!(declaration.kind, DeclarationKind::Macro) => Some((MACRO, 0)),
+                Some(_) => Some((FUNCTION, 0)),
+                None if builtins::function(name).is_some() => Some((FUNCTION, DEFAULT_LIBRARY)),
+                None => None,
+            };
+        }
+        _ => {}
+    }
+
+    let declaring = match parent.kind() {
+        "init_declarator" | "declaration" | "parameter_declaration" | "array_declarator" => is_field("declarator"),
+        _ => false,
+    };
+    let declaration_modifier = if declaring { DECLARATION } else { 0 };
+
+    if let Some(local) = symbols::local_declaration(node, name, source) {
+        if local.kind() == "parameter_declaration" {
+            return Some((PARAMETER, declaration_modifier));
+        }
+        let mut cursor = local.walk();
+        let constant = local
+            .named_children(&mut cursor)
+            .any(|child| child.kind() == "type_qualifier" && symbols::text(child, source) == "const");
+        return Some((VARIABLE, declaration_modifier | if constant { READONLY } else { 0 }));
+    }
+
+    if let Some(declaration) = globals.get(name) {
+        return Some(match declaration.kind {
+            DeclarationKind::Uniform => (
+                UNIFORM,
+                declaration_modifier | if builtins::uniform(name).is_some() { OPTIFINE } else { 0 },
+            ),
+            DeclarationKind::Varying => (VARYING, declaration_modifier),
+            DeclarationKind::Constant => (VARIABLE, declaration_modifier | READONLY),
+            DeclarationKind::Define | DeclarationKind::Macro | DeclarationKind::DisabledOption => (MACRO, 0),
+            DeclarationKind::Function => (FUNCTION, 0),
+            DeclarationKind::Struct => (STRUCT, 0),
+            DeclarationKind::Global | DeclarationKind::Field => (VARIABLE, declaration_modifier),
+        });
+    }
+
+    if builtins::variable(name).is_some() {
+        return Some((VARIABLE, DEFAULT_LIBRARY));
+    }
+
+    None
+}
+
+/// Delta encodes tokens as per the LSP specification, sorting them by position first.
+pub fn encode(mut tokens: Vec<AbsoluteToken>) -> Vec<SemanticToken> {
+    tokens.sort_by_key(|token| (token.line, token.start));
+
+    let mut prev_line = 0;
+    let mut prev_start = 0;
+    tokens
+        .into_iter()
+        .map(|token| {
+            let delta_line = token.line - prev_line;
+            let delta_start = if delta_line == 0 { token.start - prev_start } else { token.start };
+            prev_line = token.line;
+            prev_start = token.start;
+            SemanticToken {
+                delta_line,
+                delta_start,
+                length: token.length,
+                token_type: token.token_type,
+                token_modifiers_bitset: token.modifiers,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod semantic_tokens_test {
+    use std::path::PathBuf;
+
+    use rust_lsp::lsp_types::{Position, Range, SemanticToken};
+    use tree_sitter::Parser;
+
+    use crate::semantic_tokens::{
+        encode, legend, tokenize, AbsoluteToken, DECLARATION, FUNCTION, MACRO, OPTIFINE, PARAMETER, PROPERTY, READONLY, STRUCT, UNIFORM,
+        VARIABLE, VARYING,
+    };
+    use crate::symbols::{self, Declaration, DeclarationKind};
+
+    fn declaration(name: &str, kind: DeclarationKind) -> Declaration {
+        Declaration {
+            name: name.to_string(),
+            kind,
+            range: Range::default(),
+            selection_range: Range::default(),
+            signature: name.to_string(),
+            documentation: None,
+            parameters: vec![],
+            value: None,
+            container: None,
+        }
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_tokenize() {
+        let mut parser = Parser::new();
+        parser.set_language(tree_sitter_glsl::language()).unwrap();
+
+        let source = "#define SCALE 2.0
+struct Light {
+    vec3 color;
+};
+const float EXPOSURE = 1.5;
+vec3 shade(Light light, float strength) {
+    const float gamma = 2.2;
+    return light.color * strength * SCALE * EXPOSURE * gamma * frameTimeCounter * exposureBias * texcoord.x;
+}
+";
+        let tree = parser.parse(source, None).unwrap();
+        // the uniforms and varyings come from an include
+        let scope = vec![
+            (
+                PathBuf::from("/shaders/lib/uniforms.glsl"),
+                vec![
+                    declaration("frameTimeCounter", DeclarationKind::Uniform),
+                    declaration("exposureBias", DeclarationKind::Uniform),
+                    declaration("texcoord", DeclarationKind::Varying),
+                ],
+            ),
+            (PathBuf::from("/shaders/final.fsh"), symbols::collect_declarations(&tree, source)),
+        ];
+        let tokens = tokenize(&tree, source, &scope, None);
+        let token_at = |line: u32, start: u32| {
+            tokens
+                .iter()
+                .find(|token| token.line == line && token.start == start)
+                .map(|token| (token.token_type, token.modifiers))
+        };
+
+        assert_eq!(token_at(0, 8), Some((MACRO, DECLARATION)));
+        assert_eq!(token_at(1, 7), Some((STRUCT, DECLARATION)));
+        assert_eq!(token_at(2, 9), Some((PROPERTY, DECLARATION)));
+        assert_eq!(token_at(4, 12), Some((VARIABLE, DECLARATION | READONLY)));
+        assert_eq!(token_at(5, 5), Some((FUNCTION, DECLARATION)));
+        assert_eq!(token_at(5, 11), Some((STRUCT, 0)));
+        assert_eq!(token_at(5, 17), Some((PARAMETER, DECLARATION)));
+        assert_eq!(token_at(5, 30), Some((PARAMETER, DECLARATION)));
+        assert_eq!(token_at(6, 16), Some((VARIABLE, DECLARATION | READONLY)));
+
+        // `return light.color * strength * SCALE * EXPOSURE * gamma * frameTimeCounter * exposureBias * texcoord.x;`
+        assert_eq!(token_at(7, 11), Some((PARAMETER, 0)));
+        assert_eq!(token_at(7, 17), Some((PROPERTY, 0)));
+        assert_eq!(token_at(7, 25), Some((PARAMETER, 0)));
+        assert_eq!(token_at(7, 36), Some((MACRO, 0)));
+        assert_eq!(token_at(7, 44), Some((VARIABLE, READONLY)));
+        assert_eq!(token_at(7, 55), Some((VARIABLE, READONLY)));
+        assert_eq!(token_at(7, 63), Some((UNIFORM, OPTIFINE)));
+        assert_eq!(token_at(7, 82), Some((UNIFORM, 0)));
+        assert_eq!(token_at(7, 97), Some((VARYING, 0)));
+        assert_eq!(token_at(7, 106), Some((PROPERTY, 0)));
+
+        // only the tokens of the requested lines
+        let range = Range::new(Position::new(5, 0), Position::new(5, 40));
+        assert!(tokenize(&tree, source, &scope, Some(range)).iter().all(|token| token.line == 5));
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_encode() {
+        let token = |line, start, length, token_type, modifiers| AbsoluteToken {
+            line,
+            start,
+            length,
+            token_type,
+            modifiers,
+        };
+
+        let tokens = vec![
+            token(3, 4, 9, FUNCTION, 0),
+            token(0, 14, 9, UNIFORM, DECLARATION | OPTIFINE),
+            token(3, 20, 5, VARYING, 0),
+        ];
+
+        assert_eq!(
+            encode(tokens),
+            vec![
+                SemanticToken {
+                    delta_line: 0,
+                    delta_start: 14,
+                    length: 9,
+                    token_type: UNIFORM,
+                    token_modifiers_bitset: DECLARATION | OPTIFINE,
+                },
+                SemanticToken {
+                    delta_line: 3,
+                    delta_start: 4,
+                    length: 9,
+                    token_type: FUNCTION,
+                    token_modifiers_bitset: 0,
+                },
+                SemanticToken {
+                    delta_line: 0,
+                    delta_start: 16,
+                    length: 5,
+                    token_type: VARYING,
+                    token_modifiers_bitset: 0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_legend() {
+        let legend = legend();
+        assert_eq!(legend.token_types[UNIFORM as usize].as_str(), "uniform");
+        assert_eq!(legend.token_types[VARYING as usize].as_str(), "varying");
+        assert_eq!(legend.token_modifiers[OPTIFINE.trailing_zeros() as usize].as_str(), "optifine");
+    }
+}
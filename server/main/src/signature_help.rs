@@ -0,0 +1,216 @@
+use std::path::PathBuf;
+
+use rust_lsp::lsp_types::{
+    Documentation, MarkupContent, MarkupKind, ParameterInformation, ParameterLabel, SignatureHelp, SignatureInformation,
+};
+
+use crate::builtins;
+use crate::symbols::{Declaration, DeclarationKind};
+
+/// The function call the cursor is in: the name of the called function and the index of the
+/// argument being typed.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CallContext {
+    pub function: String,
+    pub active_parameter: u32,
+}
+
+/// Scans backwards from `offset` to find the innermost unclosed call, counting the arguments
+/// that come before the cursor. Works on raw text as the source is usually incomplete while typing,
+/// skipping over comments and string literals.
+pub fn call_context(source: &str, offset: usize) -> Option<CallContext> {
+    let bytes = &source.as_bytes()[..offset.min(source.len())];
+    let ignored = ignored_bytes(bytes);
+
+    let mut depth = 0usize;
+    let mut commas = 0u32;
+    let mut i = bytes.len();
+    while i > 0 {
+        i -= 1;
+        if ignored[i] {
+            continue;
+        }
+        match bytes[i] {
+            b')' | b']' => depth += 1,
+            b'[' if depth > 0 => depth -= 1,
+            b'(' if depth > 0 => depth -= 1,
+            b'(' => {
+                let end = source[..i].trim_end().len();
+                let start = source[..end]
+                    .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .map_or(0, |p| p + 1);
+                let function = &source[start..end];
+                if function.is_empty() || function.as_bytes()[0].is_ascii_digit() {
+                    // parenthesized expression, keep looking for an enclosing call
+                    commas = 0;
+                    continue;
+                }
+                return Some(CallContext {
+                    function: function.to_string(),
+                    active_parameter: commas,
+                });
+            }
+            b',' if depth == 0 => commas += 1,
+            b';' | b'{' | b'}' | b'[' => return None,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+// marks the bytes that are in comments or string literals, as far as they go before the end of `bytes`.
+fn ignored_bytes(bytes: &[u8]) -> Vec<bool> {
+    let mut ignored = vec![false; bytes.len()];
+    let mut i = 0;
+    while i < bytes.len() {
+        let end = match (bytes[i], bytes.get(i + 1)) {
+            (b'/', Some(b'/')) => bytes[i..].iter().position(|b| *b == b'\n').map_or(bytes.len(), |p| i + p),
+            (b'/', Some(b'*')) => bytes[i + 2..]
+                .windows(2)
+                .position(|w| w == b"*/")
+                .map_or(bytes.len(), |p| i + p + 4),
+            (b'"', _) => {
+                let mut j = i + 1;
+                while j < bytes.len() && bytes[j] != b'"' && bytes[j] != b'\n' {
+                    j += if bytes[j] == b'\\' { 2 } else { 1 };
+                }
+                (j + 1).min(bytes.len())
+            }
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        ignored[i..end].fill(true);
+        i = end;
+    }
+    ignored
+}
+
+/// Builds the signature help for every overload of the function in `call`, project functions
+/// taking precedence over built-ins of the same name.
+pub fn signature_help(call: &CallContext, scope: &[(PathBuf, Vec<Declaration>)]) -> Option<SignatureHelp> {
+    let mut signatures: Vec<SignatureInformation> = scope
+        .iter()
+        .flat_map(|(_, declarations)| declarations)
+        .filter(|declaration| declaration.kind == DeclarationKind::Function && declaration.name == call.function)
+        .map(project_signature)
+        .collect();
+
+    if signatures.is_empty() {
+        if let Some(function) = builtins::function(&call.function) {
+            signatures = function
+                .signatures
+                .iter()
+                .map(|signature| builtin_signature(signature, function.description))
+                .collect();
+        }
+    }
+
+    if signatures.is_empty() {
+        return None;
+    }
+
+    // prefer the first overload that can take as many arguments as are being passed
+    let active_signature = signatures
+        .iter()
+        .position(|signature| signature.parameters.as_ref().map_or(0, Vec::len) as u32 > call.active_parameter)
+        .unwrap_or(0);
+
+    Some(SignatureHelp {
+        signatures,
+        active_signature: Some(active_signature as u32),
+        active_parameter: Some(call.active_parameter),
+    })
+}
+
+fn project_signature(declaration: &Declaration) -> SignatureInformation {
+    let label = &declaration.signature;
+    // parameter labels are searched for after the opening paren so that a return type or function
+    // name matching a parameter isn't picked up
+    let mut search_from = label.find('(').unwrap_or(0);
+    let parameters = declaration
+        .parameters
+        .iter()
+        .map(|param| {
+            let label = match label[search_from..].find(&param.label) {
+                Some(start) => {
+                    let start = search_from + start;
+                    search_from = start + param.label.len();
+                    ParameterLabel::LabelOffsets([start as u32, search_from as u32])
+                }
+                None => ParameterLabel::Simple(param.label.clone()),
+            };
+            ParameterInformation {
+                label,
+                documentation: param.qualifier.as_ref().map(|q| Documentation::String(format!("`{}` parameter", q))),
+            }
+        })
+        .collect();
+
+    SignatureInformation {
+        label: label.clone(),
+        documentation: declaration.documentation.clone().map(|doc| {
+            Documentation::MarkupContent(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: doc,
+            })
+        }),
+        parameters: Some(parameters),
+        active_parameter: None,
+    }
+}
+
+fn builtin_signature(signature: &str, description: &str) -> SignatureInformation {
+    SignatureInformation {
+        label: signature.to_string(),
+        documentation: Some(Documentation::MarkupContent(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: description.to_string(),
+        })),
+        parameters: Some(
+            builtins::parameter_offsets(signature)
+                .into_iter()
+                .map(|offsets| ParameterInformation {
+                    label: ParameterLabel::LabelOffsets(offsets),
+                    documentation: None,
+                })
+                .collect(),
+        ),
+        active_parameter: None,
+    }
+}
+
+#[cfg(test)]
+mod signature_help_test {
+    use crate::signature_help::{call_context, CallContext};
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_call_context() {
+        let cases = vec![
+            ("vec3 c = mix(a, ", Some(("mix", 1))),
+            ("vec3 c = mix(", Some(("mix", 0))),
+            ("float f = clamp(dot(n, l), 0.0, ", Some(("clamp", 2))),
+            ("float f = clamp(dot(n, ", Some(("dot", 1))),
+            ("float f = max((a + b) * 2.0, ", Some(("max", 1))),
+            ("float f = max(arr[1], ", Some(("max", 1))),
+            ("getShadow (pos, ", Some(("getShadow", 1))),
+            ("float f = (a + ", None),
+            ("foo(a);\nbar", None),
+            ("foo(a, /* b, c) */ ", Some(("foo", 1))),
+            ("foo(a, // b, c)\n", Some(("foo", 1))),
+            ("foo(a, /* (b */ ", Some(("foo", 1))),
+            ("printf(\"(%d, %d\", ", Some(("printf", 1))),
+        ];
+
+        for (source, expected) in cases {
+            let expected = expected.map(|(function, active_parameter)| CallContext {
+                function: function.to_string(),
+                active_parameter,
+            });
+            assert_eq!(call_context(source, source.len()), expected, "{:?}", source);
+        }
+    }
+}
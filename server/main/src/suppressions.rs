@@ -0,0 +1,282 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use rust_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, DiagnosticTag, NumberOrString, Position, Range, TextEdit, Url};
+
+use crate::code_actions::Fix;
+use crate::consts;
+use crate::diagnostics_parser::rule_id;
+
+pub static UNUSED_SUPPRESSION_CODE: &str = "unused-suppression";
+
+lazy_static! {
+    static ref RE_SUPPRESSION: Regex =
+        Regex::new(r#"(?://|/\*)\s*mcglsl-(disable-next-line|disable-line|disable|enable)\b([^\n]*?)(?:\*/|$)"#).unwrap();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    NextLine,
+    Line,
+    Region,
+}
+
+/// A comment disabling diagnostics, either all of them or only the ones with the listed codes.
+/// Compiler diagnostics are disabled by `compiler-error` or `compiler-warning`.
+#[derive(Debug)]
+struct Suppression {
+    // the comment, for reporting it when it's unused.
+    range: Range,
+    codes: Vec<String>,
+    // lines covered, end exclusive.
+    lines: std::ops::Range<u32>,
+    used: bool,
+}
+
+impl Suppression {
+    fn matches(&self, diagnostic: &Diagnostic) -> bool {
+        self.lines.contains(&diagnostic.range.start.line) && (self.codes.is_empty() || self.codes.contains(&rule_id(diagnostic)))
+    }
+}
+
+/// Codes listed after the directive, up to a `--` starting a free form reason.
+fn parse_codes(text: &str) -> Vec<String> {
+    let mut codes: Vec<String> = text
+        .split("--")
+        .next()
+        .unwrap_or_default()
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|code| !code.is_empty())
+        .map(String::from)
+        .collect();
+    codes.sort();
+    codes
+}
+
+fn parse(source: &str) -> Vec<Suppression> {
+    let line_count = source.lines().count() as u32;
+    let mut suppressions = vec![];
+    let mut open_regions: Vec<Suppression> = vec![];
+
+    for (i, line) in source.lines().enumerate() {
+        let i = i as u32;
+        let captures = match RE_SUPPRESSION.captures(line) {
+            Some(captures) => captures,
+            None => continue,
+        };
+        let comment = captures.get(0).unwrap();
+        let range = Range::new(Position::new(i, comment.start() as u32), Position::new(i, comment.end() as u32));
+        let codes = parse_codes(&captures[2]);
+
+        let kind = match &captures[1] {
+            "disable-next-line" => Kind::NextLine,
+            "disable-line" => Kind::Line,
+            "disable" => Kind::Region,
+            _ => {
+                // a bare enable closes every region, otherwise only the ones disabling the same codes
+                let (closed, open): (Vec<Suppression>, Vec<Suppression>) =
+                    open_regions.into_iter().partition(|region| codes.is_empty() || region.codes == codes);
+                open_regions = open;
+                suppressions.extend(closed.into_iter().map(|mut region| {
+                    region.lines.end = i;
+                    region
+                }));
+                continue;
+            }
+        };
+
+        let suppression = Suppression {
+            range,
+            codes,
+            lines: match kind {
+                Kind::NextLine => i + 1..i + 2,
+                Kind::Line => i..i + 1,
+                Kind::Region => i + 1..line_count,
+            },
+            used: false,
+        };
+        match kind {
+            Kind::Region => open_regions.push(suppression),
+            _ => suppressions.push(suppression),
+        }
+    }
+
+    // regions without an enable run to the end of the file
+    suppressions.extend(open_regions);
+    suppressions
+}
+
+/// Removes the diagnostics of a file that are disabled by a suppression comment in `source`, the file's
+/// content. With `report_unused`, also warns about the suppressions that didn't disable anything, which
+/// is only right once the diagnostics of every program the file is part of are in `diagnostics`.
+pub fn apply(source: &str, diagnostics: &mut Vec<Diagnostic>, report_unused: bool) {
+    let mut suppressions = parse(source);
+    if suppressions.is_empty() {
+        return;
+    }
+
+    diagnostics.retain(|diagnostic| {
+        let mut suppressed = false;
+        for suppression in suppressions.iter_mut().filter(|suppression| suppression.matches(diagnostic)) {
+            suppression.used = true;
+            suppressed = true;
+        }
+        !suppressed
+    });
+    if !report_unused {
+        return;
+    }
+
+    diagnostics.extend(
+        suppressions
+            .iter()
+            .filter(|suppression| !suppression.used)
+            .map(|suppression| Diagnostic {
+                range: suppression.range,
+                severity: Some(DiagnosticSeverity::WARNING),
+                code: Some(NumberOrString::String(UNUSED_SUPPRESSION_CODE.into())),
+                source: Some(consts::SOURCE.into()),
+                message: "suppression comment doesn't disable any diagnostics".into(),
+                tags: Some(vec![DiagnosticTag::UNNECESSARY]),
+                ..Diagnostic::default()
+            }),
+    );
+}
+
+/// Deletes the suppression comment at `range`, along with its line if there's nothing else on it.
+pub fn remove_suppression(uri: &Url, source: &str, range: Range) -> Option<Fix> {
+    let line = source.lines().nth(range.start.line as usize)?;
+    let start = range.start.character as usize;
+    let comment = line.get(start..range.end.character as usize)?;
+    if !RE_SUPPRESSION.is_match(comment) {
+        return None;
+    }
+
+    let before = line[..start].trim_end();
+    let range = if before.is_empty() && line[range.end.character as usize..].trim().is_empty() {
+        Range::new(Position::new(range.start.line, 0), Position::new(range.start.line + 1, 0))
+    } else {
+        Range::new(Position::new(range.start.line, before.len() as u32), range.end)
+    };
+    Some(Fix::new(
+        "Remove unused suppression".into(),
+        uri,
+        vec![TextEdit {
+            range,
+            new_text: "".into(),
+        }],
+    ))
+}
+
+#[cfg(test)]
+mod suppressions_test {
+    use rust_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range, TextEdit, Url};
+
+    use crate::suppressions::{apply, remove_suppression, UNUSED_SUPPRESSION_CODE};
+
+    fn diagnostic(line: u32, code: Option<&str>) -> Diagnostic {
+        Diagnostic {
+            range: Range::new(Position::new(line, 0), Position::new(line, 1000)),
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: code.map(|code| NumberOrString::String(code.into())),
+            message: format!("line {}", line),
+            ..Diagnostic::default()
+        }
+    }
+
+    fn remaining(source: &str, diagnostics: Vec<Diagnostic>) -> Vec<(u32, String)> {
+        let mut diagnostics = diagnostics;
+        apply(source, &mut diagnostics, true);
+        diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let code = match &diagnostic.code {
+                    Some(NumberOrString::String(code)) => code.clone(),
+                    _ => "".into(),
+                };
+                (diagnostic.range.start.line, code)
+            })
+            .collect()
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_line_suppressions() {
+        let source = r#"// mcglsl-disable-next-line
+#include "/lib/a.glsl"
+#include "/lib/b.glsl" // mcglsl-disable-line unused-include -- needed by composite
+float x = y; /* mcglsl-disable-line unused-include */
+void main() {}"#;
+
+        assert_eq!(
+            remaining(
+                source,
+                vec![
+                    diagnostic(1, Some("unused-include")),
+                    diagnostic(2, Some("unused-include")),
+                    diagnostic(3, None),
+                    diagnostic(4, None),
+                ]
+            ),
+            vec![
+                (3, "".into()),
+                (4, "".into()),
+                // the code doesn't match the compiler warning on its line
+                (3, UNUSED_SUPPRESSION_CODE.into())
+            ]
+        );
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_region_suppressions() {
+        let source = r#"// mcglsl-disable compiler-warning
+float a;
+// mcglsl-disable
+float b;
+// mcglsl-enable compiler-warning
+float c;
+// mcglsl-enable
+float d;
+// mcglsl-disable unused-include
+float e;"#;
+
+        assert_eq!(
+            remaining(
+                source,
+                vec![
+                    diagnostic(1, None),
+                    diagnostic(3, Some("unused-include")),
+                    diagnostic(5, Some("unused-include")),
+                    diagnostic(7, None),
+                    diagnostic(9, None),
+                ]
+            ),
+            vec![(7, "".into()), (9, "".into()), (8, UNUSED_SUPPRESSION_CODE.into())]
+        );
+
+        // suppressions may be used by programs that weren't linted
+        let mut diagnostics = vec![diagnostic(1, None), diagnostic(7, None)];
+        apply(source, &mut diagnostics, false);
+        assert_eq!(diagnostics, vec![diagnostic(7, None)]);
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_remove_suppression() {
+        let uri = Url::parse("file:///shaders/final.fsh").unwrap();
+        let source = "// mcglsl-disable-next-line
+float x; // mcglsl-disable-line
+";
+
+        let mut diagnostics = vec![];
+        apply(source, &mut diagnostics, true);
+        assert_eq!(diagnostics.len(), 2);
+
+        let edits: Vec<TextEdit> = diagnostics
+            .iter()
+            .map(|diagnostic| remove_suppression(&uri, source, diagnostic.range).unwrap().edit.changes.unwrap()[&uri][0].clone())
+            .collect();
+        assert_eq!(edits[0].range, Range::new(Position::new(0, 0), Position::new(1, 0)));
+        assert_eq!(edits[1].range, Range::new(Position::new(1, 8), Position::new(1, 31)));
+    }
+}
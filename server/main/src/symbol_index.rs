@@ -0,0 +1,202 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tree_sitter::Tree;
+
+use crate::includes;
+use crate::symbols::{self, Declaration, DeclarationKind};
+
+/// The symbols a file declares and the names it uses.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedFile {
+    pub declarations: Vec<Declaration>,
+    pub used: HashSet<String>,
+}
+
+impl IndexedFile {
+    pub fn new(tree: &Tree, source: &str) -> IndexedFile {
+        IndexedFile {
+            declarations: symbols::collect_declarations(tree, source),
+            used: includes::used_names(tree, source),
+        }
+    }
+}
+
+/// Index of the symbols of every file in the project, for searching across the whole workspace
+/// and working out which includes are used without having to parse every file on each request.
+#[derive(Default)]
+pub struct SymbolIndex {
+    files: HashMap<PathBuf, IndexedFile>,
+}
+
+impl SymbolIndex {
+    pub fn new() -> SymbolIndex {
+        SymbolIndex::default()
+    }
+
+    /// Replaces the indexed symbols of `path`.
+    pub fn update(&mut self, path: &Path, file: IndexedFile) {
+        self.files.insert(path.to_path_buf(), file);
+    }
+
+    /// The indexed symbols of every file, keyed by path.
+    pub fn files(&self) -> &HashMap<PathBuf, IndexedFile> {
+        &self.files
+    }
+
+    // the declarations of the kinds that are searchable, with the path of their file.
+    fn searchable(&self) -> impl Iterator<Item = (&Path, &Declaration)> {
+        self.files
+            .iter()
+            .flat_map(|(path, file)| file.declarations.iter().map(move |declaration| (path.as_path(), declaration)))
+            .filter(|(_, declaration)| {
+                declaration.is_option()
+                    || matches!(
+                        declaration.kind,
+                        DeclarationKind::Function
+                            | DeclarationKind::Struct
+                            | DeclarationKind::Uniform
+                            | DeclarationKind::Define
+                            | DeclarationKind::Macro
+                    )
+            })
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        self.files.remove(path);
+    }
+
+    pub fn rename(&mut self, old: &Path, new: &Path) {
+        if let Some(file) = self.files.remove(old) {
+            self.files.insert(new.to_path_buf(), file);
+        }
+    }
+
+    /// The files defining a function named exactly `name`, in path order.
+    pub fn definitions(&self, name: &str) -> Vec<(&Path, &Declaration)> {
+        let mut definitions: Vec<(&Path, &Declaration)> = self
+            .searchable()
+            .filter(|(_, declaration)| declaration.kind == DeclarationKind::Function && declaration.name == name)
+            .collect();
+        definitions.sort_by(|a, b| a.0.cmp(b.0));
+        definitions
+    }
+
+    /// Fuzzy searches all indexed symbols for `query`, returning at most `limit` results, best matches first.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<(&Path, &Declaration)> {
+        let mut results: Vec<(i64, &Path, &Declaration)> = self
+            .searchable()
+            .filter_map(|(path, declaration)| fuzzy_score(query, &declaration.name).map(|score| (score, path, declaration)))
+            .collect();
+
+        results.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.2.name.cmp(&b.2.name)).then_with(|| a.1.cmp(b.1)));
+        results.truncate(limit);
+        results.into_iter().map(|(_, path, declaration)| (path, declaration)).collect()
+    }
+}
+
+/// Scores how well `query` matches `candidate` as a case-insensitive subsequence, or `None` if it doesn't
+/// match at all. Consecutive matches, matches at the start of words (after `_` or at a camelCase hump)
+/// and exact-case matches score higher, long candidates are penalized slightly.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    if query.is_empty() {
+        return Some(0);
+    }
+
+    let candidate_chars: Vec<char> = candidate.chars().collect();
+    let mut score = 0i64;
+    let mut last_match: Option<usize> = None;
+    let mut position = 0;
+
+    for q in query.chars() {
+        let found = (position..candidate_chars.len()).find(|i| candidate_chars[*i].eq_ignore_ascii_case(&q))?;
+
+        let c = candidate_chars[found];
+        score += 1;
+        if c == q {
+            score += 1;
+        }
+        if last_match.map_or(found == 0, |last| last + 1 == found) {
+            score += 5;
+        }
+        let word_start = found == 0
+            || candidate_chars[found - 1] == '_'
+            || (c.is_ascii_uppercase() && candidate_chars[found - 1].is_ascii_lowercase());
+        if word_start {
+            score += 3;
+        }
+
+        last_match = Some(found);
+        position = found + 1;
+    }
+
+    Some(score * 4 - candidate_chars.len() as i64)
+}
+
+#[cfg(test)]
+mod symbol_index_test {
+    use rust_lsp::lsp_types::Range;
+
+    use crate::symbol_index::{fuzzy_score, IndexedFile, SymbolIndex};
+    use crate::symbols::{Declaration, DeclarationKind};
+
+    fn file(declarations: Vec<Declaration>) -> IndexedFile {
+        IndexedFile {
+            declarations,
+            ..IndexedFile::default()
+        }
+    }
+
+    fn declaration(name: &str, kind: DeclarationKind) -> Declaration {
+        Declaration {
+            name: name.to_string(),
+            kind,
+            range: Range::default(),
+            selection_range: Range::default(),
+            signature: name.to_string(),
+            documentation: None,
+            parameters: vec![],
+            value: None,
+            container: None,
+        }
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("gsh", "getShadow").is_some());
+        assert!(fuzzy_score("shadwo", "getShadow").is_none());
+        assert!(fuzzy_score("gs", "getShadow") > fuzzy_score("gs", "ligsomething"));
+        assert!(fuzzy_score("shadow", "shadow") > fuzzy_score("shadow", "shadowMapResolution"));
+        assert!(fuzzy_score("SMR", "SHADOW_MAP_RES") > fuzzy_score("SMR", "SUMMARY"));
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_search() {
+        let mut index = SymbolIndex::new();
+        index.update(
+            "/shaders/lib/shadows.glsl".as_ref(),
+            file(vec![
+                declaration("getShadow", DeclarationKind::Function),
+                declaration("shadowPos", DeclarationKind::Global),
+                declaration("shadowtex0", DeclarationKind::Uniform),
+            ]),
+        );
+        index.update("/shaders/lib/common.glsl".as_ref(), file(vec![declaration("SHADOW_RES", DeclarationKind::Define)]));
+
+        let names: Vec<&str> = index.search("shadow", 10).iter().map(|(_, d)| d.name.as_str()).collect();
+        assert_eq!(names, vec!["shadowtex0", "getShadow", "SHADOW_RES"]);
+
+        assert_eq!(index.search("shadow", 1).len(), 1);
+
+        let definitions: Vec<&str> = index.definitions("getShadow").iter().map(|(p, _)| p.to_str().unwrap()).collect();
+        assert_eq!(definitions, vec!["/shaders/lib/shadows.glsl"]);
+        assert!(index.definitions("shadowPos").is_empty());
+
+        index.remove("/shaders/lib/shadows.glsl".as_ref());
+        let names: Vec<&str> = index.search("shadow", 10).iter().map(|(_, d)| d.name.as_str()).collect();
+        assert_eq!(names, vec!["SHADOW_RES"]);
+    }
+}
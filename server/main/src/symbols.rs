@@ -0,0 +1,569 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use rust_lsp::lsp_types::{Position, Range};
+use serde::{Deserialize, Serialize};
+use tree_sitter::{Node, Tree};
+
+use crate::builtins::GlslVersion;
+use crate::preprocessor;
+use crate::TreeType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DeclarationKind {
+    Function,
+    Struct,
+    Field,
+    Uniform,
+    // global `in`, `out`, `varying` and `attribute` declarations
+    Varying,
+    Constant,
+    Global,
+    // object-like `#define`
+    Define,
+    // function-like `#define`
+    Macro,
+    // a commented out `//#define`, i.e. a boolean option that is turned off
+    DisabledOption,
+}
+
+/// A function parameter as declared in a `parameter_declaration`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Parameter {
+    // one of `in`, `out` or `inout` if given.
+    pub qualifier: Option<String>,
+    pub ty: String,
+    pub name: Option<String>,
+    // the parameter as written, with whitespace collapsed.
+    pub label: String,
+}
+
+/// A global (or struct field) declaration found in a source file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Declaration {
+    pub name: String,
+    pub kind: DeclarationKind,
+    // range of the whole declaration.
+    pub range: Range,
+    // range of the declared identifier.
+    pub selection_range: Range,
+    // the declaration as written on a single line, without any function or struct body.
+    pub signature: String,
+    // comments directly preceding or trailing the declaration.
+    pub documentation: Option<String>,
+    pub parameters: Vec<Parameter>,
+    // the (unexpanded) value of a `#define`.
+    pub value: Option<String>,
+    // the struct a field belongs to.
+    pub container: Option<String>,
+}
+
+lazy_static! {
+    static ref RE_DISABLED_OPTION: Regex = Regex::new(r#"^//\s*#define\s+([A-Za-z_]\w*)\s*(?://\s*(.*))?$"#).unwrap();
+    static ref RE_DEFINE: Regex = Regex::new(r#"^\s*#\s*(define|undef)\s+([A-Za-z_]\w*)(\()?(.*)$"#).unwrap();
+}
+
+impl Declaration {
+    /// Whether the declaration is a shader option as presented in the OptiFine/Iris options
+    /// menu: a `#define` or `const` with its allowed values in a trailing `// [...]` comment,
+    /// or a commented out toggle `#define`.
+    pub fn is_option(&self) -> bool {
+        match self.kind {
+            DeclarationKind::DisabledOption => true,
+            DeclarationKind::Define | DeclarationKind::Constant => self
+                .documentation
+                .as_ref()
+                .map_or(false, |doc| doc.lines().last().unwrap_or_default().trim_start().starts_with('[')),
+            _ => false,
+        }
+    }
+}
+
+/// The declarations of every file that is part of the same program(s) as a given file, in include order.
+pub struct ProgramScope {
+    pub files: Vec<(PathBuf, Vec<Declaration>)>,
+    // the `#version` of the first top-level program the file is part of.
+    pub version: Option<GlslVersion>,
+    pub stage: Option<TreeType>,
+    // the macros defined by each of the programs, starting with the ones OptiFine and the preamble add.
+    pub defines: Vec<HashMap<String, String>>,
+}
+
+/// The macros defined once `sources` are preprocessed one after the other, as the files of a program
+/// are in include order. Conditionals aren't evaluated, so a macro defined in any branch counts. Function-
+/// like macros map to their own name: they count as defined, but conditions calling them can't be evaluated.
+pub fn defines<'a>(sources: impl IntoIterator<Item = &'a str>) -> HashMap<String, String> {
+    let mut defines = HashMap::new();
+    for source in sources {
+        for line in source.lines() {
+            let captures = match RE_DEFINE.captures(line) {
+                Some(captures) => captures,
+                None => continue,
+            };
+            let name = captures[2].to_string();
+            match (&captures[1], captures.get(3)) {
+                ("undef", _) => defines.remove(&name),
+                (_, Some(_)) => defines.insert(name.clone(), name),
+                (_, None) => defines.insert(name, preprocessor::split_trailing_comment(&captures[4]).0.to_string()),
+            };
+        }
+    }
+    defines
+}
+
+/// Collects all top-level declarations in a tree, including the ones nested in preprocessor
+/// conditionals, along with struct fields. Function prototypes are skipped in favour of
+/// their definitions.
+pub fn collect_declarations(tree: &Tree, source: &str) -> Vec<Declaration> {
+    let mut declarations = Vec::new();
+    collect_from_scope(tree.root_node(), source, &mut declarations);
+    declarations
+}
+
+fn collect_from_scope(scope: Node, source: &str, declarations: &mut Vec<Declaration>) {
+    let mut cursor = scope.walk();
+    for node in scope.named_children(&mut cursor) {
+        match node.kind() {
+            "function_definition" => declarations.extend(function_declaration(node, source)),
+            "declaration" => declaration(node, source, declarations),
+            "struct_specifier" => struct_declaration(node, source, declarations),
+            "preproc_def" | "preproc_function_def" => declarations.extend(define_declaration(node, source)),
+            "comment" => declarations.extend(disabled_option(node, source)),
+            "preproc_if" | "preproc_ifdef" | "preproc_else" | "preproc_elif" | "ERROR" => collect_from_scope(node, source, declarations),
+            _ => {}
+        }
+    }
+}
+
+fn function_declaration(node: Node, source: &str) -> Option<Declaration> {
+    let declarator = node.child_by_field_name("declarator")?;
+    let ident = declarator.child_by_field_name("declarator")?;
+    let body_start = node.child_by_field_name("body").map_or(node.end_byte(), |body| body.start_byte());
+
+    Some(Declaration {
+        name: text(ident, source).to_string(),
+        kind: DeclarationKind::Function,
+        range: node_range(node),
+        selection_range: node_range(ident),
+        signature: collapse_whitespace(&source[node.start_byte()..body_start]).replace("( ", "(").replace(" )", ")"),
+        documentation: documentation(node, source),
+        parameters: declarator.child_by_field_name("parameters").map(|p| parameters(p, source)).unwrap_or_default(),
+        value: None,
+        container: None,
+    })
+}
+
+fn parameters(list: Node, source: &str) -> Vec<Parameter> {
+    let mut cursor = list.walk();
+    let params = list
+        .named_children(&mut cursor)
+        .filter(|param| param.kind() == "parameter_declaration")
+        .map(|param| {
+            let mut cursor = param.walk();
+            let qualifier = param
+                .named_children(&mut cursor)
+                .filter(|child| child.kind() == "type_qualifier")
+                .map(|child| text(child, source))
+                .find(|qualifier| matches!(*qualifier, "in" | "out" | "inout"))
+                .map(str::to_string);
+
+            Parameter {
+                qualifier,
+                ty: param.child_by_field_name("type").map(|ty| text(ty, source).to_string()).unwrap_or_default(),
+                name: param
+                    .child_by_field_name("declarator")
+                    .and_then(declarator_identifier)
+                    .map(|ident| text(ident, source).to_string()),
+                label: collapse_whitespace(text(param, source)),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // `void foo(void)` takes no parameters
+    match params.as_slice() {
+        [param] if param.name.is_none() && param.ty == "void" => vec![],
+        _ => params,
+    }
+}
+
+fn declaration(node: Node, source: &str, declarations: &mut Vec<Declaration>) {
+    let mut cursor = node.walk();
+    let qualifiers: Vec<&str> = node
+        .named_children(&mut cursor)
+        .filter(|child| child.kind() == "type_qualifier")
+        .map(|child| text(child, source))
+        .collect();
+
+    let kind = if qualifiers.contains(&"uniform") {
+        DeclarationKind::Uniform
+    } else if qualifiers.iter().any(|q| matches!(*q, "in" | "out" | "varying" | "attribute")) {
+        DeclarationKind::Varying
+    } else if qualifiers.contains(&"const") {
+        DeclarationKind::Constant
+    } else {
+        DeclarationKind::Global
+    };
+
+    if let Some(ty) = node.child_by_field_name("type") {
+        if ty.kind() == "struct_specifier" {
+            struct_declaration(ty, source, declarations);
+        }
+    }
+
+    let documentation = documentation(node, source);
+    let signature = match node.child_by_field_name("type").filter(|ty| ty.kind() == "struct_specifier") {
+        Some(ty) => {
+            let body_start = ty.child_by_field_name("body").map_or(ty.end_byte(), |body| body.start_byte());
+            collapse_whitespace(&format!("{}{}", &source[node.start_byte()..body_start], &source[ty.end_byte()..node.end_byte()]))
+        }
+        None => collapse_whitespace(text(node, source)),
+    };
+
+    let mut cursor = node.walk();
+    for declarator in node.children_by_field_name("declarator", &mut cursor) {
+        // prototypes are covered by their function definitions
+        if declarator.kind() == "function_declarator" {
+            continue;
+        }
+        let ident = match declarator_identifier(declarator) {
+            Some(ident) => ident,
+            None => continue,
+        };
+        declarations.push(Declaration {
+            name: text(ident, source).to_string(),
+            kind,
+            range: node_range(node),
+            selection_range: node_range(ident),
+            signature: signature.clone(),
+            documentation: documentation.clone(),
+            parameters: vec![],
+            value: None,
+            container: None,
+        });
+    }
+}
+
+fn struct_declaration(node: Node, source: &str, declarations: &mut Vec<Declaration>) {
+    let (name, body) = match (node.child_by_field_name("name"), node.child_by_field_name("body")) {
+        (Some(name), Some(body)) => (name, body),
+        _ => return,
+    };
+    let struct_name = text(name, source).to_string();
+
+    declarations.push(Declaration {
+        name: struct_name.clone(),
+        kind: DeclarationKind::Struct,
+        range: node_range(node),
+        selection_range: node_range(name),
+        signature: format!("struct {}", struct_name),
+        documentation: documentation(node, source).or_else(|| node.parent().and_then(|parent| documentation(parent, source))),
+        parameters: vec![],
+        value: None,
+        container: None,
+    });
+
+    let mut cursor = body.walk();
+    for field in body.named_children(&mut cursor).filter(|field| field.kind() == "field_declaration") {
+        let mut cursor = field.walk();
+        for declarator in field.children_by_field_name("declarator", &mut cursor) {
+            let ident = match declarator_identifier(declarator) {
+                Some(ident) => ident,
+                None => continue,
+            };
+            declarations.push(Declaration {
+                name: text(ident, source).to_string(),
+                kind: DeclarationKind::Field,
+                range: node_range(field),
+                selection_range: node_range(ident),
+                signature: collapse_whitespace(text(field, source)),
+                documentation: documentation(field, source),
+                parameters: vec![],
+                value: None,
+                container: Some(struct_name.clone()),
+            });
+        }
+    }
+}
+
+fn define_declaration(node: Node, source: &str) -> Option<Declaration> {
+    let name = node.child_by_field_name("name")?;
+    let (value, trailing) = match node.child_by_field_name("value") {
+        Some(value) => {
+            let (value, comment) = preprocessor::split_trailing_comment(text(value, source));
+            (Some(value.to_string()), comment.map(str::to_string))
+        }
+        None => (None, None),
+    };
+    let params = node.child_by_field_name("parameters").map(|params| text(params, source));
+
+    let signature = format!(
+        "#define {}{}{}",
+        text(name, source),
+        params.unwrap_or_default(),
+        value.as_ref().map(|v| format!(" {}", v)).unwrap_or_default()
+    );
+
+    let documentation = match (documentation(node, source), trailing) {
+        (Some(doc), Some(trailing)) => Some(format!("{}\n\n{}", doc, trailing)),
+        (doc, trailing) => doc.or(trailing),
+    };
+
+    Some(Declaration {
+        name: text(name, source).to_string(),
+        kind: if params.is_some() { DeclarationKind::Macro } else { DeclarationKind::Define },
+        range: node_range(node),
+        selection_range: node_range(name),
+        signature,
+        documentation,
+        parameters: vec![],
+        value,
+        container: None,
+    })
+}
+
+fn disabled_option(node: Node, source: &str) -> Option<Declaration> {
+    let captures = RE_DISABLED_OPTION.captures(text(node, source))?;
+    let name = captures.get(1).unwrap();
+    let start = Position::new(node.start_position().row as u32, (node.start_position().column + name.start()) as u32);
+
+    Some(Declaration {
+        name: name.as_str().to_string(),
+        kind: DeclarationKind::DisabledOption,
+        range: node_range(node),
+        selection_range: Range::new(start, Position::new(start.line, start.character + name.as_str().len() as u32)),
+        signature: format!("//#define {}", name.as_str()),
+        documentation: captures.get(2).map(|doc| doc.as_str().trim().to_string()).filter(|doc| !doc.is_empty()),
+        parameters: vec![],
+        value: None,
+        container: None,
+    })
+}
+
+/// Finds the local variable `declaration` or function `parameter_declaration` that `name` refers to at `node`,
+/// by climbing through the enclosing scopes. Returns `None` if `name` refers to a global.
+pub fn local_declaration<'t>(node: Node<'t>, name: &str, source: &str) -> Option<Node<'t>> {
+    let declares = |declaration: Node| {
+        let mut cursor = declaration.walk();
+        let found = declaration
+            .children_by_field_name("declarator", &mut cursor)
+            .filter_map(declarator_identifier)
+            .any(|ident| text(ident, source) == name);
+        found
+    };
+
+    let mut scope = node.parent();
+    while let Some(current) = scope {
+        match current.kind() {
+            "translation_unit" => return None,
+            "function_definition" => {
+                let params = current
+                    .child_by_field_name("declarator")
+                    .and_then(|declarator| declarator.child_by_field_name("parameters"));
+                if let Some(params) = params {
+                    let mut cursor = params.walk();
+                    let param = params
+                        .named_children(&mut cursor)
+                        .find(|param| param.kind() == "parameter_declaration" && declares(*param));
+                    if param.is_some() {
+                        return param;
+                    }
+                }
+            }
+            "compound_statement" | "for_statement" => {
+                let mut cursor = current.walk();
+                let local = current
+                    .named_children(&mut cursor)
+                    .take_while(|child| child.start_byte() <= node.start_byte())
+                    .filter(|child| child.kind() == "declaration")
+                    .filter(|child| declares(*child))
+                    .last();
+                if local.is_some() {
+                    return local;
+                }
+            }
+            _ => {}
+        }
+        scope = current.parent();
+    }
+
+    None
+}
+
+/// Finds the identifier being declared by a (possibly nested) declarator, e.g. `foo` in `foo[4] = ...`.
+pub fn declarator_identifier(declarator: Node) -> Option<Node> {
+    match declarator.kind() {
+        "identifier" | "field_identifier" | "type_identifier" => Some(declarator),
+        "init_declarator" | "array_declarator" | "function_declarator" | "parenthesized_declarator" => {
+            declarator_identifier(declarator.child_by_field_name("declarator")?)
+        }
+        _ => None,
+    }
+}
+
+/// Gathers the comments directly above a declaration (with no blank line in between) or,
+/// failing that, a comment trailing it on the same line.
+fn documentation(node: Node, source: &str) -> Option<String> {
+    let mut comments = vec![];
+    let mut next_row = node.start_position().row;
+    let mut prev = node.prev_sibling();
+    while let Some(sibling) = prev {
+        if sibling.kind() != "comment" || sibling.end_position().row + 1 < next_row {
+            break;
+        }
+        // a comment trailing the previous statement is not about this one
+        if let Some(before) = sibling.prev_sibling() {
+            if end_row(before, source) == sibling.start_position().row && before.kind() != "comment" {
+                break;
+            }
+        }
+        comments.push(clean_comment(text(sibling, source)));
+        next_row = sibling.start_position().row;
+        prev = sibling.prev_sibling();
+    }
+
+    if comments.is_empty() {
+        if let Some(next) = node.next_sibling() {
+            if next.kind() == "comment" && next.start_position().row == end_row(node, source) {
+                comments.push(clean_comment(text(next, source)));
+            }
+        }
+    }
+
+    comments.reverse();
+    let doc = comments.join("\n");
+    if doc.trim().is_empty() {
+        None
+    } else {
+        Some(doc)
+    }
+}
+
+// the last row of `node` with any of its text, as preprocessor directives take the line breaks after them
+// along.
+fn end_row(node: Node, source: &str) -> usize {
+    node.start_position().row + text(node, source).trim_end().matches('\n').count()
+}
+
+/// Strips comment delimiters and leading `*` decorations from a comment.
+pub fn clean_comment(comment: &str) -> String {
+    if let Some(line) = comment.strip_prefix("//") {
+        return line.trim_start_matches('/').trim().to_string();
+    }
+
+    let inner = comment.trim_start_matches("/*").trim_start_matches('*').trim_end_matches("*/");
+    inner
+        .lines()
+        .map(|line| {
+            let line = line.trim();
+            line.strip_prefix('*').map_or(line, str::trim_start)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Returns the identifier under (or directly before) `position` in `source`, along with its range.
+pub fn word_at(source: &str, position: Position) -> Option<(String, Range)> {
+    let line = source.lines().nth(position.line as usize)?;
+    let is_word = |c: u8| c.is_ascii_alphanumeric() || c == b'_';
+    let bytes = line.as_bytes();
+    let col = (position.character as usize).min(bytes.len());
+
+    let mut start = col;
+    while start > 0 && is_word(bytes[start - 1]) {
+        start -= 1;
+    }
+    let mut end = col;
+    while end < bytes.len() && is_word(bytes[end]) {
+        end += 1;
+    }
+
+    if start == end || bytes[start].is_ascii_digit() {
+        return None;
+    }
+
+    Some((
+        line[start..end].to_string(),
+        Range::new(Position::new(position.line, start as u32), Position::new(position.line, end as u32)),
+    ))
+}
+
+pub fn node_range(node: Node) -> Range {
+    Range::new(
+        Position::new(node.start_position().row as u32, node.start_position().column as u32),
+        Position::new(node.end_position().row as u32, node.end_position().column as u32),
+    )
+}
+
+pub fn text<'a>(node: Node, source: &'a str) -> &'a str {
+    node.utf8_text(source.as_bytes()).unwrap_or_default()
+}
+
+pub fn collapse_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod symbols_test {
+    use std::collections::HashMap;
+
+    use rust_lsp::lsp_types::{Position, Range};
+
+    use crate::symbols::{clean_comment, collapse_whitespace, defines, word_at};
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_word_at() {
+        let source = "uniform float frameTimeCounter;\nvec3 color = texture2D(gcolor, uv).rgb;";
+
+        assert_eq!(
+            word_at(source, Position::new(0, 16)),
+            Some(("frameTimeCounter".to_string(), Range::new(Position::new(0, 14), Position::new(0, 30))))
+        );
+        // end of an identifier
+        assert_eq!(word_at(source, Position::new(1, 22)).unwrap().0, "texture2D");
+        assert_eq!(word_at(source, Position::new(1, 11)), None);
+        assert_eq!(word_at(source, Position::new(5, 0)), None);
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_clean_comment() {
+        assert_eq!(clean_comment("// the sun direction"), "the sun direction");
+        assert_eq!(clean_comment("/// doc"), "doc");
+        assert_eq!(clean_comment("/**\n * first\n * second\n */"), "first\nsecond");
+        assert_eq!(clean_comment("/* inline */"), "inline");
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_collapse_whitespace() {
+        assert_eq!(collapse_whitespace("vec3 getColor(\n    in vec2 uv,\n    float  t\n)"), "vec3 getColor( in vec2 uv, float t )");
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_defines() {
+        let preamble = "#define MC_VERSION 11902\n#define QUALITY 1";
+        let settings = "#define QUALITY 2 // [1 2 3]\n  #  define SHADOWS\n//#define FOG\n#define SAMPLE(tex, uv) texture2D(tex, uv)";
+        let common = "#ifdef SHADOWS\n#undef SHADOWS\n#endif\n#define LIGHT (QUALITY * 2)";
+
+        let expected: HashMap<String, String> = vec![
+            ("MC_VERSION", "11902"),
+            ("QUALITY", "2"),
+            ("SAMPLE", "SAMPLE"),
+            ("LIGHT", "(QUALITY * 2)"),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+        assert_eq!(defines([preamble, settings, common]), expected);
+
+        // the same files in another order
+        assert_eq!(defines([common, settings])["SHADOWS"], "");
+        assert_eq!(defines([settings, preamble])["QUALITY"], "1");
+    }
+}
@@ -38,12 +38,22 @@ pub fn new_temp_server(opengl_context: Option<Box<dyn opengl::ShaderValidator>>)
 
     MinecraftShaderLanguageServer {
         endpoint,
-        graph: Rc::new(RefCell::new(graph::CachedStableGraph::new())),
+        graph: Arc::new(Mutex::new(graph::CachedStableGraph::new())),
+        symbol_index: Arc::new(Mutex::new(symbol_index::SymbolIndex::new())),
         root: "".into(),
         command_provider: None,
         opengl_context: context.into(),
         log_guard: None,
-        tree_sitter: Rc::new(RefCell::new(Parser::new())),
+        lints: Arc::new(executor::LintExecutor::new()),
+        scan: Mutex::new(None),
+        client_progress: false,
+        pending_rename: Mutex::new(None),
+        baseline: None,
+        notifier: None,
+        tree_sitter: Arc::new(Mutex::new(Parser::new())),
+        documents: HashMap::new(),
+        document_paths: HashMap::new(),
+        config: configuration::ProjectConfig::default(),
     }
 }
 
@@ -56,7 +66,7 @@ fn copy_files(files: &str, dest: &TempDir) {
     copy_items(&files, dest.path().join("shaders"), opts).unwrap();
 }
 
-pub fn copy_to_and_set_root(test_path: &str, server: &mut MinecraftShaderLanguageServer) -> (Rc<TempDir>, PathBuf) {
+pub fn copy_to_and_set_root(test_path: &str, server: &mut MinecraftShaderLanguageServer) -> (Arc<TempDir>, PathBuf) {
     let (_tmp_dir, tmp_path) = copy_to_tmp_dir(test_path);
 
     server.root = tmp_path.clone(); //format!("{}{}", "file://", tmp_path);
@@ -64,8 +74,8 @@ pub fn copy_to_and_set_root(test_path: &str, server: &mut MinecraftShaderLanguag
     (_tmp_dir, tmp_path)
 }
 
-fn copy_to_tmp_dir(test_path: &str) -> (Rc<TempDir>, PathBuf) {
-    let tmp_dir = Rc::new(TempDir::new("mcshader").unwrap());
+fn copy_to_tmp_dir(test_path: &str) -> (Arc<TempDir>, PathBuf) {
+    let tmp_dir = Arc::new(TempDir::new("mcshader").unwrap());
     fs::create_dir(tmp_dir.path().join("shaders")).unwrap();
 
     copy_files(test_path, &tmp_dir);
@@ -119,8 +129,8 @@ fn test_empty_initialize() {
 
     assert_eq!(server.root, tmp_path);
 
-    assert_eq!(server.graph.borrow().graph.edge_count(), 0);
-    assert_eq!(server.graph.borrow().graph.node_count(), 0);
+    assert_eq!(server.graph.lock().unwrap().graph.edge_count(), 0);
+    assert_eq!(server.graph.lock().unwrap().graph.node_count(), 0);
 
     server.endpoint.request_shutdown();
 }
@@ -165,26 +175,28 @@ fn test_01_initialize() {
     let completable = MethodCompletable::new(ResponseCompletable::new(Some(Id::Number(1)), Box::new(on_response)));
     server.initialize(initialize_params, completable);
     server.endpoint.request_shutdown();
+    // the graph is built in the background
+    server.finish_scan();
 
     // Assert there is one edge between two nodes
-    assert_eq!(server.graph.borrow().graph.edge_count(), 1);
+    assert_eq!(server.graph.lock().unwrap().graph.edge_count(), 1);
 
-    let edge = server.graph.borrow().graph.edge_indices().next().unwrap();
-    let (node1, node2) = server.graph.borrow().graph.edge_endpoints(edge).unwrap();
+    let edge = server.graph.lock().unwrap().graph.edge_indices().next().unwrap();
+    let (node1, node2) = server.graph.lock().unwrap().graph.edge_endpoints(edge).unwrap();
 
     // Assert the values of the two nodes in the tree
     assert_eq!(
-        server.graph.borrow().graph[node1],
+        server.graph.lock().unwrap().graph[node1],
         //format!("{:?}/{}/{}", tmp_path, "shaders", "final.fsh")
         tmp_path.join("shaders").join("final.fsh").to_str().unwrap().to_string()
     );
     assert_eq!(
-        server.graph.borrow().graph[node2],
+        server.graph.lock().unwrap().graph[node2],
         //format!("{:?}/{}/{}", tmp_path, "shaders", "common.glsl")
         tmp_path.join("shaders").join("common.glsl").to_str().unwrap().to_string()
     );
 
-    assert_eq!(server.graph.borrow().graph.edge_weight(edge).unwrap().line, 2);
+    assert_eq!(server.graph.lock().unwrap().graph.edge_weight(edge).unwrap().line, 2);
 }
 
 #[allow(deprecated)]
@@ -227,11 +239,13 @@ fn test_05_initialize() {
     let completable = MethodCompletable::new(ResponseCompletable::new(Some(Id::Number(1)), Box::new(on_response)));
     server.initialize(initialize_params, completable);
     server.endpoint.request_shutdown();
+    // the graph is built in the background
+    server.finish_scan();
 
     // Assert there is one edge between two nodes
-    assert_eq!(server.graph.borrow().graph.edge_count(), 3);
+    assert_eq!(server.graph.lock().unwrap().graph.edge_count(), 3);
 
-    assert_eq!(server.graph.borrow().graph.node_count(), 4);
+    assert_eq!(server.graph.lock().unwrap().graph.node_count(), 4);
 
     let pairs: HashSet<(PathBuf, PathBuf)> = vec![
         (
@@ -271,11 +285,280 @@ fn test_05_initialize() {
     .into_iter()
     .collect();
 
-    for edge in server.graph.borrow().graph.edge_indices() {
-        let endpoints = server.graph.borrow().graph.edge_endpoints(edge).unwrap();
-        let first = server.graph.borrow().get_node(endpoints.0);
-        let second = server.graph.borrow().get_node(endpoints.1);
+    let graph = server.graph.lock().unwrap();
+    for edge in graph.graph.edge_indices() {
+        let endpoints = graph.graph.edge_endpoints(edge).unwrap();
+        let first = graph.get_node(endpoints.0);
+        let second = graph.get_node(endpoints.1);
         let contains = pairs.contains(&(first.clone(), second.clone()));
         assert!(contains, "doesn't contain ({:?}, {:?})", first, second);
     }
 }
+
+#[test]
+#[logging_macro::log_scope]
+fn test_will_rename_files() {
+    let mut server = new_temp_server(None);
+
+    let (_tmp_dir, tmp_path) = copy_to_and_set_root("./testdata/05", &mut server);
+    server.build_initial_graph();
+
+    let shaders = tmp_path.join("shaders");
+    let renames = vec![
+        (shaders.join("test"), shaders.join("lib")),
+        (shaders.join("common.glsl"), shaders.join("utils").join("common.glsl")),
+    ];
+    // an includer that can't be read is left as it is rather than failing the rename
+    {
+        let mut graph = server.graph.lock().unwrap();
+        let gone = graph.add_node(&shaders.join("gone.fsh"));
+        let common = graph.find_node(&shaders.join("common.glsl")).unwrap();
+        graph.add_edge(gone, common, IncludePosition { line: 1, start: 10, end: 22 });
+    }
+    let edit = server.will_rename_files(&renames).unwrap();
+
+    // the include between the two moved files is relative and still resolves
+    let changes = edit.changes.unwrap();
+    assert_eq!(changes.len(), 1);
+    let mut edits = changes[&Url::from_file_path(shaders.join("final.fsh")).unwrap()].clone();
+    edits.sort_by_key(|edit| edit.range.start.line);
+    assert_eq!(
+        edits,
+        vec![
+            TextEdit {
+                range: Range::new(Position::new(2, 10), Position::new(2, 22)),
+                new_text: "/utils/common.glsl".into(),
+            },
+            TextEdit {
+                range: Range::new(Position::new(3, 10), Position::new(3, 27)),
+                new_text: "/lib/banana.glsl".into(),
+            },
+        ]
+    );
+
+    // nothing moves until the files are renamed
+    assert!(server.graph.lock().unwrap().find_node(&shaders.join("test").join("banana.glsl")).is_some());
+    server.did_rename_files(&renames);
+
+    let graph = server.graph.lock().unwrap();
+    assert_eq!(graph.find_node(&shaders.join("test").join("banana.glsl")), None);
+    let banana = graph.find_node(&shaders.join("lib").join("banana.glsl")).unwrap();
+    let burger = graph.find_node(&shaders.join("lib").join("burger.glsl")).unwrap();
+    let final_fsh = graph.find_node(&shaders.join("final.fsh")).unwrap();
+    assert_eq!(graph.get_child_positions(banana, burger).count(), 1);
+    assert_eq!(
+        graph.get_child_positions(final_fsh, banana).collect::<Vec<_>>(),
+        vec![IncludePosition { line: 3, start: 10, end: 26 }]
+    );
+}
+
+#[test]
+#[logging_macro::log_scope]
+fn test_lint_in_background() {
+    let (started, running) = std::sync::mpsc::channel();
+    let (release, blocked) = std::sync::mpsc::channel::<()>();
+    let blocked = Mutex::new(blocked);
+    let compiled = Arc::new(Mutex::new(0));
+
+    let mut validator = opengl::MockShaderValidator::new();
+    let validated = compiled.clone();
+    validator.expect_validate().returning(move |_, _| {
+        *validated.lock().unwrap() += 1;
+        started.send(()).unwrap();
+        let _ = blocked.lock().unwrap().recv();
+        None
+    });
+    validator.expect_vendor().returning(|| "NVIDIA Corporation".into());
+
+    let mut server = new_temp_server(Some(Box::new(validator)));
+    let (_tmp_dir, tmp_path) = copy_to_and_set_root("./testdata/01", &mut server);
+    server.build_initial_graph();
+
+    let open = |server: &mut MinecraftShaderLanguageServer, path: &Path| {
+        server.did_open_text_document(DidOpenTextDocumentParams {
+            text_document: TextDocumentItem {
+                uri: Url::from_file_path(path).unwrap(),
+                language_id: "glsl".into(),
+                version: 1,
+                text: fs::read_to_string(path).unwrap(),
+            },
+        })
+    };
+    let final_fsh = tmp_path.join("shaders").join("final.fsh");
+    let common = tmp_path.join("shaders").join("common.glsl");
+
+    // the program is compiling while the server goes on with other requests
+    open(&mut server, &final_fsh);
+    running.recv().unwrap();
+    assert_eq!(server.workspace_symbol_search("main").len(), 1);
+
+    // an edit supersedes the lint queued behind it
+    open(&mut server, &common);
+    server.did_change_text_document(DidChangeTextDocumentParams {
+        text_document: VersionedTextDocumentIdentifier {
+            uri: Url::from_file_path(&common).unwrap(),
+            version: 2,
+        },
+        content_changes: vec![TextDocumentContentChangeEvent {
+            range: None,
+            range_length: None,
+            text: "void helper() {}\n".into(),
+        }],
+    });
+
+    drop(release);
+    server.lints.wait();
+    assert_eq!(*compiled.lock().unwrap(), 1);
+}
+
+#[cfg(unix)]
+#[test]
+#[logging_macro::log_scope]
+fn test_open_through_symlink() {
+    let mut validator = opengl::MockShaderValidator::new();
+    validator.expect_validate().returning(|_, _| None);
+    let mut server = new_temp_server(Some(Box::new(validator)));
+    let (_tmp_dir, tmp_path) = copy_to_and_set_root("./testdata/01", &mut server);
+    server.build_initial_graph();
+    std::os::unix::fs::symlink(tmp_path.join("shaders"), tmp_path.join("linked")).unwrap();
+    let nodes = server.graph.lock().unwrap().graph.node_count();
+
+    let linked = tmp_path.join("linked").join("final.fsh");
+    server.did_open_text_document(DidOpenTextDocumentParams {
+        text_document: TextDocumentItem {
+            uri: Url::from_file_path(&linked).unwrap(),
+            language_id: "glsl".into(),
+            version: 1,
+            text: "#version 120\nvoid main() {}\n".into(),
+        },
+    });
+    server.lints.wait();
+
+    // the same file as the one the pack was scanned with, not a new one
+    assert_eq!(server.graph.lock().unwrap().graph.node_count(), nodes);
+    let final_fsh = tmp_path.join("shaders").join("final.fsh");
+    assert_eq!(server.document_source(&final_fsh).unwrap(), "#version 120\nvoid main() {}\n");
+    assert_eq!(server.open_document(&final_fsh).unwrap().0, &linked);
+
+    server.did_close_text_document(DidCloseTextDocumentParams {
+        text_document: TextDocumentIdentifier {
+            uri: Url::from_file_path(&linked).unwrap(),
+        },
+    });
+    assert!(server.open_document(&final_fsh).is_none());
+}
+
+#[test]
+#[logging_macro::log_scope]
+fn test_suppressions_in_shared_includes() {
+    let mut server = new_temp_server(None);
+    let tmp_dir = TempDir::new("mcshader").unwrap();
+    let shaders = tmp_dir.path().join("shaders");
+    fs::create_dir(&shaders).unwrap();
+    server.root = tmp_dir.path().to_path_buf();
+
+    let suppressed = "// mcglsl-disable-next-line\nfloat x;\n";
+    for file in ["final.fsh", "composite.fsh", "common.glsl"] {
+        fs::write(shaders.join(file), suppressed).unwrap();
+    }
+    {
+        let mut graph = server.graph.lock().unwrap();
+        let common = graph.add_node(&shaders.join("common.glsl"));
+        for program in ["final.fsh", "composite.fsh"] {
+            let program = graph.add_node(&shaders.join(program));
+            graph.add_edge(program, common, IncludePosition { line: 1, start: 10, end: 22 });
+        }
+    }
+
+    let unused = |diagnostics: &HashMap<Url, Vec<Diagnostic>>, file: &str| -> usize {
+        diagnostics[&Url::from_file_path(shaders.join(file)).unwrap()]
+            .iter()
+            .filter(|diagnostic| diagnostic.code == Some(NumberOrString::String(suppressions::UNUSED_SUPPRESSION_CODE.into())))
+            .count()
+    };
+    let mut diagnostics: HashMap<Url, Vec<Diagnostic>> = ["final.fsh", "common.glsl"]
+        .iter()
+        .map(|file| (Url::from_file_path(shaders.join(file)).unwrap(), vec![]))
+        .collect();
+
+    // composite.fsh may still need the suppression in common.glsl
+    server.apply_suppressions(&shaders.join("final.fsh"), &mut diagnostics);
+    assert_eq!(unused(&diagnostics, "final.fsh"), 1);
+    assert_eq!(unused(&diagnostics, "common.glsl"), 0);
+
+    // linting common.glsl compiles both programs
+    let common = Url::from_file_path(shaders.join("common.glsl")).unwrap();
+    let mut diagnostics: HashMap<Url, Vec<Diagnostic>> = HashMap::from([(common, vec![])]);
+    server.apply_suppressions(&shaders.join("common.glsl"), &mut diagnostics);
+    assert_eq!(unused(&diagnostics, "common.glsl"), 1);
+}
+
+#[test]
+#[logging_macro::log_scope]
+fn test_save_with_includes() {
+    let mut validator = opengl::MockShaderValidator::new();
+    validator.expect_validate().returning(|_, _| None);
+    let mut server = new_temp_server(Some(Box::new(validator)));
+    let (_tmp_dir, tmp_path) = copy_to_and_set_root("./testdata/01", &mut server);
+    server.build_initial_graph();
+
+    let shaders = tmp_path.join("shaders");
+    let final_fsh = shaders.join("final.fsh");
+    fs::write(shaders.join("other.glsl"), "float other() {\n\treturn 1.0;\n}\n").unwrap();
+    fs::write(&final_fsh, "#version 120\n\n#include \"/other.glsl\"\n\nvoid main() {\n\tgl_FragColor[0] = vec4(other());\n}\n").unwrap();
+
+    server.did_save_text_document(DidSaveTextDocumentParams {
+        text_document: TextDocumentIdentifier {
+            uri: Url::from_file_path(&final_fsh).unwrap(),
+        },
+        text: None,
+    });
+    server.lints.wait();
+
+    let graph = server.graph.lock().unwrap();
+    let final_node = graph.find_node(&final_fsh).unwrap();
+    let other = graph.find_node(&shaders.join("other.glsl")).unwrap();
+    let common = graph.find_node(&shaders.join("common.glsl")).unwrap();
+    assert_eq!(
+        graph.get_child_positions(final_node, other).collect::<Vec<_>>(),
+        vec![IncludePosition { line: 2, start: 10, end: 21 }]
+    );
+    assert_eq!(graph.get_child_positions(final_node, common).count(), 0);
+}
+
+// records the responses the requests are completed with.
+fn recording_completable<T: serde::Serialize>(responses: &Arc<Mutex<Vec<ResponseResult>>>) -> MethodCompletable<T, ()> {
+    let responses = responses.clone();
+    MethodCompletable::new(ResponseCompletable::new(
+        Some(Id::Number(1)),
+        Box::new(move |response: Option<Response>| responses.lock().unwrap().push(response.unwrap().result_or_error)),
+    ))
+}
+
+#[test]
+#[logging_macro::log_scope]
+fn test_requests_outside_root() {
+    let mut server = new_temp_server(None);
+    let (_tmp_dir, _) = copy_to_and_set_root("./testdata/01", &mut server);
+    let elsewhere = TempDir::new("mcshader").unwrap();
+    let position = TextDocumentPositionParams {
+        text_document: TextDocumentIdentifier {
+            uri: Url::from_file_path(elsewhere.path().join("final.fsh")).unwrap(),
+        },
+        position: Position::new(0, 0),
+    };
+
+    // answered with nothing rather than left pending
+    let responses = Arc::new(Mutex::new(vec![]));
+    server.hover(position.clone(), recording_completable(&responses));
+    server.signature_help(position.clone(), recording_completable(&responses));
+    server.document_highlight(position, recording_completable(&responses));
+    assert_eq!(
+        *responses.lock().unwrap(),
+        vec![
+            ResponseResult::Result(serde_json::json!({ "contents": [] })),
+            ResponseResult::Result(serde_json::json!({ "signatures": [] })),
+            ResponseResult::Result(serde_json::json!([])),
+        ]
+    );
+}
@@ -1,10 +1,12 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use slog_scope::trace;
 use anyhow::Result;
 use path_slash::PathBufExt;
 use url::Url;
 
+use crate::archive;
+
 pub trait FromUrl {
     fn from_url(u: Url) -> Self;
 }
@@ -71,3 +73,20 @@ impl FromJson for PathBuf {
         Ok(PathBuf::from_slash(path))
     }
 }
+
+pub trait ToUrl {
+    fn to_url(&self) -> Result<Url, ()>;
+}
+
+impl ToUrl for Path {
+    /// The URL the editor knows the file by, which is a `file` URL for files on disk and uses the archive
+    /// scheme for files in a zipped pack. `FromUrl` only looks at the path, so it turns either back into
+    /// the same path.
+    fn to_url(&self) -> Result<Url, ()> {
+        let url = Url::from_file_path(self)?;
+        if archive::split(self).is_none() {
+            return Ok(url);
+        }
+        Url::parse(&format!("{}:{}", archive::ARCHIVE_SCHEME, &url.as_str()["file:".len()..])).map_err(|_| ())
+    }
+}
@@ -0,0 +1,707 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use rust_lsp::jsonrpc::jsonrpc_common::error_JSON_RPC_MethodNotFound;
+use rust_lsp::jsonrpc::jsonrpc_request::RequestParams;
+use rust_lsp::jsonrpc::jsonrpc_response::ResponseResult;
+use rust_lsp::jsonrpc::{method_types::*, *};
+use rust_lsp::lsp::*;
+use rust_lsp::lsp_types::notification::{self, Notification as _};
+use rust_lsp::lsp_types::request::{self, Request as _};
+use rust_lsp::lsp_types::*;
+use serde_json::Value;
+use slog_scope::{debug, error, info, warn};
+use tree_sitter::Parser;
+use walkdir::WalkDir;
+
+use crate::archive;
+use crate::url_norm::{FromJson, FromUrl, ToUrl};
+use crate::{
+    configuration, executor, file_renames, graph, input, lsp_ext, opengl, symbol_index, MinecraftShaderLanguageServer, MAX_WORKSPACE_SYMBOLS,
+};
+
+// how far below a workspace folder packs are looked for.
+const MAX_PACK_DEPTH: usize = 3;
+
+fn is_pack_root(dir: &Path) -> bool {
+    dir.join("shaders").is_dir()
+}
+
+/// The roots of the packs in a workspace folder, which is the folder itself if it has a `shaders` folder
+/// and otherwise every folder or zip archive below it that has one. A folder without any pack is served
+/// as a single pack.
+pub fn find_packs(folder: &Path) -> Vec<PathBuf> {
+    if is_pack_root(folder) {
+        return vec![folder.to_path_buf()];
+    }
+
+    let mut packs = vec![];
+    let mut entries = WalkDir::new(folder)
+        .min_depth(1)
+        .max_depth(MAX_PACK_DEPTH)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            (entry.file_type().is_dir() || archive::is_archive(entry.path())) && !entry.file_name().to_string_lossy().starts_with('.')
+        });
+    while let Some(entry) = entries.next() {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(_) => continue,
+        };
+        if archive::is_archive(entry.path()) {
+            packs.extend(archive::pack_root(entry.path()));
+        } else if is_pack_root(entry.path()) {
+            packs.push(entry.into_path());
+            // packs don't nest, so there's no need to look inside this one
+            entries.skip_current_dir();
+        }
+    }
+
+    if packs.is_empty() {
+        packs.push(folder.to_path_buf());
+    }
+    packs
+}
+
+/// The language server for a whole editor workspace. Every shader pack found in the workspace folders
+/// gets its own server, with its own include graph and project config, and requests are routed to the
+/// server of the pack the file they're about belongs to.
+pub struct Workspace {
+    endpoint: Endpoint,
+    // the validator packs start out with, until their project config picks another one.
+    opengl_context: Arc<dyn opengl::ShaderValidator>,
+    tree_sitter: Arc<Mutex<Parser>>,
+    folders: Vec<PathBuf>,
+    // keyed by pack root.
+    packs: BTreeMap<PathBuf, MinecraftShaderLanguageServer>,
+    log_guard: Option<slog_scope::GlobalLoggerGuard>,
+    lints: Arc<executor::LintExecutor>,
+    // whether the client shows progress for work done by the server.
+    client_progress: bool,
+    // handed to every pack, for their scans to notify the main loop when they're done.
+    notifier: Option<input::Notifier>,
+}
+
+impl Workspace {
+    pub fn new(
+        endpoint: Endpoint, opengl_context: Arc<dyn opengl::ShaderValidator>, tree_sitter: Arc<Mutex<Parser>>,
+        log_guard: Option<slog_scope::GlobalLoggerGuard>, notifier: Option<input::Notifier>,
+    ) -> Workspace {
+        Workspace {
+            endpoint,
+            opengl_context,
+            tree_sitter,
+            folders: vec![],
+            packs: BTreeMap::new(),
+            log_guard,
+            lints: Arc::new(executor::LintExecutor::new()),
+            client_progress: false,
+            notifier,
+        }
+    }
+
+    /// The root of the innermost pack containing `path`.
+    fn pack_root(&self, path: &Path) -> Option<PathBuf> {
+        self.packs
+            .keys()
+            .filter(|root| path.starts_with(root))
+            .max_by_key(|root| root.components().count())
+            .cloned()
+    }
+
+    fn pack_for(&mut self, uri: &Url) -> Option<&mut MinecraftShaderLanguageServer> {
+        let root = self.pack_root(&PathBuf::from_url(uri.clone()))?;
+        self.packs.get_mut(&root)
+    }
+
+    fn add_folder(&mut self, folder: PathBuf) {
+        for root in find_packs(&folder) {
+            if self.packs.contains_key(&root) {
+                continue;
+            }
+            info!("opening pack"; "root" => root.to_str().unwrap());
+
+            let mut pack = MinecraftShaderLanguageServer {
+                endpoint: self.endpoint.clone(),
+                graph: Arc::new(Mutex::new(graph::CachedStableGraph::new())),
+                symbol_index: Arc::new(Mutex::new(symbol_index::SymbolIndex::new())),
+                root: "".into(),
+                command_provider: None,
+                opengl_context: self.opengl_context.clone(),
+                tree_sitter: self.tree_sitter.clone(),
+                documents: HashMap::new(),
+                document_paths: HashMap::new(),
+                config: configuration::ProjectConfig::default(),
+                log_guard: None,
+                lints: self.lints.clone(),
+                scan: Mutex::new(None),
+                client_progress: self.client_progress,
+                pending_rename: Mutex::new(None),
+                baseline: None,
+                notifier: self.notifier.clone(),
+            };
+            pack.open_pack(root.clone());
+            self.packs.insert(root, pack);
+        }
+        self.folders.push(folder);
+    }
+
+    /// Drops the packs of a folder removed from the workspace, unless another folder still holds them,
+    /// and clears the diagnostics published for their files.
+    fn remove_folder(&mut self, folder: &Path) {
+        self.folders.retain(|other| other != folder);
+        let roots: Vec<PathBuf> = self
+            .packs
+            .keys()
+            .filter(|root| root.starts_with(folder) && !self.folders.iter().any(|other| root.starts_with(other)))
+            .cloned()
+            .collect();
+
+        for root in roots {
+            info!("closing pack"; "root" => root.to_str().unwrap());
+            let pack = self.packs.remove(&root).unwrap();
+            let cleared: HashMap<Url, Vec<Diagnostic>> = {
+                let graph = pack.graph.lock().unwrap();
+                graph
+                    .graph
+                    .node_indices()
+                    .filter_map(|node| graph.get_node(node).to_url().ok())
+                    .map(|uri| (uri, vec![]))
+                    .collect()
+            };
+            pack.publish_diagnostic(cleared, None);
+        }
+    }
+
+    fn did_change_workspace_folders(&mut self, params: DidChangeWorkspaceFoldersParams) {
+        for folder in params.event.removed {
+            self.remove_folder(&PathBuf::from_url(folder.uri));
+        }
+        if params.event.added.is_empty() {
+            return;
+        }
+
+        lsp_ext::set_status(&self.endpoint, "loading", "Building dependency graph...", "$(loading~spin)");
+        for folder in params.event.added {
+            self.add_folder(PathBuf::from_url(folder.uri));
+        }
+        lsp_ext::set_status(&self.endpoint, "ready", "Project initialized", "$(check)");
+    }
+
+    /// Rewrites the includes of every pack, as a file may be moved from one pack into another.
+    fn will_rename_files(&self, params: RenameFilesParams, completable: LSCompletable<Option<WorkspaceEdit>>) {
+        let renames = file_renames(&params);
+        let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+        for pack in self.packs.values() {
+            pack.finish_scan();
+            match pack.will_rename_files(&renames) {
+                Ok(edit) => {
+                    for (uri, edits) in edit.changes.unwrap_or_default() {
+                        changes.entry(uri).or_default().extend(edits);
+                    }
+                }
+                Err(e) => {
+                    return completable.complete(Err(MethodError {
+                        code: 42069,
+                        message: format!("error updating includes: error={}, renames={:?}", e, renames),
+                        data: (),
+                    }))
+                }
+            }
+        }
+        completable.complete(Ok(Some(WorkspaceEdit::new(changes))))
+    }
+
+    /// Moves the renamed files in the graph of every pack, once the client renamed them.
+    fn did_rename_files(&self, params: RenameFilesParams) {
+        let renames = file_renames(&params);
+        for pack in self.packs.values() {
+            pack.finish_scan();
+            pack.did_rename_files(&renames);
+        }
+    }
+
+    /// Passes a request that isn't part of `LanguageServerHandling` on to the pack of the document it's
+    /// about.
+    fn route_other_method(&mut self, method_name: &str, params: RequestParams, completable: ResponseCompletable) {
+        let value = params.clone().into_value();
+        let uri = ["/textDocument/uri", "/item/uri"]
+            .iter()
+            .find_map(|pointer| Url::parse(value.pointer(pointer)?.as_str()?).ok());
+
+        match uri {
+            Some(uri) => match self.pack_for(&uri) {
+                Some(pack) => pack.handle_other_method(method_name, params, completable),
+                None => {
+                    debug!("no pack for document"; "method" => method_name, "uri" => uri.as_str());
+                    completable.complete(Some(ResponseResult::Result(Value::Null)))
+                }
+            },
+            None => {
+                debug!("unhandled method"; "method" => method_name);
+                completable.complete_with_error(error_JSON_RPC_MethodNotFound())
+            }
+        }
+    }
+}
+
+impl LanguageServerHandling for Workspace {
+    fn initialize(&mut self, params: InitializeParams, completable: MethodCompletable<InitializeResult, InitializeError>) {
+        logging::slog_with_trace_id(|| {
+            info!("starting server...");
+
+            let folders: Vec<PathBuf> = match (params.workspace_folders, params.root_uri) {
+                (Some(folders), _) if !folders.is_empty() => folders.into_iter().map(|folder| PathBuf::from_url(folder.uri)).collect(),
+                (_, Some(uri)) => vec![PathBuf::from_url(uri)],
+                _ => {
+                    completable.complete(Err(MethodError {
+                        code: 42069,
+                        message: "Must be in workspace".into(),
+                        data: InitializeError { retry: false },
+                    }));
+                    return;
+                }
+            };
+
+            self.client_progress = lsp_ext::supports_work_done_progress(&params.capabilities);
+            completable.complete(Ok(InitializeResult {
+                capabilities: MinecraftShaderLanguageServer::capabilities(),
+                server_info: None,
+            }));
+
+            lsp_ext::set_status(&self.endpoint, "loading", "Building dependency graph...", "$(loading~spin)");
+            for folder in folders {
+                self.add_folder(folder);
+            }
+            info!("found shader packs"; "packs" => format!("{:?}", self.packs.keys().collect::<Vec<_>>()));
+            lsp_ext::set_status(&self.endpoint, "ready", "Project initialized", "$(check)");
+        });
+    }
+
+    fn shutdown(&mut self, _: (), completable: LSCompletable<()>) {
+        warn!("shutting down language server...");
+        // nothing may be published after the response
+        self.lints.cancel_all();
+        self.lints.wait();
+        completable.complete(Ok(()));
+    }
+
+    fn exit(&mut self, _: ()) {
+        self.endpoint.request_shutdown();
+    }
+
+    fn workspace_change_configuration(&mut self, params: DidChangeConfigurationParams) {
+        logging::slog_with_trace_id(|| configuration::handle_settings_change(&params.settings, &mut self.log_guard));
+    }
+
+    fn did_open_text_document(&mut self, params: DidOpenTextDocumentParams) {
+        if let Some(pack) = self.pack_for(&params.text_document.uri) {
+            pack.did_open_text_document(params)
+        }
+    }
+
+    fn did_change_text_document(&mut self, params: DidChangeTextDocumentParams) {
+        if let Some(pack) = self.pack_for(&params.text_document.uri) {
+            pack.did_change_text_document(params)
+        }
+    }
+
+    fn did_close_text_document(&mut self, params: DidCloseTextDocumentParams) {
+        if let Some(pack) = self.pack_for(&params.text_document.uri) {
+            pack.did_close_text_document(params)
+        }
+    }
+
+    fn did_save_text_document(&mut self, params: DidSaveTextDocumentParams) {
+        if let Some(pack) = self.pack_for(&params.text_document.uri) {
+            pack.did_save_text_document(params)
+        }
+    }
+
+    fn did_change_watched_files(&mut self, params: DidChangeWatchedFilesParams) {
+        let mut changes: BTreeMap<PathBuf, Vec<FileEvent>> = BTreeMap::new();
+        for change in params.changes {
+            if let Some(root) = self.pack_root(&PathBuf::from_url(change.uri.clone())) {
+                changes.entry(root).or_default().push(change);
+            }
+        }
+        for (root, changes) in changes {
+            self.packs
+                .get_mut(&root)
+                .unwrap()
+                .did_change_watched_files(DidChangeWatchedFilesParams { changes });
+        }
+    }
+
+    fn completion(&mut self, _: TextDocumentPositionParams, completable: LSCompletable<CompletionList>) {
+        completable.complete(Err(MinecraftShaderLanguageServer::error_not_available(())));
+    }
+
+    fn resolve_completion_item(&mut self, _: CompletionItem, completable: LSCompletable<CompletionItem>) {
+        completable.complete(Err(MinecraftShaderLanguageServer::error_not_available(())));
+    }
+
+    fn hover(&mut self, params: TextDocumentPositionParams, completable: LSCompletable<Hover>) {
+        match self.pack_for(&params.text_document.uri) {
+            Some(pack) => pack.hover(params, completable),
+            None => completable.complete(Err(MinecraftShaderLanguageServer::error_not_available(()))),
+        }
+    }
+
+    /// Commands run in the pack of the path given as their first argument, which is either a file of
+    /// the pack or a workspace folder holding it.
+    fn execute_command(&mut self, params: ExecuteCommandParams, completable: LSCompletable<Option<Value>>) {
+        let root = params
+            .arguments
+            .first()
+            .and_then(|arg| PathBuf::from_json(arg).ok())
+            .and_then(|path| {
+                self.pack_root(&path)
+                    .or_else(|| self.packs.keys().find(|root| root.starts_with(&path)).cloned())
+            });
+
+        match root {
+            Some(root) => self.packs.get_mut(&root).unwrap().execute_command(params, completable),
+            None => logging::slog_with_trace_id(|| {
+                error!("no pack to run command in"; "command" => &params.command, "arguments" => format!("{:?}", params.arguments));
+                completable.complete(Err(MethodError::new(
+                    32420,
+                    "no shader pack found to run the command in".into(),
+                    (),
+                )))
+            }),
+        }
+    }
+
+    fn signature_help(&mut self, params: TextDocumentPositionParams, completable: LSCompletable<SignatureHelp>) {
+        match self.pack_for(&params.text_document.uri) {
+            Some(pack) => pack.signature_help(params, completable),
+            None => completable.complete(Err(MinecraftShaderLanguageServer::error_not_available(()))),
+        }
+    }
+
+    fn goto_definition(&mut self, params: TextDocumentPositionParams, completable: LSCompletable<Vec<Location>>) {
+        match self.pack_for(&params.text_document.uri) {
+            Some(pack) => pack.goto_definition(params, completable),
+            None => completable.complete(Ok(vec![])),
+        }
+    }
+
+    fn references(&mut self, params: ReferenceParams, completable: LSCompletable<Vec<Location>>) {
+        match self.pack_for(&params.text_document_position.text_document.uri) {
+            Some(pack) => pack.references(params, completable),
+            None => completable.complete(Ok(vec![])),
+        }
+    }
+
+    fn document_highlight(&mut self, params: TextDocumentPositionParams, completable: LSCompletable<Vec<DocumentHighlight>>) {
+        match self.pack_for(&params.text_document.uri) {
+            Some(pack) => pack.document_highlight(params, completable),
+            None => completable.complete(Ok(vec![])),
+        }
+    }
+
+    fn document_symbols(&mut self, params: DocumentSymbolParams, completable: LSCompletable<DocumentSymbolResponse>) {
+        match self.pack_for(&params.text_document.uri) {
+            Some(pack) => pack.document_symbols(params, completable),
+            None => completable.complete(Ok(DocumentSymbolResponse::Nested(vec![]))),
+        }
+    }
+
+    fn workspace_symbols(&mut self, params: WorkspaceSymbolParams, completable: LSCompletable<DocumentSymbolResponse>) {
+        logging::slog_with_trace_id(|| {
+            let mut ranked: Vec<(i64, SymbolInformation)> = self
+                .packs
+                .values()
+                .flat_map(|pack| {
+                    pack.finish_scan();
+                    pack.workspace_symbol_search(&params.query)
+                })
+                .map(|symbol| (symbol_index::fuzzy_score(&params.query, &symbol.name).unwrap_or_default(), symbol))
+                .collect();
+            // each pack only returns its own best matches, ranked again together the same way a pack ranks them
+            ranked.sort_by(|a, b| {
+                b.0.cmp(&a.0)
+                    .then_with(|| a.1.name.cmp(&b.1.name))
+                    .then_with(|| a.1.location.uri.cmp(&b.1.location.uri))
+            });
+            ranked.truncate(MAX_WORKSPACE_SYMBOLS);
+            let symbols: Vec<SymbolInformation> = ranked.into_iter().map(|(_, symbol)| symbol).collect();
+
+            info!("workspace symbol search results"; "query" => &params.query, "count" => symbols.len(), "packs" => self.packs.len());
+            completable.complete(Ok(DocumentSymbolResponse::Flat(symbols)));
+        });
+    }
+
+    fn code_action(&mut self, params: CodeActionParams, completable: LSCompletable<Vec<Command>>) {
+        match self.pack_for(&params.text_document.uri) {
+            Some(pack) => pack.code_action(params, completable),
+            None => completable.complete(Ok(vec![])),
+        }
+    }
+
+    fn code_lens(&mut self, _: CodeLensParams, completable: LSCompletable<Vec<CodeLens>>) {
+        completable.complete(Err(MinecraftShaderLanguageServer::error_not_available(())));
+    }
+
+    fn code_lens_resolve(&mut self, _: CodeLens, completable: LSCompletable<CodeLens>) {
+        completable.complete(Err(MinecraftShaderLanguageServer::error_not_available(())));
+    }
+
+    fn document_link(&mut self, params: DocumentLinkParams, completable: LSCompletable<Vec<DocumentLink>>) {
+        match self.pack_for(&params.text_document.uri) {
+            Some(pack) => pack.document_link(params, completable),
+            None => completable.complete(Ok(vec![])),
+        }
+    }
+
+    fn document_link_resolve(&mut self, _: DocumentLink, completable: LSCompletable<DocumentLink>) {
+        completable.complete(Err(MinecraftShaderLanguageServer::error_not_available(())));
+    }
+
+    fn formatting(&mut self, params: DocumentFormattingParams, completable: LSCompletable<Vec<TextEdit>>) {
+        match self.pack_for(&params.text_document.uri) {
+            Some(pack) => pack.formatting(params, completable),
+            None => completable.complete(Ok(vec![])),
+        }
+    }
+
+    fn range_formatting(&mut self, params: DocumentRangeFormattingParams, completable: LSCompletable<Vec<TextEdit>>) {
+        match self.pack_for(&params.text_document.uri) {
+            Some(pack) => pack.range_formatting(params, completable),
+            None => completable.complete(Ok(vec![])),
+        }
+    }
+
+    fn on_type_formatting(&mut self, _: DocumentOnTypeFormattingParams, completable: LSCompletable<Vec<TextEdit>>) {
+        completable.complete(Err(MinecraftShaderLanguageServer::error_not_available(())));
+    }
+
+    fn rename(&mut self, _: RenameParams, completable: LSCompletable<WorkspaceEdit>) {
+        completable.complete(Err(MinecraftShaderLanguageServer::error_not_available(())));
+    }
+
+    fn handle_other_method(&mut self, method_name: &str, params: RequestParams, completable: ResponseCompletable) {
+        match method_name {
+            notification::DidChangeWorkspaceFolders::METHOD => logging::slog_with_trace_id(|| {
+                completable.handle_notification_with(params, |params: DidChangeWorkspaceFoldersParams| {
+                    self.did_change_workspace_folders(params)
+                })
+            }),
+            request::WillRenameFiles::METHOD => logging::slog_with_trace_id(|| {
+                completable.handle_request_with(
+                    params,
+                    |params: RenameFilesParams, completable: LSCompletable<Option<WorkspaceEdit>>| {
+                        self.will_rename_files(params, completable)
+                    },
+                )
+            }),
+            notification::DidRenameFiles::METHOD => logging::slog_with_trace_id(|| {
+                completable.handle_notification_with(params, |params: RenameFilesParams| self.did_rename_files(params))
+            }),
+            // requests are answered in order, so they're done by the time a cancel for them arrives. lints
+            // aren't requests, they're cancelled through their progress token below or dropped by newer edits.
+            notification::Cancel::METHOD => completable.handle_notification_with(params, |_: CancelParams| {}),
+            notification::WorkDoneProgressCancel::METHOD => logging::slog_with_trace_id(|| {
+                completable.handle_notification_with(params, |params: WorkDoneProgressCancelParams| {
+                    self.lints.cancel(&params.token);
+                })
+            }),
+            // sent by the scan threads, not the client
+            method if method == input::SCAN_FINISHED => logging::slog_with_trace_id(|| {
+                completable.handle_notification_with(params, |_: Value| {
+                    for pack in self.packs.values() {
+                        pack.poll_scan();
+                    }
+                })
+            }),
+            _ => self.route_other_method(method_name, params, completable),
+        }
+    }
+}
+
+#[cfg(test)]
+mod workspace_test {
+    use std::cell::RefCell;
+    use std::fs;
+    use std::io;
+    use std::path::Path;
+    use std::rc::Rc;
+    use std::sync::{Arc, Mutex};
+
+    use rust_lsp::jsonrpc::jsonrpc_request::RequestParams;
+    use rust_lsp::jsonrpc::jsonrpc_response::ResponseResult;
+    use rust_lsp::jsonrpc::*;
+    use rust_lsp::lsp::*;
+    use rust_lsp::lsp_types::*;
+    use tempdir::TempDir;
+    use tree_sitter::Parser;
+
+    use crate::workspace::{find_packs, Workspace};
+    use crate::{opengl, MAX_WORKSPACE_SYMBOLS};
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn new_workspace() -> Workspace {
+        let endpoint = LSPEndpoint::create_lsp_output_with_output_stream(io::sink);
+        let mut parser = Parser::new();
+        parser.set_language(tree_sitter_glsl::language()).unwrap();
+        Workspace::new(
+            endpoint,
+            Arc::new(opengl::MockShaderValidator::new()),
+            Arc::new(Mutex::new(parser)),
+            None,
+            None,
+        )
+    }
+
+    #[allow(deprecated)]
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_workspace_packs() {
+        let tmp_dir = TempDir::new("mcshader").unwrap();
+        let root = tmp_dir.path();
+        write(
+            &root.join("packs/a/shaders/final.fsh"),
+            "#version 120\n#include \"/lib/common.glsl\"\nvoid main() {}\n",
+        );
+        write(&root.join("packs/a/shaders/lib/common.glsl"), "float f() { return 1.0; }\n");
+        write(&root.join("packs/b/shaders/final.fsh"), "#version 120\nvoid main() {}\n");
+        write(&root.join(".git/shaders/final.fsh"), "");
+        write(&root.join("notes/todo.txt"), "");
+
+        assert_eq!(find_packs(root), vec![root.join("packs/a"), root.join("packs/b")]);
+        assert_eq!(find_packs(&root.join("packs/a")), vec![root.join("packs/a")]);
+        assert_eq!(find_packs(&root.join("notes")), vec![root.join("notes")]);
+
+        let mut workspace = new_workspace();
+        let initialize_params = InitializeParams {
+            process_id: None,
+            root_path: None,
+            root_uri: None,
+            client_info: None,
+            initialization_options: None,
+            capabilities: ClientCapabilities::default(),
+            trace: None,
+            workspace_folders: Some(vec![WorkspaceFolder {
+                uri: Url::from_directory_path(root).unwrap(),
+                name: "packs".into(),
+            }]),
+            locale: None,
+        };
+        let completable = MethodCompletable::new(ResponseCompletable::new(None, Box::new(|_| {})));
+        workspace.initialize(initialize_params, completable);
+
+        assert_eq!(workspace.packs.len(), 2);
+        let common = root.join("packs/a/shaders/lib/common.glsl");
+        assert_eq!(workspace.pack_root(&common), Some(root.join("packs/a")));
+        assert_eq!(workspace.pack_root(&root.join("notes/todo.txt")), None);
+
+        // each pack only has its own files in its graph, once it's been scanned
+        for pack in workspace.packs.values() {
+            pack.finish_scan();
+        }
+        let a = &workspace.packs[&root.join("packs/a")];
+        assert_eq!(a.graph.lock().unwrap().graph.node_count(), 2);
+        assert_eq!(a.graph.lock().unwrap().graph.edge_count(), 1);
+        let b = &workspace.packs[&root.join("packs/b")];
+        assert_eq!(b.graph.lock().unwrap().graph.node_count(), 1);
+
+        let params = serde_json::json!({
+            "event": {
+                "added": [],
+                "removed": [{ "uri": Url::from_directory_path(root).unwrap(), "name": "packs" }],
+            }
+        });
+        let completable = ResponseCompletable::new(None, Box::new(|_| {}));
+        workspace.handle_other_method(
+            "workspace/didChangeWorkspaceFolders",
+            RequestParams::Object(params.as_object().unwrap().clone()),
+            completable,
+        );
+        assert!(workspace.packs.is_empty());
+    }
+
+    // records the response the request is completed with.
+    fn completable<T: serde::Serialize>(responses: &Rc<RefCell<Vec<ResponseResult>>>) -> MethodCompletable<T, ()> {
+        let responses = responses.clone();
+        MethodCompletable::new(ResponseCompletable::new(
+            None,
+            Box::new(move |response| responses.borrow_mut().push(response.unwrap().result_or_error)),
+        ))
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_request_outside_packs() {
+        let tmp_dir = TempDir::new("mcshader").unwrap();
+        let root = tmp_dir.path();
+        write(&root.join("pack/shaders/final.fsh"), "#version 120\nvoid main() {}\n");
+        write(&root.join("elsewhere/common.glsl"), "float f() { return 1.0; }\n");
+
+        let mut workspace = new_workspace();
+        workspace.add_folder(root.join("pack"));
+
+        // requests about documents of no pack are still answered
+        let uri = Url::from_file_path(root.join("elsewhere/common.glsl")).unwrap();
+        let responses = Rc::new(RefCell::new(vec![]));
+        let position = TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            position: Position::new(0, 6),
+        };
+        workspace.hover(position.clone(), completable(&responses));
+        workspace.goto_definition(position, completable(&responses));
+        workspace.document_symbols(
+            DocumentSymbolParams {
+                text_document: TextDocumentIdentifier { uri },
+                work_done_progress_params: WorkDoneProgressParams::default(),
+                partial_result_params: PartialResultParams::default(),
+            },
+            completable(&responses),
+        );
+
+        let responses = responses.borrow();
+        assert_eq!(responses.len(), 3);
+        assert!(matches!(responses[0], ResponseResult::Error(_)));
+        assert_eq!(responses[1], ResponseResult::Result(serde_json::json!([])));
+        assert_eq!(responses[2], ResponseResult::Result(serde_json::json!([])));
+    }
+
+    #[test]
+    #[logging_macro::log_scope]
+    fn test_workspace_symbols_across_packs() {
+        let tmp_dir = TempDir::new("mcshader").unwrap();
+        let root = tmp_dir.path();
+        let weaker: String = (0..MAX_WORKSPACE_SYMBOLS + 10).map(|i| format!("float skyLight{}() {{ return 1.0; }}\n", i)).collect();
+        write(&root.join("a/shaders/lib/sky.glsl"), &weaker);
+        write(&root.join("b/shaders/lib/light.glsl"), "float light() { return 1.0; }\n");
+
+        let mut workspace = new_workspace();
+        workspace.add_folder(root.join("a"));
+        workspace.add_folder(root.join("b"));
+
+        let responses = Rc::new(RefCell::new(vec![]));
+        workspace.workspace_symbols(
+            WorkspaceSymbolParams {
+                query: "light".into(),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+                partial_result_params: PartialResultParams::default(),
+            },
+            completable(&responses),
+        );
+
+        // the best match comes first whichever pack it's from, ahead of the other pack's weaker ones
+        let symbols = match &responses.borrow()[0] {
+            ResponseResult::Result(symbols) => symbols.as_array().unwrap().clone(),
+            ResponseResult::Error(e) => panic!("expected symbols, got {:?}", e),
+        };
+        assert_eq!(symbols.len(), MAX_WORKSPACE_SYMBOLS);
+        assert_eq!(symbols[0]["name"], "light");
+        assert_eq!(symbols[1]["name"], "skyLight0");
+    }
+}